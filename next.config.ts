@@ -3,6 +3,8 @@ import type { NextConfig } from "next";
 
 const nextConfig: NextConfig = {
   output: "standalone",
+  // Optional database drivers for STORAGE_BACKEND=postgres|sqlite, loaded at runtime
+  serverExternalPackages: ["pg", "better-sqlite3"],
   async headers() {
     return [
       {
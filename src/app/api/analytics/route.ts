@@ -1,21 +1,14 @@
 import { NextRequest } from "next/server";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
 import { ok, handleError } from "@/lib/api";
 
 export async function GET(request: NextRequest) {
   try {
     const days = Math.min(parseInt(new URL(request.url).searchParams.get("days") || "30"), 365);
-    const supabase = await createClient();
-    const since = new Date(Date.now() - days * 86400000).toISOString();
+    const since = new Date(Date.now() - days * 86400000);
 
-    const { data: reviews, error } = await supabase
-      .from("review_history")
-      .select("repo_full_name,created_at,result")
-      .gte("created_at", since)
-      .order("created_at", { ascending: true });
-
-    if (error) throw error;
-    if (!reviews) return ok({ summary: {}, byDate: [], byRepo: [], byCategory: [] });
+    // Oldest first, so dates come out in order
+    const reviews = (await getStorage().history.list({ since, limit: 1000 })).reverse();
 
     const byDate: Record<string, { count: number; issues: number; critical: number }> = {};
     const byRepo: Record<string, { count: number; issues: number }> = {};
@@ -23,8 +16,8 @@ export async function GET(request: NextRequest) {
     let totalIssues = 0, totalCritical = 0, totalHigh = 0;
 
     for (const r of reviews) {
-      const date = r.created_at.split("T")[0];
-      const issues = r.result?.line_comments || [];
+      const date = r.created_at.slice(0, 10);
+      const issues: any[] = (r.result as { line_comments?: any[] } | null)?.line_comments || [];
       const critical = issues.filter((c: any) => c.severity === "critical").length;
       const high = issues.filter((c: any) => c.severity === "high").length;
 
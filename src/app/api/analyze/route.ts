@@ -1,5 +1,5 @@
 import { NextRequest, NextResponse } from "next/server";
-import { analyzePR, PRContext, getDepthPrompt } from "@/lib/analysis";
+import { analyzePR, PRContext, getDepthPrompt } from "@/lib/review/engine";
 
 export const runtime = "edge";
 
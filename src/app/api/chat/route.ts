@@ -1,41 +1,28 @@
 import { NextRequest, NextResponse } from "next/server";
-import { chat } from "@/lib/llm";
-import { executeTool, toolDefinitions } from "@/lib/tools";
+import { chatStream, ChatMessage } from "@/lib/llm";
+import { executeTool, toolFunctionSpecs, toToolArgs } from "@/lib/tools";
+import { runToolLoop, ToolLoopEvent } from "@/lib/tools/agent";
 import { rateLimit } from "@/lib/rate-limit";
 import { logger } from "@/lib/logger";
+import { encodeSSE, sseResponse } from "@/lib/sse";
 
 const limiter = rateLimit({ maxRequests: 30, windowMs: 60000 });
 
-function parseToolCall(response: string): { tool: string; params: Record<string, string> } | null {
-  const match = response.match(/\[TOOL:([\w-]+)(?:\s+(.+?))?\]/);
-  if (!match) return null;
-  const params: Record<string, string> = {};
-  if (match[2]) {
-    for (const m of match[2].matchAll(/(\w+)="([^"]+)"/g)) {
-      if (m[1] && m[2]) params[m[1]] = m[2];
-    }
-  }
-  return { tool: match[1] || "", params };
-}
-
-const toolList = toolDefinitions.map(t => `${t.name}(${t.params.join(", ")})`).join(", ");
-
-const SYSTEM_PROMPT = `You are an AI assistant for FoodShare AI code review platform. Use tools to fetch real data.
-
-## Tool Syntax
-[TOOL:tool-name param1="value1" param2="value2"]
+const MAX_TOOL_STEPS = 5;
+const UUID_RE = /^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$/i;
 
-## Tools: ${toolList}
+const SYSTEM_PROMPT = `You are an AI assistant for FoodShare AI code review platform.
 
 ## Rules
-1. ALWAYS use tools for data - never fabricate
-2. Call ONE tool per response
-3. Be concise and helpful
+1. ALWAYS use the provided tools for data - never fabricate
+2. Call tools one after another when an answer needs several lookups
+3. Be concise and helpful`;
 
-## Examples
-"Show reviews" → [TOOL:reviews limit="5"]
-"Scan foodshare-ios" → [TOOL:trigger-scan repo="Foodshareclub/foodshare-ios"]
-"Stats" → [TOOL:stats]`;
+interface ChatContext {
+  correlationId: string;
+  conversationId: string;
+  ip: string;
+}
 
 export async function POST(request: NextRequest) {
   const correlationId = crypto.randomUUID();
@@ -48,39 +35,76 @@ export async function POST(request: NextRequest) {
       return NextResponse.json({ error: "Rate limit exceeded" }, { status: 429 });
     }
     
-    const { message, history = [] } = await request.json();
+    const { message, history = [], stream = false, conversationId: requested } = await request.json();
+    const conversationId: string = UUID_RE.test(requested || "") ? requested : crypto.randomUUID();
     if (!message?.trim()) {
       return NextResponse.json({ error: "Message required" }, { status: 400 });
     }
     
     if (message.trim().toLowerCase() === "/help") {
-      const result = await executeTool("help", {}, { correlationId });
+      const result = await executeTool("help", {}, { correlationId, conversationId });
       return NextResponse.json({ response: result.data || result.error });
     }
     
-    const conv = history.slice(-6).map((m: { role: string; content: string }) => 
-      `${m.role === "user" ? "User" : "Assistant"}: ${m.content}`
-    ).join("\n");
-    
-    const prompt = `${SYSTEM_PROMPT}\n\n${conv ? `Recent:\n${conv}\n\n` : ""}User: ${message}\nAssistant:`;
-    let response = await chat(prompt, { temperature: 0.2 });
-    
-    const toolCall = parseToolCall(response);
-    if (toolCall) {
-      const result = await executeTool(toolCall.tool, toolCall.params, { correlationId });
-      
-      if (result.success && result.data) {
-        const followUp = `${prompt} ${response}\n\nResult:\n${result.data}\n\nSummarize helpfully:`;
-        response = await chat(followUp, { temperature: 0.4 });
-      } else {
-        response = result.error || "Command failed";
-      }
-    }
-    
-    response = response.replace(/\[TOOL:[^\]]+\]/g, "").trim();
-    return NextResponse.json({ response, correlationId });
+    const messages: ChatMessage[] = [
+      { role: "system", content: SYSTEM_PROMPT },
+      ...history.slice(-6).map((m: { role: string; content: string }) => ({
+        role: m.role === "user" ? "user" : "assistant",
+        content: m.content,
+      })),
+      { role: "user", content: message },
+    ];
+
+    const ctx: ChatContext = { correlationId, conversationId, ip };
+    if (stream) return streamChat(messages, ctx);
+
+    const result = await runChat(messages, ctx);
+    return NextResponse.json({
+      response: result.response,
+      correlationId,
+      conversationId,
+      steps: result.steps,
+      usage: result.usage,
+      ...result.servedBy,
+    });
   } catch (error) {
     logger.error("Chat error", error instanceof Error ? error : new Error(String(error)), { correlationId });
     return NextResponse.json({ error: "Chat failed" }, { status: 500 });
   }
 }
+
+// Each tool call is audited with the conversation and step it belongs to
+function runChat(messages: ChatMessage[], ctx: ChatContext, onEvent?: (event: ToolLoopEvent) => void) {
+  return runToolLoop(messages, {
+    tools: toolFunctionSpecs(),
+    stream: chatStream,
+    execute: (call, step) => executeTool(call.name, toToolArgs(call.arguments), { ...ctx, step }),
+    maxSteps: MAX_TOOL_STEPS,
+    temperature: 0.2,
+    onEvent,
+  });
+}
+
+// SSE variant: token, tool_call, tool_result, done and error events
+function streamChat(messages: ChatMessage[], ctx: ChatContext): Response {
+  const encoder = new TextEncoder();
+
+  const body = new ReadableStream<Uint8Array>({
+    async start(controller) {
+      const send = (event: string, data: unknown) => controller.enqueue(encoder.encode(encodeSSE(event, data)));
+      const { correlationId, conversationId } = ctx;
+
+      try {
+        const result = await runChat(messages, ctx, ({ type, ...data }) => send(type, data));
+        send("done", { response: result.response, correlationId, conversationId, steps: result.steps, usage: result.usage, ...result.servedBy });
+      } catch (error) {
+        logger.error("Chat stream error", error instanceof Error ? error : new Error(String(error)), { correlationId });
+        send("error", { error: "Chat failed", correlationId });
+      } finally {
+        controller.close();
+      }
+    },
+  });
+
+  return sseResponse(body);
+}
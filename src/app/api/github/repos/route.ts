@@ -1,4 +1,5 @@
 import { NextResponse } from "next/server";
+import { isGitHubAppConfigured, listInstallationRepos } from "@/lib/github-app";
 
 const GITHUB_API = "https://api.github.com";
 
@@ -11,6 +12,24 @@ function getHeaders() {
 
 export async function GET() {
   try {
+    if (isGitHubAppConfigured()) {
+      const repos = (await listInstallationRepos()) as any[];
+      return NextResponse.json({
+        repos: repos
+          .filter((r) => !r.archived)
+          .map((r) => ({
+            id: r.id,
+            full_name: r.full_name,
+            name: r.name,
+            owner: r.owner.login,
+            private: r.private,
+            updated_at: r.updated_at,
+            installation_id: r.installation_id,
+          }))
+          .sort((a, b) => new Date(b.updated_at).getTime() - new Date(a.updated_at).getTime()),
+      });
+    }
+
     // Get user repos
     const userRes = await fetch(`${GITHUB_API}/user/repos?per_page=100&sort=updated`, {
       headers: getHeaders(),
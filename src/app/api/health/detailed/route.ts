@@ -1,6 +1,7 @@
 import { NextResponse } from 'next/server';
-import { createClient } from '@/lib/supabase/server';
+import { getStorage } from '@/lib/storage';
 import { logger } from '@/lib/logger';
+import { getAppInfo, isGitHubAppConfigured } from '@/lib/github-app';
 
 export async function GET() {
   try {
@@ -15,9 +16,8 @@ export async function GET() {
 
     // Database check
     try {
-      const supabase = await createClient();
-      const { error } = await supabase.from('repo_configs').select('id').limit(1);
-      checks.checks.database = error ? 'unhealthy' : 'healthy';
+      await getStorage().ping();
+      checks.checks.database = 'healthy';
     } catch {
       checks.checks.database = 'unhealthy';
     }
@@ -25,7 +25,10 @@ export async function GET() {
     // GitHub API check
     try {
       const token = process.env.GITHUB_TOKEN;
-      if (token) {
+      if (isGitHubAppConfigured()) {
+        await getAppInfo();
+        checks.checks.github = 'healthy';
+      } else if (token) {
         const res = await fetch('https://api.github.com/rate_limit', {
           headers: { Authorization: `Bearer ${token}` },
         });
@@ -0,0 +1,35 @@
+import { NextRequest } from "next/server";
+import { ok, err, handleError } from "@/lib/api";
+import { getAdmin } from "@/lib/auth/permissions";
+import { getDlqEntry, replayDlq } from "@/lib/dlq";
+
+// Inspect one entry, including every error the job hit
+export async function GET(_request: NextRequest, { params }: { params: Promise<{ id: string }> }) {
+  try {
+    const { id } = await params;
+    const entry = await getDlqEntry(id);
+    if (!entry) return err("DLQ entry not found", 404);
+    return ok({ entry });
+  } catch (error) {
+    return handleError(error);
+  }
+}
+
+// Replay this entry as a fresh job
+export async function POST(_request: NextRequest, { params }: { params: Promise<{ id: string }> }) {
+  try {
+    const admin = await getAdmin();
+    if (!admin) return err("Only admins can replay DLQ entries", 403);
+
+    const { id } = await params;
+    const entry = await getDlqEntry(id);
+    if (!entry) return err("DLQ entry not found", 404);
+
+    // An explicit replay may repeat an earlier one
+    const result = await replayDlq({ ids: [id], replayed: entry.replayed_at !== null }, admin.id);
+    if (!result.replayed.length) return err(result.skipped[0]?.reason || "Replay failed", 409);
+    return ok({ job_id: result.replayed[0]!.job_id });
+  } catch (error) {
+    return handleError(error);
+  }
+}
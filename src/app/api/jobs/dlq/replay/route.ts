@@ -0,0 +1,27 @@
+import { NextRequest } from "next/server";
+import { ok, err, handleError } from "@/lib/api";
+import { getAdmin } from "@/lib/auth/permissions";
+import { hasFilter, parseDlqFilter, replayDlq, type DlqFilter } from "@/lib/dlq";
+
+// Bulk replay entries not yet replayed that match a filter (ids, repo, pr, error, before, limit)
+export async function POST(request: NextRequest) {
+  try {
+    const admin = await getAdmin();
+    if (!admin) return err("Only admins can replay DLQ entries", 403);
+
+    const body = await request.json().catch(() => ({}));
+    let filter: DlqFilter;
+    try {
+      filter = parseDlqFilter(body);
+    } catch (e) {
+      return err(e instanceof Error ? e.message : "Invalid filter");
+    }
+    if (!hasFilter(filter) && body.all !== true) {
+      return err("Pass a filter, or all: true to replay every entry");
+    }
+
+    return ok(await replayDlq(filter, admin.id));
+  } catch (error) {
+    return handleError(error);
+  }
+}
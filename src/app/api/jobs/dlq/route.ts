@@ -0,0 +1,39 @@
+import { NextRequest } from "next/server";
+import { ok, err, handleError } from "@/lib/api";
+import { getAdmin } from "@/lib/auth/permissions";
+import { hasFilter, listDlq, parseDlqFilter, purgeDlq, type DlqFilter } from "@/lib/dlq";
+
+// List DLQ entries; filters: repo, pr, error, before, replayed, limit
+export async function GET(request: NextRequest) {
+  try {
+    let filter: DlqFilter;
+    try {
+      filter = parseDlqFilter(Object.fromEntries(request.nextUrl.searchParams));
+    } catch (e) {
+      return err(e instanceof Error ? e.message : "Invalid filter");
+    }
+    return ok(await listDlq(filter));
+  } catch (error) {
+    return handleError(error);
+  }
+}
+
+// Purge entries matching a filter (ids, repo, pr, error, before, replayed)
+export async function DELETE(request: NextRequest) {
+  try {
+    const admin = await getAdmin();
+    if (!admin) return err("Only admins can purge the dead letter queue", 403);
+
+    let filter: DlqFilter;
+    try {
+      filter = parseDlqFilter(await request.json().catch(() => ({})));
+    } catch (e) {
+      return err(e instanceof Error ? e.message : "Invalid filter");
+    }
+    if (!hasFilter(filter)) return err("Purge requires a filter (ids, repo, pr, error, before or replayed)");
+
+    return ok({ purged: await purgeDlq(filter, admin.id) });
+  } catch (error) {
+    return handleError(error);
+  }
+}
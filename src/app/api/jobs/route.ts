@@ -1,18 +1,14 @@
 import { getJobStats } from "@/lib/queue";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
 import { ok, handleError } from "@/lib/api";
 
 export async function GET() {
   try {
-    const [stats, supabase] = await Promise.all([getJobStats(), createClient()]);
-    const { data: recentFailed } = await supabase
-      .from("review_jobs")
-      .select("id, repo_full_name, pr_number, error, attempts, updated_at")
-      .eq("status", "failed")
-      .order("updated_at", { ascending: false })
-      .limit(5);
+    const [stats, failed] = await Promise.all([getJobStats(), getStorage().jobs.list({ status: "failed", limit: 5 })]);
+    const recentFailed = failed.map(({ id, repo_full_name, pr_number, error, attempts, updated_at }) =>
+      ({ id, repo_full_name, pr_number, error, attempts, updated_at }));
 
-    return ok({ stats, recent_failed: recentFailed || [] });
+    return ok({ stats, recent_failed: recentFailed });
   } catch (error) {
     return handleError(error);
   }
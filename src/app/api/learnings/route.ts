@@ -1,18 +1,20 @@
 import { NextRequest, NextResponse } from "next/server";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
+
+// Supabase errors are plain objects with a message, not Error instances
+const errorMessage = (error: unknown) => (error as { message?: string })?.message || String(error);
 
 export async function GET(request: NextRequest) {
   const { searchParams } = new URL(request.url);
   const repo = searchParams.get("repo");
+  const status = searchParams.get("status");
 
-  const supabase = await createClient();
-  let query = supabase.from("review_learnings").select("*").order("created_at", { ascending: false });
-
-  if (repo) query = query.eq("repo_full_name", repo);
-
-  const { data, error } = await query.limit(100);
-  if (error) return NextResponse.json({ error: error.message }, { status: 500 });
-  return NextResponse.json(data);
+  try {
+    const data = await getStorage().learnings.list({ repo: repo || undefined, status: status || undefined, limit: 100 });
+    return NextResponse.json(data);
+  } catch (error) {
+    return NextResponse.json({ error: errorMessage(error) }, { status: 500 });
+  }
 }
 
 export async function POST(request: NextRequest) {
@@ -22,15 +24,29 @@ export async function POST(request: NextRequest) {
     return NextResponse.json({ error: "repo_full_name, pattern, learning required" }, { status: 400 });
   }
 
-  const supabase = await createClient();
-  const { data, error } = await supabase
-    .from("review_learnings")
-    .insert({ repo_full_name, pattern, learning, category })
-    .select()
-    .single();
+  try {
+    const data = await getStorage().learnings.create({ repo_full_name, pattern, learning, category });
+    return NextResponse.json(data);
+  } catch (error) {
+    return NextResponse.json({ error: errorMessage(error) }, { status: 500 });
+  }
+}
+
+// Accept or reject learnings proposed from review feedback
+export async function PATCH(request: NextRequest) {
+  const { id, status, learning } = await request.json();
+
+  if (!id || !["active", "rejected"].includes(status)) {
+    return NextResponse.json({ error: "id and status (active|rejected) required" }, { status: 400 });
+  }
 
-  if (error) return NextResponse.json({ error: error.message }, { status: 500 });
-  return NextResponse.json(data);
+  try {
+    const data = await getStorage().learnings.update(id, { status, ...(learning && { learning }) });
+    if (!data) return NextResponse.json({ error: "Learning not found" }, { status: 404 });
+    return NextResponse.json(data);
+  } catch (error) {
+    return NextResponse.json({ error: errorMessage(error) }, { status: 500 });
+  }
 }
 
 export async function DELETE(request: NextRequest) {
@@ -39,9 +55,10 @@ export async function DELETE(request: NextRequest) {
 
   if (!id) return NextResponse.json({ error: "id required" }, { status: 400 });
 
-  const supabase = await createClient();
-  const { error } = await supabase.from("review_learnings").delete().eq("id", id);
-
-  if (error) return NextResponse.json({ error: error.message }, { status: 500 });
-  return NextResponse.json({ success: true });
+  try {
+    await getStorage().learnings.remove(id);
+    return NextResponse.json({ success: true });
+  } catch (error) {
+    return NextResponse.json({ error: errorMessage(error) }, { status: 500 });
+  }
 }
@@ -1,6 +1,6 @@
 import { NextRequest, NextResponse } from "next/server";
 import { metrics } from "@/lib/metrics";
-import { githubCircuitBreaker, llmCircuitBreaker } from "@/lib/circuit-breaker";
+import { githubCircuitBreaker, getCircuitBreakerStates } from "@/lib/circuit-breaker";
 
 export async function GET(request: NextRequest) {
   const format = request.nextUrl.searchParams.get("format");
@@ -9,7 +9,7 @@ export async function GET(request: NextRequest) {
     ...metrics.getMetrics(),
     circuitBreakers: {
       github: githubCircuitBreaker.getState(),
-      llm: llmCircuitBreaker.getState(),
+      llm: getCircuitBreakerStates("llm:"),
     },
     timestamp: new Date().toISOString(),
   };
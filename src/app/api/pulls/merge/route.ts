@@ -1,7 +1,11 @@
-import { NextRequest } from "next/server";
+import { NextRequest, NextResponse } from "next/server";
 import { ok, err, handleError, v, validate } from "@/lib/api";
 import { pr } from "@/lib/github";
 import { upsertPullRequest, type GitHubPullRequest } from "@/lib/pr-store";
+import { getStorage } from "@/lib/storage";
+import { evaluatePolicy, openFindings } from "@/lib/review/engine";
+import { getAdmin } from "@/lib/auth/permissions";
+import { audit } from "@/lib/audit";
 
 interface MergeInput {
   owner: string;
@@ -10,29 +14,89 @@ interface MergeInput {
   merge_method?: "merge" | "squash" | "rebase";
   commit_title?: string;
   commit_message?: string;
+  override?: boolean;
+  override_reason?: string;
+}
+
+interface MergeBlock {
+  reason: "unreviewed" | "stale" | "policy";
+  message: string;
+  status: number;
+  violations?: string[];
+}
+
+/**
+ * Re-check the latest review's open findings against the repo's severity
+ * policy, if it has one. An unreviewed PR, or one whose review is of an older
+ * head, is blocked too: nothing vouches for its current commits. Every block
+ * can be overridden by an admin.
+ */
+async function mergeGate(owner: string, repo: string, prNumber: number): Promise<{ block: MergeBlock | null; headSha: string }> {
+  const fullName = `${owner}/${repo}`;
+  const { history, configs } = getStorage();
+  const [latest, config, current] = await Promise.all([
+    history.latest(fullName, prNumber),
+    configs.get(fullName),
+    pr.get(owner, repo, prNumber) as Promise<GitHubPullRequest>,
+  ]);
+  const headSha = current.head.sha;
+
+  if (!latest?.result) {
+    return { headSha, block: { reason: "unreviewed", status: 409, message: "This pull request has not been reviewed yet" } };
+  }
+  if (latest.head_sha !== headSha) {
+    return { headSha, block: { reason: "stale", status: 409, message: "New commits since the last review: wait for the review of the current head before merging" } };
+  }
+  const policy = config?.severity_policy ? evaluatePolicy(openFindings(latest.result), config.severity_policy) : null;
+  if (policy && !policy.passed) {
+    return {
+      headSha,
+      block: { reason: "policy", status: 403, message: `Merge blocked by review policy: ${policy.violations.join(", ")}`, violations: policy.violations },
+    };
+  }
+  return { headSha, block: null };
 }
 
 export async function POST(request: NextRequest) {
   try {
     const body = await request.json();
-    const { owner, repo, pr_number, merge_method = "squash", commit_title, commit_message } = validate<MergeInput>(body, {
+    const { owner, repo, pr_number, merge_method = "squash", commit_title, commit_message, override, override_reason } = validate<MergeInput>(body, {
       owner: v.slug,
       repo: v.slug,
       pr_number: v.posInt,
       merge_method: v.oneOf("merge", "squash", "rebase"),
       commit_title: v.optString,
       commit_message: v.optString,
+      override: v.optBool,
+      override_reason: v.optString,
     });
+    const repoFullName = `${owner}/${repo}`;
+
+    const { block, headSha } = await mergeGate(owner, repo, pr_number);
+    if (block) {
+      // gate tells the dashboard an admin can override this
+      if (!override) return NextResponse.json({ error: block.message, gate: block.reason }, { status: block.status });
+      const admin = await getAdmin();
+      if (!admin) return err("Only admins can override the review gate", 403);
+      await audit({
+        action: "merge.policy_override",
+        actor_id: admin.id,
+        resource_type: "pull_request",
+        resource_id: `${repoFullName}#${pr_number}`,
+        metadata: { gate: block.reason, violations: block.violations || [], head_sha: headSha, reason: override_reason },
+      });
+    }
 
     // Merge the PR via GitHub API
+    // Pinned to the head the gate checked, so a push after the check can't slip through
     const result = await pr.merge(owner, repo, pr_number, {
       merge_method,
       commit_title,
       commit_message,
+      sha: headSha,
     });
 
     // Fetch updated PR data and sync to database
-    const repoFullName = `${owner}/${repo}`;
     try {
       const updatedPR = await pr.get(owner, repo, pr_number) as GitHubPullRequest;
       await upsertPullRequest({ pr: updatedPR, repoFullName });
@@ -1,5 +1,5 @@
 import { NextRequest } from "next/server";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
 import { ok, err, handleError } from "@/lib/api";
 import { syncRepoPRs } from "@/lib/pr-store";
 
@@ -14,17 +14,10 @@ export async function POST(request: NextRequest) {
     const body = await request.json().catch(() => ({}));
     const { state = "open" } = body as { state?: "open" | "closed" | "all" };
 
-    const supabase = await createClient();
-
     // Fetch all enabled repos from repo_configs
-    const { data: configs, error: configError } = await supabase
-      .from("repo_configs")
-      .select("full_name")
-      .eq("enabled", true);
-
-    if (configError) throw configError;
+    const configs = (await getStorage().configs.list()).filter((c) => c.enabled);
 
-    if (!configs || configs.length === 0) {
+    if (configs.length === 0) {
       return err("No enabled repos found. Configure repos in the dashboard first.");
     }
 
@@ -1,34 +1,27 @@
 import { NextRequest, NextResponse } from "next/server";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
+
+// Supabase errors are plain objects with a message, not Error instances
+const errorMessage = (error: unknown) => (error as { message?: string })?.message || String(error);
 
 export async function PATCH(request: NextRequest, { params }: { params: Promise<{ id: string }> }) {
   const { id } = await params;
   const body = await request.json();
-  const supabase = await createClient();
-
-  const { data, error } = await supabase
-    .from("repo_configs")
-    .update({ ...body, updated_at: new Date().toISOString() })
-    .eq("id", id)
-    .select()
-    .single();
-
-  if (error) {
-    return NextResponse.json({ error: error.message }, { status: 500 });
+  try {
+    const [config] = await getStorage().configs.update({ id }, body);
+    if (!config) return NextResponse.json({ error: "Config not found" }, { status: 404 });
+    return NextResponse.json({ config });
+  } catch (error) {
+    return NextResponse.json({ error: errorMessage(error) }, { status: 500 });
   }
-
-  return NextResponse.json({ config: data });
 }
 
 export async function DELETE(_request: NextRequest, { params }: { params: Promise<{ id: string }> }) {
   const { id } = await params;
-  const supabase = await createClient();
-
-  const { error } = await supabase.from("repo_configs").delete().eq("id", id);
-
-  if (error) {
-    return NextResponse.json({ error: error.message }, { status: 500 });
+  try {
+    await getStorage().configs.remove({ id });
+    return NextResponse.json({ success: true });
+  } catch (error) {
+    return NextResponse.json({ error: errorMessage(error) }, { status: 500 });
   }
-
-  return NextResponse.json({ success: true });
 }
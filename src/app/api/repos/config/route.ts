@@ -1,13 +1,13 @@
 import { NextRequest } from "next/server";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
 import { ok, err, handleError } from "@/lib/api";
 import { syncRepoPRs } from "@/lib/pr-store";
+import { parsePolicy } from "@/lib/review/engine";
 
 export async function GET() {
   try {
-    const supabase = await createClient();
-    const { data, error } = await supabase.from("repo_configs").select("*").order("updated_at", { ascending: false });
-    if (error) throw error;
+    const data = await getStorage().configs.list();
+    data.sort((a, b) => (b.updated_at || "").localeCompare(a.updated_at || ""));
     return ok({ configs: data });
   } catch (error) {
     return handleError(error);
@@ -16,27 +16,45 @@ export async function GET() {
 
 export async function POST(request: NextRequest) {
   try {
-    const { full_name, enabled = true, auto_review = true, categories, ignore_paths, custom_instructions, skip_initial_sync = false } = await request.json();
+    const {
+      full_name, enabled = true, auto_review = true, categories, ignore_paths, custom_instructions, skip_initial_sync = false,
+      monthly_token_budget, monthly_cost_budget, over_budget_action, severity_policy, max_concurrent_reviews,
+    } = await request.json();
     if (!full_name?.includes("/")) return err("Invalid repo format (expected owner/repo)");
+    if (over_budget_action !== undefined && !["downgrade", "skip"].includes(over_budget_action)) {
+      return err("over_budget_action must be downgrade or skip");
+    }
+    if (max_concurrent_reviews != null && !(Number.isInteger(max_concurrent_reviews) && max_concurrent_reviews > 0)) {
+      return err("max_concurrent_reviews must be a positive integer");
+    }
+
+    let policy;
+    try {
+      policy = severity_policy == null ? severity_policy : parsePolicy(severity_policy);
+    } catch (e) {
+      return err(e instanceof Error ? e.message : "Invalid severity_policy");
+    }
+
+    // Budgets, policy and the concurrency cap are only changed when sent; null clears them
+    const budget = {
+      ...(monthly_token_budget !== undefined && { monthly_token_budget }),
+      ...(monthly_cost_budget !== undefined && { monthly_cost_budget }),
+      ...(over_budget_action !== undefined && { over_budget_action }),
+      ...(policy !== undefined && { severity_policy: policy }),
+      ...(max_concurrent_reviews !== undefined && { max_concurrent_reviews }),
+    };
 
-    const supabase = await createClient();
+    const { configs } = getStorage();
 
     // Check if this is a new config (not just an update)
-    const { data: existing } = await supabase
-      .from("repo_configs")
-      .select("id, enabled")
-      .eq("full_name", full_name)
-      .single();
+    const existing = await configs.get(full_name);
 
     const isNewOrNewlyEnabled = !existing || (!existing.enabled && enabled);
 
-    const { data, error } = await supabase
-      .from("repo_configs")
-      .upsert({ full_name, enabled, auto_review, categories: categories || ["security", "bug", "performance"], ignore_paths, custom_instructions, updated_at: new Date().toISOString() }, { onConflict: "full_name" })
-      .select()
-      .single();
-
-    if (error) throw error;
+    const [data] = await configs.upsert([
+      // A choice made here overrides any automatic disable
+      { full_name, enabled, disabled_reason: null, auto_review, categories: categories || ["security", "bug", "performance"], ignore_paths, custom_instructions, ...budget },
+    ]);
 
     // Trigger initial PR sync for newly enabled repos
     let syncResult = null;
@@ -67,9 +85,7 @@ export async function DELETE(request: NextRequest) {
     const full_name = new URL(request.url).searchParams.get("full_name");
     if (!full_name) return err("full_name required");
 
-    const supabase = await createClient();
-    const { error } = await supabase.from("repo_configs").delete().eq("full_name", full_name);
-    if (error) throw error;
+    await getStorage().configs.remove({ full_name });
     return ok({ success: true });
   } catch (error) {
     return handleError(error);
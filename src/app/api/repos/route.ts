@@ -1,11 +1,13 @@
 import { NextRequest } from "next/server";
 import { repos } from "@/lib/github";
 import { ok, handleError } from "@/lib/api";
+import { isGitHubAppConfigured } from "@/lib/github-app";
 
 export async function GET(request: NextRequest) {
   try {
     const org = new URL(request.url).searchParams.get("org");
-    const data = org ? await repos.org(org) : await repos.user();
+    // An app has no user; list what its installations can see instead
+    const data = org ? await repos.org(org) : isGitHubAppConfigured() ? await repos.installation() : await repos.user();
     return ok(data);
   } catch (error) {
     return handleError(error);
@@ -1,6 +1,7 @@
 import { NextRequest, NextResponse } from "next/server";
 import { reviewPullRequest } from "@/lib/review";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
+import { usageColumns } from "@/lib/budget";
 
 export async function POST(request: NextRequest) {
   try {
@@ -18,14 +19,14 @@ export async function POST(request: NextRequest) {
     const results = await Promise.allSettled(
       reviews.map(async (r: { owner: string; repo: string; pr_number: number }) => {
         const review = await reviewPullRequest(r.owner, r.repo, r.pr_number);
-        const supabase = await createClient();
-        await supabase.from("review_history").insert({
+        await getStorage().history.insert({
           repo_full_name: `${r.owner}/${r.repo}`,
           pr_number: r.pr_number,
           status: "completed",
           result: review,
           head_sha: review.headSha,
           is_incremental: review.isIncremental,
+          ...usageColumns(review.usage),
         });
         return { ...r, success: true, issues: review.line_comments?.length || 0 };
       })
@@ -1,7 +1,8 @@
 import { NextRequest } from "next/server";
 import { reviewPullRequest, reviewAndPost } from "@/lib/review";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
 import { ok, handleError, validate, v } from "@/lib/api";
+import { usageColumns } from "@/lib/budget";
 
 interface ReviewInput {
   owner: string;
@@ -29,29 +30,31 @@ export async function POST(request: NextRequest) {
     const fullName = `${owner}/${repo}`;
     const options = depth || focus_areas ? { depth, focus_areas } : undefined;
 
-    const supabase = await createClient();
+    const { history } = getStorage();
 
     if (post) {
       const result = await reviewAndPost(owner, repo, pr_number, undefined, options);
-      await supabase.from("review_history").insert({
+      await history.insert({
         repo_full_name: fullName,
         pr_number,
         status: "completed",
         result: { ...result.review, _analysis: result.analysis },
         head_sha: result.headSha,
         is_incremental: result.isIncremental,
+        ...usageColumns(result.review.usage),
       });
       return ok(result);
     }
 
     const review = await reviewPullRequest(owner, repo, pr_number, undefined, undefined, undefined, options);
-    await supabase.from("review_history").insert({
+    await history.insert({
       repo_full_name: fullName,
       pr_number,
       status: "completed",
       result: review,
       head_sha: review.headSha,
       is_incremental: review.isIncremental,
+      ...usageColumns(review.usage),
     });
     return ok(review);
   } catch (error) {
@@ -1,5 +1,5 @@
 import { NextRequest } from "next/server";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
 import { ok, handleError } from "@/lib/api";
 
 export async function GET(request: NextRequest) {
@@ -9,13 +9,7 @@ export async function GET(request: NextRequest) {
     const repo = searchParams.get("repo");
     const pr = searchParams.get("pr");
 
-    const supabase = await createClient();
-    let query = supabase.from("review_history").select("*").order("created_at", { ascending: false }).limit(limit);
-    if (repo) query = query.eq("repo_full_name", repo);
-    if (pr) query = query.eq("pr_number", parseInt(pr));
-
-    const { data, error } = await query;
-    if (error) throw error;
+    const data = await getStorage().history.list({ repo: repo || undefined, pr: pr ? parseInt(pr) : undefined, limit });
     return ok({ reviews: data });
   } catch (error) {
     return handleError(error);
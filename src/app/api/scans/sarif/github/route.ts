@@ -0,0 +1,33 @@
+import { NextRequest } from "next/server";
+import { err, handleError, ok, v, validate } from "@/lib/api";
+import { getAdmin } from "@/lib/auth/permissions";
+import { findScan, isRepoName, uploadScanToGitHub } from "@/lib/sarif";
+
+interface UploadInput {
+  id?: string;
+  repo?: string;
+  branch?: string;
+}
+
+// Push a scan (by id, or the latest for a repo) to GitHub code scanning
+export async function POST(request: NextRequest) {
+  try {
+    const admin = await getAdmin();
+    if (!admin) return err("Only admins can upload scans to GitHub", 403);
+
+    const { id, repo, branch } = validate<UploadInput>(await request.json(), {
+      id: v.optString,
+      repo: (value) => value === undefined || isRepoName(value),
+      branch: (value) => value === undefined || (typeof value === "string" && /^[\w./-]+$/.test(value)),
+    });
+    if (!id && !repo) return err("id or repo required");
+
+    const scan = await findScan({ id, repo });
+    if (!scan) return err("Scan not found", 404);
+
+    const result = await uploadScanToGitHub(scan, branch, admin.id);
+    return ok({ scan_id: scan.id, repo: scan.repo_full_name, ...result });
+  } catch (error) {
+    return handleError(error);
+  }
+}
@@ -0,0 +1,72 @@
+import { NextRequest, NextResponse } from "next/server";
+import { ApiError, err, handleError, ok } from "@/lib/api";
+import { getAdmin } from "@/lib/auth/permissions";
+import { findScan, importSarif, isRepoName, MAX_SARIF_BYTES, scanSarif } from "@/lib/sarif";
+import { assertSarif } from "@/lib/review/engine";
+
+// Export a scan (?id=, or the latest for ?repo=owner/repo) as SARIF 2.1.0
+export async function GET(request: NextRequest) {
+  try {
+    const { searchParams } = new URL(request.url);
+    const id = searchParams.get("id");
+    const repo = searchParams.get("repo");
+    if (!id && !isRepoName(repo)) return err("id or repo (owner/repo) required");
+
+    const scan = await findScan({ id, repo });
+    if (!scan) return err("Scan not found", 404);
+
+    const filename = `${scan.repo_full_name.replace("/", "-")}-${scan.created_at.slice(0, 10)}.sarif`;
+    return new NextResponse(JSON.stringify(scanSarif(scan), null, 2), {
+      headers: {
+        "Content-Type": "application/sarif+json",
+        "Content-Disposition": `attachment; filename="${filename}"`,
+      },
+    });
+  } catch (error) {
+    return handleError(error);
+  }
+}
+
+// Import a SARIF log from another tool: the raw JSON body, or a multipart form with a `file` field.
+// The repo comes from ?repo=, a `repo` form field, or the log's versionControlProvenance.
+export async function POST(request: NextRequest) {
+  try {
+    const admin = await getAdmin();
+    if (!admin) return err("Only admins can import scans", 403);
+
+    if (Number(request.headers.get("content-length") || 0) > MAX_SARIF_BYTES) return err("SARIF log too large", 413);
+
+    let repo = new URL(request.url).searchParams.get("repo");
+    let text: string;
+    if (request.headers.get("content-type")?.includes("multipart/form-data")) {
+      const form = await request.formData();
+      const file = form.get("file");
+      if (!file || typeof file === "string") return err("file required");
+      text = await file.text();
+      repo = repo || (form.get("repo") as string | null) || null;
+    } else {
+      text = await request.text();
+    }
+    if (text.length > MAX_SARIF_BYTES) return err("SARIF log too large", 413);
+    if (repo && !isRepoName(repo)) return err("Invalid repo");
+
+    let log: unknown;
+    try {
+      log = JSON.parse(text);
+      assertSarif(log);
+    } catch (e) {
+      throw new ApiError(400, e instanceof SyntaxError ? "Invalid JSON" : "Not a SARIF 2.1.0 log");
+    }
+
+    let scan;
+    try {
+      scan = await importSarif(log, repo, admin.id);
+    } catch (e) {
+      if (e instanceof Error && e.message.startsWith("Repository")) throw new ApiError(400, e.message);
+      throw e;
+    }
+    return ok({ scan: { id: scan.id, repo_full_name: scan.repo_full_name, security_score: scan.security_score, summary: scan.summary } });
+  } catch (error) {
+    return handleError(error);
+  }
+}
@@ -1,7 +1,8 @@
 import { NextRequest, NextResponse } from "next/server";
 import { reviewAndPost } from "@/lib/review";
-import { createClient } from "@/lib/supabase/server";
-import { ReviewCategory } from "@/lib/review/models";
+import { getStorage } from "@/lib/storage";
+import { ReviewCategory } from "@/lib/review/engine";
+import { usageColumns } from "@/lib/budget";
 
 export async function POST(request: NextRequest) {
   try {
@@ -12,13 +13,9 @@ export async function POST(request: NextRequest) {
     }
 
     const fullName = `${owner}/${repo}`;
-    const supabase = await createClient();
+    const { configs, history } = getStorage();
 
-    const { data: config } = await supabase
-      .from("repo_configs")
-      .select("categories")
-      .eq("full_name", fullName)
-      .single();
+    const config = await configs.get(fullName).catch(() => null);
 
     const categories = (config?.categories || ["security", "bug", "performance"]).map(
       (c: string) => c as ReviewCategory
@@ -26,13 +23,14 @@ export async function POST(request: NextRequest) {
 
     const { review, headSha, isIncremental } = await reviewAndPost(owner, repo, pr_number, categories);
 
-    await supabase.from("review_history").insert({
+    await history.insert({
       repo_full_name: fullName,
       pr_number,
       status: "completed",
       result: JSON.stringify(review),
       head_sha: headSha,
       is_incremental: isIncremental,
+      ...usageColumns(review.usage),
     });
 
     return NextResponse.json({ success: true, review, isIncremental });
@@ -2,6 +2,9 @@ import { NextRequest, NextResponse } from "next/server";
 import { createHmac } from "crypto";
 import { chat } from "@/lib/llm";
 import { createReviewComment } from "@/lib/github";
+import { classifyReply } from "@/lib/review/engine";
+import { recordReplyFeedback, refreshRepoFeedback } from "@/lib/feedback-store";
+import { rememberInstallation } from "@/lib/github-app";
 
 const WEBHOOK_SECRET = process.env.GITHUB_WEBHOOK_SECRET;
 
@@ -12,6 +15,17 @@ function verifySignature(payload: string, signature: string | null): boolean {
   return signature === expected;
 }
 
+// With a token instead of an app, the bot comments as this user
+const BOT_LOGIN = process.env.GITHUB_BOT_LOGIN;
+
+function isBotComment(comment: { user?: { type?: string; login?: string } }): boolean {
+  return comment.user?.type === "Bot" || (!!BOT_LOGIN && comment.user?.login === BOT_LOGIN);
+}
+
+function mentionsBot(body: string): boolean {
+  return body.includes("@foodshare-ai") || body.toLowerCase().startsWith("@ai");
+}
+
 const CHAT_PROMPT = `You are an AI code reviewer assistant. A developer is replying to your review comment.
 Be helpful, concise, and provide code examples when relevant.
 
@@ -39,16 +53,35 @@ export async function POST(request: NextRequest) {
     }
 
     const body = JSON.parse(payload);
+    if (body.installation?.id && body.repository?.full_name) {
+      await rememberInstallation(body.repository.full_name, body.installation.id);
+    }
     
     if (body.action !== "created") {
       return NextResponse.json({ message: "Ignored action" });
     }
 
     const comment = body.comment;
-    const isReplyToBot = comment.in_reply_to_id && 
-      (comment.body.includes("@foodshare-ai") || comment.body.toLowerCase().startsWith("@ai"));
+    if (isBotComment(comment)) {
+      return NextResponse.json({ message: "Ignored bot comment" });
+    }
+    const mention = mentionsBot(comment.body || "");
+
+    // "resolved" / "won't fix" replies on bot comments feed back into learnings;
+    // questions for the bot are answered below instead
+    if (event === "pull_request_review_comment" && comment.in_reply_to_id && !mention) {
+      const signal = classifyReply(comment.body);
+      if (signal) {
+        try {
+          const recorded = await recordReplyFeedback(body.repository.full_name, comment.in_reply_to_id, signal, comment.body);
+          if (recorded) await refreshRepoFeedback(body.repository.full_name);
+        } catch (err) {
+          console.error("Failed to record reply feedback:", err);
+        }
+      }
+    }
 
-    if (!isReplyToBot) {
+    if (!comment.in_reply_to_id || !mention) {
       return NextResponse.json({ message: "Not a reply to bot" });
     }
 
@@ -1,11 +1,15 @@
 import { NextRequest, NextResponse } from "next/server";
 import { createHmac } from "crypto";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
 import { getPullRequestFiles, pr as githubPR } from "@/lib/github";
-import { analyzePR, PRContext } from "@/lib/analysis";
+import { analyzePR, PRContext } from "@/lib/review/engine";
 import { enqueueReview } from "@/lib/queue";
+import { getEmbeddedWorker } from "@/lib/worker";
 import { upsertPullRequest, type GitHubPullRequest } from "@/lib/pr-store";
+import { recordDismissedReview, recordResolvedThread, syncReactions, refreshRepoFeedback } from "@/lib/feedback-store";
 import type { PRData } from "@/lib/llm-detection";
+import { rememberInstallation } from "@/lib/github-app";
+import { applyRepoEvent, REPO_LIFECYCLE_EVENTS } from "@/lib/repo-sync";
 
 const WEBHOOK_SECRET = process.env.GITHUB_WEBHOOK_SECRET;
 
@@ -17,6 +21,13 @@ function verifySignature(payload: string, signature: string | null): boolean {
 }
 
 async function triggerWorker() {
+  // The embedded worker polls anyway; waking it just skips the wait
+  const embedded = getEmbeddedWorker();
+  if (embedded) {
+    embedded.wake();
+    return;
+  }
+
   // Trigger Supabase edge function to process queue
   const supabaseUrl = process.env.NEXT_PUBLIC_SUPABASE_URL;
   const cronSecret = process.env.CRON_SECRET;
@@ -31,6 +42,32 @@ async function triggerWorker() {
   }
 }
 
+interface FeedbackEventPayload {
+  action: string;
+  repository: { full_name: string };
+  review?: { id: number; body?: string | null };
+  thread?: { comments?: { id: number }[] };
+}
+
+async function handleFeedbackEvent(event: string, body: FeedbackEventPayload) {
+  const fullName = body.repository.full_name;
+  let recorded = 0;
+
+  try {
+    if (event === "pull_request_review" && body.action === "dismissed" && body.review) {
+      recorded = await recordDismissedReview(fullName, body.review.id, body.review.body || undefined);
+    } else if (event === "pull_request_review_thread" && body.action === "resolved") {
+      const ids = (body.thread?.comments || []).map((c) => c.id);
+      recorded = await recordResolvedThread(fullName, ids);
+    }
+    if (recorded) await refreshRepoFeedback(fullName);
+  } catch (err) {
+    console.error("Failed to record review feedback:", err);
+  }
+
+  return NextResponse.json({ message: recorded ? "Feedback recorded" : "Ignored action", recorded });
+}
+
 export async function POST(request: NextRequest) {
   try {
     const payload = await request.text();
@@ -47,6 +84,20 @@ export async function POST(request: NextRequest) {
 
     const body = JSON.parse(payload);
 
+    if (event && REPO_LIFECYCLE_EVENTS.includes(event)) {
+      const changes = await applyRepoEvent(event, body);
+      return NextResponse.json({ message: changes.length ? "Repos updated" : `Ignored action: ${body.action}`, changes });
+    }
+
+    // App webhooks say which installation covers the repo; later API calls use its token
+    if (body.installation?.id && body.repository?.full_name) {
+      await rememberInstallation(body.repository.full_name, body.installation.id);
+    }
+
+    if (event === "pull_request_review" || event === "pull_request_review_thread") {
+      return handleFeedbackEvent(event, body);
+    }
+
     if (event !== "pull_request") return NextResponse.json({ message: "Ignored event" });
 
     const pr = body.pull_request;
@@ -76,16 +127,22 @@ export async function POST(request: NextRequest) {
       }
     }
 
+    // Reactions don't fire webhooks, so pick them up when the PR moves on
+    if (["synchronize", "closed"].includes(body.action)) {
+      try {
+        if (await syncReactions(repo.owner.login, repo.name, pr.number)) {
+          await refreshRepoFeedback(fullName);
+        }
+      } catch (err) {
+        console.error("Failed to sync reactions:", err);
+      }
+    }
+
     if (!["opened", "synchronize"].includes(body.action)) {
       return NextResponse.json({ message: `Ignored action: ${body.action}` });
     }
 
-    const supabase = await createClient();
-    const { data: config } = await supabase
-      .from("repo_configs")
-      .select("enabled, auto_review")
-      .eq("full_name", fullName)
-      .single();
+    const config = await getStorage().configs.get(fullName).catch(() => null);
 
     if (!config?.enabled || !config?.auto_review) {
       return NextResponse.json({ message: "Auto-review disabled" });
@@ -116,7 +173,7 @@ export async function POST(request: NextRequest) {
 
     // Enqueue instead of direct processing
     try {
-      const job = await enqueueReview(repo.owner.login, repo.name, pr.number, analysis);
+      const job = await enqueueReview(repo.owner.login, repo.name, pr.number, analysis, pr.head?.sha);
       
       // Trigger worker asynchronously
       triggerWorker();
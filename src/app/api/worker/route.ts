@@ -1,63 +1,56 @@
 import { NextRequest, NextResponse } from "next/server";
-import { claimJob, completeJob, failJob } from "@/lib/queue";
-import { reviewAndPost } from "@/lib/review";
-import { notifyReviewFailed, notifyReviewCompleted } from "@/lib/notify";
-import { createClient } from "@/lib/supabase/server";
-import { ReviewCategory } from "@/lib/review/models";
+import { claimJob } from "@/lib/queue";
+import { withServiceRole } from "@/lib/storage";
+import { getEmbeddedWorker, processJob } from "@/lib/worker";
+
+function authorized(request: NextRequest): boolean {
+  const cronSecret = process.env.CRON_SECRET;
+  return !cronSecret || request.headers.get("authorization") === `Bearer ${cronSecret}`;
+}
 
 // Cron or manual trigger to process queued jobs
 export async function POST(request: NextRequest) {
-  const authHeader = request.headers.get("authorization");
-  const cronSecret = process.env.CRON_SECRET;
-  if (cronSecret && authHeader !== `Bearer ${cronSecret}`) {
+  if (!authorized(request)) {
     return NextResponse.json({ error: "Unauthorized" }, { status: 401 });
   }
 
+  // With the embedded worker running, a trigger only needs to wake it
+  const embedded = getEmbeddedWorker();
+  if (embedded) {
+    embedded.wake();
+    return NextResponse.json({ embedded: true, ...embedded.status() });
+  }
+
   const processed: string[] = [];
+  const skipped: string[] = [];
   const errors: string[] = [];
   const maxJobs = 5;
   const startTime = Date.now();
   const timeout = 55000; // 55s to stay under Vercel's 60s limit
 
-  let job = await claimJob();
-
-  while (job && processed.length + errors.length < maxJobs && Date.now() - startTime < timeout) {
-    const jobKey = `${job.repo_full_name}#${job.pr_number}`;
-    try {
-      const supabase = await createClient();
-      const { data: config } = await supabase
-        .from("repo_configs")
-        .select("categories")
-        .eq("full_name", job.repo_full_name)
-        .single();
-
-      const categories = (config?.categories || ["security", "bug", "performance"]).map((c: string) => c as ReviewCategory);
-      const analysis = job.analysis as { depth?: string; focus_areas?: string[] } | undefined;
-      const options = analysis ? { depth: analysis.depth as "quick" | "standard" | "deep", focus_areas: analysis.focus_areas } : undefined;
-
-      const result = await reviewAndPost(job.owner, job.repo, job.pr_number, categories, options);
-
-      await supabase.from("review_history").insert({
-        repo_full_name: job.repo_full_name,
-        pr_number: job.pr_number,
-        status: "completed",
-        result: { ...result.review, _analysis: job.analysis },
-        head_sha: result.headSha,
-        is_incremental: result.isIncremental,
-      });
-
-      await completeJob(job.id);
-      await notifyReviewCompleted(job.repo_full_name, job.pr_number, result.review.line_comments?.length || 0);
-      processed.push(jobKey);
-    } catch (err) {
-      const error = err instanceof Error ? err.message : "Unknown error";
-      const permanentlyFailed = await failJob(job.id, error, job.attempts + 1, job.max_attempts);
-      await notifyReviewFailed(job.repo_full_name, job.pr_number, error, job.attempts + 1, !permanentlyFailed);
-      errors.push(`${jobKey}: ${error}`);
+  // Claiming updates review_jobs, so the whole drain runs as the worker does.
+  // Check the limits before claiming so no job is left leased but unprocessed
+  await withServiceRole(async () => {
+    while (processed.length + errors.length < maxJobs && Date.now() - startTime < timeout) {
+      const job = await claimJob();
+      if (!job) break;
+
+      const outcome = await processJob(job);
+      if (outcome.status === "processed") processed.push(outcome.key);
+      else if (outcome.status === "skipped") skipped.push(outcome.key);
+      else errors.push(`${outcome.key}: ${outcome.error}`);
     }
+  });
+
+  return NextResponse.json({ processed, skipped, errors, count: processed.length, duration_ms: Date.now() - startTime });
+}
 
-    job = await claimJob();
+// Embedded worker status, for health checks
+export async function GET(request: NextRequest) {
+  if (!authorized(request)) {
+    return NextResponse.json({ error: "Unauthorized" }, { status: 401 });
   }
 
-  return NextResponse.json({ processed, errors, count: processed.length, duration_ms: Date.now() - startTime });
+  const embedded = getEmbeddedWorker();
+  return NextResponse.json(embedded ? { embedded: true, ...embedded.status() } : { embedded: false });
 }
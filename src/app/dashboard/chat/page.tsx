@@ -2,6 +2,7 @@
 
 import { useState, useEffect, useRef, useCallback, useMemo } from "react";
 import { cn } from "@/lib/utils";
+import { parseSSE } from "@/lib/sse";
 
 interface ToolDef {
   name: string;
@@ -20,6 +21,14 @@ interface ChatMessage {
   status?: "success" | "error";
   duration?: number;
   cached?: boolean;
+  streaming?: boolean;
+  tools?: ToolActivity[];
+}
+
+interface ToolActivity {
+  name: string;
+  status: "running" | "success" | "error";
+  duration?: number;
 }
 
 const CATEGORIES: Record<string, { icon: string; color: string }> = {
@@ -81,6 +90,7 @@ export default function ChatPage() {
   const [selectedIndex, setSelectedIndex] = useState(0);
   const messagesEndRef = useRef<HTMLDivElement>(null);
   const inputRef = useRef<HTMLTextAreaElement>(null);
+  const conversationId = useRef(crypto.randomUUID());
 
   // Fetch tools on mount
   useEffect(() => {
@@ -179,23 +189,61 @@ export default function ChatPage() {
       return;
     }
 
-    // Natural language
+    // Natural language, streamed as server-sent events
+    const assistantId = crypto.randomUUID();
+    const update = (fn: (msg: ChatMessage) => ChatMessage) =>
+      setMessages(prev => prev.map(m => (m.id === assistantId ? fn(m) : m)));
+
     try {
       const res = await fetch("/api/chat", {
         method: "POST",
-        headers: { "Content-Type": "application/json" },
-        body: JSON.stringify({ message: userMsg, history: messages.slice(-10) }),
+        headers: { "Content-Type": "application/json", Accept: "text/event-stream" },
+        body: JSON.stringify({ message: userMsg, history: messages.slice(-10), stream: true, conversationId: conversationId.current }),
       });
-      const data = await res.json();
+
+      if (!res.body || !res.headers.get("content-type")?.includes("text/event-stream")) {
+        const data = await res.json();
+        setMessages(prev => [...prev, {
+          id: assistantId,
+          role: "assistant",
+          content: data.response || data.error || "No response",
+          timestamp: new Date(),
+          status: data.error ? "error" : "success",
+        }]);
+        return;
+      }
+
       setMessages(prev => [...prev, {
-        id: crypto.randomUUID(),
+        id: assistantId,
         role: "assistant",
-        content: data.response || data.error || "No response",
+        content: "",
         timestamp: new Date(),
-        status: data.error ? "error" : "success",
+        streaming: true,
       }]);
+
+      for await (const { event, data } of parseSSE(res.body)) {
+        const payload = JSON.parse(data);
+        if (event === "token") {
+          update(m => ({ ...m, content: m.content + payload.text }));
+        } else if (event === "tool_call") {
+          update(m => ({ ...m, tools: [...(m.tools || []), { name: payload.tool, status: "running" as const }] }));
+        } else if (event === "tool_result") {
+          update(m => ({
+            ...m,
+            cached: m.cached || payload.cached,
+            tools: (m.tools || []).map((t): ToolActivity => t.name === payload.tool && t.status === "running"
+              ? { ...t, status: payload.success ? "success" : "error", duration: payload.duration }
+              : t),
+          }));
+        } else if (event === "done") {
+          update(m => ({ ...m, content: payload.response || m.content || "No response", streaming: false, status: "success" }));
+        } else if (event === "error") {
+          update(m => ({ ...m, content: m.content || payload.error, streaming: false, status: "error" }));
+        }
+      }
+      update(m => (m.streaming ? { ...m, streaming: false } : m));
     } catch {
-      setMessages(prev => [...prev, {
+      setMessages(prev => [...prev.filter(m => m.id !== assistantId || m.content), {
         id: crypto.randomUUID(),
         role: "assistant",
         content: "Connection error. Please try again.",
@@ -258,7 +306,10 @@ export default function ChatPage() {
         </div>
         {messages.length > 0 && (
           <button
-            onClick={() => setMessages([])}
+            onClick={() => {
+              setMessages([]);
+              conversationId.current = crypto.randomUUID();
+            }}
             className="text-sm text-zinc-500 hover:text-white px-3 py-1.5 rounded-lg hover:bg-zinc-800 transition-colors flex items-center gap-1.5"
           >
             <svg className="w-4 h-4" fill="none" stroke="currentColor" viewBox="0 0 24 24">
@@ -314,6 +365,23 @@ export default function ChatPage() {
                       ? cn("rounded-tl-sm border", msg.status === "error" ? "bg-red-900/20 border-red-700/50" : "bg-blue-900/20 border-blue-700/50")
                       : "bg-zinc-800/80 text-zinc-200 rounded-tl-sm border border-zinc-700/50"
                   )}>
+                    {msg.tools && msg.tools.length > 0 && (
+                      <div className="flex flex-wrap gap-1.5 mb-2">
+                        {msg.tools.map((t, i) => (
+                          <span
+                            key={i}
+                            className={cn(
+                              "text-[11px] font-mono px-2 py-0.5 rounded border",
+                              t.status === "running" ? "border-zinc-600 text-zinc-400 animate-pulse"
+                                : t.status === "success" ? "border-emerald-700/50 text-emerald-400"
+                                : "border-red-700/50 text-red-400"
+                            )}
+                          >
+                            /{t.name}{t.duration ? ` • ${t.duration}ms` : ""}
+                          </span>
+                        ))}
+                      </div>
+                    )}
                     {msg.role !== "user" ? (
                       <div className="prose-sm" dangerouslySetInnerHTML={{ __html: formatMarkdown(msg.content) }} />
                     ) : (
@@ -324,7 +392,8 @@ export default function ChatPage() {
                     <TimeAgo date={msg.timestamp} />
                     {msg.duration && <span>• {msg.duration}ms</span>}
                     {msg.cached && <span className="text-emerald-500">• cached</span>}
-                    {msg.role !== "user" && (
+                    {msg.streaming && <span className="text-emerald-500">• streaming</span>}
+                    {msg.role !== "user" && !msg.streaming && (
                       <button
                         onClick={() => copyToClipboard(msg.content, msg.id)}
                         className="hover:text-white opacity-0 group-hover:opacity-100 transition-opacity"
@@ -341,7 +410,7 @@ export default function ChatPage() {
                 )}
               </div>
             ))}
-            {loading && (
+            {loading && !messages.some(m => m.streaming) && (
               <div className="flex gap-3">
                 <div className="w-8 h-8 rounded-lg bg-gradient-to-br from-emerald-400 to-emerald-600 flex items-center justify-center">
                   <span className="text-sm">🤖</span>
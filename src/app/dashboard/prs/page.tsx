@@ -67,6 +67,7 @@ export default function PRsPage() {
   const [merging, setMerging] = useState(false);
   const [mergeMethod, setMergeMethod] = useState<"merge" | "squash" | "rebase">("squash");
   const [mergeError, setMergeError] = useState<string | null>(null);
+  const [policyBlocked, setPolicyBlocked] = useState(false);
 
   const fetchData = async () => {
     try {
@@ -122,11 +123,12 @@ export default function PRsPage() {
     }
   };
 
-  const mergePR = async () => {
+  const mergePR = async (override = false) => {
     if (!mergingPR) return;
 
     setMerging(true);
     setMergeError(null);
+    setPolicyBlocked(false);
 
     const [owner, repo] = (mergingPR.repo || "").split("/");
     if (!owner || !repo) {
@@ -144,6 +146,7 @@ export default function PRsPage() {
           repo,
           pr_number: mergingPR.number,
           merge_method: mergeMethod,
+          ...(override && { override: true, override_reason: "Overridden from dashboard" }),
         }),
       });
 
@@ -151,6 +154,7 @@ export default function PRsPage() {
 
       if (!res.ok) {
         setMergeError(data.error || "Failed to merge PR");
+        setPolicyBlocked(Boolean(data.gate));
         setMerging(false);
         return;
       }
@@ -170,6 +174,7 @@ export default function PRsPage() {
     e.preventDefault();
     e.stopPropagation();
     setMergeError(null);
+    setPolicyBlocked(false);
     setMergingPR(pr);
   };
 
@@ -504,8 +509,18 @@ export default function PRsPage() {
             >
               Cancel
             </Button>
+            {policyBlocked && (
+              <Button
+                variant="outline"
+                onClick={() => mergePR(true)}
+                disabled={merging}
+                className="border-red-500/50 text-red-400 hover:bg-red-500/10"
+              >
+                Override as Admin
+              </Button>
+            )}
             <Button
-              onClick={mergePR}
+              onClick={() => mergePR()}
               disabled={merging}
               className="bg-green-600 hover:bg-green-700 text-white"
             >
@@ -18,6 +18,8 @@ interface Scan {
     threat_level?: string;
     by_type?: { security: number; bugs: number; quality: number };
     by_severity?: { critical: number; high: number; medium: number; low: number };
+    tools?: string[];
+    coverage?: { total_files: number; scanned_files: number; percent: number };
   };
   created_at: string;
 }
@@ -31,6 +33,9 @@ export default function ScansPage() {
   const [loadingMore, setLoadingMore] = useState(false);
   const [hasMore, setHasMore] = useState(true);
   const [scanning, setScanning] = useState<string | null>(null);
+  const [importing, setImporting] = useState(false);
+  const [importError, setImportError] = useState<string | null>(null);
+  const fileInputRef = useRef<HTMLInputElement>(null);
   const [expanded, setExpanded] = useState<string | null>(null);
   const [repoFilter, setRepoFilter] = useState<string>("all");
   const [severityFilter, setSeverityFilter] = useState<string>("all");
@@ -107,6 +112,26 @@ export default function ScansPage() {
     }
   };
 
+  // Repo comes from the filter, or from the log's versionControlProvenance
+  const importSarif = async (file: File) => {
+    setImporting(true);
+    setImportError(null);
+    try {
+      const form = new FormData();
+      form.append("file", file);
+      if (repoFilter !== "all") form.append("repo", repoFilter);
+      const res = await fetch("/api/scans/sarif", { method: "POST", body: form });
+      if (!res.ok) {
+        setImportError((await res.json().catch(() => null))?.error || "Import failed");
+        return;
+      }
+      fetchScans();
+    } finally {
+      setImporting(false);
+      if (fileInputRef.current) fileInputRef.current.value = "";
+    }
+  };
+
   const gradeColor: Record<string, string> = {
     A: "bg-emerald-500", B: "bg-green-500", C: "bg-yellow-500", D: "bg-orange-500", F: "bg-red-500"
   };
@@ -122,11 +147,25 @@ export default function ScansPage() {
           <h1 className="text-2xl font-bold text-white">Security Scans</h1>
           <p className="text-zinc-500">{uniqueRepos.length} repos • {total} scans</p>
         </div>
-        <Button onClick={() => triggerScan()} disabled={!!scanning} className="bg-emerald-600 hover:bg-emerald-700">
-          {scanning === "all" ? "Scanning..." : "🔍 Scan All Repos"}
-        </Button>
+        <div className="flex items-center gap-2">
+          <input
+            ref={fileInputRef}
+            type="file"
+            accept=".sarif,.json,application/json"
+            className="hidden"
+            onChange={e => e.target.files?.[0] && importSarif(e.target.files[0])}
+          />
+          <Button variant="outline" onClick={() => fileInputRef.current?.click()} disabled={importing}>
+            {importing ? "Importing..." : "📥 Import SARIF"}
+          </Button>
+          <Button onClick={() => triggerScan()} disabled={!!scanning} className="bg-emerald-600 hover:bg-emerald-700">
+            {scanning === "all" ? "Scanning..." : "🔍 Scan All Repos"}
+          </Button>
+        </div>
       </div>
 
+      {importError && <p className="text-sm text-red-400">{importError}</p>}
+
       {/* Stats Summary */}
       <div className="grid grid-cols-3 gap-4">
         <Card className="bg-zinc-900 border-zinc-800">
@@ -194,11 +233,14 @@ export default function ScansPage() {
                       <div className="flex items-center gap-2">
                         <span className="font-medium text-white">{scan.repo_full_name}</span>
                         <Badge variant="outline" className={threatColor[threat]}>{threat}</Badge>
+                        {scan.scan_metadata?.tools?.map(tool => (
+                          <Badge key={tool} variant="outline" className="text-zinc-400">{tool}</Badge>
+                        ))}
                       </div>
                       <p className="text-sm text-zinc-500 mt-1 line-clamp-1">{scan.summary}</p>
                       <div className="flex gap-4 mt-2 text-xs text-zinc-600">
                         <span>Score: {scan.security_score}/100</span>
-                        <span>Files: {scan.files_scanned}</span>
+                        <span>Files: {scan.scan_metadata?.coverage ? `${scan.files_scanned} of ${scan.scan_metadata.coverage.total_files} (${scan.scan_metadata.coverage.percent}%)` : scan.files_scanned}</span>
                         <span>{new Date(scan.created_at).toLocaleString()}</span>
                       </div>
                     </div>
@@ -211,6 +253,9 @@ export default function ScansPage() {
                         {scan.scan_metadata.by_severity.medium > 0 && <Badge className="bg-yellow-600">{scan.scan_metadata.by_severity.medium} med</Badge>}
                       </div>
                     )}
+                    <Button variant="outline" size="sm" asChild>
+                      <a href={`/api/scans/sarif?id=${scan.id}`} download>SARIF</a>
+                    </Button>
                     <Button variant="outline" size="sm" onClick={() => setExpanded(isExpanded ? null : scan.id)}>
                       {isExpanded ? "Hide" : "Details"}
                     </Button>
@@ -232,6 +277,7 @@ export default function ScansPage() {
                             </Badge>
                             <span className="text-zinc-400">{issue.type || issue.category}</span>
                             <span className="text-zinc-600">• {issue.file}:{issue.line}</span>
+                            {issue.tool && <span className="text-zinc-500">• {issue.tool}{issue.rule ? ` ${issue.rule}` : ""}</span>}
                           </div>
                           <p className="text-white">{issue.title || issue.problem}</p>
                           {issue.fix && <p className="text-emerald-400 text-xs mt-1">Fix: {issue.fix}</p>}
@@ -3,6 +3,12 @@ import * as Sentry from "@sentry/nextjs";
 export async function register() {
   if (process.env.NEXT_RUNTIME === "nodejs") {
     await import("../sentry.server.config");
+
+    // Self-hosted deployments process the queue in-process instead of via edge functions
+    if (process.env.EMBEDDED_WORKER === "true") {
+      const { startEmbeddedWorker } = await import("./lib/worker");
+      startEmbeddedWorker();
+    }
   }
 
   if (process.env.NEXT_RUNTIME === "edge") {
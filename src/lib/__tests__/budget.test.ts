@@ -0,0 +1,47 @@
+import { describe, it, expect } from 'vitest';
+import { evaluateBudget, monthStart, usageColumns } from '../budget';
+import { addUsage, emptyUsage, estimateCost, parsePricing, sumUsage } from '../llm/usage';
+
+describe('Token Budgets', () => {
+  it('should price usage per model with env overrides', () => {
+    const pricing = { ...parsePricing('local=0/0, big=2/8, broken=x'), 'gpt-4o-mini': [0.15, 0.6] as [number, number] };
+    expect(pricing.big).toEqual([2, 8]);
+    expect(pricing.broken).toBeUndefined();
+    expect(estimateCost('big', { promptTokens: 1_000_000, completionTokens: 500_000 }, pricing)).toBe(6);
+    expect(estimateCost('unknown', { promptTokens: 1000, completionTokens: 1000 }, pricing)).toBe(0);
+  });
+
+  it('should accumulate usage across calls', () => {
+    const totals = emptyUsage();
+    addUsage(totals, 'gpt-4o-mini', { promptTokens: 1000, completionTokens: 200 });
+    addUsage(totals, 'gpt-4o-mini', undefined);
+    addUsage(totals, 'gpt-4o-mini', { promptTokens: 500, completionTokens: 100 });
+
+    expect(totals).toMatchObject({ prompt_tokens: 1500, completion_tokens: 300 });
+    expect(totals.cost_usd).toBeCloseTo(0.000405, 6);
+    expect(sumUsage(undefined, { promptTokens: 1, completionTokens: 2 })).toEqual({ promptTokens: 1, completionTokens: 2 });
+    expect(usageColumns(undefined)).toEqual(emptyUsage());
+  });
+
+  it('should allow repos under budget', () => {
+    const usage = { prompt_tokens: 400, completion_tokens: 100, cost_usd: 0.5 };
+    expect(evaluateBudget({ monthly_token_budget: 1000, monthly_cost_budget: 1 }, usage).action).toBe('allow');
+    expect(evaluateBudget({}, usage).action).toBe('allow');
+  });
+
+  it('should downgrade or skip once a budget is used up', () => {
+    const usage = { prompt_tokens: 900, completion_tokens: 200, cost_usd: 2.5 };
+
+    const tokens = evaluateBudget({ monthly_token_budget: 1000 }, usage);
+    expect(tokens.action).toBe('downgrade');
+    expect(tokens.reason).toContain('tokens used');
+
+    const cost = evaluateBudget({ monthly_cost_budget: 2, over_budget_action: 'skip' }, usage);
+    expect(cost.action).toBe('skip');
+    expect(cost.reason).toContain('$2.50 of $2.00');
+  });
+
+  it('should reset budgets at the start of the UTC month', () => {
+    expect(monthStart(new Date('2026-03-17T12:00:00Z')).toISOString()).toBe('2026-03-01T00:00:00.000Z');
+  });
+});
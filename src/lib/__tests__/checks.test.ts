@@ -0,0 +1,37 @@
+import { describe, it, expect } from 'vitest';
+import { batchAnnotations, reviewConclusion, severityCounts, toAnnotations } from '../checks';
+import type { LineComment } from '../review/engine';
+
+const comment = (severity: string, line = 10, extra: Partial<LineComment> = {}): LineComment => ({
+  path: 'src/app.ts', line, body: `${severity} issue`, severity, category: 'bug', ...extra,
+});
+
+describe('Check Runs', () => {
+  it('should derive the conclusion from the worst severity', () => {
+    expect(reviewConclusion([comment('low'), comment('critical')])).toBe('failure');
+    expect(reviewConclusion([comment('medium'), comment('high')])).toBe('neutral');
+    expect(reviewConclusion([comment('low')])).toBe('success');
+    expect(reviewConclusion([])).toBe('success');
+    expect(severityCounts([comment('HIGH'), comment('high'), comment('low')])).toMatchObject({ high: 2, low: 1 });
+  });
+
+  it('should turn line comments into annotations', () => {
+    const [critical, medium, range] = toAnnotations([
+      comment('critical'),
+      comment('medium', 4, { suggestion: 'const x = 1;' }),
+      comment('low', 20, { start_line: 15 }),
+      comment('info', 0),
+    ]);
+
+    expect(critical).toMatchObject({ annotation_level: 'failure', start_line: 10, end_line: 10, title: '[CRITICAL] bug' });
+    expect(medium).toMatchObject({ annotation_level: 'warning', raw_details: 'const x = 1;' });
+    expect(range).toMatchObject({ annotation_level: 'notice', start_line: 15, end_line: 20 });
+    expect(toAnnotations([comment('info', 0)])).toEqual([]);
+  });
+
+  it('should batch annotations to 50 per request', () => {
+    const annotations = toAnnotations(Array.from({ length: 120 }, (_, i) => comment('low', i + 1)));
+    expect(batchAnnotations(annotations).map((b) => b.length)).toEqual([50, 50, 20]);
+    expect(batchAnnotations([])).toEqual([[]]);
+  });
+});
@@ -0,0 +1,99 @@
+import { describe, it, expect } from 'vitest';
+import { parseDiff, chunkDiff, mergeReviewResults, CodeReviewResult, Severity } from '../review/engine';
+
+function fileDiff(path: string, lines: number, hunks = 1): string {
+  const parts = [`diff --git a/${path} b/${path}`];
+  for (let h = 0; h < hunks; h++) {
+    const start = h * 100 + 1;
+    parts.push(`@@ -${start},0 +${start},${lines} @@`);
+    for (let i = 0; i < lines; i++) parts.push(`+const value${h}_${i} = ${i};`);
+  }
+  return parts.join('\n');
+}
+
+function result(overrides: Partial<CodeReviewResult>): CodeReviewResult {
+  return {
+    summary: { overview: '', changes_description: '', risk_assessment: 'Low', recommendations: [] },
+    walkthrough: [],
+    line_comments: [],
+    approval_recommendation: 'approve',
+    ...overrides,
+  };
+}
+
+describe('Diff Chunking', () => {
+  it('should keep small diffs in one batch', () => {
+    const files = parseDiff([fileDiff('a.ts', 5), fileDiff('b.ts', 5)].join('\n'));
+    const batches = chunkDiff(files, 2000);
+    expect(batches).toHaveLength(1);
+    expect(batches[0]!.files.map(f => f.path)).toEqual(['a.ts', 'b.ts']);
+  });
+
+  it('should split large diffs without dropping files', () => {
+    const diff = Array.from({ length: 10 }, (_, i) => fileDiff(`src/file${i}.ts`, 40)).join('\n');
+    const batches = chunkDiff(parseDiff(diff), 500);
+
+    expect(batches.length).toBeGreaterThan(1);
+    for (const b of batches) expect(b.tokens).toBeLessThanOrEqual(500);
+    const paths = new Set(batches.flatMap(b => b.files.map(f => f.path)));
+    expect(paths.size).toBe(10);
+  });
+
+  it('should split an oversized file on hunk boundaries', () => {
+    const batches = chunkDiff(parseDiff(fileDiff('big.ts', 40, 4)), 400);
+    expect(batches.length).toBeGreaterThan(1);
+    expect(batches.flatMap(b => b.files.flatMap(f => f.hunks))).toHaveLength(4);
+  });
+
+  it('should split an oversized hunk on lines without dropping any', () => {
+    const batches = chunkDiff(parseDiff(fileDiff('new.ts', 300)), 400);
+    const hunks = batches.flatMap(b => b.files.flatMap(f => f.hunks));
+
+    expect(hunks.length).toBeGreaterThan(1);
+    for (const b of batches) expect(b.diff.length).toBeLessThanOrEqual(1600);
+    // Each part picks up on the line where the previous one stopped
+    let next = 1;
+    for (const h of hunks) {
+      expect(h.newStart).toBe(next);
+      next += h.newCount;
+    }
+    expect(next).toBe(301);
+    expect(batches.map(b => b.diff).join('\n')).not.toContain('truncated');
+
+    const last = hunks[hunks.length - 1]!;
+    expect(last.content.split('\n')[0]).toBe(`@@ -1,0 +${last.newStart},${last.newCount} @@`);
+    expect(last.content).toContain('const value0_299 = 299;');
+  });
+
+  it('should skip lock files', () => {
+    const files = parseDiff([fileDiff('package-lock.json', 5), fileDiff('a.ts', 5)].join('\n'));
+    expect(chunkDiff(files).flatMap(b => b.files.map(f => f.path))).toEqual(['a.ts']);
+  });
+});
+
+describe('Review Merging', () => {
+  it('should dedupe comments keeping the most severe', () => {
+    const merged = mergeReviewResults([
+      result({ line_comments: [{ path: 'a.ts', line: 3, body: 'x', severity: Severity.LOW, category: 'bug' }] }),
+      result({ line_comments: [
+        { path: 'a.ts', line: 3, body: 'y', severity: Severity.HIGH, category: 'bug' },
+        { path: 'b.ts', line: 1, body: 'z', severity: Severity.MEDIUM, category: 'style' },
+      ] }),
+    ]);
+
+    expect(merged.line_comments).toHaveLength(2);
+    expect(merged.line_comments[0]!.body).toBe('y');
+  });
+
+  it('should take the most cautious verdict and risk', () => {
+    const merged = mergeReviewResults([
+      result({ approval_recommendation: 'approve', summary: { overview: 'One.', changes_description: '', risk_assessment: 'Low', recommendations: ['Add tests'] } }),
+      result({ approval_recommendation: 'request_changes', summary: { overview: 'Two.', changes_description: '', risk_assessment: 'High - auth change', recommendations: ['add tests'] } }),
+    ]);
+
+    expect(merged.approval_recommendation).toBe('request_changes');
+    expect(merged.summary.risk_assessment).toBe('High - auth change');
+    expect(merged.summary.recommendations).toEqual(['Add tests']);
+    expect(merged.summary.overview).toBe('One. Two.');
+  });
+});
@@ -0,0 +1,61 @@
+import { describe, it, expect } from 'vitest';
+import { hasFilter, parseDlqFilter, toReplayJob, type DlqEntry } from '../dlq';
+
+const entry: DlqEntry = {
+  id: 'dlq-1',
+  original_job_id: 'job-1',
+  repo_full_name: 'acme/api',
+  pr_number: 42,
+  owner: 'acme',
+  repo: 'api',
+  attempts: 3,
+  error: 'GitHub API 502',
+  analysis: { priority: 'high', score: 6, depth: 'deep' },
+  head_sha: 'abc123',
+  error_history: [{ at: '2026-01-01T00:00:00Z', attempt: 1, error: 'timeout' }],
+  original_created_at: '2026-01-01T00:00:00Z',
+  moved_to_dlq_at: '2026-01-08T00:00:00Z',
+  replay_count: 0,
+  replayed_at: null,
+  replayed_job_id: null,
+  metadata: null,
+};
+
+describe('Dead Letter Queue', () => {
+  it('should parse filters from query params and tool args', () => {
+    expect(parseDlqFilter({ repo: 'acme/api', pr: '42', replayed: 'false', limit: '900' })).toEqual({
+      repo: 'acme/api', pr: 42, replayed: false, limit: 500,
+    });
+    expect(parseDlqFilter({ ids: 'a, b,' })).toEqual({ ids: ['a', 'b'] });
+    expect(parseDlqFilter({ ids: ['a'], before: '2026-01-02' }).before).toBe('2026-01-02T00:00:00.000Z');
+    expect(parseDlqFilter({ repo: '', error: undefined })).toEqual({});
+  });
+
+  it('should reject malformed filters', () => {
+    expect(() => parseDlqFilter({ repo: 'acme' })).toThrow('owner/repo');
+    expect(() => parseDlqFilter({ pr: '-1' })).toThrow('positive integer');
+    expect(() => parseDlqFilter({ before: 'yesterday' })).toThrow('date');
+    expect(() => parseDlqFilter({ replayed: 'maybe' })).toThrow('true or false');
+  });
+
+  it('should require a filter before bulk operations', () => {
+    expect(hasFilter({})).toBe(false);
+    expect(hasFilter({ limit: 10 })).toBe(false);
+    expect(hasFilter({ replayed: false })).toBe(true);
+    expect(hasFilter({ repo: 'acme/api' })).toBe(true);
+  });
+
+  it('should replay as a fresh job for the current head that keeps its history', () => {
+    expect(toReplayJob(entry)).toEqual({
+      repo_full_name: 'acme/api',
+      pr_number: 42,
+      owner: 'acme',
+      repo: 'api',
+      analysis: entry.analysis,
+      head_sha: null,
+      error_history: entry.error_history,
+      priority: 2,
+      score: 6,
+    });
+  });
+});
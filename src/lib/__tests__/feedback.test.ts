@@ -0,0 +1,117 @@
+import { describe, it, expect, vi, afterEach } from 'vitest';
+import {
+  classifyReply,
+  summarizeFeedback,
+  computeCategoryWeights,
+  filterNoisyComments,
+  proposeLearnings,
+  Severity,
+} from '../review/engine';
+import { recordReplyFeedback, refreshRepoFeedback } from '../feedback-store';
+import { hasServiceRole, setStorage, type Storage } from '../storage';
+
+describe('Review Feedback', () => {
+  it('should classify replies', () => {
+    expect(classifyReply("Won't fix, this is intentional")).toBe('wont_fix');
+    expect(classifyReply('false positive')).toBe('wont_fix');
+    expect(classifyReply('Fixed in the latest commit')).toBe('resolved');
+    expect(classifyReply('Can you explain?')).toBeNull();
+  });
+
+  it('should only count short replies that lead with a fix as resolved', () => {
+    expect(classifyReply('Done')).toBe('resolved');
+    expect(classifyReply('Good catch, fixed.')).toBe('resolved');
+    expect(classifyReply('is this fixed upstream?')).toBeNull();
+    expect(classifyReply('Not done yet, will look tomorrow')).toBeNull();
+    expect(classifyReply(`Fixed ${'x'.repeat(300)}`)).toBeNull();
+  });
+
+  it('should down-weight categories the team rejects', () => {
+    const stats = summarizeFeedback([
+      { signal: 'thumbs_down', count: 3, category: 'style', path: 'a.ts' },
+      { signal: 'wont_fix', count: 1, category: 'style', path: 'b.ts' },
+      { signal: 'thumbs_up', count: 4, category: 'security', path: 'c.ts' },
+      { signal: 'thumbs_down', count: 1, category: 'bug', path: 'd.ts' },
+    ]);
+
+    const weights = computeCategoryWeights(stats);
+    expect(weights.style).toBeLessThan(0.5);
+    expect(weights.security).toBe(1);
+    expect(weights.bug).toBeUndefined(); // not enough signals
+  });
+
+  it('should keep high severity comments in noisy categories', () => {
+    const comments = [
+      { path: 'a.ts', line: 1, body: 'nit', severity: Severity.LOW, category: 'style' },
+      { path: 'a.ts', line: 2, body: 'bad', severity: Severity.HIGH, category: 'style' },
+      { path: 'a.ts', line: 3, body: 'bug', severity: Severity.LOW, category: 'bug' },
+    ];
+
+    const result = filterNoisyComments(comments, { style: 0.2 });
+    expect(result.map(c => c.line)).toEqual([2, 3]);
+  });
+
+  it('should propose learnings without duplicating existing ones', () => {
+    const rows = [
+      { signal: 'wont_fix' as const, count: 1, category: 'performance', path: 'src/db/query.ts', note: "Won't fix, the table is tiny" },
+    ];
+
+    const proposals = proposeLearnings(rows, { style: 0.2 }, [{ pattern: '*', category: 'style' }]);
+    expect(proposals).toHaveLength(1);
+    expect(proposals[0]!.pattern).toBe('src/db/*');
+    expect(proposals[0]!.learning).toContain('the table is tiny');
+  });
+});
+
+describe('Feedback on Storage', () => {
+  afterEach(() => {
+    setStorage(undefined);
+  });
+
+  it('should weigh model comments only and save weights and proposals through storage', async () => {
+    const update = vi.fn().mockResolvedValue([]);
+    const create = vi.fn();
+    setStorage({
+      feedback: {
+        signals: vi.fn().mockResolvedValue([
+          { signal: 'thumbs_down', count: 4, note: null, category: 'style', path: 'src/a.ts', source: null },
+          { signal: 'thumbs_down', count: 9, note: null, category: 'security', path: 'src/b.ts', source: 'semgrep' },
+        ]),
+      },
+      configs: { update },
+      learnings: { list: vi.fn().mockResolvedValue([]), create },
+    } as unknown as Storage);
+
+    const { weights, proposed } = await refreshRepoFeedback('acme/api');
+    expect(weights.style).toBeLessThan(0.5);
+    expect(weights.security).toBeUndefined();
+    expect(update).toHaveBeenCalledWith({ full_name: 'acme/api' }, { category_weights: weights });
+    expect(proposed).toBe(1);
+    expect(create).toHaveBeenCalledWith(expect.objectContaining({
+      repo_full_name: 'acme/api', category: 'style', source: 'feedback', status: 'proposed',
+    }));
+  });
+
+  it('should write feedback with the service role even from a webhook', async () => {
+    // Only the service role may write these tables; an anon write would silently match nothing
+    const roles: boolean[] = [];
+    const write = vi.fn(async () => { roles.push(hasServiceRole()); return []; });
+    setStorage({
+      feedback: {
+        comments: vi.fn().mockResolvedValue([{ github_comment_id: 7 }]),
+        upsertSignal: write,
+        signals: vi.fn().mockResolvedValue([
+          { signal: 'wont_fix', count: 5, note: 'noise', category: 'style', path: 'src/a.ts', source: null },
+        ]),
+      },
+      configs: { update: write },
+      learnings: { list: vi.fn().mockResolvedValue([]), create: write },
+    } as unknown as Storage);
+
+    expect(await recordReplyFeedback('acme/api', 7, 'wont_fix', "Won't fix")).toBe(true);
+    await refreshRepoFeedback('acme/api');
+
+    expect(roles.length).toBeGreaterThanOrEqual(3);
+    expect(roles.every(Boolean)).toBe(true);
+  });
+});
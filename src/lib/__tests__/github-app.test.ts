@@ -0,0 +1,70 @@
+// @vitest-environment node
+import { describe, it, expect, vi } from 'vitest';
+import { createVerify, generateKeyPairSync } from 'crypto';
+import { createAppJwt, getAuthForEndpoint, githubAppConfigFromEnv } from '../github-app';
+import { setStorage, type Storage } from '../storage';
+
+const { privateKey, publicKey } = generateKeyPairSync('rsa', {
+  modulusLength: 2048,
+  privateKeyEncoding: { type: 'pkcs1', format: 'pem' },
+  publicKeyEncoding: { type: 'spki', format: 'pem' },
+});
+
+const decode = (part: string) => JSON.parse(Buffer.from(part, 'base64url').toString('utf8'));
+
+describe('GitHub App Auth', () => {
+  it('should read the private key escaped or base64-encoded', () => {
+    const escaped = githubAppConfigFromEnv({ GITHUB_APP_ID: '42', GITHUB_APP_PRIVATE_KEY: privateKey.replace(/\n/g, '\\n') });
+    const encoded = githubAppConfigFromEnv({
+      GITHUB_APP_ID: '42',
+      GITHUB_APP_PRIVATE_KEY: Buffer.from(privateKey).toString('base64'),
+      GITHUB_APP_INSTALLATION_ID: '7',
+    });
+
+    expect(escaped?.privateKey).toBe(privateKey);
+    expect(encoded).toEqual({ appId: '42', privateKey, installationId: 7 });
+    expect(githubAppConfigFromEnv({ GITHUB_APP_ID: '42' })).toBeNull();
+  });
+
+  it('should sign an RS256 app JWT', () => {
+    const now = Date.parse('2026-01-23T12:00:00Z');
+    const jwt = createAppJwt('42', privateKey, now);
+    const [header, payload, signature] = jwt.split('.') as [string, string, string];
+
+    expect(decode(header)).toEqual({ alg: 'RS256', typ: 'JWT' });
+    const claims = decode(payload);
+    expect(claims.iss).toBe('42');
+    expect(claims.iat).toBe(now / 1000 - 60);
+    expect(claims.exp - claims.iat).toBeLessThanOrEqual(600);
+
+    const valid = createVerify('RSA-SHA256').update(`${header}.${payload}`).verify(publicKey, Buffer.from(signature, 'base64url'));
+    expect(valid).toBe(true);
+  });
+
+  it('should fall back to GITHUB_TOKEN without an app', async () => {
+    const saved = { ...process.env };
+    delete process.env.GITHUB_APP_ID;
+    delete process.env.GITHUB_APP_PRIVATE_KEY;
+    process.env.GITHUB_TOKEN = 'ghp_test';
+    try {
+      expect(await getAuthForEndpoint('/repos/acme/api/pulls')).toEqual({ token: 'ghp_test' });
+    } finally {
+      process.env = saved;
+    }
+  });
+
+  it('should keep GITHUB_TOKEN for repos the app is not installed on', async () => {
+    const saved = { ...process.env };
+    Object.assign(process.env, { GITHUB_APP_ID: '42', GITHUB_APP_PRIVATE_KEY: privateKey, GITHUB_APP_INSTALLATION_ID: '7', GITHUB_TOKEN: 'ghp_test' });
+    setStorage({ configs: { get: vi.fn().mockResolvedValue(null) } } as unknown as Storage);
+    const fetch = vi.spyOn(globalThis, 'fetch').mockResolvedValue(new Response('Not Found', { status: 404 }));
+    try {
+      expect(await getAuthForEndpoint('/repos/acme/legacy/pulls')).toEqual({ token: 'ghp_test' });
+      expect(String(fetch.mock.calls[0]![0])).toContain('/repos/acme/legacy/installation');
+    } finally {
+      fetch.mockRestore();
+      setStorage(undefined);
+      process.env = saved;
+    }
+  });
+});
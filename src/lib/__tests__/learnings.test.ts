@@ -0,0 +1,59 @@
+import { describe, it, expect } from 'vitest';
+import { selectLearnings, buildLearningsPrompt } from '../review/engine';
+
+const learning = (id: string, pattern: string, text: string, category: string | null = null) => ({
+  id,
+  repo_full_name: 'owner/repo',
+  pattern,
+  learning: text,
+  category,
+});
+
+describe('Review Learnings', () => {
+  it('should match learnings by path pattern', () => {
+    const learnings = [
+      learning('1', 'src/api/*', 'Handlers validate input with zod'),
+      learning('2', 'migrations/', 'Migrations are append-only'),
+    ];
+
+    const result = selectLearnings(learnings, ['src/api/users.ts'], ['security']);
+    expect(result.map(l => l.id)).toEqual(['1']);
+  });
+
+  it('should filter by category but keep uncategorized learnings', () => {
+    const learnings = [
+      learning('1', '*', 'Prefer early returns', 'style'),
+      learning('2', '*', 'Secrets come from the vault', 'security'),
+      learning('3', '*', 'We target Node 20'),
+    ];
+
+    const result = selectLearnings(learnings, ['a.ts'], ['security', 'bug']);
+    expect(result.map(l => l.id)).toEqual(['2', '3']);
+  });
+
+  it('should prefer path-scoped learnings over repo-wide ones', () => {
+    const learnings = [
+      learning('1', '*', 'Repo-wide rule'),
+      learning('2', 'src/*', 'Scoped rule'),
+    ];
+
+    const result = selectLearnings(learnings, ['src/index.ts'], []);
+    expect(result.map(l => l.id)).toEqual(['2', '1']);
+  });
+
+  it('should respect the token budget', () => {
+    const learnings = [
+      learning('1', '*', 'x'.repeat(100)),
+      learning('2', '*', 'y'.repeat(100)),
+    ];
+
+    const result = selectLearnings(learnings, ['a.ts'], [], 40);
+    expect(result).toHaveLength(1);
+  });
+
+  it('should build an empty prompt when nothing applies', () => {
+    expect(buildLearningsPrompt([])).toBe('');
+    expect(buildLearningsPrompt([learning('1', 'src/*', 'Use the logger', 'best_practices')]))
+      .toContain('[best_practices] Use the logger (`src/*`)');
+  });
+});
@@ -0,0 +1,155 @@
+// @vitest-environment node
+import { describe, it, expect, beforeAll, afterAll } from 'vitest';
+import { createServer, IncomingMessage, Server, ServerResponse } from 'http';
+import { AddressInfo } from 'net';
+import { createOpenAICompatibleProvider } from '../llm/openai';
+import { createAnthropicProvider, toAnthropicMessages } from '../llm/anthropic';
+import { groqProvider } from '../llm/groq';
+import { LLMError } from '../llm/provider';
+
+interface Recorded {
+  url: string;
+  headers: IncomingMessage['headers'];
+  body: Record<string, unknown>;
+}
+
+let server: Server;
+let baseUrl: string;
+let requests: Recorded[] = [];
+let reply: (req: Recorded, res: ServerResponse) => void;
+
+const json = (res: ServerResponse, status: number, body: unknown, headers: Record<string, string> = {}) => {
+  res.writeHead(status, { 'Content-Type': 'application/json', ...headers });
+  res.end(JSON.stringify(body));
+};
+
+beforeAll(async () => {
+  server = createServer((req, res) => {
+    let data = '';
+    req.on('data', (chunk) => (data += chunk));
+    req.on('end', () => {
+      const recorded = { url: req.url || '', headers: req.headers, body: JSON.parse(data || '{}') };
+      requests.push(recorded);
+      reply(recorded, res);
+    });
+  });
+  await new Promise<void>((resolve) => server.listen(0, '127.0.0.1', resolve));
+  baseUrl = `http://127.0.0.1:${(server.address() as AddressInfo).port}`;
+});
+
+afterAll(() => {
+  server.close();
+});
+
+const reset = (handler: typeof reply) => {
+  requests = [];
+  reply = handler;
+};
+
+describe('OpenAI-compatible provider', () => {
+  const provider = () => createOpenAICompatibleProvider({
+    baseUrl: `${baseUrl}/v1/`,
+    apiKey: 'sk-test',
+    model: 'local-small',
+    reviewModel: 'local-large',
+    modelMap: { 'llama-3.1-8b-instant': 'local-small' },
+  }, 'vllm');
+
+  it('should send chat completions with mapped models', async () => {
+    reset((_, res) => json(res, 200, { choices: [{ message: { content: 'hello' } }], usage: { prompt_tokens: 12, completion_tokens: 3 } }));
+
+    expect(await provider().chat([{ role: 'user', content: 'hi' }], { useReviewModel: true, jsonMode: true })).toEqual({
+      content: 'hello',
+      usage: { promptTokens: 12, completionTokens: 3 },
+    });
+    expect((await provider().chat([{ role: 'user', content: 'hi' }], { model: 'llama-3.1-8b-instant' })).content).toBe('hello');
+
+    expect(requests[0]!.url).toBe('/v1/chat/completions');
+    expect(requests[0]!.headers.authorization).toBe('Bearer sk-test');
+    expect(requests[0]!.body.model).toBe('local-large');
+    expect(requests[0]!.body.response_format).toEqual({ type: 'json_object' });
+    expect(requests[1]!.body.model).toBe('local-small');
+  });
+
+  it('should retry rate limits and classify the final error', async () => {
+    reset((_, res) => json(res, 429, { error: { message: 'Too many requests' } }, { 'retry-after': '0' }));
+
+    const err = await provider().chat([{ role: 'user', content: 'hi' }], { maxRetries: 2 }).catch((e) => e);
+    expect(err).toBeInstanceOf(LLMError);
+    expect(err.kind).toBe('rate_limit');
+    expect(err.provider).toBe('vllm');
+    expect(err.retryable).toBe(true);
+    expect(requests).toHaveLength(2);
+  });
+
+  it('should not retry auth or context errors', async () => {
+    reset((_, res) => json(res, 400, { error: { message: 'too long', code: 'context_length_exceeded' } }));
+    const err = await provider().chat([{ role: 'user', content: 'hi' }]).catch((e) => e);
+    expect(err.kind).toBe('context_length');
+    expect(requests).toHaveLength(1);
+
+    reset((_, res) => json(res, 401, { error: { message: 'bad key' } }));
+    expect((await provider().chat([{ role: 'user', content: 'hi' }]).catch((e) => e)).kind).toBe('auth');
+  });
+});
+
+describe('Groq provider', () => {
+  it('should resolve requested models through GROQ_MODEL_MAP', () => {
+    process.env.GROQ_MODEL_MAP = 'deep=llama-3.3-70b-versatile';
+    try {
+      expect(groqProvider.model({ model: 'deep' })).toBe('llama-3.3-70b-versatile');
+      expect(groqProvider.model({ model: 'gemma2-9b-it' })).toBe('gemma2-9b-it');
+      expect(groqProvider.model()).toBe(process.env.GROQ_MODEL || 'llama-3.1-8b-instant');
+    } finally {
+      delete process.env.GROQ_MODEL_MAP;
+    }
+  });
+});
+
+describe('Anthropic provider', () => {
+  const provider = () => createAnthropicProvider({
+    baseUrl,
+    apiKey: 'ak-test',
+    model: 'claude-small',
+    maxTokens: 1000,
+  });
+
+  it('should call the Messages API with a separate system prompt', async () => {
+    reset((_, res) => json(res, 200, {
+      content: [{ type: 'text', text: 'Hi ' }, { type: 'text', text: 'there' }],
+      usage: { input_tokens: 8, output_tokens: 2 },
+    }));
+
+    const { content: text, usage } = await provider().chat([
+      { role: 'system', content: 'Be brief' },
+      { role: 'user', content: 'hello' },
+    ]);
+
+    expect(text).toBe('Hi there');
+    expect(usage).toEqual({ promptTokens: 8, completionTokens: 2 });
+    expect(requests[0]!.url).toBe('/v1/messages');
+    expect(requests[0]!.headers['x-api-key']).toBe('ak-test');
+    expect(requests[0]!.headers['anthropic-version']).toBeDefined();
+    expect(requests[0]!.body).toMatchObject({ model: 'claude-small', max_tokens: 1000, system: 'Be brief' });
+    expect(requests[0]!.body.messages).toEqual([{ role: 'user', content: 'hello' }]);
+  });
+
+  it('should classify errors from the response body', async () => {
+    reset((_, res) => json(res, 529, { type: 'error', error: { type: 'overloaded_error', message: 'Overloaded' } }));
+    const err = await provider().chat([{ role: 'user', content: 'hi' }], { maxRetries: 1 }).catch((e) => e);
+    expect(err.kind).toBe('server');
+    expect(err.status).toBe(529);
+
+    reset((_, res) => json(res, 400, { type: 'error', error: { type: 'authentication_error', message: 'bad key' } }));
+    expect((await provider().chat([{ role: 'user', content: 'hi' }]).catch((e) => e)).kind).toBe('auth');
+  });
+
+  it('should merge consecutive turns from the same role', () => {
+    const { messages } = toAnthropicMessages([
+      { role: 'user', content: 'a' },
+      { role: 'user', content: 'b' },
+      { role: 'assistant', content: 'c' },
+    ]);
+    expect(messages).toEqual([{ role: 'user', content: 'a\n\nb' }, { role: 'assistant', content: 'c' }]);
+  });
+});
@@ -0,0 +1,112 @@
+import { describe, it, expect } from 'vitest';
+import { routeChat, routeStream, getProviderChain } from '../llm/router';
+import { LLMError, LLMProviderClient } from '../llm/provider';
+import { getCircuitBreakerStates } from '../circuit-breaker';
+
+const messages = [{ role: 'user' as const, content: 'hi' }];
+
+function fakeProvider(name: string, behaviour: (model: string) => string | LLMError): LLMProviderClient & { calls: number } {
+  const provider = {
+    name,
+    calls: 0,
+    model: (options?: { model?: string }) => options?.model || `${name}-default`,
+    chat: async (_: unknown, options?: { model?: string }) => {
+      provider.calls++;
+      const result = behaviour(options?.model || `${name}-default`);
+      if (result instanceof LLMError) throw result;
+      return { content: result, usage: { promptTokens: 10, completionTokens: 5 } };
+    },
+    classifyError: (err: unknown) => err as LLMError,
+  };
+  return provider;
+}
+
+describe('LLM Router', () => {
+  it('should build the chain from env with the preferred provider first', () => {
+    expect(getProviderChain(undefined, { LLM_DEFAULT_PROVIDER: 'groq', LLM_FALLBACK_PROVIDER: 'ollama' })).toEqual(['groq', 'ollama']);
+    expect(getProviderChain('anthropic', { LLM_PROVIDERS: 'groq, ollama,anthropic' })).toEqual(['anthropic', 'groq', 'ollama']);
+    expect(getProviderChain(undefined, {})).toEqual(['groq']);
+  });
+
+  it('should fall through the chain and record who served the response', async () => {
+    const down = fakeProvider('r1-down', () => new LLMError('boom', 'server', 'r1-down', 503));
+    const up = fakeProvider('r1-up', () => 'ok');
+    const providers: Record<string, LLMProviderClient> = { 'r1-down': down, 'r1-up': up };
+
+    const result = await routeChat(messages, undefined, ['r1-down', 'r1-up'], (n) => providers[n]!);
+    expect(result).toMatchObject({ content: 'ok', provider: 'r1-up', model: 'r1-up-default', usage: { promptTokens: 10 } });
+  });
+
+  it('should skip a provider once its breaker opens without affecting others', async () => {
+    const down = fakeProvider('r2-down', () => new LLMError('timeout', 'timeout', 'r2-down'));
+    const up = fakeProvider('r2-up', () => 'ok');
+    const providers: Record<string, LLMProviderClient> = { 'r2-down': down, 'r2-up': up };
+
+    for (let i = 0; i < 5; i++) {
+      await routeChat(messages, undefined, ['r2-down', 'r2-up'], (n) => providers[n]!);
+    }
+
+    expect(down.calls).toBe(3); // failure threshold, then skipped
+    expect(up.calls).toBe(5);
+    expect(getCircuitBreakerStates('llm:r2-down')['llm:r2-down']).toBe('OPEN');
+    expect(getCircuitBreakerStates('llm:r2-up')['llm:r2-up']).toBe('CLOSED');
+  });
+
+  it('should open only the model breaker for request errors', async () => {
+    const provider = fakeProvider('r3', (model) => (model === 'bad' ? new LLMError('no such model', 'bad_request', 'r3', 404) : 'ok'));
+
+    for (let i = 0; i < 3; i++) {
+      await expect(routeChat(messages, { model: 'bad' }, ['r3'], () => provider)).rejects.toThrow('no such model');
+    }
+
+    const states = getCircuitBreakerStates('llm:r3');
+    expect(states['llm:r3:bad']).toBe('OPEN');
+    expect(states['llm:r3']).toBe('CLOSED');
+    expect((await routeChat(messages, { model: 'good' }, ['r3'], () => provider)).content).toBe('ok');
+  });
+
+  it('should not open any breaker for errors in the request itself', async () => {
+    const provider = fakeProvider('r6', () => new LLMError('prompt too long', 'context_length', 'r6', 400));
+
+    for (let i = 0; i < 5; i++) {
+      await expect(routeChat(messages, undefined, ['r6'], () => provider)).rejects.toThrow('prompt too long');
+    }
+
+    expect(provider.calls).toBe(5);
+    const states = getCircuitBreakerStates('llm:r6');
+    expect(states['llm:r6:r6-default']).toBe('CLOSED');
+    expect(states['llm:r6']).toBe('CLOSED');
+  });
+
+  it('should report every failure when the whole chain is down', async () => {
+    const a = fakeProvider('r4-a', () => new LLMError('a failed', 'network', 'r4-a'));
+    const err = await routeChat(messages, undefined, ['r4-a', 'r4-missing'], (n) => {
+      if (n === 'r4-a') return a;
+      throw new Error(`Unknown LLM provider: ${n}`);
+    }).catch((e) => e);
+
+    expect(err).toBeInstanceOf(LLMError);
+    expect(err.kind).toBe('unavailable');
+    expect(err.message).toContain('r4-a/r4-a-default: network');
+    expect(err.message).toContain('Unknown LLM provider: r4-missing');
+  });
+
+  it('should fall back before the first streamed chunk', async () => {
+    const down = fakeProvider('r5-down', () => new LLMError('refused', 'network', 'r5-down'));
+    const streaming: LLMProviderClient = {
+      ...fakeProvider('r5-up', () => 'unused'),
+      async *stream() {
+        yield { type: 'text' as const, text: 'Hel' };
+        yield { type: 'text' as const, text: 'lo' };
+      },
+    };
+    const providers: Record<string, LLMProviderClient> = { 'r5-down': down, 'r5-up': streaming };
+
+    const chunks = [];
+    for await (const chunk of routeStream(messages, undefined, ['r5-down', 'r5-up'], (n) => providers[n]!)) {
+      chunks.push(chunk);
+    }
+    expect(chunks.map((c) => (c.type === 'text' ? c.text : '')).join('')).toBe('Hello');
+    expect(chunks[0]).toMatchObject({ provider: 'r5-up', model: 'r5-up-default' });
+  });
+});
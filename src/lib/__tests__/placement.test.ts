@@ -0,0 +1,69 @@
+import { describe, it, expect } from 'vitest';
+import { parseDiff, placeComments, getHunkLines, Severity } from '../review/engine';
+
+const diff = `diff --git a/src/app.ts b/src/app.ts
+index 111..222 100644
+--- a/src/app.ts
++++ b/src/app.ts
+@@ -10,4 +10,5 @@ export function main() {
+   const a = 1;
+-  const b = 2;
++  const b = 3;
++  const c = 4;
+   return a + b;
+ }
+@@ -50,2 +51,3 @@ function other() {
+   log();
++  audit();
+ }`;
+
+const files = parseDiff(diff);
+const comment = (path: string, line: number, extra = {}) => ({
+  path, line, body: 'Issue', severity: Severity.HIGH, category: 'bug', ...extra,
+});
+
+describe('Comment Placement', () => {
+  it('should compute new-side line numbers per hunk', () => {
+    const hunks = getHunkLines(files[0]!);
+    expect(hunks[0]!.added).toEqual([11, 12]);
+    expect(hunks[0]!.commentable).toEqual([10, 11, 12, 13, 14]);
+    expect(hunks[1]!.added).toEqual([52]);
+  });
+
+  it('should keep comments on diff lines', () => {
+    const { placed, unplaced } = placeComments([comment('src/app.ts', 12)], files);
+    expect(placed).toHaveLength(1);
+    expect(placed[0]!.snapped).toBeUndefined();
+    expect(unplaced).toHaveLength(0);
+  });
+
+  it('should snap nearby lines to the closest changed line', () => {
+    const { placed } = placeComments([comment('src/app.ts', 56, { suggestion: 'x' })], files);
+    expect(placed[0]!.line).toBe(52);
+    expect(placed[0]!.snapped).toBe(true);
+  });
+
+  it('should move far-away or unknown comments to unplaced', () => {
+    const { placed, unplaced } = placeComments([
+      comment('src/app.ts', 200),
+      comment('src/missing.ts', 12),
+      comment('src/app.ts', 0),
+    ], files);
+    expect(placed).toHaveLength(0);
+    expect(unplaced).toHaveLength(3);
+  });
+
+  it('should resolve loosely formatted paths', () => {
+    const { placed } = placeComments([comment('./app.ts', 11)], files);
+    expect(placed[0]!.path).toBe('src/app.ts');
+  });
+
+  it('should only keep start_line within the same hunk', () => {
+    const { placed } = placeComments([
+      comment('src/app.ts', 12, { start_line: 10 }),
+      comment('src/app.ts', 52, { start_line: 12 }),
+    ], files);
+    expect(placed[0]!.start_line).toBe(10);
+    expect(placed[1]!.start_line).toBeUndefined();
+  });
+});
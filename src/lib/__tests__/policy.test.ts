@@ -0,0 +1,100 @@
+import { describe, it, expect } from 'vitest';
+import {
+  applyPolicy, carryOpenFindings, describePolicy, evaluatePolicy, openFindings, parseDiff, parsePolicy, parseFailedResult, type LineComment,
+} from '../review/engine';
+
+const comment = (severity: string, category = 'bug'): LineComment => ({
+  path: 'src/app.ts', line: 1, body: 'issue', severity, category,
+});
+
+describe('Severity Policy', () => {
+  it('should block on any critical by default', () => {
+    const result = evaluatePolicy([comment('critical'), comment('low')]);
+    expect(result.passed).toBe(false);
+    expect(result.violations).toEqual(['1 critical (none allowed)']);
+    expect(evaluatePolicy([comment('high'), comment('medium')]).passed).toBe(true);
+  });
+
+  it('should enforce severity limits', () => {
+    const policy = { max_high: 2 };
+    expect(evaluatePolicy([comment('high'), comment('high')], policy).passed).toBe(true);
+
+    const result = evaluatePolicy([comment('high'), comment('HIGH'), comment('high')], policy);
+    expect(result.passed).toBe(false);
+    expect(result.violations).toEqual(['3 high (max 2)']);
+    expect(result.counts.high).toBe(3);
+  });
+
+  it('should block on listed categories', () => {
+    const result = evaluatePolicy([comment('low', 'security'), comment('low', 'style')], { block_categories: ['security'] });
+    expect(result.violations).toEqual(['1 security finding']);
+  });
+
+  it('should validate policies from config', () => {
+    expect(parsePolicy({ max_critical: 0, block_categories: ['Security'] })).toEqual({ max_critical: 0, block_categories: ['security'] });
+    expect(() => parsePolicy({ max_high: -1 })).toThrow('non-negative');
+    expect(() => parsePolicy({ max_hihg: 2 })).toThrow('Unknown severity_policy keys: max_hihg');
+    expect(() => parsePolicy([])).toThrow('must be an object');
+  });
+
+  it('should describe the result within the commit status limit', () => {
+    expect(describePolicy({ passed: true, violations: [], counts: {} })).toBe('Review policy passed');
+    const long = describePolicy({ passed: false, violations: Array(20).fill('9 critical (none allowed)'), counts: {} });
+    expect(long.length).toBe(140);
+    expect(long.startsWith('Blocked: ')).toBe(true);
+  });
+});
+
+describe('Open Findings', () => {
+  const at = (line: number, path = 'src/app.ts', start_line?: number): LineComment => ({
+    path, line, body: 'issue', severity: 'critical', category: 'security', ...(start_line && { start_line }),
+  });
+  const increment = parseDiff([
+    'diff --git a/src/app.ts b/src/app.ts',
+    '@@ -10,2 +10,3 @@',
+    ' context',
+    '-old',
+    '+new',
+    '+added',
+    '@@ -40,0 +42,2 @@',
+    '+x',
+    '+y',
+  ].join('\n'));
+
+  it('should drop findings on lines the new pass saw', () => {
+    expect(carryOpenFindings([at(11), at(12, 'src/app.ts', 9)], increment)).toEqual([]);
+  });
+
+  it('should move the rest with the lines around them', () => {
+    const carried = carryOpenFindings([at(5), at(20), at(40), at(50, 'src/app.ts', 45), at(3, 'src/other.ts'), at(0)], increment);
+    expect(carried.map((c) => [c.path, c.start_line, c.line])).toEqual([
+      ['src/app.ts', undefined, 5],
+      ['src/app.ts', undefined, 21],
+      ['src/app.ts', undefined, 41],
+      ['src/app.ts', 48, 53],
+      ['src/other.ts', undefined, 3],
+      ['src/app.ts', undefined, 0],
+    ]);
+  });
+
+  it('should read open findings from stored reviews, old and new', () => {
+    expect(openFindings({ line_comments: [at(1)] })).toEqual([at(1)]);
+    expect(openFindings({ line_comments: [], open_findings: [at(2)] })).toEqual([at(2)]);
+    expect(openFindings(null)).toEqual([]);
+  });
+
+  it('should block on earlier findings an increment left open', () => {
+    const review = applyPolicy({ ...parseFailedResult(), approval_recommendation: 'approve', line_comments: [], open_findings: [at(5)] }, { max_critical: 0 });
+    expect(review.policy!.passed).toBe(false);
+    expect(review.approval_recommendation).toBe('request_changes');
+  });
+
+  it('should leave the verdict to the model when the repo has no policy', () => {
+    const blocking = applyPolicy({ ...parseFailedResult(), approval_recommendation: 'approve', line_comments: [at(5)] }, null);
+    expect(blocking.policy).toBeUndefined();
+    expect(blocking.approval_recommendation).toBe('approve');
+
+    const cautious = applyPolicy({ ...parseFailedResult(), approval_recommendation: 'request_changes', line_comments: [] });
+    expect(cautious.approval_recommendation).toBe('request_changes');
+  });
+});
@@ -0,0 +1,57 @@
+import { describe, it, expect, vi, afterEach } from 'vitest';
+import { jobPriority, startHeartbeat } from '../queue';
+
+describe('Job Leases', () => {
+  afterEach(() => {
+    vi.useRealTimers();
+  });
+
+  it('should renew the lease until stopped', async () => {
+    vi.useFakeTimers();
+    const beat = vi.fn().mockResolvedValue(true);
+    const stop = startHeartbeat(beat, 1000);
+
+    await vi.advanceTimersByTimeAsync(3500);
+    expect(beat).toHaveBeenCalledTimes(3);
+
+    stop();
+    await vi.advanceTimersByTimeAsync(5000);
+    expect(beat).toHaveBeenCalledTimes(3);
+  });
+
+  it('should report a lost lease once and stop renewing', async () => {
+    vi.useFakeTimers();
+    const beat = vi.fn().mockResolvedValueOnce(true).mockResolvedValue(false);
+    const onLost = vi.fn();
+    startHeartbeat(beat, 1000, onLost);
+
+    await vi.advanceTimersByTimeAsync(5000);
+    expect(onLost).toHaveBeenCalledTimes(1);
+    expect(beat).toHaveBeenCalledTimes(2);
+  });
+
+  it('should keep renewing through transient heartbeat errors', async () => {
+    vi.useFakeTimers();
+    const beat = vi.fn().mockRejectedValueOnce(new Error('network')).mockResolvedValue(true);
+    const onLost = vi.fn();
+    const stop = startHeartbeat(beat, 1000, onLost);
+
+    await vi.advanceTimersByTimeAsync(2500);
+    expect(onLost).not.toHaveBeenCalled();
+    expect(beat).toHaveBeenCalledTimes(2);
+    stop();
+  });
+});
+
+describe('Job Priority', () => {
+  it('should rank jobs by the analysis priority and score', () => {
+    expect(jobPriority({ priority: 'critical', score: 9 })).toEqual({ priority: 3, score: 9 });
+    expect(jobPriority({ priority: 'low', score: 0 })).toEqual({ priority: 0, score: 0 });
+    expect(jobPriority({ priority: 'high', score: 5.6 }).score).toBe(6);
+  });
+
+  it('should default jobs without analysis to medium', () => {
+    expect(jobPriority()).toEqual({ priority: 1, score: 0 });
+    expect(jobPriority({ priority: 'urgent' })).toEqual({ priority: 1, score: 0 });
+  });
+});
@@ -0,0 +1,128 @@
+import { describe, it, expect, vi, afterEach } from 'vitest';
+import { applyRepoEvent, repoChangesFor } from '../repo-sync';
+import { rememberInstallation } from '../github-app';
+import { createSqlStorage, hasServiceRole, setStorage, type SqlDriver, type Storage } from '../storage';
+
+const repository = { full_name: 'acme/api-v2', name: 'api-v2', owner: { login: 'acme' } };
+
+describe('Repo Sync', () => {
+  it('should register repos when the app is installed or repos are added', () => {
+    expect(repoChangesFor('installation', {
+      action: 'created',
+      installation: { id: 9 },
+      repositories: [{ full_name: 'acme/api' }, { full_name: 'acme/web' }],
+    })).toEqual([{ type: 'register', repos: ['acme/api', 'acme/web'], installationId: 9 }]);
+
+    expect(repoChangesFor('installation_repositories', {
+      action: 'added',
+      installation: { id: 9 },
+      repositories_added: [{ full_name: 'acme/new' }],
+      repositories_removed: [{ full_name: 'acme/old' }],
+    })).toEqual([
+      { type: 'register', repos: ['acme/new'], installationId: 9 },
+      { type: 'disable', repos: ['acme/old'], reason: 'removed from installation' },
+    ]);
+  });
+
+  it('should disable every repo of an uninstalled app', () => {
+    const [change] = repoChangesFor('installation', { action: 'deleted', installation: { id: 9 } });
+    expect(change).toEqual({ type: 'disable', repos: [], reason: 'installation deleted', installationId: 9 });
+  });
+
+  it('should migrate renamed and transferred repos', () => {
+    expect(repoChangesFor('repository', {
+      action: 'renamed',
+      repository,
+      changes: { repository: { name: { from: 'api' } } },
+    })).toEqual([{ type: 'rename', from: 'acme/api', to: 'acme/api-v2' }]);
+
+    expect(repoChangesFor('repository', {
+      action: 'transferred',
+      repository,
+      changes: { owner: { from: { user: { login: 'alice' } } } },
+    })).toEqual([{ type: 'rename', from: 'alice/api-v2', to: 'acme/api-v2' }]);
+  });
+
+  it('should disable archived and deleted repos', () => {
+    for (const action of ['archived', 'deleted']) {
+      expect(repoChangesFor('repository', { action, repository })).toEqual([
+        { type: 'disable', repos: ['acme/api-v2'], reason: `repository ${action}` },
+      ]);
+    }
+    expect(repoChangesFor('repository', { action: 'publicized', repository })).toEqual([]);
+  });
+});
+
+describe('Repo Sync on Storage', () => {
+  afterEach(() => {
+    setStorage(undefined);
+  });
+
+  it('should re-enable only the repos it switched off itself', async () => {
+    const upsert = vi.fn().mockResolvedValue([
+      { full_name: 'acme/api', enabled: false, disabled_reason: null },
+      { full_name: 'acme/web', enabled: false, disabled_reason: 'installation deleted' },
+    ]);
+    const update = vi.fn().mockResolvedValue([]);
+    setStorage({ configs: { upsert, update } } as unknown as Storage);
+
+    await applyRepoEvent('installation', {
+      action: 'created',
+      installation: { id: 9 },
+      repositories: [{ full_name: 'acme/api' }, { full_name: 'acme/web' }],
+    });
+
+    expect(upsert).toHaveBeenCalledWith([
+      { full_name: 'acme/api', installation_id: 9 },
+      { full_name: 'acme/web', installation_id: 9 },
+    ]);
+    expect(update).toHaveBeenCalledTimes(1);
+    expect(update).toHaveBeenCalledWith({ full_names: ['acme/web'] }, { enabled: true, disabled_reason: null });
+  });
+
+  it('should write lifecycle changes with the service role, not the anon client', async () => {
+    // RLS filters anon writes to repo_configs without an error, so check the scope each call ran in
+    const roles: boolean[] = [];
+    const record = async () => { roles.push(hasServiceRole()); return []; };
+    setStorage({ configs: { upsert: vi.fn(record), update: vi.fn(record), rename: vi.fn(record) } } as unknown as Storage);
+
+    await applyRepoEvent('installation', { action: 'created', installation: { id: 9 }, repositories: [{ full_name: 'acme/api' }] });
+    await applyRepoEvent('installation', { action: 'deleted', installation: { id: 9 }, repositories: [{ full_name: 'acme/api' }] });
+    await applyRepoEvent('repository', { action: 'renamed', repository, changes: { repository: { name: { from: 'api' } } } });
+    await rememberInstallation('acme/web', 12);
+
+    expect(roles).toHaveLength(5);
+    expect(roles.every(Boolean)).toBe(true);
+    expect(hasServiceRole()).toBe(false);
+  });
+
+  it('should move learnings and job coordinates and fold a config registered under the new name', async () => {
+    const calls: { sql: string; params: unknown[] }[] = [];
+    const driver: SqlDriver = {
+      dialect: 'sqlite',
+      query: async <T,>(sql: string, params: unknown[] = []) => {
+        calls.push({ sql, params });
+        if (sql.startsWith('SELECT id, installation_id FROM repo_configs')) return [{ id: 'cfg-old', installation_id: null }] as T[];
+        if (sql.startsWith('SELECT installation_id FROM repo_configs')) return [{ installation_id: 9 }] as T[];
+        return [];
+      },
+      transaction: async (fn) => fn(driver),
+      close: async () => {},
+    };
+    await createSqlStorage('sqlite', async () => driver).configs.rename('acme/api', 'acme/api-v2');
+
+    expect(calls).toContainEqual({ sql: 'DELETE FROM repo_configs WHERE full_name = ?', params: ['acme/api-v2'] });
+    const moved = calls.find((c) => c.sql.startsWith('UPDATE repo_configs'))!;
+    expect(moved.params).toEqual(expect.arrayContaining(['acme/api-v2', 9, 'cfg-old']));
+    expect(calls).toContainEqual({
+      sql: 'UPDATE review_learnings SET repo_full_name = ? WHERE repo_full_name = ?',
+      params: ['acme/api-v2', 'acme/api'],
+    });
+    for (const table of ['review_jobs', 'review_jobs_dlq']) {
+      expect(calls).toContainEqual({
+        sql: `UPDATE ${table} SET repo_full_name = ?, owner = ?, repo = ? WHERE repo_full_name = ?`,
+        params: ['acme/api-v2', 'acme', 'api-v2', 'acme/api'],
+      });
+    }
+  });
+});
@@ -0,0 +1,126 @@
+import { describe, it, expect } from 'vitest';
+import { generateReview, publishReview, Severity, StaleReviewError, type ReviewResponse, type ReviewRuntime } from '../review/engine';
+
+const diff = `diff --git a/src/app.ts b/src/app.ts
+index 111..222 100644
+--- a/src/app.ts
++++ b/src/app.ts
+@@ -1,2 +1,3 @@
+ const a = 1;
++const b = eval(input);
+ export default a;`;
+
+const response = (severity: Severity): ReviewResponse => ({
+  summary: { overview: 'Looks risky', changes_description: 'Adds b', risk_assessment: 'High', recommendations: [] },
+  walkthrough: [],
+  line_comments: [{ path: 'src/app.ts', line: 2, body: 'Avoid eval', severity, category: 'security' }],
+  approval_recommendation: 'approve',
+});
+
+function fakeRuntime(review: ReviewResponse | null, overrides: Partial<ReviewRuntime> = {}) {
+  const calls = { prompts: [] as string[], compared: [] as string[], reviews: [] as unknown[][], statuses: [] as unknown[][] };
+  let failReviews = 0;
+  const runtime: ReviewRuntime = {
+    github: {
+      pullRequest: async () => ({ title: 'Add b', body: null, head: { sha: 'head' } }),
+      diff: async () => diff,
+      compare: async (base, head) => { calls.compared.push(`${base}...${head}`); return diff; },
+      createReview: async (...args) => {
+        calls.reviews.push(args);
+        if (failReviews-- > 0) throw new Error('422');
+        return { id: 7 };
+      },
+      setStatus: async (...args) => { calls.statuses.push(args); },
+    },
+    complete: async (prompt) => {
+      calls.prompts.push(prompt);
+      return { review: review && JSON.parse(JSON.stringify(review)), servedBy: { provider: 'groq', model: 'llama' } };
+    },
+    config: {},
+    learnings: [],
+    ...overrides,
+  };
+  return { runtime, calls, failNextReview: () => { failReviews = 1; } };
+}
+
+describe('Review Engine', () => {
+  it('should review the diff in one call and apply the severity policy', async () => {
+    const { runtime, calls } = fakeRuntime(response(Severity.CRITICAL), { config: { severity_policy: { max_critical: 0 } } });
+    const { review, headSha, isIncremental } = await generateReview(runtime);
+
+    expect(calls.prompts).toHaveLength(1);
+    expect(calls.prompts[0]).toContain('eval(input)');
+    expect(headSha).toBe('head');
+    expect(isIncremental).toBe(false);
+    expect(review.policy?.passed).toBe(false);
+    expect(review.approval_recommendation).toBe('request_changes');
+    expect(review.served_by).toEqual([{ provider: 'groq', model: 'llama' }]);
+  });
+
+  it('should review only new commits since the last reviewed sha', async () => {
+    const { runtime, calls } = fakeRuntime(response(Severity.LOW), { lastReviewedSha: 'old' });
+    const { isIncremental, review } = await generateReview(runtime);
+
+    expect(isIncremental).toBe(true);
+    expect(calls.compared).toEqual(['old...head']);
+    expect(review.approval_recommendation).toBe('approve');
+  });
+
+  it('should fall back to a failed result when the output never validates', async () => {
+    const { runtime } = fakeRuntime(null);
+    const { review } = await generateReview(runtime);
+
+    expect(review.summary.overview).toContain('Review failed');
+    expect(review.line_comments).toEqual([]);
+    expect(review.policy).toBeUndefined();
+  });
+
+  it('should keep the model verdict and post no policy status without a policy', async () => {
+    const { runtime, calls } = fakeRuntime(response(Severity.CRITICAL));
+    const generated = await generateReview(runtime);
+    expect(generated.review.approval_recommendation).toBe('approve');
+
+    await publishReview(runtime, generated);
+    expect(calls.reviews[0]![1]).toBe('APPROVE');
+    expect(calls.statuses).toEqual([]);
+  });
+
+  it('should post inline comments and the policy status', async () => {
+    const { runtime, calls } = fakeRuntime(response(Severity.HIGH), { config: { severity_policy: { max_critical: 0 } } });
+    const { reviewId, placed } = await publishReview(runtime, await generateReview(runtime));
+
+    expect(reviewId).toBe(7);
+    expect(placed).toHaveLength(1);
+    expect(calls.reviews[0]![1]).toBe('APPROVE');
+    expect(calls.statuses[0]!.slice(0, 3)).toEqual(['head', 'success', 'ai-review/policy']);
+  });
+
+  it('should repost as a plain comment when GitHub rejects inline comments', async () => {
+    const { runtime, calls, failNextReview } = fakeRuntime(response(Severity.HIGH));
+    failNextReview();
+    const { reviewId, placed } = await publishReview(runtime, await generateReview(runtime));
+
+    expect(reviewId).toBeUndefined();
+    expect(placed).toEqual([]);
+    expect(calls.reviews[1]!.slice(1)).toEqual(['COMMENT', []]);
+    expect(calls.reviews[1]![0]).toContain('Avoid eval');
+  });
+
+  it('should refuse to review a commit that is no longer the PR head', async () => {
+    const { runtime, calls } = fakeRuntime(response(Severity.LOW));
+
+    await expect(generateReview(runtime, { headSha: 'older' })).rejects.toBeInstanceOf(StaleReviewError);
+    expect(calls.prompts).toEqual([]);
+  });
+
+  it('should not post when new commits arrive during the review', async () => {
+    const heads = ['head', 'newer'];
+    const { runtime, calls } = fakeRuntime(response(Severity.LOW));
+    runtime.github.pullRequest = async () => ({ title: 'Add b', head: { sha: heads.shift() || 'newer' } });
+
+    const generated = await generateReview(runtime);
+    await expect(publishReview(runtime, generated)).rejects.toThrow('PR head moved from head to newer');
+    expect(calls.reviews).toEqual([]);
+    expect(calls.statuses).toEqual([]);
+  });
+});
@@ -0,0 +1,116 @@
+import { describe, it, expect, vi, afterEach } from 'vitest';
+import { assertSarif, sarifProvenance, sarifToFindings, scanToSarif, scoreFindings, type ScanFinding } from '../review/engine';
+import { importSarif, scanSarif } from '../sarif';
+import { setStorage, type SecurityScan, type Storage } from '../storage';
+
+const findings: ScanFinding[] = [
+  {
+    severity: 'critical', type: 'security', title: 'SQL injection in user lookup', file: 'src/db.ts', line: 12,
+    problem: 'Query built from request input', fix: 'Use a parameterized query', cwe: 'CWE-89',
+  },
+  { severity: 'medium', type: 'quality', title: 'Function too long', file: './src/app.ts', line: 0 },
+  { severity: 'low', type: 'bug', title: 'Missing file', file: null },
+  { severity: 'high', type: 'security', title: 'Eval of user input', file: 'src/run.js', line: 3, tool: 'semgrep', rule: 'js.eval', help_url: 'https://semgrep.dev/r/js.eval' },
+];
+
+const scan = { repo_full_name: 'acme/api', issues: findings, scan_metadata: { commit_sha: 'abc123', branch: 'main' } };
+
+describe('SARIF Export', () => {
+  const log = scanToSarif(scan) as { version: string; runs: any[] };
+
+  it('should write one run per tool with repo provenance', () => {
+    expect(log.version).toBe('2.1.0');
+    expect(log.runs.map((r) => r.tool.driver.name)).toEqual(['FoodShare Scan', 'semgrep']);
+    expect(log.runs[0].versionControlProvenance).toEqual([
+      { repositoryUri: 'https://github.com/acme/api', revisionId: 'abc123', branch: 'main' },
+    ]);
+  });
+
+  it('should map severity, CWE and location', () => {
+    const [sqli, long, missing] = log.runs[0].results;
+    const rule = log.runs[0].rules[sqli.ruleIndex];
+    expect(sqli).toMatchObject({
+      ruleId: 'security/cwe-89',
+      level: 'error',
+      locations: [{ physicalLocation: { artifactLocation: { uri: 'src/db.ts' }, region: { startLine: 12 } } }],
+      properties: { severity: 'critical', cwe: 'CWE-89' },
+    });
+    expect(sqli.message.text).toContain('Fix: Use a parameterized query');
+    expect(rule.properties).toEqual({ tags: ['security', 'external/cwe/cwe-89'], 'security-severity': '9.5' });
+
+    expect(long).toMatchObject({ level: 'warning', locations: [{ physicalLocation: { artifactLocation: { uri: 'src/app.ts' }, region: { startLine: 1 } } }] });
+    expect(log.runs[0].rules[long.ruleIndex].properties['security-severity']).toBeUndefined();
+    expect(missing.locations).toBeUndefined();
+  });
+
+  it('should keep imported findings under their tool and rule', () => {
+    const [result] = log.runs[1].results;
+    expect(result).toMatchObject({ ruleId: 'js.eval', level: 'error' });
+    expect(log.runs[1].rules[0]).toMatchObject({ id: 'js.eval', helpUri: 'https://semgrep.dev/r/js.eval' });
+  });
+
+  it('should fall back to legacy findings for older scans', () => {
+    const legacy = { repo_full_name: 'acme/api', issues: [], findings: [findings[0]] } as unknown as SecurityScan;
+    expect((scanSarif(legacy) as { runs: any[] }).runs[0].results).toHaveLength(1);
+  });
+});
+
+describe('SARIF Import', () => {
+  afterEach(() => {
+    setStorage(undefined);
+  });
+
+  it('should read back what it exports', () => {
+    const imported = sarifToFindings(scanToSarif(scan));
+    expect(imported.map((f) => [f.severity, f.type, f.file, f.line, f.cwe, f.tool])).toEqual([
+      ['critical', 'security', 'src/db.ts', 12, 'CWE-89', null],
+      ['medium', 'quality', 'src/app.ts', 1, null, null],
+      ['high', 'security', 'src/run.js', 3, null, 'semgrep'],
+    ]);
+    expect(imported[0]!.title).toBe('SQL injection in user lookup');
+  });
+
+  it('should reject anything but SARIF 2.1.0', () => {
+    expect(() => assertSarif({ version: '2.0.0', runs: [] })).toThrow('Not a SARIF 2.1.0 log');
+    expect(() => assertSarif([])).toThrow();
+    expect(() => assertSarif({ version: '2.1.0', runs: [] })).not.toThrow();
+  });
+
+  it('should find the repo in version control provenance', () => {
+    const log = { version: '2.1.0', runs: [{ versionControlProvenance: [{ repositoryUri: 'git@github.com:acme/web.git', revisionId: 'def', branch: 'refs/heads/dev' }] }] };
+    expect(sarifProvenance(log)).toEqual({ repo: 'acme/web', commit_sha: 'def', branch: 'dev' });
+    expect(sarifProvenance({ version: '2.1.0', runs: [{}] })).toEqual({ repo: null });
+  });
+
+  it('should score findings the way scans do', () => {
+    expect(scoreFindings(findings)).toEqual({
+      score: 53,
+      grade: 'F',
+      threat_level: 'CRITICAL',
+      by_severity: { critical: 1, high: 1, medium: 1, low: 1 },
+    });
+    expect(scoreFindings([])).toMatchObject({ score: 100, grade: 'A', threat_level: 'SAFE' });
+  });
+
+  it('should store an imported log as a scored scan', async () => {
+    const insert = vi.fn(async (row: Record<string, unknown>) => ({ ...row, id: 's1', created_at: '2026-01-01T00:00:00Z' }));
+    const audit = vi.fn();
+    setStorage({ scans: { insert }, audit: { insert: audit } } as unknown as Storage);
+
+    const sarif = scanToSarif({ repo_full_name: 'acme/web', issues: [findings[3]!] });
+    const stored = await importSarif(sarif, undefined, 'admin-1');
+
+    expect(stored.repo_full_name).toBe('acme/web');
+    expect(insert.mock.lastCall![0]).toMatchObject({
+      security_score: 85,
+      files_scanned: 1,
+      scan_metadata: { grade: 'B', source: 'sarif', tools: ['semgrep'] },
+    });
+    expect(audit).toHaveBeenCalledWith(expect.objectContaining({ action: 'scan.imported', actor_id: 'admin-1', resource_id: 's1' }));
+  });
+
+  it('should require a repo when the log names none', async () => {
+    setStorage({ scans: { insert: vi.fn() } } as unknown as Storage);
+    await expect(importSarif({ version: '2.1.0', runs: [] })).rejects.toThrow('Repository');
+  });
+});
@@ -0,0 +1,115 @@
+import { describe, it, expect } from 'vitest';
+import {
+  assignFindings, chunkFile, coverageSummary, formatScanBatch, packScanBatches, planScanFiles, scanCoverage, type ScanFinding,
+} from '../review/engine';
+
+describe('Scan Planning', () => {
+  const tree = [
+    { path: 'src', type: 'tree' },
+    { path: 'src/utils/math.ts', type: 'blob', size: 300, sha: 'b' },
+    { path: 'src/auth/login.ts', type: 'blob', size: 400, sha: 'a' },
+    { path: 'src/routes/users.ts', type: 'blob', size: 200000, sha: 'c' },
+    { path: 'src/api/handler.ts', type: 'blob', size: 500, sha: 'd' },
+    { path: 'node_modules/x/index.js', type: 'blob', size: 500, sha: 'e' },
+    { path: 'README.md', type: 'blob', size: 500, sha: 'f' },
+    { path: 'src/index.ts', type: 'blob', size: 20, sha: 'g' },
+  ];
+
+  it('should keep every source file, most sensitive first', () => {
+    const files = planScanFiles(tree, { maxFileBytes: 100000, maxFiles: 10 });
+    expect(files.map((f) => f.path)).toEqual(['src/api/handler.ts', 'src/auth/login.ts', 'src/routes/users.ts', 'src/utils/math.ts']);
+    expect(files[0]).toEqual({ path: 'src/api/handler.ts', blob_sha: 'd', size: 500, priority: 0, status: 'pending' });
+  });
+
+  it('should record why files are skipped', () => {
+    const files = planScanFiles(tree, { maxFileBytes: 100000, maxFiles: 2 });
+    expect(files.map((f) => [f.status, f.skip_reason])).toEqual([
+      ['pending', undefined],
+      ['pending', undefined],
+      ['skipped', 'too large'],
+      ['skipped', 'file limit'],
+    ]);
+  });
+});
+
+describe('Scan Batching', () => {
+  it('should split files into line-numbered chunks', () => {
+    const chunks = chunkFile('a.ts', 'a\nb\nc\nd\ne', 10);
+    expect(chunks.map((c) => [c.start_line, c.end_line])).toEqual([[1, 2], [3, 4], [5, 5]]);
+    expect(chunks[0]!.text).toBe('1| a\n2| b');
+  });
+
+  it('should pad line numbers and cut overlong lines', () => {
+    const content = ['x'.repeat(600), ...Array.from({ length: 11 }, (_, i) => `line ${i}`)].join('\n');
+    const [chunk] = chunkFile('min.js', content, 10000);
+    const [first, second] = chunk!.text.split('\n');
+    expect(first).toBe(` 1| ${'x'.repeat(500)} …`);
+    expect(second).toBe(' 2| line 0');
+    expect(chunk!.end_line).toBe(12);
+  });
+
+  it('should pack chunks in order within the budget', () => {
+    const chunks = [
+      ...chunkFile('src/a.ts', Array.from({ length: 40 }, (_, i) => `const a${i} = ${i};`).join('\n'), 200),
+      ...chunkFile('src/b.ts', 'export const b = 1;', 200),
+    ];
+    const batches = packScanBatches(chunks, 500);
+
+    expect(batches.length).toBeGreaterThan(1);
+    expect(batches.flat()).toEqual(chunks);
+    for (const batch of batches) expect(formatScanBatch(batch).length).toBeLessThanOrEqual(500);
+    expect(formatScanBatch(batches.at(-1)!)).toContain('=== src/b.ts (lines 1-1) ===\n1| export const b = 1;');
+  });
+
+  it('should give a chunk over the budget a batch of its own', () => {
+    const big = { path: 'big.ts', start_line: 1, end_line: 1, text: 'x'.repeat(100) };
+    const small = { path: 'small.ts', start_line: 1, end_line: 1, text: 'y' };
+    expect(packScanBatches([small, big, small], 50)).toEqual([[small], [big], [small]]);
+  });
+});
+
+describe('Finding Assignment', () => {
+  const finding = (file: string | null): ScanFinding => ({ severity: 'high', title: 'Issue', file });
+
+  it('should match findings to batch files by path or suffix', () => {
+    const byPath = assignFindings(
+      [finding('src/a.ts'), finding('./lib/b.ts'), finding('b.ts'), finding('repo/src/a.ts'), finding('other.ts'), finding(null)],
+      ['src/a.ts', 'lib/b.ts'],
+    );
+    expect(byPath.get('src/a.ts')!.map((f) => f.file)).toEqual(['src/a.ts', 'src/a.ts']);
+    expect(byPath.get('lib/b.ts')!.map((f) => f.file)).toEqual(['lib/b.ts', 'lib/b.ts']);
+  });
+
+  it('should give a single-file batch every finding', () => {
+    const byPath = assignFindings([finding(null), finding('wrong.ts')], ['src/a.ts']);
+    expect(byPath.get('src/a.ts')!.map((f) => f.file)).toEqual(['src/a.ts', 'src/a.ts']);
+  });
+});
+
+describe('Scan Coverage', () => {
+  it('should count every file and say what was left out', () => {
+    const coverage = scanCoverage([
+      { status: 'scanned' },
+      { status: 'scanned' },
+      { status: 'scanned' },
+      { status: 'skipped', skip_reason: 'too large' },
+      { status: 'skipped', skip_reason: 'file limit' },
+      { status: 'failed' },
+    ]);
+    expect(coverage).toEqual({
+      total_files: 6,
+      scanned_files: 3,
+      skipped_files: 2,
+      failed_files: 1,
+      pending_files: 0,
+      percent: 50,
+      skipped_reasons: { 'too large': 1, 'file limit': 1 },
+    });
+    expect(coverageSummary(coverage)).toBe('Scanned 3 of 6 source files (50%): 2 skipped (1 too large, 1 file limit), 1 failed.');
+  });
+
+  it('should report full coverage plainly', () => {
+    expect(coverageSummary(scanCoverage([{ status: 'scanned' }]))).toBe('Scanned 1 of 1 source files (100%).');
+    expect(scanCoverage([]).percent).toBe(0);
+  });
+});
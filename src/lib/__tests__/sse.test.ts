@@ -0,0 +1,31 @@
+// @vitest-environment node
+import { describe, it, expect } from 'vitest';
+import { encodeSSE, parseSSE } from '../sse';
+
+function streamOf(chunks: string[]): ReadableStream<Uint8Array> {
+  const encoder = new TextEncoder();
+  return new ReadableStream({
+    start(controller) {
+      chunks.forEach((c) => controller.enqueue(encoder.encode(c)));
+      controller.close();
+    },
+  });
+}
+
+async function collect<T>(iter: AsyncIterable<T>): Promise<T[]> {
+  const out: T[] = [];
+  for await (const item of iter) out.push(item);
+  return out;
+}
+
+describe('Server-Sent Events', () => {
+  it('should round-trip server-sent events across chunk boundaries', async () => {
+    const raw = encodeSSE('token', { text: 'hel' }) + encodeSSE('token', { text: 'lo\nworld' }) + encodeSSE('done', 'ok');
+    const chunks = [raw.slice(0, 7), raw.slice(7, 30), raw.slice(30)];
+
+    const events = await collect(parseSSE(streamOf(chunks)));
+    expect(events.map((e) => e.event)).toEqual(['token', 'token', 'done']);
+    expect(JSON.parse(events[1]!.data)).toEqual({ text: 'lo\nworld' });
+    expect(events[2]!.data).toBe('ok');
+  });
+});
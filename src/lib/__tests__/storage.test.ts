@@ -0,0 +1,237 @@
+import { describe, it, expect, vi, afterEach } from 'vitest';
+import { createStorage, hasServiceRole, setStorage, type Storage } from '../storage';
+import { createSqlStorage, decodeRow, dlqMatch, encodeRow, rankJobs, where, type RankedJob, type SqlDriver } from '../storage/sql';
+import { schemaStatements } from '../storage/schema';
+import { numberPlaceholders, sqliteParam } from '../storage/drivers';
+import { enqueueReview, failJob } from '../queue';
+
+const now = new Date('2026-01-01T12:00:00Z');
+const minutesAgo = (m: number) => new Date(now.getTime() - m * 60000).toISOString();
+
+const job = (id: string, repo: string, priority: number, waited: number, score = 0): RankedJob => ({
+  id,
+  repo_full_name: repo,
+  priority,
+  score,
+  created_at: minutesAgo(waited),
+});
+
+// Records every statement and answers with canned rows
+function fakeDriver(answer: (sql: string, params: unknown[]) => unknown[] = () => []) {
+  const calls: { sql: string; params: unknown[] }[] = [];
+  const driver: SqlDriver = {
+    dialect: 'sqlite',
+    query: async <T,>(sql: string, params: unknown[] = []) => {
+      calls.push({ sql, params });
+      return answer(sql, params) as T[];
+    },
+    transaction: async (fn) => fn(driver),
+    close: async () => {},
+  };
+  return { driver, calls };
+}
+
+describe('Job Ranking', () => {
+  const options = { repoLimit: 2, agingMinutes: 15 };
+
+  it('should order by priority, then score, then age', () => {
+    const ranked = rankJobs([
+      job('low', 'a/a', 0, 1),
+      job('high', 'a/a', 2, 1),
+      job('med-old', 'a/a', 1, 2),
+      job('med-scored', 'a/a', 1, 1, 80),
+    ], new Map(), new Map(), options, now);
+    expect(ranked.map((j) => j.id)).toEqual(['high', 'med-scored', 'med-old', 'low']);
+  });
+
+  it('should promote jobs that have waited long enough', () => {
+    const ranked = rankJobs([job('fresh-high', 'a/a', 2, 0), job('stale-low', 'a/a', 0, 45)], new Map(), new Map(), options, now);
+    expect(ranked[0]!.id).toBe('stale-low');
+  });
+
+  it('should skip repos at their concurrency cap', () => {
+    const jobs = [job('busy', 'a/busy', 3, 0), job('capped', 'a/capped', 3, 0), job('free', 'a/free', 0, 0)];
+    const running = new Map([['a/busy', 2], ['a/capped', 1]]);
+    const caps = new Map([['a/capped', 1]]);
+    expect(rankJobs(jobs, running, caps, options, now).map((j) => j.id)).toEqual(['free']);
+  });
+});
+
+describe('SQL Storage', () => {
+  it('should convert JSON, boolean and numeric columns', () => {
+    expect(encodeRow('review_history', { result: { ok: true }, is_incremental: true, head_sha: undefined })).toEqual({
+      result: '{"ok":true}',
+      is_incremental: true,
+    });
+    expect(decodeRow('review_history', {
+      result: '{"ok":true}',
+      is_incremental: 0,
+      cost_usd: '0.25',
+      created_at: new Date('2026-01-01T00:00:00Z'),
+    })).toEqual({ result: { ok: true }, is_incremental: false, cost_usd: 0.25, created_at: '2026-01-01T00:00:00.000Z' });
+  });
+
+  it('should build WHERE clauses from the conditions that are set', () => {
+    expect(where({ 'repo_full_name = ?': 'a/b', 'status = ?': undefined, 'pr_number = ?': 3 })).toEqual({
+      clause: 'WHERE repo_full_name = ? AND pr_number = ?',
+      params: ['a/b', 3],
+    });
+    expect(where({ 'status = ?': '' })).toEqual({ clause: '', params: [] });
+  });
+
+  it('should write the schema for each dialect', () => {
+    const postgres = schemaStatements('postgres').join('\n');
+    const sqlite = schemaStatements('sqlite').join('\n');
+    expect(postgres).toContain('error_history JSONB');
+    expect(postgres).toContain('created_at TIMESTAMPTZ NOT NULL');
+    expect(sqlite).toContain('error_history TEXT');
+    expect(sqlite).toContain('is_incremental INTEGER NOT NULL DEFAULT 0');
+    expect(sqlite).not.toMatch(/\{\w+\}/);
+  });
+
+  it('should adapt placeholders and parameters per driver', () => {
+    expect(numberPlaceholders('SELECT * FROM t WHERE a = ? AND b IN (?, ?)')).toBe('SELECT * FROM t WHERE a = $1 AND b IN ($2, $3)');
+    expect([true, false, undefined, new Date('2026-01-01T00:00:00Z'), 'x'].map(sqliteParam))
+      .toEqual([1, 0, null, '2026-01-01T00:00:00.000Z', 'x']);
+  });
+
+  it('should create tables once, on first use', async () => {
+    const { driver, calls } = fakeDriver();
+    const storage = createSqlStorage('sqlite', async () => driver);
+    await storage.ping();
+    await storage.ping();
+    const creates = calls.filter((c) => c.sql.startsWith('CREATE TABLE'));
+    expect(creates).toHaveLength(12);
+    expect(calls.filter((c) => c.sql === 'SELECT 1')).toHaveLength(2);
+  });
+
+  it('should claim the best ranked job and record expired leases', async () => {
+    const { driver, calls } = fakeDriver((sql) => {
+      if (sql.includes("status = 'processing'") && sql.startsWith('SELECT *')) {
+        return [{ id: 'stuck', attempts: 0, max_attempts: 3, worker_id: 'dead', error: null, error_history: '[]' }];
+      }
+      if (sql.includes("status = 'pending' AND (next_retry_at")) {
+        return [job('low', 'a/a', 0, 1), job('high', 'a/a', 2, 1)];
+      }
+      if (sql.startsWith('UPDATE review_jobs') && sql.includes("AND status = 'pending'")) return [{ id: 'high', status: 'processing' }];
+      return [];
+    });
+    const storage = createSqlStorage('sqlite', async () => driver);
+
+    const claimed = await storage.jobs.claim('w1', { leaseSeconds: 60, repoLimit: 2, agingMinutes: 15 });
+    expect(claimed?.id).toBe('high');
+
+    const requeue = calls.find((c) => c.sql.startsWith('UPDATE review_jobs') && c.params.includes('stuck'))!;
+    expect(requeue.params).toContain('pending');
+    const history = JSON.parse(requeue.params.find((p) => typeof p === 'string' && p.startsWith('[')) as string);
+    expect(history).toEqual([expect.objectContaining({ attempt: 1, error: 'Lease expired on worker dead', worker_id: 'dead' })]);
+  });
+
+  it('should send an expired lease with no attempts left straight to the DLQ', async () => {
+    const { driver, calls } = fakeDriver((sql) => {
+      if (sql.includes("status = 'processing'") && sql.startsWith('SELECT *')) {
+        return [{
+          id: 'crashed', repo_full_name: 'a/a', pr_number: 7, owner: 'a', repo: 'a', attempts: 2, max_attempts: 3,
+          worker_id: 'dead', error: 'boom', error_history: '[]', created_at: minutesAgo(30),
+        }];
+      }
+      if (sql.startsWith('INSERT')) return [{ id: 'dlq-1' }];
+      return [];
+    });
+    const storage = createSqlStorage('sqlite', async () => driver);
+    await storage.jobs.claim('w1', { leaseSeconds: 60, repoLimit: 2, agingMinutes: 15 });
+
+    const moved = calls.find((c) => c.sql.startsWith('INSERT INTO review_jobs_dlq'))!;
+    expect(moved.params).toEqual(expect.arrayContaining(['crashed', 'a/a', 7, 3, 'Lease expired on worker dead']));
+    const history = JSON.parse(moved.params.find((p) => typeof p === 'string' && p.startsWith('[')) as string);
+    expect(history).toEqual([expect.objectContaining({ attempt: 3, error: 'Lease expired on worker dead' })]);
+    expect(calls.some((c) => c.sql === 'DELETE FROM review_jobs WHERE id = ?' && c.params[0] === 'crashed')).toBe(true);
+  });
+
+  it('should move jobs out of retries for a week to the DLQ when claiming', async () => {
+    const { driver, calls } = fakeDriver((sql) => {
+      if (sql.includes("status = 'failed' AND attempts >= max_attempts")) {
+        return [{
+          id: 'dead', repo_full_name: 'a/a', pr_number: 7, owner: 'a', repo: 'a', status: 'failed', attempts: 3, max_attempts: 3,
+          error: 'boom', analysis: '{"priority":"high"}', error_history: '[]', created_at: minutesAgo(20000),
+        }];
+      }
+      if (sql.startsWith('INSERT')) return [{ id: 'dlq-1' }];
+      return [];
+    });
+    const storage = createSqlStorage('sqlite', async () => driver);
+    await storage.jobs.claim('w1', { leaseSeconds: 60, repoLimit: 2, agingMinutes: 15 });
+
+    const moved = calls.find((c) => c.sql.startsWith('INSERT INTO review_jobs_dlq'))!;
+    expect(moved.params).toEqual(expect.arrayContaining(['dead', 'a/a', 7, 'boom', '{"priority":"high"}']));
+    expect(calls.some((c) => c.sql === 'DELETE FROM review_jobs WHERE id = ?' && c.params[0] === 'dead')).toBe(true);
+  });
+
+  it('should match DLQ entries on every filter that is set', () => {
+    expect(dlqMatch({})).toEqual({ sql: '1 = 1', params: [] });
+    expect(dlqMatch({ ids: ['a', 'b'], repo: 'acme/api', error: 'Timeout', replayed: false })).toEqual({
+      sql: 'id IN (?, ?) AND repo_full_name = ? AND LOWER(error) LIKE ? AND replayed_at IS NULL',
+      params: ['a', 'b', 'acme/api', '%timeout%'],
+    });
+  });
+
+  it('should choose the backend from the environment', () => {
+    expect(createStorage({}).backend).toBe('supabase');
+    expect(createStorage({ STORAGE_BACKEND: 'sqlite' }).backend).toBe('sqlite');
+    expect(createStorage({ STORAGE_BACKEND: 'postgres', DATABASE_URL: 'postgres://localhost/db' }).backend).toBe('postgres');
+    expect(() => createStorage({ STORAGE_BACKEND: 'postgres' })).toThrow('DATABASE_URL');
+    expect(() => createStorage({ STORAGE_BACKEND: 'mongo' })).toThrow('Unknown STORAGE_BACKEND');
+  });
+});
+
+describe('Queue on Storage', () => {
+  afterEach(() => {
+    setStorage(undefined);
+  });
+
+  function fakeStorage(jobs: Partial<Storage['jobs']>) {
+    setStorage({ jobs } as Storage);
+  }
+
+  it('should refuse a second review of the same commit', async () => {
+    const create = vi.fn();
+    fakeStorage({ active: vi.fn().mockResolvedValue([{ id: 'j1', head_sha: 'abc' }]), create });
+    await expect(enqueueReview('acme', 'api', 1, undefined, 'abc')).rejects.toThrow('Review already queued');
+    expect(create).not.toHaveBeenCalled();
+  });
+
+  it('should treat a racing insert for the same commit as already queued', async () => {
+    const conflict = Object.assign(new Error('duplicate key value violates unique constraint'), { code: '23505' });
+    fakeStorage({ active: vi.fn().mockResolvedValue([]), create: vi.fn().mockRejectedValue(conflict) });
+    await expect(enqueueReview('acme', 'api', 1, undefined, 'abc')).rejects.toThrow('Review already queued');
+  });
+
+  it('should supersede older pending jobs with the service role', async () => {
+    let asService = false;
+    const supersedePending = vi.fn(async () => {
+      asService = hasServiceRole();
+      return [];
+    });
+    fakeStorage({
+      active: vi.fn().mockResolvedValue([{ id: 'j1', head_sha: 'old' }]),
+      create: vi.fn().mockResolvedValue({ id: 'j2', head_sha: 'new' }),
+      supersedePending,
+    });
+
+    await enqueueReview('acme', 'api', 1, undefined, 'new');
+    expect(supersedePending).toHaveBeenCalledWith(['j1'], 'j2', 'Superseded by new');
+    expect(asService).toBe(true);
+  });
+
+  it('should release failed jobs for retry until attempts run out', async () => {
+    const release = vi.fn();
+    fakeStorage({ release });
+
+    expect(await failJob('j1', 'boom', 1, 3, 'w1')).toBe(false);
+    expect(release).toHaveBeenLastCalledWith('j1', 'w1', expect.objectContaining({ status: 'pending', attempts: 1, error: 'boom' }));
+    expect(release.mock.lastCall![2].next_retry_at).toBeTruthy();
+
+    expect(await failJob('j1', 'boom', 3, 3, 'w1')).toBe(true);
+    expect(release).toHaveBeenLastCalledWith('j1', 'w1', expect.objectContaining({ status: 'failed', next_retry_at: null }));
+  });
+});
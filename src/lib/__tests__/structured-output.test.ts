@@ -0,0 +1,51 @@
+import { describe, it, expect } from 'vitest';
+import { extractJson, parseStructured, buildRepairPrompt, toJsonSchema } from '../llm/structured';
+import { reviewResponseSchema } from '../review/schema';
+
+describe('Structured Output', () => {
+  it('should extract JSON from fences and prose', () => {
+    expect(extractJson('```json\n{"a": 1}\n```')).toBe('{"a": 1}');
+    expect(extractJson('Here is the review:\n{"a": 1}\nThanks')).toBe('{"a": 1}');
+    expect(extractJson('[1, 2]')).toBe('[1, 2]');
+  });
+
+  it('should normalize a loosely formatted review', () => {
+    const raw = JSON.stringify({
+      summary: { overview: 'Looks fine', recommendations: null },
+      line_comments: [{ path: 'a.ts', line: '12', body: 'Bug', severity: 'HIGH', category: 'nonsense', suggestion: null }],
+      approval_recommendation: 'maybe',
+    });
+
+    const result = parseStructured(raw, reviewResponseSchema);
+    expect(result.success).toBe(true);
+    if (!result.success) return;
+    expect(result.data.summary.recommendations).toEqual([]);
+    expect(result.data.summary.risk_assessment).toBe('Unknown');
+    expect(result.data.line_comments[0]).toMatchObject({ line: 12, severity: 'high', category: 'other' });
+    expect(result.data.line_comments[0]!.suggestion).toBeUndefined();
+    expect(result.data.approval_recommendation).toBe('comment');
+  });
+
+  it('should report validation errors with paths', () => {
+    const result = parseStructured('{"summary": {}, "line_comments": [{"line": 3}]}', reviewResponseSchema);
+    expect(result.success).toBe(false);
+    if (result.success) return;
+    expect(result.error).toContain('line_comments.0.path');
+
+    const invalid = parseStructured('not json at all', reviewResponseSchema);
+    expect(invalid.success).toBe(false);
+  });
+
+  it('should include errors and previous output in the repair prompt', () => {
+    const prompt = buildRepairPrompt('Review this', '{"bad": true}', '- summary: Required');
+    expect(prompt).toContain('Review this');
+    expect(prompt).toContain('{"bad": true}');
+    expect(prompt).toContain('- summary: Required');
+  });
+
+  it('should generate a JSON schema for provider JSON modes', () => {
+    const schema = toJsonSchema(reviewResponseSchema);
+    expect(schema.type).toBe('object');
+    expect(schema.properties).toHaveProperty('line_comments');
+  });
+});
@@ -0,0 +1,124 @@
+// @vitest-environment node
+import { describe, it, expect } from 'vitest';
+import { toolFunctionSpecs, toToolArgs } from '../tools';
+import { runToolLoop, ToolLoopEvent } from '../tools/agent';
+import { ChatMessage, ProviderChatOptions, StreamEvent } from '../llm/provider';
+import { StreamChunk } from '../llm/router';
+import { ToolCallAccumulator, toOpenAIMessages } from '../llm/openai';
+import { toAnthropicMessages } from '../llm/anthropic';
+
+const served = { provider: 'fake', model: 'fake-model' };
+
+// Replays one scripted model turn per call and records what it was sent
+function scripted(turns: StreamEvent[][]) {
+  const requests: { messages: ChatMessage[]; options: ProviderChatOptions }[] = [];
+  async function* stream(messages: ChatMessage[], options: ProviderChatOptions): AsyncGenerator<StreamChunk> {
+    requests.push({ messages: [...messages], options });
+    for (const event of turns[requests.length - 1] || []) yield { ...served, ...event };
+  }
+  return { stream, requests };
+}
+
+const call = (id: string, name: string, args: Record<string, unknown> = {}): StreamEvent => ({
+  type: 'tool_call',
+  call: { id, name, arguments: args },
+});
+
+describe('Tool Calling', () => {
+  it('should export tools as JSON-schema function specs', () => {
+    const specs = toolFunctionSpecs(['read']);
+    const reviews = specs.find((s) => s.name === 'reviews');
+
+    expect(reviews?.parameters).toMatchObject({ type: 'object' });
+    expect(specs.some((s) => s.name === 'help')).toBe(false);
+    expect(specs.length).toBeLessThan(toolFunctionSpecs(['admin']).length);
+  });
+
+  it('should stringify model arguments for tool validation', () => {
+    expect(toToolArgs({ repo: 'a/b', limit: 5, deep: true, skip: null })).toEqual({ repo: 'a/b', limit: '5', deep: 'true' });
+  });
+
+  it('should run sequential tool calls and feed results back', async () => {
+    const { stream, requests } = scripted([
+      [{ type: 'text', text: 'Checking.' }, call('c1', 'stats', { days: 7 })],
+      [call('c2', 'reviews', { limit: 2 })],
+      [{ type: 'text', text: 'All good.' }],
+    ]);
+    const executed: string[] = [];
+    const events: ToolLoopEvent[] = [];
+
+    const result = await runToolLoop([{ role: 'user', content: 'how are we doing?' }], {
+      tools: [{ name: 'stats', description: '', parameters: {} }],
+      stream,
+      execute: async (c, step) => {
+        executed.push(`${step}:${c.name}`);
+        return { success: true, data: `${c.name} data`, metadata: { duration: 1 } };
+      },
+      onEvent: (e) => events.push(e),
+    });
+
+    expect(executed).toEqual(['1:stats', '2:reviews']);
+    expect(result.response).toBe('Checking.\n\nAll good.');
+    expect(result.steps.map((s) => s.tool)).toEqual(['stats', 'reviews']);
+    expect(result.servedBy).toEqual(served);
+    expect(events.filter((e) => e.type === 'tool_result')).toHaveLength(2);
+
+    const last = requests[2]!.messages;
+    expect(last[1]).toMatchObject({ role: 'assistant', content: 'Checking.', toolCalls: [{ id: 'c1', name: 'stats' }] });
+    expect(last[2]).toMatchObject({ role: 'tool', toolCallId: 'c1', content: 'stats data' });
+    expect(last[4]).toMatchObject({ role: 'tool', toolCallId: 'c2', content: 'reviews data' });
+  });
+
+  it('should stop offering tools once the step limit is reached', async () => {
+    const { stream, requests } = scripted([
+      [call('c1', 'stats')],
+      [call('c2', 'stats')],
+      [{ type: 'text', text: 'Done' }, call('c3', 'stats')],
+    ]);
+    let executions = 0;
+
+    const result = await runToolLoop([{ role: 'user', content: 'loop' }], {
+      tools: [{ name: 'stats', description: '', parameters: {} }],
+      stream,
+      execute: async () => {
+        executions++;
+        return { success: false, error: 'boom' };
+      },
+      maxSteps: 2,
+    });
+
+    expect(executions).toBe(2);
+    expect(result).toMatchObject({ response: 'Done', stepLimitReached: true });
+    expect(requests[2]!.options.tools).toBeUndefined();
+    expect(requests[1]!.messages.at(-1)).toMatchObject({ role: 'tool', content: 'Error: boom' });
+  });
+
+  it('should assemble streamed OpenAI tool call fragments', () => {
+    const acc = new ToolCallAccumulator();
+    acc.add([{ index: 0, id: 'call_a', function: { name: 'reviews', arguments: '{"lim' } }]);
+    acc.add([{ index: 0, function: { arguments: 'it": 3}' } }, { index: 1, id: 'call_b', function: { name: 'stats' } }]);
+
+    expect(acc.finish()).toEqual([
+      { id: 'call_a', name: 'reviews', arguments: { limit: 3 } },
+      { id: 'call_b', name: 'stats', arguments: {} },
+    ]);
+  });
+
+  it('should translate tool turns for each API', () => {
+    const messages: ChatMessage[] = [
+      { role: 'user', content: 'stats?' },
+      { role: 'assistant', content: '', toolCalls: [{ id: 't1', name: 'stats', arguments: { days: 7 } }] },
+      { role: 'tool', toolCallId: 't1', name: 'stats', content: '42 reviews' },
+    ];
+
+    expect(toOpenAIMessages(messages)[1]).toEqual({
+      role: 'assistant',
+      content: null,
+      tool_calls: [{ id: 't1', type: 'function', function: { name: 'stats', arguments: '{"days":7}' } }],
+    });
+    expect(toAnthropicMessages(messages).messages.slice(1)).toEqual([
+      { role: 'assistant', content: [{ type: 'tool_use', id: 't1', name: 'stats', input: { days: 7 } }] },
+      { role: 'user', content: [{ type: 'tool_result', tool_use_id: 't1', content: '42 reviews' }] },
+    ]);
+  });
+});
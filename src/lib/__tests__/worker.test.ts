@@ -0,0 +1,113 @@
+import { describe, it, expect, vi, afterEach } from 'vitest';
+import { startWorker, type JobOutcome } from '../worker';
+import type { ReviewJob } from '../queue';
+
+const job = (id: string): ReviewJob => ({
+  id,
+  repo_full_name: 'acme/api',
+  pr_number: Number(id),
+  owner: 'acme',
+  repo: 'api',
+  status: 'processing',
+  attempts: 0,
+  max_attempts: 3,
+  priority: 1,
+  score: 0,
+  created_at: '2026-01-01T00:00:00Z',
+});
+
+// Claims hand out the queued jobs in order, then nothing
+function queue(ids: string[]) {
+  const pending = ids.map(job);
+  return vi.fn(async () => pending.shift() ?? null);
+}
+
+function deferred() {
+  let resolve!: (outcome: JobOutcome) => void;
+  const promise = new Promise<JobOutcome>((r) => { resolve = r; });
+  return { promise, resolve };
+}
+
+describe('Embedded Worker', () => {
+  afterEach(() => {
+    vi.useRealTimers();
+  });
+
+  it('should run up to the configured number of jobs at once', async () => {
+    const running = new Map<string, ReturnType<typeof deferred>>();
+    const worker = startWorker({
+      workerId: 'w1',
+      concurrency: 2,
+      pollIntervalMs: 10,
+      claim: queue(['1', '2', '3']),
+      process: (j) => {
+        const d = deferred();
+        running.set(j.id, d);
+        return d.promise;
+      },
+    });
+
+    await vi.waitFor(() => expect(worker.status().active).toHaveLength(2));
+    expect([...running.keys()]).toEqual(['1', '2']);
+
+    running.get('1')!.resolve({ key: 'acme/api#1', status: 'processed' });
+    await vi.waitFor(() => expect(running.has('3')).toBe(true));
+    running.get('2')!.resolve({ key: 'acme/api#2', status: 'failed', error: 'boom' });
+    running.get('3')!.resolve({ key: 'acme/api#3', status: 'skipped' });
+
+    await worker.stop();
+    expect(worker.status()).toMatchObject({ running: false, active: [], processed: 1, failed: 1, skipped: 1 });
+  });
+
+  it('should poll while idle and pick up work as soon as it is woken', async () => {
+    vi.useFakeTimers();
+    const claim = vi.fn().mockResolvedValue(null);
+    const worker = startWorker({ concurrency: 1, pollIntervalMs: 5000, claim, process: vi.fn() });
+
+    await vi.advanceTimersByTimeAsync(0);
+    expect(claim).toHaveBeenCalledTimes(1);
+    await vi.advanceTimersByTimeAsync(5000);
+    expect(claim).toHaveBeenCalledTimes(2);
+
+    worker.wake();
+    await vi.advanceTimersByTimeAsync(0);
+    expect(claim).toHaveBeenCalledTimes(3);
+
+    await worker.stop();
+  });
+
+  it('should finish in-flight jobs on stop without claiming more', async () => {
+    const d = deferred();
+    const claim = queue(['1', '2']);
+    const worker = startWorker({ concurrency: 1, pollIntervalMs: 10, claim, process: () => d.promise });
+
+    await vi.waitFor(() => expect(worker.status().active).toEqual(['acme/api#1']));
+    let stopped = false;
+    const stopping = worker.stop().then(() => { stopped = true; });
+
+    await new Promise((r) => setTimeout(r, 20));
+    expect(stopped).toBe(false);
+
+    d.resolve({ key: 'acme/api#1', status: 'processed' });
+    await stopping;
+    expect(claim).toHaveBeenCalledTimes(1);
+    expect(worker.status().processed).toBe(1);
+  });
+
+  it('should keep polling after claim errors and job crashes', async () => {
+    const claim = vi.fn()
+      .mockRejectedValueOnce(new Error('db down'))
+      .mockResolvedValueOnce(job('1'))
+      .mockResolvedValue(null);
+    const worker = startWorker({
+      concurrency: 1,
+      pollIntervalMs: 1,
+      claim,
+      process: async () => { throw new Error('crash'); },
+    });
+
+    await vi.waitFor(() => expect(worker.status().failed).toBe(1));
+    await worker.stop();
+    expect(claim.mock.calls.length).toBeGreaterThanOrEqual(3);
+  });
+});
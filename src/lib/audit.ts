@@ -1,4 +1,4 @@
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
 
 export type AuditAction =
   | "review.created"
@@ -9,9 +9,14 @@ export type AuditAction =
   | "config.updated"
   | "scan.started"
   | "scan.completed"
+  | "scan.imported"
+  | "scan.uploaded"
   | "user.login"
   | "user.logout"
   | "webhook.received"
+  | "merge.policy_override"
+  | "dlq.replay"
+  | "dlq.purge"
   | "api.rate_limited";
 
 interface AuditLogEntry {
@@ -26,8 +31,7 @@ interface AuditLogEntry {
 
 export async function audit(entry: AuditLogEntry): Promise<void> {
   try {
-    const supabase = await createClient();
-    await supabase.from("audit_logs").insert({
+    await getStorage().audit.insert({
       action: entry.action,
       actor_id: entry.actor_id,
       resource_type: entry.resource_type,
@@ -51,19 +55,5 @@ export async function getAuditLogs(filters?: {
   to?: Date;
   limit?: number;
 }) {
-  const supabase = await createClient();
-  let query = supabase
-    .from("audit_logs")
-    .select("*")
-    .order("created_at", { ascending: false })
-    .limit(filters?.limit || 100);
-
-  if (filters?.action) query = query.eq("action", filters.action);
-  if (filters?.actor_id) query = query.eq("actor_id", filters.actor_id);
-  if (filters?.resource_type) query = query.eq("resource_type", filters.resource_type);
-  if (filters?.from) query = query.gte("created_at", filters.from.toISOString());
-  if (filters?.to) query = query.lte("created_at", filters.to.toISOString());
-
-  const { data } = await query;
-  return data || [];
+  return getStorage().audit.list(filters);
 }
@@ -19,3 +19,11 @@ export async function isAuthorized(): Promise<boolean> {
   const { data: { user } } = await supabase.auth.getUser();
   return user?.email === ALLOWED_EMAIL;
 }
+
+// Admins may override merge policy; ADMIN_EMAILS (comma-separated) adds to the owner
+export async function getAdmin() {
+  const supabase = await createClient();
+  const { data: { user } } = await supabase.auth.getUser();
+  const admins = [ALLOWED_EMAIL, ...(process.env.ADMIN_EMAILS || "").split(",").map((e) => e.trim()).filter(Boolean)];
+  return user?.email && admins.includes(user.email) ? user : null;
+}
@@ -0,0 +1,86 @@
+import { getStorage } from "./storage";
+import { notify } from "./notify";
+import { emptyUsage, type UsageTotals } from "./llm/usage";
+
+export interface RepoBudget {
+  monthly_token_budget?: number | null;
+  monthly_cost_budget?: number | null;
+  over_budget_action?: "downgrade" | "skip" | null;
+  budget_notified_at?: string | null;
+}
+
+export interface BudgetDecision {
+  action: "allow" | "downgrade" | "skip";
+  usage: UsageTotals;
+  reason?: string;
+}
+
+// Budgets reset at the start of each calendar month (UTC)
+export function monthStart(now = new Date()): Date {
+  return new Date(Date.UTC(now.getUTCFullYear(), now.getUTCMonth(), 1));
+}
+
+export function evaluateBudget(budget: RepoBudget, usage: UsageTotals): BudgetDecision {
+  const tokens = usage.prompt_tokens + usage.completion_tokens;
+  const reasons: string[] = [];
+  if (budget.monthly_token_budget && tokens >= budget.monthly_token_budget) {
+    reasons.push(`${tokens.toLocaleString()} of ${budget.monthly_token_budget.toLocaleString()} tokens used`);
+  }
+  if (budget.monthly_cost_budget && usage.cost_usd >= Number(budget.monthly_cost_budget)) {
+    reasons.push(`$${usage.cost_usd.toFixed(2)} of $${Number(budget.monthly_cost_budget).toFixed(2)} spent`);
+  }
+  if (!reasons.length) return { action: "allow", usage };
+  return { action: budget.over_budget_action === "skip" ? "skip" : "downgrade", usage, reason: reasons.join(", ") };
+}
+
+// Columns stored with review_history and security_scans rows
+export function usageColumns(usage: UsageTotals | undefined): UsageTotals {
+  return { ...emptyUsage(), ...usage };
+}
+
+export async function getMonthlyUsage(fullName: string, now = new Date()): Promise<UsageTotals> {
+  return getStorage().history.usageSince(fullName, monthStart(now));
+}
+
+/**
+ * Decide whether an auto-review may run at full depth. Repos without a budget
+ * are always allowed. The first time a repo goes over budget in a month, a
+ * warning is sent via notify().
+ */
+export async function checkRepoBudget(fullName: string, now = new Date()): Promise<BudgetDecision> {
+  const { configs } = getStorage();
+  const budget = await configs.get(fullName).catch(() => null);
+
+  if (!budget?.monthly_token_budget && !budget?.monthly_cost_budget) {
+    return { action: "allow", usage: emptyUsage() };
+  }
+
+  let usage: UsageTotals;
+  try {
+    usage = await getMonthlyUsage(fullName, now);
+  } catch (err) {
+    // Accounting problems shouldn't block reviews
+    console.error(`Budget check failed for ${fullName}:`, err);
+    return { action: "allow", usage: emptyUsage() };
+  }
+
+  const decision = evaluateBudget(budget, usage);
+  const alreadyNotified = budget.budget_notified_at && new Date(budget.budget_notified_at) >= monthStart(now);
+
+  if (decision.action !== "allow" && !alreadyNotified) {
+    await notify({
+      type: "warning",
+      message: decision.action === "skip"
+        ? `Monthly LLM budget exceeded, auto-reviews skipped for ${fullName}`
+        : `Monthly LLM budget exceeded, auto-reviews downgraded to quick for ${fullName}`,
+      metadata: {
+        repo: fullName,
+        usage: decision.reason,
+        action: decision.action,
+      },
+    });
+    await configs.update({ full_name: fullName }, { budget_notified_at: now.toISOString() });
+  }
+
+  return decision;
+}
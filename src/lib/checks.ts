@@ -0,0 +1,163 @@
+/**
+ * Check Runs Module
+ * Mirrors review jobs as GitHub check runs so branch protection can gate on the bot
+ */
+
+import { checks, pr, type CheckAnnotation, type CheckRunInput } from "./github";
+import { isGitHubAppConfigured } from "./github-app";
+import type { CodeReviewResult, LineComment } from "./review/engine";
+
+export const REVIEW_CHECK_NAME = "AI Code Review";
+export const ANNOTATIONS_PER_REQUEST = 50; // GitHub rejects more per create/update call
+
+export type CheckConclusion = NonNullable<CheckRunInput["conclusion"]>;
+
+// Writing check runs needs a GitHub App; a PAT gets 403
+export function checksEnabled(): boolean {
+  return isGitHubAppConfigured() && process.env.GITHUB_CHECKS !== "false";
+}
+
+export function severityCounts(comments: LineComment[]): Record<string, number> {
+  const counts: Record<string, number> = { critical: 0, high: 0, medium: 0, low: 0, info: 0 };
+  for (const c of comments) {
+    const severity = String(c.severity || "info").toLowerCase();
+    counts[severity] = (counts[severity] || 0) + 1;
+  }
+  return counts;
+}
+
+export function reviewConclusion(comments: LineComment[]): CheckConclusion {
+  const counts = severityCounts(comments);
+  if (counts.critical) return "failure";
+  if (counts.high) return "neutral";
+  return "success";
+}
+
+const annotationLevel = (severity: string): CheckAnnotation["annotation_level"] =>
+  severity === "critical" || severity === "high" ? "failure" : severity === "medium" ? "warning" : "notice";
+
+export function toAnnotations(comments: LineComment[]): CheckAnnotation[] {
+  return comments
+    .filter((c) => c.path && c.line > 0)
+    .map((c) => {
+      const severity = String(c.severity || "info").toLowerCase();
+      return {
+        path: c.path,
+        start_line: c.start_line && c.start_line < c.line ? c.start_line : c.line,
+        end_line: c.line,
+        annotation_level: annotationLevel(severity),
+        title: `[${severity.toUpperCase()}] ${c.category}`,
+        message: c.body.slice(0, 60000),
+        ...(c.suggestion && { raw_details: c.suggestion.slice(0, 60000) }),
+      };
+    });
+}
+
+export function batchAnnotations(annotations: CheckAnnotation[], size = ANNOTATIONS_PER_REQUEST): CheckAnnotation[][] {
+  if (!annotations.length) return [[]];
+  const batches: CheckAnnotation[][] = [];
+  for (let i = 0; i < annotations.length; i += size) batches.push(annotations.slice(i, i + size));
+  return batches;
+}
+
+function summarize(review: CodeReviewResult): { title: string; summary: string } {
+  const counts = severityCounts(review.line_comments);
+  const found = Object.entries(counts).filter(([, n]) => n > 0).map(([s, n]) => `${n} ${s}`);
+  return {
+    title: found.length ? `${review.line_comments.length} issues: ${found.join(", ")}` : "No issues found",
+    summary: `${review.summary.overview}\n\n**Risk Level:** ${review.summary.risk_assessment}`,
+  };
+}
+
+/** Create a review check run; returns null when checks are off or GitHub refuses. */
+export async function createReviewCheck(
+  owner: string,
+  repo: string,
+  headSha: string,
+  status: "queued" | "in_progress" = "queued"
+): Promise<number | null> {
+  if (!checksEnabled()) return null;
+  try {
+    const run = await checks.create(owner, repo, {
+      name: REVIEW_CHECK_NAME,
+      head_sha: headSha,
+      status,
+      output: { title: status === "queued" ? "Review queued" : "Review in progress", summary: "The AI review has not finished yet." },
+    });
+    return run.id;
+  } catch (err) {
+    console.error(`Failed to create check run for ${owner}/${repo}:`, err);
+    return null;
+  }
+}
+
+/** Move a queued check to in progress, creating it if the job was queued without one. */
+export async function startReviewCheck(owner: string, repo: string, prNumber: number, checkRunId?: number | null): Promise<number | null> {
+  if (!checksEnabled()) return null;
+  if (!checkRunId) {
+    try {
+      const { head } = await pr.get(owner, repo, prNumber) as { head: { sha: string } };
+      return createReviewCheck(owner, repo, head.sha, "in_progress");
+    } catch (err) {
+      console.error(`Failed to start check run for ${owner}/${repo}#${prNumber}:`, err);
+      return null;
+    }
+  }
+  try {
+    await checks.update(owner, repo, checkRunId, {
+      status: "in_progress",
+      output: { title: "Review in progress", summary: "The AI review has not finished yet." },
+    });
+  } catch (err) {
+    console.error(`Failed to update check run ${checkRunId}:`, err);
+  }
+  return checkRunId;
+}
+
+/**
+ * Finish the check with the review's line comments as annotations. Each update
+ * appends up to 50 annotations; the last one also sets the conclusion, which
+ * follows the repo's severity policy when the review was checked against one.
+ */
+export async function completeReviewCheck(
+  owner: string,
+  repo: string,
+  checkRunId: number | null | undefined,
+  review: CodeReviewResult,
+  conclusion: CheckConclusion = review.policy
+    ? (review.policy.passed ? "success" : "failure")
+    : reviewConclusion(review.line_comments)
+): Promise<void> {
+  if (!checkRunId) return;
+  const output = summarize(review);
+  const batches = batchAnnotations(toAnnotations(review.line_comments));
+
+  try {
+    for (let i = 0; i < batches.length; i++) {
+      const last = i === batches.length - 1;
+      await checks.update(owner, repo, checkRunId, {
+        ...(last && { status: "completed" as const, conclusion }),
+        output: { ...output, annotations: batches[i] },
+      });
+    }
+  } catch (err) {
+    console.error(`Failed to complete check run ${checkRunId}:`, err);
+  }
+}
+
+/** Close a check without a review, e.g. when the job failed or was skipped. */
+export async function concludeCheck(
+  owner: string,
+  repo: string,
+  checkRunId: number | null | undefined,
+  conclusion: CheckConclusion,
+  title: string,
+  summary: string
+): Promise<void> {
+  if (!checkRunId) return;
+  try {
+    await checks.update(owner, repo, checkRunId, { status: "completed", conclusion, output: { title, summary } });
+  } catch (err) {
+    console.error(`Failed to conclude check run ${checkRunId}:`, err);
+  }
+}
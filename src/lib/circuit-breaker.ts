@@ -1,16 +1,16 @@
-enum CircuitState {
+export enum CircuitState {
   CLOSED = 'CLOSED',
   OPEN = 'OPEN',
   HALF_OPEN = 'HALF_OPEN',
 }
 
-interface CircuitBreakerConfig {
+export interface CircuitBreakerConfig {
   failureThreshold: number;
   resetTimeout: number;
   monitoringPeriod: number;
 }
 
-class CircuitBreaker {
+export class CircuitBreaker {
   private state = CircuitState.CLOSED;
   private failures = 0;
   private lastFailureTime = 0;
@@ -19,13 +19,8 @@ class CircuitBreaker {
   constructor(private config: CircuitBreakerConfig) {}
 
   async execute<T>(fn: () => Promise<T>): Promise<T> {
-    if (this.state === CircuitState.OPEN) {
-      if (Date.now() - this.lastFailureTime > this.config.resetTimeout) {
-        this.state = CircuitState.HALF_OPEN;
-        this.successCount = 0;
-      } else {
-        throw new Error('Circuit breaker is OPEN');
-      }
+    if (!this.isAvailable()) {
+      throw new Error('Circuit breaker is OPEN');
     }
 
     try {
@@ -38,7 +33,16 @@ class CircuitBreaker {
     }
   }
 
-  private onSuccess() {
+  // Whether a call may go through now; moves OPEN to HALF_OPEN once the reset timeout has passed
+  isAvailable(): boolean {
+    if (this.state === CircuitState.OPEN && Date.now() - this.lastFailureTime > this.config.resetTimeout) {
+      this.state = CircuitState.HALF_OPEN;
+      this.successCount = 0;
+    }
+    return this.state !== CircuitState.OPEN;
+  }
+
+  onSuccess() {
     this.failures = 0;
     if (this.state === CircuitState.HALF_OPEN) {
       this.successCount++;
@@ -48,7 +52,7 @@ class CircuitBreaker {
     }
   }
 
-  private onFailure() {
+  onFailure() {
     this.failures++;
     this.lastFailureTime = Date.now();
     if (this.failures >= this.config.failureThreshold) {
@@ -67,8 +71,28 @@ export const githubCircuitBreaker = new CircuitBreaker({
   monitoringPeriod: 120000,
 });
 
-export const llmCircuitBreaker = new CircuitBreaker({
+export const LLM_BREAKER_CONFIG: CircuitBreakerConfig = {
   failureThreshold: 3,
   resetTimeout: 30000,
   monitoringPeriod: 60000,
-});
+};
+
+const breakers = new Map<string, CircuitBreaker>();
+
+// Keyed breakers, e.g. one per LLM provider and one per provider/model pair
+export function getCircuitBreaker(key: string, config: CircuitBreakerConfig = LLM_BREAKER_CONFIG): CircuitBreaker {
+  let breaker = breakers.get(key);
+  if (!breaker) {
+    breaker = new CircuitBreaker(config);
+    breakers.set(key, breaker);
+  }
+  return breaker;
+}
+
+export function getCircuitBreakerStates(prefix = ''): Record<string, CircuitState> {
+  const states: Record<string, CircuitState> = {};
+  for (const [key, breaker] of breakers) {
+    if (key.startsWith(prefix)) states[key] = breaker.getState();
+  }
+  return states;
+}
@@ -61,4 +61,6 @@ export const tables = {
   passkeys: "passkeys",
   passkeyChallenges: "passkey_challenges",
   reviewLearnings: "review_learnings",
+  reviewComments: "review_comments",
+  reviewFeedback: "review_feedback",
 } as const;
@@ -0,0 +1,138 @@
+import { getStorage, type DlqEntry, type DlqFilter, type ReviewJob } from "./storage";
+import { audit } from "./audit";
+import { jobPriority } from "./queue";
+
+/**
+ * Dead Letter Queue
+ * Jobs that exhausted their retries, moved out of review_jobs a week after
+ * failing (by move_to_dlq() on Supabase, on claim by the SQL backends)
+ */
+
+export type { DlqEntry, DlqFilter, JobError } from "./storage";
+
+export interface ReplayResult {
+  replayed: { id: string; job_id: string }[];
+  skipped: { id: string; reason: string }[];
+}
+
+const MAX_LIMIT = 500;
+
+/** Build a filter from query params, a JSON body or tool args. Throws on bad values. */
+export function parseDlqFilter(input: Record<string, unknown>): DlqFilter {
+  const filter: DlqFilter = {};
+  const str = (v: unknown) => (v === undefined || v === null || v === "" ? undefined : String(v));
+
+  const ids = Array.isArray(input.ids) ? input.ids.map(String) : str(input.ids)?.split(",").map((s) => s.trim()).filter(Boolean);
+  if (ids?.length) filter.ids = ids;
+
+  const repo = str(input.repo);
+  if (repo) {
+    if (!/^[\w.-]+\/[\w.-]+$/.test(repo)) throw new Error("repo must be owner/repo");
+    filter.repo = repo;
+  }
+
+  const pr = str(input.pr);
+  if (pr) {
+    if (!/^\d+$/.test(pr) || Number(pr) < 1) throw new Error("pr must be a positive integer");
+    filter.pr = Number(pr);
+  }
+
+  const error = str(input.error);
+  if (error) filter.error = error;
+
+  const before = str(input.before);
+  if (before) {
+    if (isNaN(Date.parse(before))) throw new Error("before must be a date");
+    filter.before = new Date(before).toISOString();
+  }
+
+  const replayed = str(input.replayed);
+  if (replayed) {
+    if (!["true", "false"].includes(replayed)) throw new Error("replayed must be true or false");
+    filter.replayed = replayed === "true";
+  }
+
+  const limit = str(input.limit);
+  if (limit) {
+    if (!/^\d+$/.test(limit) || Number(limit) < 1) throw new Error("limit must be a positive integer");
+    filter.limit = Math.min(Number(limit), MAX_LIMIT);
+  }
+
+  return filter;
+}
+
+export function hasFilter(filter: DlqFilter): boolean {
+  return !!(filter.ids?.length || filter.repo || filter.pr || filter.error || filter.before || filter.replayed !== undefined);
+}
+
+export async function listDlq(filter: DlqFilter = {}): Promise<{ entries: DlqEntry[]; total: number }> {
+  return getStorage().dlq.list({ ...filter, limit: Math.min(filter.limit || 50, MAX_LIMIT) });
+}
+
+export async function getDlqEntry(id: string): Promise<DlqEntry | null> {
+  return getStorage().dlq.get(id);
+}
+
+/** A fresh job for a DLQ entry, with its retries reset but its history kept. */
+export function toReplayJob(entry: DlqEntry) {
+  return {
+    repo_full_name: entry.repo_full_name,
+    pr_number: entry.pr_number,
+    owner: entry.owner,
+    repo: entry.repo,
+    analysis: entry.analysis,
+    head_sha: null, // entries are days old; review whatever the PR head is now
+    error_history: entry.error_history || [],
+    ...jobPriority(entry.analysis),
+  };
+}
+
+/** Re-queue DLQ entries. Entries whose PR already has a live job are skipped. */
+export async function replayDlq(filter: DlqFilter, actorId?: string): Promise<ReplayResult> {
+  const { jobs, dlq } = getStorage();
+  const { entries } = await listDlq({ ...filter, replayed: filter.replayed ?? false, limit: filter.limit || MAX_LIMIT });
+  const result: ReplayResult = { replayed: [], skipped: [] };
+
+  for (const entry of entries) {
+    const active = await jobs.active(entry.repo_full_name, entry.pr_number);
+    if (active.length) {
+      result.skipped.push({ id: entry.id, reason: "Review already queued" });
+      continue;
+    }
+
+    let job: ReviewJob;
+    try {
+      job = await jobs.create(toReplayJob(entry));
+    } catch (err) {
+      result.skipped.push({ id: entry.id, reason: err instanceof Error ? err.message : "Insert failed" });
+      continue;
+    }
+
+    await dlq.markReplayed(entry.id, job.id, entry.replay_count + 1);
+
+    await audit({
+      action: "dlq.replay",
+      actor_id: actorId,
+      resource_type: "review_job",
+      resource_id: `${entry.repo_full_name}#${entry.pr_number}`,
+      metadata: { dlq_id: entry.id, job_id: job.id, last_error: entry.error, replay_count: entry.replay_count + 1 },
+    });
+    result.replayed.push({ id: entry.id, job_id: job.id });
+  }
+
+  return result;
+}
+
+/** Delete DLQ entries. A filter is required so a stray call can't empty the table. */
+export async function purgeDlq(filter: DlqFilter, actorId?: string): Promise<number> {
+  if (!hasFilter(filter)) throw new Error("Purge requires a filter (ids, repo, pr, error, before or replayed)");
+
+  const count = await getStorage().dlq.remove(filter);
+  await audit({
+    action: "dlq.purge",
+    actor_id: actorId,
+    resource_type: "review_jobs_dlq",
+    metadata: { filter, count },
+  });
+  return count;
+}
@@ -0,0 +1,166 @@
+/**
+ * Feedback Store Module
+ * Tracks bot review comments and developer reactions to them, and turns
+ * those signals into category weights and proposed learnings per repo.
+ * Signals come from GitHub, not the signed-in user, so every write here runs
+ * with the service role; RLS keeps these tables read-only for anyone else.
+ */
+
+import { getStorage, withServiceRole } from "./storage";
+import { pr as githubPR, review as githubReview } from "./github";
+import {
+  LineComment,
+  FeedbackRow,
+  FeedbackSignal,
+  summarizeFeedback,
+  computeCategoryWeights,
+  proposeLearnings,
+} from "./review/engine";
+
+interface GitHubReviewComment {
+  id: number;
+  path: string;
+  line?: number | null;
+  original_line?: number | null;
+  body: string;
+  reactions?: { "+1"?: number; "-1"?: number };
+}
+
+/**
+ * Remember which GitHub comments a posted review produced, so later
+ * reactions can be mapped back to their category and severity
+ */
+export async function recordPostedComments(
+  owner: string,
+  repo: string,
+  prNumber: number,
+  reviewId: number,
+  lineComments: LineComment[]
+): Promise<number> {
+  const posted = await githubReview.comments(owner, repo, prNumber, reviewId) as GitHubReviewComment[];
+  if (!posted.length) return 0;
+
+  const rows = posted.map((c) => {
+    const line = c.line ?? c.original_line ?? null;
+    const match = lineComments.find((lc) => lc.path === c.path && lc.line === line)
+      || lineComments.find((lc) => lc.path === c.path && c.body.includes(lc.body.slice(0, 60)));
+    return {
+      repo_full_name: `${owner}/${repo}`,
+      pr_number: prNumber,
+      github_review_id: reviewId,
+      github_comment_id: c.id,
+      path: c.path,
+      line,
+      category: match ? String(match.category) : null,
+      severity: match ? String(match.severity) : null,
+      source: match?.source || null,
+      body: c.body.slice(0, 1000),
+    };
+  });
+
+  await withServiceRole(() => getStorage().feedback.recordComments(rows));
+  return rows.length;
+}
+
+async function upsertSignal(
+  fullName: string,
+  commentId: number,
+  signal: FeedbackSignal,
+  count = 1,
+  note?: string
+): Promise<boolean> {
+  const { feedback } = getStorage();
+  const [known] = await feedback.comments({ commentId });
+  if (!known) return false;
+
+  await withServiceRole(() => feedback.upsertSignal({
+    repo_full_name: fullName,
+    github_comment_id: commentId,
+    signal,
+    count,
+    note: note?.slice(0, 500),
+  }));
+  return true;
+}
+
+export async function recordReplyFeedback(
+  fullName: string,
+  parentCommentId: number,
+  signal: FeedbackSignal,
+  reply: string
+): Promise<boolean> {
+  return upsertSignal(fullName, parentCommentId, signal, 1, reply);
+}
+
+export async function recordResolvedThread(fullName: string, commentIds: number[]): Promise<number> {
+  let recorded = 0;
+  for (const id of commentIds) {
+    if (await upsertSignal(fullName, id, "resolved")) recorded++;
+  }
+  return recorded;
+}
+
+export async function recordDismissedReview(fullName: string, reviewId: number, message?: string): Promise<number> {
+  const comments = await getStorage().feedback.comments({ reviewId });
+
+  let recorded = 0;
+  for (const row of comments) {
+    if (await upsertSignal(fullName, row.github_comment_id, "dismissed", 1, message)) recorded++;
+  }
+  return recorded;
+}
+
+/**
+ * Reactions don't trigger webhooks, so pull the current 👍/👎 counts
+ * for every bot comment on the PR
+ */
+export async function syncReactions(owner: string, repo: string, prNumber: number): Promise<number> {
+  const fullName = `${owner}/${repo}`;
+  const known = await getStorage().feedback.comments({ repo: fullName, pr: prNumber });
+  if (!known.length) return 0;
+
+  const ids = new Set(known.map((k) => Number(k.github_comment_id)));
+  const comments = await githubPR.reviewComments(owner, repo, prNumber) as GitHubReviewComment[];
+
+  let recorded = 0;
+  for (const c of comments) {
+    if (!ids.has(c.id)) continue;
+    const up = c.reactions?.["+1"] || 0;
+    const down = c.reactions?.["-1"] || 0;
+    if (up && await upsertSignal(fullName, c.id, "thumbs_up", up)) recorded++;
+    if (down && await upsertSignal(fullName, c.id, "thumbs_down", down)) recorded++;
+  }
+  return recorded;
+}
+
+/**
+ * Recompute category weights for a repo and propose learnings for
+ * feedback the team keeps rejecting
+ */
+export async function refreshRepoFeedback(fullName: string): Promise<{ weights: Record<string, number>; proposed: number }> {
+  const storage = getStorage();
+  const signals = await storage.feedback.signals(fullName);
+
+  // Weights steer the model, so feedback on analyzer comments doesn't count
+  const rows: FeedbackRow[] = signals.filter((r) => !r.source).map((r) => ({
+    signal: r.signal as FeedbackSignal,
+    count: r.count || 1,
+    note: r.note,
+    category: r.category || null,
+    path: r.path || "",
+  }));
+
+  const weights = computeCategoryWeights(summarizeFeedback(rows));
+  await withServiceRole(() => storage.configs.update({ full_name: fullName }, { category_weights: weights }));
+
+  const existing = await storage.learnings.list({ repo: fullName, limit: 1000 });
+
+  const proposals = proposeLearnings(rows, weights, existing);
+  await withServiceRole(async () => {
+    for (const p of proposals) {
+      await storage.learnings.create({ ...p, repo_full_name: fullName, source: "feedback", status: "proposed" });
+    }
+  });
+
+  return { weights, proposed: proposals.length };
+}
@@ -0,0 +1,234 @@
+import { createSign } from "crypto";
+import { getStorage, withServiceRole } from "./storage";
+
+const GITHUB_API = "https://api.github.com";
+
+// Installation tokens last an hour and app JWTs at most ten minutes; renew both early
+const TOKEN_REFRESH_MARGIN_MS = 5 * 60 * 1000;
+const JWT_TTL_SECONDS = 540;
+
+export interface GitHubAppConfig {
+  appId: string;
+  privateKey: string;
+  installationId?: number; // fallback for requests not tied to a repo
+}
+
+export interface GitHubAuth {
+  token: string;
+  installationId?: number; // set when the token is an installation token
+}
+
+export function githubAppConfigFromEnv(env: Record<string, string | undefined> = process.env): GitHubAppConfig | null {
+  const appId = env.GITHUB_APP_ID;
+  const rawKey = env.GITHUB_APP_PRIVATE_KEY;
+  if (!appId || !rawKey) return null;
+
+  // Accept the PEM as-is, with escaped newlines, or base64-encoded
+  const privateKey = rawKey.includes("BEGIN") ? rawKey.replace(/\\n/g, "\n") : Buffer.from(rawKey, "base64").toString("utf8");
+  const installationId = env.GITHUB_APP_INSTALLATION_ID ? parseInt(env.GITHUB_APP_INSTALLATION_ID, 10) : undefined;
+  return { appId, privateKey, ...(installationId && { installationId }) };
+}
+
+export function isGitHubAppConfigured(): boolean {
+  return githubAppConfigFromEnv() !== null;
+}
+
+const base64url = (input: string | Buffer) => Buffer.from(input).toString("base64url");
+
+// RS256 JWT identifying the app itself; only used to mint installation tokens
+export function createAppJwt(appId: string, privateKey: string, now = Date.now()): string {
+  const iat = Math.floor(now / 1000) - 60; // allow for clock drift
+  const header = base64url(JSON.stringify({ alg: "RS256", typ: "JWT" }));
+  const payload = base64url(JSON.stringify({ iat, exp: iat + 60 + JWT_TTL_SECONDS, iss: appId }));
+  const signature = createSign("RSA-SHA256").update(`${header}.${payload}`).sign(privateKey);
+  return `${header}.${payload}.${base64url(signature)}`;
+}
+
+let cachedJwt: { token: string; expiresAt: number } | null = null;
+
+function appJwt(config: GitHubAppConfig): string {
+  if (cachedJwt && cachedJwt.expiresAt > Date.now() + 60_000) return cachedJwt.token;
+  const token = createAppJwt(config.appId, config.privateKey);
+  cachedJwt = { token, expiresAt: Date.now() + JWT_TTL_SECONDS * 1000 };
+  return token;
+}
+
+function requireConfig(): GitHubAppConfig {
+  const config = githubAppConfigFromEnv();
+  if (!config) throw new Error("GitHub App not configured (GITHUB_APP_ID, GITHUB_APP_PRIVATE_KEY)");
+  return config;
+}
+
+async function appRequest<T>(endpoint: string, config: GitHubAppConfig, init?: RequestInit): Promise<T> {
+  const res = await fetch(`${GITHUB_API}${endpoint}`, {
+    ...init,
+    headers: {
+      Authorization: `Bearer ${appJwt(config)}`,
+      Accept: "application/vnd.github+json",
+      "X-GitHub-Api-Version": "2022-11-28",
+    },
+  });
+  if (!res.ok) {
+    const error = new Error(`GitHub App API [${res.status}] ${endpoint}: ${await res.text().catch(() => res.statusText)}`);
+    throw Object.assign(error, { status: res.status });
+  }
+  return res.json();
+}
+
+// Installation tokens, with in-flight mints shared so concurrent requests don't each mint one
+const tokenCache = new Map<number, { token: string; expiresAt: number }>();
+const pendingTokens = new Map<number, Promise<string>>();
+
+export async function getInstallationToken(installationId: number, config = requireConfig()): Promise<string> {
+  const cached = tokenCache.get(installationId);
+  if (cached && cached.expiresAt - TOKEN_REFRESH_MARGIN_MS > Date.now()) return cached.token;
+
+  const pending = pendingTokens.get(installationId);
+  if (pending) return pending;
+
+  const mint = appRequest<{ token: string; expires_at: string }>(`/app/installations/${installationId}/access_tokens`, config, { method: "POST" })
+    .then(({ token, expires_at }) => {
+      tokenCache.set(installationId, { token, expiresAt: new Date(expires_at).getTime() });
+      return token;
+    })
+    .finally(() => pendingTokens.delete(installationId));
+
+  pendingTokens.set(installationId, mint);
+  return mint;
+}
+
+// Drop a token GitHub rejected, e.g. after the installation's permissions changed
+export function invalidateInstallationToken(installationId: number): void {
+  tokenCache.delete(installationId);
+}
+
+// owner/repo (lowercased) -> installation id
+const installationCache = new Map<string, number>();
+
+/**
+ * Record the installation a repo belongs to, as seen in webhook payloads.
+ * Persisted on repo_configs so workers without the payload can find it.
+ */
+export async function rememberInstallation(fullName: string, installationId: number | undefined): Promise<void> {
+  if (!installationId) return;
+  const key = fullName.toLowerCase();
+  if (installationCache.get(key) === installationId) return;
+  installationCache.set(key, installationId);
+
+  try {
+    // Called from webhooks, which only the service role may write for
+    await withServiceRole(() => getStorage().configs.update({ full_name: fullName }, { installation_id: installationId }));
+  } catch (err) {
+    console.error(`Failed to store installation for ${fullName}:`, err);
+  }
+}
+
+async function storedInstallation(fullName: string): Promise<number | null> {
+  try {
+    const config = await getStorage().configs.get(fullName);
+    return config?.installation_id || null;
+  } catch {
+    return null;
+  }
+}
+
+// Repos the app was found not to be installed on, until this time; a webhook
+// that names an installation still wins, as it fills installationCache
+const NOT_INSTALLED_TTL_MS = 10 * 60 * 1000;
+const notInstalled = new Map<string, number>();
+
+/**
+ * Find the installation that covers a repo: webhook-seeded cache, then
+ * repo_configs, then the GitHub API. Null when the app isn't installed on it.
+ */
+async function repoInstallation(owner: string, repo: string, config: GitHubAppConfig): Promise<number | null> {
+  const fullName = `${owner}/${repo}`;
+  const key = fullName.toLowerCase();
+  const cached = installationCache.get(key);
+  if (cached) return cached;
+  if ((notInstalled.get(key) || 0) > Date.now()) return null;
+
+  const installationId = await storedInstallation(fullName)
+    || await appRequest<{ id: number }>(`/repos/${owner}/${repo}/installation`, config).then((i) => i.id, () => null);
+  if (installationId) installationCache.set(key, installationId);
+  else notInstalled.set(key, Date.now() + NOT_INSTALLED_TTL_MS);
+  return installationId;
+}
+
+async function resolveOrgInstallation(org: string, config: GitHubAppConfig): Promise<number> {
+  const key = org.toLowerCase();
+  const cached = installationCache.get(key);
+  if (cached) return cached;
+  const { id } = await appRequest<{ id: number }>(`/orgs/${org}/installation`, config);
+  installationCache.set(key, id);
+  return id;
+}
+
+function patAuth(): GitHubAuth {
+  const token = process.env.GITHUB_TOKEN;
+  if (!token) throw new Error("GITHUB_TOKEN not configured");
+  return { token };
+}
+
+/**
+ * Token for a GitHub API endpoint. Without a GitHub App, GITHUB_TOKEN is used
+ * for everything. With one:
+ *   - a repo endpoint uses the installation covering the repo; for a repo the
+ *     app isn't installed on, GITHUB_TOKEN, then the default installation
+ *   - an org endpoint uses the org's installation, else the default one
+ *   - anything else uses GITHUB_TOKEN, then the default installation
+ */
+export async function getAuthForEndpoint(endpoint: string): Promise<GitHubAuth> {
+  const config = githubAppConfigFromEnv();
+  if (!config) return patAuth();
+
+  const repoMatch = endpoint.match(/^\/repos\/([^/]+)\/([^/?]+)/);
+  const orgMatch = endpoint.match(/^\/orgs\/([^/?]+)/);
+
+  let installationId: number | undefined;
+  if (repoMatch) {
+    // Repos the app doesn't cover keep working on the PAT they used before the app was set up
+    installationId = await repoInstallation(repoMatch[1]!, repoMatch[2]!, config) || undefined;
+    if (!installationId && process.env.GITHUB_TOKEN) return patAuth();
+    installationId ??= config.installationId;
+  } else if (orgMatch) {
+    installationId = await resolveOrgInstallation(orgMatch[1]!, config).catch(() => config.installationId);
+  } else if (process.env.GITHUB_TOKEN) {
+    return patAuth();
+  } else {
+    installationId = config.installationId;
+  }
+
+  if (!installationId) throw new Error(`No GitHub App installation for ${endpoint}`);
+  return { token: await getInstallationToken(installationId, config), installationId };
+}
+
+export async function getRepoToken(owner: string, repo: string): Promise<string> {
+  return (await getAuthForEndpoint(`/repos/${owner}/${repo}`)).token;
+}
+
+// Every repo the app can see, across all of its installations
+export async function listInstallationRepos(): Promise<{ full_name: string; installation_id: number; [key: string]: unknown }[]> {
+  const config = requireConfig();
+  const installations = await appRequest<{ id: number }[]>("/app/installations?per_page=100", config);
+  const repos = [];
+  for (const { id } of installations) {
+    const token = await getInstallationToken(id, config);
+    for (let page = 1; ; page++) {
+      const res = await fetch(`${GITHUB_API}/installation/repositories?per_page=100&page=${page}`, {
+        headers: { Authorization: `Bearer ${token}`, Accept: "application/vnd.github+json" },
+      });
+      if (!res.ok) break;
+      const { repositories = [] } = await res.json() as { repositories?: { full_name: string }[] };
+      repos.push(...repositories.map((r) => ({ ...r, installation_id: id })));
+      if (repositories.length < 100) break;
+    }
+  }
+  for (const r of repos) installationCache.set(r.full_name.toLowerCase(), r.installation_id);
+  return repos;
+}
+
+// Health probe: confirms the app credentials are accepted
+export async function getAppInfo(): Promise<{ id: number; slug: string; name: string }> {
+  return appRequest("/app", requireConfig());
+}
@@ -1,5 +1,6 @@
 import { githubCircuitBreaker } from "./circuit-breaker";
 import { metrics } from "./metrics";
+import { getAuthForEndpoint, invalidateInstallationToken, listInstallationRepos } from "./github-app";
 
 const GITHUB_API = "https://api.github.com";
 
@@ -10,22 +11,31 @@ class GitHubError extends Error {
   }
 }
 
-function getToken() {
-  const token = process.env.GITHUB_TOKEN;
-  if (!token) throw new Error("GITHUB_TOKEN not configured");
-  return token;
-}
-
-const headers = () => ({
-  Authorization: `Bearer ${getToken()}`,
+const headers = (token: string) => ({
+  Authorization: `Bearer ${token}`,
   Accept: "application/vnd.github+json",
   "X-GitHub-Api-Version": "2022-11-28",
 });
 
+// Authenticates with the token for this endpoint (installation token or PAT).
+// A rejected installation token is re-minted once.
+async function ghFetch(endpoint: string, init?: RequestInit): Promise<Response> {
+  const send = async () => {
+    const auth = await getAuthForEndpoint(endpoint);
+    const res = await fetch(`${GITHUB_API}${endpoint}`, { ...init, headers: { ...headers(auth.token), ...init?.headers } });
+    return { res, auth };
+  };
+
+  const { res, auth } = await send();
+  if (res.status !== 401 || !auth.installationId) return res;
+  invalidateInstallationToken(auth.installationId);
+  return (await send()).res;
+}
+
 async function gh<T = unknown>(endpoint: string, init?: RequestInit): Promise<T> {
   const start = Date.now();
   return githubCircuitBreaker.execute(async () => {
-    const res = await fetch(`${GITHUB_API}${endpoint}`, { ...init, headers: { ...headers(), ...init?.headers } });
+    const res = await ghFetch(endpoint, init);
     metrics.timing("github_api_latency", start, { endpoint: endpoint.split("/")[1] || "unknown" });
     metrics.increment("github_api_requests", 1, { status: String(res.status) });
     if (!res.ok) throw new GitHubError(res.status, endpoint, await res.text().catch(() => res.statusText));
@@ -34,7 +44,7 @@ async function gh<T = unknown>(endpoint: string, init?: RequestInit): Promise<T>
 }
 
 async function ghText(endpoint: string, accept: string): Promise<string> {
-  const res = await fetch(`${GITHUB_API}${endpoint}`, { headers: { ...headers(), Accept: accept } });
+  const res = await ghFetch(endpoint, { headers: { Accept: accept } });
   if (!res.ok) throw new GitHubError(res.status, endpoint, res.statusText);
   return res.text();
 }
@@ -57,29 +67,89 @@ export const pr = {
   commits: (owner: string, repo: string, num: number) => gh(`/repos/${owner}/${repo}/pulls/${num}/commits`),
   files: (owner: string, repo: string, num: number) => gh(`/repos/${owner}/${repo}/pulls/${num}/files`),
   list: (owner: string, repo: string, state: "open" | "closed" | "all" = "open") => ghPaginate(`/repos/${owner}/${repo}/pulls?state=${state}`),
+  reviewComments: (owner: string, repo: string, num: number) => ghPaginate(`/repos/${owner}/${repo}/pulls/${num}/comments`),
   compare: (owner: string, repo: string, base: string, head: string) => ghText(`/repos/${owner}/${repo}/compare/${base}...${head}`, "application/vnd.github.v3.diff"),
   merge: (owner: string, repo: string, num: number, options?: {
     commit_title?: string;
     commit_message?: string;
     merge_method?: "merge" | "squash" | "rebase";
+    sha?: string; // GitHub refuses the merge with 409 if the head moved past it
   }) => gh<{ sha: string; merged: boolean; message: string }>(
     `/repos/${owner}/${repo}/pulls/${num}/merge`,
     { method: "PUT", body: JSON.stringify(options || {}) }
   ),
 };
 
+export interface ReviewCommentInput {
+  path: string;
+  line: number;
+  body: string;
+  side?: "LEFT" | "RIGHT";
+  start_line?: number;
+  start_side?: "LEFT" | "RIGHT";
+}
+
 // Review operations
 export const review = {
-  create: (owner: string, repo: string, num: number, body: string, event: "APPROVE" | "REQUEST_CHANGES" | "COMMENT" = "COMMENT", comments?: { path: string; line: number; body: string }[]) =>
+  create: (owner: string, repo: string, num: number, body: string, event: "APPROVE" | "REQUEST_CHANGES" | "COMMENT" = "COMMENT", comments?: ReviewCommentInput[]) =>
     gh(`/repos/${owner}/${repo}/pulls/${num}/reviews`, { method: "POST", body: JSON.stringify({ body, event, ...(comments?.length && { comments }) }) }),
   comment: (owner: string, repo: string, num: number, body: string, inReplyTo: number) =>
     gh(`/repos/${owner}/${repo}/pulls/${num}/comments`, { method: "POST", body: JSON.stringify({ body, in_reply_to: inReplyTo }) }),
+  comments: (owner: string, repo: string, num: number, reviewId: number) =>
+    ghPaginate(`/repos/${owner}/${repo}/pulls/${num}/reviews/${reviewId}/comments`),
+};
+
+export interface CheckAnnotation {
+  path: string;
+  start_line: number;
+  end_line: number;
+  annotation_level: "notice" | "warning" | "failure";
+  message: string;
+  title?: string;
+  raw_details?: string;
+}
+
+export interface CheckRunInput {
+  name?: string;
+  head_sha?: string;
+  status?: "queued" | "in_progress" | "completed";
+  conclusion?: "success" | "failure" | "neutral" | "cancelled" | "skipped" | "action_required";
+  external_id?: string;
+  details_url?: string;
+  output?: { title: string; summary: string; text?: string; annotations?: CheckAnnotation[] };
+}
+
+// Check runs (GitHub App only; PATs can't write checks)
+export const checks = {
+  create: (owner: string, repo: string, input: CheckRunInput) =>
+    gh<{ id: number }>(`/repos/${owner}/${repo}/check-runs`, { method: "POST", body: JSON.stringify(input) }),
+  update: (owner: string, repo: string, checkRunId: number, input: CheckRunInput) =>
+    gh<{ id: number }>(`/repos/${owner}/${repo}/check-runs/${checkRunId}`, { method: "PATCH", body: JSON.stringify(input) }),
+};
+
+// Commit statuses
+export const statuses = {
+  create: (owner: string, repo: string, sha: string, input: {
+    state: "pending" | "success" | "failure" | "error";
+    context: string;
+    description?: string;
+    target_url?: string;
+  }) => gh(`/repos/${owner}/${repo}/statuses/${sha}`, { method: "POST", body: JSON.stringify(input) }),
+};
+
+// Code scanning; sarif is the gzipped, base64-encoded log (needs security_events: write)
+export const codeScanning = {
+  uploadSarif: (owner: string, repo: string, input: { commit_sha: string; ref: string; sarif: string; tool_name?: string }) =>
+    gh<{ id: string; url: string }>(`/repos/${owner}/${repo}/code-scanning/sarifs`, { method: "POST", body: JSON.stringify(input) }),
 };
 
 // Repo operations
 export const repos = {
+  get: (owner: string, repo: string) => gh<{ default_branch: string }>(`/repos/${owner}/${repo}`),
+  commit: (owner: string, repo: string, ref: string) => gh<{ sha: string }>(`/repos/${owner}/${repo}/commits/${encodeURIComponent(ref)}`),
   user: () => gh(`/user/repos?per_page=100&sort=updated`),
   org: (org: string) => gh(`/orgs/${org}/repos?per_page=100&sort=updated`),
+  installation: () => listInstallationRepos(),
 };
 
 // Legacy exports for compatibility
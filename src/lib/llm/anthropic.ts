@@ -0,0 +1,202 @@
+import {
+  ChatMessage,
+  LLMError,
+  LLMProviderClient,
+  ProviderChatOptions,
+  ProviderConfig,
+  ProviderResponse,
+  StreamEvent,
+  ToolSpec,
+  classifyHttpError,
+  parseRetryAfter,
+  postJson,
+  parseToolArguments,
+  postStream,
+  resolveModel,
+  withRetries,
+} from "./provider";
+import { parseSSE } from "../sse";
+import { parseModelMap } from "./openai";
+
+const ANTHROPIC_VERSION = "2023-06-01";
+
+type AnthropicBlock =
+  | { type: "text"; text: string }
+  | { type: "tool_use"; id: string; name: string; input: Record<string, unknown> }
+  | { type: "tool_result"; tool_use_id: string; content: string };
+
+interface AnthropicTurn {
+  role: "user" | "assistant";
+  content: string | AnthropicBlock[];
+}
+
+interface AnthropicUsage {
+  input_tokens?: number;
+  output_tokens?: number;
+}
+
+interface AnthropicResponse {
+  content?: { type: string; text?: string }[];
+  usage?: AnthropicUsage;
+  error?: { type?: string; message?: string };
+}
+
+export function anthropicConfigFromEnv(): ProviderConfig {
+  return {
+    baseUrl: process.env.ANTHROPIC_BASE_URL || "https://api.anthropic.com",
+    apiKey: process.env.ANTHROPIC_API_KEY,
+    model: process.env.ANTHROPIC_MODEL || "claude-3-5-haiku-latest",
+    reviewModel: process.env.ANTHROPIC_REVIEW_MODEL,
+    modelMap: parseModelMap(process.env.ANTHROPIC_MODEL_MAP),
+    timeoutMs: 120000,
+    maxTokens: 4096,
+  };
+}
+
+// Anthropic reports the failure type in the body; map it before falling back to status codes
+const ERROR_TYPES: Record<string, LLMError["kind"]> = {
+  rate_limit_error: "rate_limit",
+  overloaded_error: "server",
+  api_error: "server",
+  authentication_error: "auth",
+  permission_error: "auth",
+  invalid_request_error: "bad_request",
+  request_too_large: "context_length",
+};
+
+export function classifyAnthropicError(error: unknown): LLMError {
+  if (error instanceof LLMError) return error;
+  const message = error instanceof Error ? error.message : String(error);
+  return classifyHttpError("anthropic", (error as { status?: number })?.status, message);
+}
+
+const toBlocks = (content: string | AnthropicBlock[]): AnthropicBlock[] =>
+  typeof content === "string" ? (content ? [{ type: "text", text: content }] : []) : content;
+
+function toAnthropicTurn(m: ChatMessage): AnthropicTurn {
+  // Tool results go back as user turns holding tool_result blocks
+  if (m.role === "tool") {
+    return { role: "user", content: [{ type: "tool_result", tool_use_id: m.toolCallId || "", content: m.content }] };
+  }
+  if (m.role === "assistant" && m.toolCalls?.length) {
+    const calls = m.toolCalls.map((c): AnthropicBlock => ({ type: "tool_use", id: c.id, name: c.name, input: c.arguments }));
+    return { role: "assistant", content: [...toBlocks(m.content), ...calls] };
+  }
+  return { role: m.role === "assistant" ? "assistant" : "user", content: m.content };
+}
+
+// The Messages API takes the system prompt separately and requires alternating user/assistant turns
+export function toAnthropicMessages(messages: ChatMessage[]): { system?: string; messages: AnthropicTurn[] } {
+  const system = messages.filter((m) => m.role === "system").map((m) => m.content).join("\n\n");
+  const turns: AnthropicTurn[] = [];
+  for (const m of messages) {
+    if (m.role === "system") continue;
+    const turn = toAnthropicTurn(m);
+    const last = turns[turns.length - 1];
+    if (!last || last.role !== turn.role) turns.push(turn);
+    else if (typeof last.content === "string" && typeof turn.content === "string") last.content += `\n\n${turn.content}`;
+    else last.content = [...toBlocks(last.content), ...toBlocks(turn.content)];
+  }
+  return { ...(system && { system }), messages: turns };
+}
+
+export function toAnthropicTools(tools: ToolSpec[] | undefined) {
+  if (!tools?.length) return undefined;
+  return tools.map((t) => ({ name: t.name, description: t.description, input_schema: t.parameters }));
+}
+
+export function createAnthropicProvider(config: ProviderConfig): LLMProviderClient {
+  const baseUrl = config.baseUrl.replace(/\/+$/, "");
+
+  const headers = {
+    "anthropic-version": ANTHROPIC_VERSION,
+    ...(config.apiKey && { "x-api-key": config.apiKey }),
+    ...config.headers,
+  };
+
+  const buildBody = (messages: ChatMessage[], options?: ProviderChatOptions) => ({
+    model: resolveModel(config, options),
+    max_tokens: options?.maxTokens ?? config.maxTokens ?? 4096,
+    temperature: options?.temperature ?? 0.1,
+    ...toAnthropicMessages(messages),
+    ...(options?.tools?.length && { tools: toAnthropicTools(options.tools) }),
+  });
+
+  const refineError = (error: LLMError, data: unknown) => {
+    const type = (data as AnthropicResponse | null)?.error?.type;
+    const mapped = type && ERROR_TYPES[type];
+    if (mapped) error.kind = mapped;
+  };
+
+  const request = async (messages: ChatMessage[], options?: ProviderChatOptions): Promise<ProviderResponse> => {
+    const res = await postJson(`${baseUrl}/v1/messages`, buildBody(messages, options), headers, config.timeoutMs ?? 120000);
+
+    const data = res.data as AnthropicResponse | null;
+    if (!res.ok) {
+      const error = classifyHttpError("anthropic", res.status, data?.error?.message || res.text || `HTTP ${res.status}`, parseRetryAfter(res.headers.get("retry-after")));
+      refineError(error, data);
+      throw error;
+    }
+    return {
+      content: (data?.content || []).filter((b) => b.type === "text").map((b) => b.text || "").join(""),
+      usage: data?.usage && { promptTokens: data.usage.input_tokens ?? 0, completionTokens: data.usage.output_tokens ?? 0 },
+    };
+  };
+
+  // Text arrives in content_block_delta events; tool_use blocks stream their input as partial JSON
+  // and are emitted at content_block_stop. Input tokens are reported in message_start, output
+  // tokens in message_delta. Errors can also arrive mid-stream.
+  async function* stream(messages: ChatMessage[], options?: ProviderChatOptions): AsyncGenerator<StreamEvent> {
+    const body = await withRetries(
+      () => postStream("anthropic", `${baseUrl}/v1/messages`, { ...buildBody(messages, options), stream: true }, headers, config.timeoutMs ?? 120000, refineError),
+      classifyAnthropicError,
+      options?.maxRetries
+    );
+    const toolUses = new Map<number, { id: string; name: string; json: string }>();
+    let promptTokens = 0;
+    for await (const { event, data } of parseSSE(body)) {
+      if (event === "message_stop") break;
+      if (event === "message_start") {
+        promptTokens = (JSON.parse(data) as { message?: { usage?: AnthropicUsage } }).message?.usage?.input_tokens ?? 0;
+        continue;
+      }
+      if (event === "message_delta") {
+        const usage = (JSON.parse(data) as { usage?: AnthropicUsage }).usage;
+        if (usage) yield { type: "usage", usage: { promptTokens, completionTokens: usage.output_tokens ?? 0 } };
+        continue;
+      }
+      if (event === "error") {
+        const payload = JSON.parse(data) as AnthropicResponse;
+        const error = classifyHttpError("anthropic", undefined, payload.error?.message || data);
+        refineError(error, payload);
+        throw error;
+      }
+      if (!event.startsWith("content_block_")) continue;
+      const chunk = JSON.parse(data) as {
+        index?: number;
+        content_block?: { type?: string; id?: string; name?: string };
+        delta?: { type?: string; text?: string; partial_json?: string };
+      };
+      const index = chunk.index ?? 0;
+      if (event === "content_block_start" && chunk.content_block?.type === "tool_use") {
+        toolUses.set(index, { id: chunk.content_block.id || `toolu_${index}`, name: chunk.content_block.name || "", json: "" });
+      } else if (event === "content_block_delta") {
+        if (chunk.delta?.type === "text_delta" && chunk.delta.text) yield { type: "text", text: chunk.delta.text };
+        const toolUse = toolUses.get(index);
+        if (chunk.delta?.type === "input_json_delta" && toolUse) toolUse.json += chunk.delta.partial_json || "";
+      } else if (event === "content_block_stop" && toolUses.has(index)) {
+        const { id, name, json } = toolUses.get(index)!;
+        toolUses.delete(index);
+        yield { type: "tool_call", call: { id, name, arguments: parseToolArguments(json) } };
+      }
+    }
+  }
+
+  return {
+    name: "anthropic",
+    model: (options) => resolveModel(config, options),
+    chat: (messages, options) => withRetries(() => request(messages, options), classifyAnthropicError, options?.maxRetries),
+    stream,
+    classifyError: classifyAnthropicError,
+  };
+}
@@ -1,6 +1,17 @@
 import Groq from "groq-sdk";
-import { llmCircuitBreaker } from "../circuit-breaker";
 import { metrics } from "../metrics";
+import {
+  ChatMessage,
+  LLMError,
+  LLMProviderClient,
+  ProviderChatOptions,
+  ProviderResponse,
+  StreamEvent,
+  classifyHttpError,
+  resolveModel,
+  toMessages,
+} from "./provider";
+import { ToolCallAccumulator, fromOpenAIUsage, parseModelMap, toOpenAIMessages, toOpenAITools } from "./openai";
 
 let groqClient: Groq | null = null;
 
@@ -24,44 +35,89 @@ function isRateLimitError(error: unknown): { isRateLimit: boolean; retryAfter?:
   return { isRateLimit, retryAfter: retryMatch ? parseInt(retryMatch[1]!, 10) * 1000 : undefined };
 }
 
-export interface ChatOptions {
-  model?: string;
-  temperature?: number;
-  useReviewModel?: boolean;
-  maxRetries?: number;
-}
+export type ChatOptions = ProviderChatOptions;
 
-export async function chatWithGroq(prompt: string, options?: ChatOptions): Promise<string> {
-  const groq = getGroqClient();
-  const maxRetries = options?.maxRetries ?? 4;
-  const model = options?.useReviewModel
-    ? process.env.GROQ_REVIEW_MODEL || process.env.GROQ_MODEL || "llama-3.1-8b-instant"
-    : options?.model || process.env.GROQ_MODEL || "llama-3.1-8b-instant";
+// Requested models go through GROQ_MODEL_MAP first, like the other providers
+function groqModel(options?: ChatOptions): string {
+  return resolveModel({
+    model: process.env.GROQ_MODEL || "llama-3.1-8b-instant",
+    reviewModel: process.env.GROQ_REVIEW_MODEL,
+    modelMap: parseModelMap(process.env.GROQ_MODEL_MAP),
+  }, options);
+}
 
-  const start = Date.now();
-  return llmCircuitBreaker.execute(async () => {
-    for (let attempt = 0; attempt < maxRetries; attempt++) {
-      try {
-        const response = await groq.chat.completions.create({
-          model,
-          messages: [{ role: "user", content: prompt }],
-          temperature: options?.temperature ?? 0.1,
-        });
-        metrics.timing("llm_latency", start, { model });
-        metrics.increment("llm_requests", 1, { model, status: "success" });
-        return response.choices[0]?.message?.content || "";
-      } catch (error) {
-        const { isRateLimit, retryAfter } = isRateLimitError(error);
-        if (isRateLimit && attempt < maxRetries - 1) {
-          const delay = retryAfter || Math.min(1000 * Math.pow(2, attempt + 1), 30000);
-          metrics.increment("llm_rate_limits", 1, { model });
-          await sleep(delay);
-          continue;
-        }
-        metrics.increment("llm_requests", 1, { model, status: "error" });
-        throw error;
+// Rate-limited requests are retried with backoff; anything else is thrown
+async function withRateLimitRetries<T>(model: string, maxRetries: number, fn: () => Promise<T>): Promise<T> {
+  for (let attempt = 0; attempt < maxRetries; attempt++) {
+    try {
+      return await fn();
+    } catch (error) {
+      const { isRateLimit, retryAfter } = isRateLimitError(error);
+      if (isRateLimit && attempt < maxRetries - 1) {
+        const delay = retryAfter || Math.min(1000 * Math.pow(2, attempt + 1), 30000);
+        metrics.increment("llm_rate_limits", 1, { provider: "groq", model });
+        await sleep(delay);
+        continue;
       }
+      throw error;
     }
-    throw new Error("Max retries exceeded");
-  });
+  }
+  throw new Error("Max retries exceeded");
+}
+
+function buildRequest(prompt: string | ChatMessage[], options: ChatOptions | undefined, model: string) {
+  return {
+    model,
+    messages: toOpenAIMessages(toMessages(prompt)),
+    temperature: options?.temperature ?? 0.1,
+    ...(options?.maxTokens && { max_tokens: options.maxTokens }),
+    ...(options?.tools?.length && { tools: toOpenAITools(options.tools) }),
+    ...(options?.jsonMode && { response_format: { type: "json_object" as const } }),
+  };
+}
+
+async function requestGroq(prompt: string | ChatMessage[], options?: ChatOptions): Promise<ProviderResponse> {
+  const groq = getGroqClient();
+  const model = groqModel(options);
+  const response = await withRateLimitRetries(model, options?.maxRetries ?? 4, () =>
+    groq.chat.completions.create(buildRequest(prompt, options, model))
+  );
+  return { content: response.choices[0]?.message?.content || "", usage: fromOpenAIUsage(response.usage) };
 }
+
+export async function chatWithGroq(prompt: string | ChatMessage[], options?: ChatOptions): Promise<string> {
+  return (await requestGroq(prompt, options)).content;
+}
+
+export async function* streamWithGroq(prompt: string | ChatMessage[], options?: ChatOptions): AsyncGenerator<StreamEvent> {
+  const groq = getGroqClient();
+  const model = groqModel(options);
+  const stream = await withRateLimitRetries(model, options?.maxRetries ?? 4, () =>
+    groq.chat.completions.create({ ...buildRequest(prompt, options, model), stream: true })
+  );
+  const toolCalls = new ToolCallAccumulator();
+  for await (const chunk of stream) {
+    // Groq reports usage on the final chunk under x_groq
+    const usage = fromOpenAIUsage(chunk.x_groq?.usage);
+    if (usage) yield { type: "usage", usage };
+    const delta = chunk.choices[0]?.delta;
+    if (delta?.content) yield { type: "text", text: delta.content };
+    toolCalls.add(delta?.tool_calls);
+  }
+  for (const call of toolCalls.finish()) yield { type: "tool_call", call };
+}
+
+export function classifyGroqError(error: unknown): LLMError {
+  if (error instanceof LLMError) return error;
+  const message = error instanceof Error ? error.message : String(error);
+  const { retryAfter } = isRateLimitError(error);
+  return classifyHttpError("groq", (error as { status?: number })?.status, message, retryAfter);
+}
+
+export const groqProvider: LLMProviderClient = {
+  name: "groq",
+  model: groqModel,
+  chat: (messages, options) => requestGroq(messages, options),
+  stream: (messages, options) => streamWithGroq(messages, options),
+  classifyError: classifyGroqError,
+};
@@ -1,36 +1,141 @@
-import { chatWithGroq, ChatOptions } from "./groq";
-import { chatWithOllama } from "./ollama";
+import { z } from "zod";
+import { chatWithGroq, ChatOptions, groqProvider } from "./groq";
+import { chatWithOllama, ollamaProvider } from "./ollama";
+import { createOpenAICompatibleProvider, openAIConfigFromEnv } from "./openai";
+import { createAnthropicProvider, anthropicConfigFromEnv } from "./anthropic";
+import { ChatMessage, LLMProviderClient, ServedBy, TokenUsage, toMessages } from "./provider";
+import { getProviderChain, routeChat, routeStream, LLMResponse, StreamChunk } from "./router";
+import { buildRepairPrompt, parseStructured, StructuredOutputError, toJsonSchema } from "./structured";
+import { sumUsage } from "./usage";
 
-export type LLMProvider = "groq" | "ollama";
+export type LLMProvider = "groq" | "ollama" | "openai" | "anthropic" | (string & {});
 
 export interface LLMChatOptions extends ChatOptions {
   provider?: LLMProvider;
 }
 
-export async function chat(
-  prompt: string,
-  options?: LLMChatOptions
-): Promise<string> {
-  const defaultProvider = options?.provider || (process.env.LLM_DEFAULT_PROVIDER as LLMProvider) || (process.env.LLM_PROVIDER as LLMProvider) || "groq";
-  const fallbackProvider = process.env.LLM_FALLBACK_PROVIDER as LLMProvider | undefined;
+const providerFactories = new Map<string, () => LLMProviderClient>([
+  ["groq", () => groqProvider],
+  ["ollama", () => ollamaProvider],
+  ["openai", () => createOpenAICompatibleProvider(openAIConfigFromEnv())],
+  ["anthropic", () => createAnthropicProvider(anthropicConfigFromEnv())],
+]);
+const providerCache = new Map<string, LLMProviderClient>();
 
-  const callProvider = async (provider: LLMProvider): Promise<string> => {
-    if (provider === "ollama") {
-      return chatWithOllama(prompt, options);
-    }
-    return chatWithGroq(prompt, options);
+// Add or replace a provider, e.g. a second OpenAI-compatible endpoint under its own name
+export function registerProvider(name: string, factory: () => LLMProviderClient) {
+  providerFactories.set(name, factory);
+  providerCache.delete(name);
+}
+
+export function getProvider(name: LLMProvider): LLMProviderClient {
+  const cached = providerCache.get(name);
+  if (cached) return cached;
+  const factory = providerFactories.get(name);
+  if (!factory) throw new Error(`Unknown LLM provider: ${name}`);
+  const provider = factory();
+  providerCache.set(name, provider);
+  return provider;
+}
+
+export function listProviders(): string[] {
+  return [...providerFactories.keys()];
+}
+
+export interface StructuredChatOptions<T> extends LLMChatOptions {
+  schema: z.ZodType<T>;
+  repairAttempts?: number;
+}
+
+export interface LLMResult<T> extends ServedBy {
+  content: T;
+  latencyMs: number;
+  usage?: TokenUsage; // includes repair attempts
+}
+
+async function chatText(prompt: string, options?: LLMChatOptions): Promise<LLMResponse> {
+  return routeChat([{ role: "user", content: prompt }], options, getProviderChain(options?.provider), getProvider);
+}
+
+async function chatStructured<T>(prompt: string, options: StructuredChatOptions<T>): Promise<LLMResult<T>> {
+  const { schema, repairAttempts = 1, ...rest } = options;
+  const jsonSchema = toJsonSchema(schema);
+  const providerOptions: LLMChatOptions = {
+    ...rest,
+    // Groq's JSON mode only accepts a top-level object
+    jsonMode: jsonSchema.type === "object",
+    format: jsonSchema,
   };
 
-  try {
-    return await callProvider(defaultProvider);
-  } catch (err) {
-    if (fallbackProvider && fallbackProvider !== defaultProvider) {
-      console.log(`${defaultProvider} failed, falling back to ${fallbackProvider}`);
-      return callProvider(fallbackProvider);
-    }
-    throw err;
+  let response = await chatText(prompt, providerOptions);
+  let result = parseStructured(response.content, schema);
+  let usage = response.usage;
+
+  for (let attempt = 0; !result.success && attempt < repairAttempts; attempt++) {
+    console.log(`Structured output invalid, repair attempt ${attempt + 1}/${repairAttempts}`);
+    response = await chatText(buildRepairPrompt(prompt, response.content, result.error), providerOptions);
+    result = parseStructured(response.content, schema);
+    usage = sumUsage(usage, response.usage);
+  }
+
+  if (!result.success) throw new StructuredOutputError(response.content, result.error);
+  return { ...response, content: result.data, usage };
+}
+
+/**
+ * Like chat(), but also reports which provider and model served the response.
+ */
+export async function complete<T>(prompt: string, options: StructuredChatOptions<T>): Promise<LLMResult<T>>;
+export async function complete(prompt: string, options?: LLMChatOptions): Promise<LLMResult<string>>;
+export async function complete<T>(prompt: string, options?: LLMChatOptions | StructuredChatOptions<T>): Promise<LLMResult<T | string>> {
+  if (options && "schema" in options) {
+    return chatStructured(prompt, options);
   }
+  return chatText(prompt, options);
+}
+
+/**
+ * Send a prompt through the provider chain. With a Zod `schema`, the provider's
+ * JSON mode is enabled and the validated object is returned; invalid output is
+ * retried with the validation errors before throwing StructuredOutputError.
+ */
+export async function chat<T>(prompt: string, options: StructuredChatOptions<T>): Promise<T>;
+export async function chat(prompt: string, options?: LLMChatOptions): Promise<string>;
+export async function chat<T>(prompt: string, options?: LLMChatOptions | StructuredChatOptions<T>): Promise<T | string> {
+  if (options && "schema" in options) {
+    return (await chatStructured(prompt, options)).content;
+  }
+  return (await chatText(prompt, options)).content;
+}
+
+/**
+ * Stream text deltas (and, when `tools` are passed, completed tool calls) from
+ * the provider chain. Each chunk carries the provider and model serving it.
+ */
+export function chatStream(prompt: string | ChatMessage[], options?: LLMChatOptions): AsyncGenerator<StreamChunk> {
+  return routeStream(toMessages(prompt), options, getProviderChain(options?.provider), getProvider);
 }
 
 export { chatWithGroq, chatWithOllama };
+export { streamWithGroq } from "./groq";
+export { streamWithOllama } from "./ollama";
+export type { StreamChunk } from "./router";
 export type { ChatOptions } from "./groq";
+export { StructuredOutputError } from "./structured";
+export { LLMError } from "./provider";
+export type {
+  ChatMessage,
+  LLMProviderClient,
+  ProviderConfig,
+  LLMErrorKind,
+  ServedBy,
+  StreamEvent,
+  TokenUsage,
+  ToolCallRequest,
+  ToolSpec,
+} from "./provider";
+export { getProviderChain } from "./router";
+export { addUsage, emptyUsage, estimateCost } from "./usage";
+export type { UsageTotals } from "./usage";
+export { createOpenAICompatibleProvider } from "./openai";
+export { createAnthropicProvider } from "./anthropic";
@@ -1,16 +1,66 @@
-export async function chatWithOllama(
-  prompt: string,
-  options?: { model?: string; temperature?: number }
-): Promise<string> {
-  const host = process.env.OLLAMA_HOST || "http://localhost:11434";
-  const model = options?.model || process.env.OLLAMA_MODEL || "qwen2.5-coder:7b";
-  const temperature = options?.temperature ?? 0.1;
+import {
+  ChatMessage,
+  LLMError,
+  LLMProviderClient,
+  ProviderChatOptions,
+  ProviderResponse,
+  StreamEvent,
+  TokenUsage,
+  classifyHttpError,
+  parseToolArguments,
+  toMessages,
+} from "./provider";
+import { readLines } from "../sse";
+import { toOpenAITools } from "./openai";
+
+type OllamaOptions = Pick<ProviderChatOptions, "model" | "temperature" | "format" | "tools">;
 
-  const headers: HeadersInit = { "Content-Type": "application/json" };
+interface OllamaToolCall {
+  function?: { name?: string; arguments?: Record<string, unknown> | string };
+}
+
+// Final responses report prompt_eval_count and eval_count
+function ollamaUsage(data: { prompt_eval_count?: number; eval_count?: number }): TokenUsage | undefined {
+  if (data.prompt_eval_count === undefined && data.eval_count === undefined) return undefined;
+  return { promptTokens: data.prompt_eval_count ?? 0, completionTokens: data.eval_count ?? 0 };
+}
+
+// Ollama has no tool call ids; results are matched to calls by tool_name instead
+function toOllamaMessages(prompt: string | ChatMessage[]) {
+  return toMessages(prompt).map((m) => {
+    if (m.role === "tool") return { role: "tool", content: m.content, ...(m.name && { tool_name: m.name }) };
+    if (m.role === "assistant" && m.toolCalls?.length) {
+      return {
+        role: "assistant",
+        content: m.content,
+        tool_calls: m.toolCalls.map((c) => ({ function: { name: c.name, arguments: c.arguments } })),
+      };
+    }
+    return { role: m.role, content: m.content };
+  });
+}
+
+function ollamaModel(options?: Pick<ProviderChatOptions, "model">): string {
+  return options?.model || process.env.OLLAMA_MODEL || "qwen2.5-coder:7b";
+}
+
+function ollamaHeaders(): Record<string, string> {
+  const headers: Record<string, string> = { "Content-Type": "application/json" };
   if (process.env.CF_ACCESS_CLIENT_ID && process.env.CF_ACCESS_CLIENT_SECRET) {
     headers["CF-Access-Client-Id"] = process.env.CF_ACCESS_CLIENT_ID;
     headers["CF-Access-Client-Secret"] = process.env.CF_ACCESS_CLIENT_SECRET;
   }
+  return headers;
+}
+
+export async function chatWithOllama(prompt: string | ChatMessage[], options?: OllamaOptions): Promise<string> {
+  return (await requestOllama(prompt, options)).content;
+}
+
+async function requestOllama(prompt: string | ChatMessage[], options?: OllamaOptions): Promise<ProviderResponse> {
+  const host = process.env.OLLAMA_HOST || "http://localhost:11434";
+  const model = ollamaModel(options);
+  const temperature = options?.temperature ?? 0.1;
 
   const controller = new AbortController();
   const timeout = setTimeout(() => controller.abort(), 55000); // 55s timeout
@@ -18,23 +68,96 @@ export async function chatWithOllama(
   try {
     const response = await fetch(`${host}/api/chat`, {
       method: "POST",
-      headers,
+      headers: ollamaHeaders(),
       signal: controller.signal,
       body: JSON.stringify({
         model,
-        messages: [{ role: "user", content: prompt }],
+        messages: toOllamaMessages(prompt),
         stream: false,
+        ...(options?.format && { format: options.format }),
+        ...(options?.tools?.length && { tools: toOpenAITools(options.tools) }),
         options: { temperature, num_ctx: 4096 },
       }),
     });
 
     if (!response.ok) {
-      throw new Error(`Ollama API error: ${response.statusText}`);
+      throw Object.assign(new Error(`Ollama API error: ${response.statusText}`), { status: response.status });
     }
 
     const data = await response.json();
-    return data.message?.content || "";
+    return { content: data.message?.content || "", usage: ollamaUsage(data) };
   } finally {
     clearTimeout(timeout);
   }
 }
+
+// Ollama streams newline-delimited JSON objects, the last one with done: true.
+// Tool calls arrive whole in a single chunk.
+export async function* streamWithOllama(prompt: string | ChatMessage[], options?: OllamaOptions): AsyncGenerator<StreamEvent> {
+  const host = process.env.OLLAMA_HOST || "http://localhost:11434";
+  const controller = new AbortController();
+  const timeout = setTimeout(() => controller.abort(), 55000);
+
+  let response: Response;
+  try {
+    response = await fetch(`${host}/api/chat`, {
+      method: "POST",
+      headers: ollamaHeaders(),
+      signal: controller.signal,
+      body: JSON.stringify({
+        model: ollamaModel(options),
+        messages: toOllamaMessages(prompt),
+        stream: true,
+        ...(options?.format && { format: options.format }),
+        ...(options?.tools?.length && { tools: toOpenAITools(options.tools) }),
+        options: { temperature: options?.temperature ?? 0.1, num_ctx: 4096 },
+      }),
+    });
+  } finally {
+    clearTimeout(timeout);
+  }
+
+  if (!response.ok || !response.body) {
+    throw Object.assign(new Error(`Ollama API error: ${response.statusText}`), { status: response.status });
+  }
+
+  let callCount = 0;
+  for await (const line of readLines(response.body)) {
+    if (!line.trim()) continue;
+    const chunk = JSON.parse(line) as {
+      message?: { content?: string; tool_calls?: OllamaToolCall[] };
+      done?: boolean;
+      error?: string;
+      prompt_eval_count?: number;
+      eval_count?: number;
+    };
+    if (chunk.error) throw new Error(`Ollama API error: ${chunk.error}`);
+    if (chunk.message?.content) yield { type: "text", text: chunk.message.content };
+    for (const call of chunk.message?.tool_calls || []) {
+      if (!call.function?.name) continue;
+      yield {
+        type: "tool_call",
+        call: { id: `call_${callCount++}`, name: call.function.name, arguments: parseToolArguments(call.function.arguments) },
+      };
+    }
+    if (chunk.done) {
+      const usage = ollamaUsage(chunk);
+      if (usage) yield { type: "usage", usage };
+      break;
+    }
+  }
+}
+
+export function classifyOllamaError(error: unknown): LLMError {
+  if (error instanceof LLMError) return error;
+  const message = error instanceof Error ? error.message : String(error);
+  return classifyHttpError("ollama", (error as { status?: number })?.status, message);
+}
+
+export const ollamaProvider: LLMProviderClient = {
+  name: "ollama",
+  model: ollamaModel,
+  chat: (messages, options) => requestOllama(messages, options),
+  stream: (messages, options) => streamWithOllama(messages, options),
+  classifyError: classifyOllamaError,
+};
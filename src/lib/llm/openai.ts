@@ -0,0 +1,202 @@
+import {
+  ChatMessage,
+  LLMError,
+  LLMProviderClient,
+  ProviderChatOptions,
+  ProviderConfig,
+  ProviderResponse,
+  StreamEvent,
+  TokenUsage,
+  ToolCallRequest,
+  ToolSpec,
+  classifyHttpError,
+  parseRetryAfter,
+  postJson,
+  parseToolArguments,
+  postStream,
+  resolveModel,
+  withRetries,
+} from "./provider";
+import { parseSSE } from "../sse";
+
+interface OpenAIToolCall {
+  id?: string;
+  function?: { name?: string; arguments?: string };
+}
+
+interface OpenAIUsage {
+  prompt_tokens?: number;
+  completion_tokens?: number;
+}
+
+interface OpenAIResponse {
+  choices?: { message?: { content?: string | null; tool_calls?: OpenAIToolCall[] } }[];
+  usage?: OpenAIUsage | null;
+  error?: { message?: string; type?: string; code?: string | null };
+}
+
+interface OpenAIToolCallDelta extends OpenAIToolCall {
+  index: number;
+}
+
+// Shared by every OpenAI-style API (OpenAI-compatible servers and Groq)
+export function toOpenAIMessages(messages: ChatMessage[]) {
+  return messages.map((m) => {
+    if (m.role === "tool") {
+      return { role: "tool" as const, tool_call_id: m.toolCallId || "", content: m.content };
+    }
+    if (m.role === "assistant" && m.toolCalls?.length) {
+      return {
+        role: "assistant" as const,
+        content: m.content || null,
+        tool_calls: m.toolCalls.map((call) => ({
+          id: call.id,
+          type: "function" as const,
+          function: { name: call.name, arguments: JSON.stringify(call.arguments) },
+        })),
+      };
+    }
+    return { role: m.role, content: m.content };
+  });
+}
+
+export function fromOpenAIUsage(usage: OpenAIUsage | null | undefined): TokenUsage | undefined {
+  if (!usage) return undefined;
+  return { promptTokens: usage.prompt_tokens ?? 0, completionTokens: usage.completion_tokens ?? 0 };
+}
+
+export function toOpenAITools(tools: ToolSpec[] | undefined) {
+  if (!tools?.length) return undefined;
+  return tools.map((t) => ({
+    type: "function" as const,
+    function: { name: t.name, description: t.description, parameters: t.parameters },
+  }));
+}
+
+export function fromOpenAIToolCalls(calls: OpenAIToolCall[] | undefined): ToolCallRequest[] {
+  return (calls || [])
+    .filter((c) => c.function?.name)
+    .map((c, i) => ({ id: c.id || `call_${i}`, name: c.function!.name!, arguments: parseToolArguments(c.function?.arguments) }));
+}
+
+/**
+ * Streamed tool calls arrive as fragments keyed by index: the first carries the
+ * id and name, later ones append to the JSON arguments string.
+ */
+export class ToolCallAccumulator {
+  private calls = new Map<number, OpenAIToolCall & { function: { name?: string; arguments: string } }>();
+
+  add(deltas: OpenAIToolCallDelta[] | undefined): void {
+    for (const delta of deltas || []) {
+      const call = this.calls.get(delta.index) || { function: { arguments: "" } };
+      if (delta.id) call.id = delta.id;
+      if (delta.function?.name) call.function.name = delta.function.name;
+      call.function.arguments += delta.function?.arguments || "";
+      this.calls.set(delta.index, call);
+    }
+  }
+
+  finish(): ToolCallRequest[] {
+    const calls = fromOpenAIToolCalls([...this.calls.values()]);
+    this.calls.clear();
+    return calls;
+  }
+}
+
+// Works with any server exposing /v1/chat/completions (OpenAI, vLLM, LM Studio, llama.cpp, OpenRouter)
+export function openAIConfigFromEnv(): ProviderConfig {
+  return {
+    baseUrl: process.env.OPENAI_BASE_URL || "https://api.openai.com/v1",
+    apiKey: process.env.OPENAI_API_KEY,
+    model: process.env.OPENAI_MODEL || "gpt-4o-mini",
+    reviewModel: process.env.OPENAI_REVIEW_MODEL,
+    modelMap: parseModelMap(process.env.OPENAI_MODEL_MAP),
+    timeoutMs: 60000,
+  };
+}
+
+// "groq-model=local-model,other=local-other"
+export function parseModelMap(value: string | undefined): Record<string, string> | undefined {
+  if (!value) return undefined;
+  const entries = value.split(",").map((pair) => pair.split("=").map((s) => s.trim()));
+  return Object.fromEntries(entries.filter(([from, to]) => from && to));
+}
+
+export function classifyOpenAIError(error: unknown, provider = "openai"): LLMError {
+  if (error instanceof LLMError) return error;
+  const message = error instanceof Error ? error.message : String(error);
+  return classifyHttpError(provider, (error as { status?: number })?.status, message);
+}
+
+export function createOpenAICompatibleProvider(config: ProviderConfig, name = "openai"): LLMProviderClient {
+  const baseUrl = config.baseUrl.replace(/\/+$/, "");
+
+  const headers = {
+    ...(config.apiKey && { Authorization: `Bearer ${config.apiKey}` }),
+    ...config.headers,
+  };
+
+  const buildBody = (messages: ChatMessage[], options?: ProviderChatOptions) => {
+    const maxTokens = options?.maxTokens ?? config.maxTokens;
+    return {
+      model: resolveModel(config, options),
+      messages: toOpenAIMessages(messages),
+      temperature: options?.temperature ?? 0.1,
+      ...(maxTokens && { max_tokens: maxTokens }),
+      ...(options?.tools?.length && { tools: toOpenAITools(options.tools) }),
+      ...(options?.jsonMode && { response_format: { type: "json_object" } }),
+    };
+  };
+
+  const classifyError = (error: unknown) => classifyOpenAIError(error, name);
+
+  const refineError = (error: LLMError, data: unknown) => {
+    const code = (data as OpenAIResponse | null)?.error?.code;
+    if (code === "context_length_exceeded") error.kind = "context_length";
+    if (code === "invalid_api_key") error.kind = "auth";
+  };
+
+  const request = async (messages: ChatMessage[], options?: ProviderChatOptions): Promise<ProviderResponse> => {
+    const res = await postJson(`${baseUrl}/chat/completions`, buildBody(messages, options), headers, config.timeoutMs ?? 60000);
+
+    const data = res.data as OpenAIResponse | null;
+    if (!res.ok) {
+      const error = classifyHttpError(name, res.status, data?.error?.message || res.text || `HTTP ${res.status}`, parseRetryAfter(res.headers.get("retry-after")));
+      refineError(error, data);
+      throw error;
+    }
+    return { content: data?.choices?.[0]?.message?.content || "", usage: fromOpenAIUsage(data?.usage) };
+  };
+
+  // Streamed responses are SSE "data:" lines ending with [DONE]; tool calls are emitted once complete
+  async function* stream(messages: ChatMessage[], options?: ProviderChatOptions): AsyncGenerator<StreamEvent> {
+    const body = await withRetries(
+      () => postStream(name, `${baseUrl}/chat/completions`, { ...buildBody(messages, options), stream: true, stream_options: { include_usage: true } }, headers, config.timeoutMs ?? 60000, refineError),
+      classifyError,
+      options?.maxRetries
+    );
+    const toolCalls = new ToolCallAccumulator();
+    for await (const { data } of parseSSE(body)) {
+      if (data === "[DONE]") break;
+      const chunk = JSON.parse(data) as {
+        choices?: { delta?: { content?: string | null; tool_calls?: OpenAIToolCallDelta[] } }[];
+        usage?: OpenAIUsage | null;
+      };
+      // With include_usage, the last chunk has no choices and carries the totals
+      const usage = fromOpenAIUsage(chunk.usage);
+      if (usage) yield { type: "usage", usage };
+      const delta = chunk.choices?.[0]?.delta;
+      if (delta?.content) yield { type: "text", text: delta.content };
+      toolCalls.add(delta?.tool_calls);
+    }
+    for (const call of toolCalls.finish()) yield { type: "tool_call", call };
+  }
+
+  return {
+    name,
+    model: (options) => resolveModel(config, options),
+    chat: (messages, options) => withRetries(() => request(messages, options), classifyError, options?.maxRetries),
+    stream,
+    classifyError,
+  };
+}
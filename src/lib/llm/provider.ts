@@ -0,0 +1,216 @@
+export interface ChatMessage {
+  role: "system" | "user" | "assistant" | "tool";
+  content: string;
+  toolCalls?: ToolCallRequest[]; // assistant turns that called tools
+  toolCallId?: string; // tool results
+  name?: string; // tool results: the tool that produced it
+}
+
+// Function spec passed to native tool-calling APIs; parameters is a JSON Schema object
+export interface ToolSpec {
+  name: string;
+  description: string;
+  parameters: Record<string, unknown>;
+}
+
+export interface ToolCallRequest {
+  id: string;
+  name: string;
+  arguments: Record<string, unknown>;
+}
+
+export interface TokenUsage {
+  promptTokens: number;
+  completionTokens: number;
+}
+
+export interface ProviderResponse {
+  content: string;
+  usage?: TokenUsage; // absent when the server doesn't report it
+}
+
+export type StreamEvent =
+  | { type: "text"; text: string }
+  | { type: "tool_call"; call: ToolCallRequest }
+  | { type: "usage"; usage: TokenUsage };
+
+export interface ProviderChatOptions {
+  model?: string;
+  temperature?: number;
+  maxTokens?: number;
+  useReviewModel?: boolean;
+  maxRetries?: number;
+  jsonMode?: boolean;
+  format?: "json" | Record<string, unknown>;
+  tools?: ToolSpec[];
+}
+
+export type LLMErrorKind = "rate_limit" | "auth" | "bad_request" | "context_length" | "timeout" | "server" | "network" | "unavailable" | "unknown";
+
+const RETRYABLE_KINDS: LLMErrorKind[] = ["rate_limit", "timeout", "server", "network", "unavailable"];
+
+export class LLMError extends Error {
+  constructor(
+    message: string,
+    public kind: LLMErrorKind,
+    public provider: string,
+    public status?: number,
+    public retryAfter?: number
+  ) {
+    super(message);
+    this.name = "LLMError";
+  }
+
+  get retryable(): boolean {
+    return RETRYABLE_KINDS.includes(this.kind);
+  }
+}
+
+export interface ProviderConfig {
+  baseUrl: string;
+  apiKey?: string;
+  model: string;
+  reviewModel?: string;
+  // Aliases callers may pass (e.g. another provider's model names) mapped to this provider's models
+  modelMap?: Record<string, string>;
+  headers?: Record<string, string>;
+  timeoutMs?: number;
+  maxTokens?: number;
+}
+
+export interface ServedBy {
+  provider: string;
+  model: string;
+}
+
+export interface LLMProviderClient {
+  name: string;
+  // Model a request with these options would be sent to
+  model(options?: ProviderChatOptions): string;
+  chat(messages: ChatMessage[], options?: ProviderChatOptions): Promise<ProviderResponse>;
+  // Yields text deltas, completed tool calls and token usage; providers without it are streamed as one chunk
+  stream?(messages: ChatMessage[], options?: ProviderChatOptions): AsyncIterable<StreamEvent>;
+  classifyError(error: unknown): LLMError;
+}
+
+export function resolveModel(config: Pick<ProviderConfig, "model" | "reviewModel" | "modelMap">, options?: ProviderChatOptions): string {
+  if (options?.model) return config.modelMap?.[options.model] ?? options.model;
+  return options?.useReviewModel ? config.reviewModel || config.model : config.model;
+}
+
+export function toMessages(prompt: string | ChatMessage[]): ChatMessage[] {
+  return typeof prompt === "string" ? [{ role: "user", content: prompt }] : prompt;
+}
+
+// Tool arguments arrive as a JSON string (OpenAI-style) or an object (Ollama, Anthropic)
+export function parseToolArguments(raw: unknown): Record<string, unknown> {
+  if (raw && typeof raw === "object") return raw as Record<string, unknown>;
+  if (typeof raw !== "string" || !raw.trim()) return {};
+  try {
+    const parsed = JSON.parse(raw);
+    return parsed && typeof parsed === "object" ? parsed : {};
+  } catch {
+    return {};
+  }
+}
+
+export function parseRetryAfter(value: string | null | undefined): number | undefined {
+  if (!value) return undefined;
+  const seconds = Number(value);
+  if (!Number.isNaN(seconds)) return seconds * 1000;
+  const date = Date.parse(value);
+  return Number.isNaN(date) ? undefined : Math.max(0, date - Date.now());
+}
+
+// Shared status/message classification; providers refine it with their own error codes
+export function classifyHttpError(provider: string, status: number | undefined, message: string, retryAfter?: number): LLMError {
+  const msg = message.toLowerCase();
+  let kind: LLMErrorKind = "unknown";
+  if (status === 429 || msg.includes("rate limit") || msg.includes("rate_limit")) kind = "rate_limit";
+  else if (status === 401 || status === 403) kind = "auth";
+  else if (status === 408 || msg.includes("timeout") || msg.includes("aborted")) kind = "timeout";
+  else if (msg.includes("context length") || msg.includes("context_length") || msg.includes("too many tokens")) kind = "context_length";
+  else if (status && status >= 500) kind = "server";
+  else if (status && status >= 400) kind = "bad_request";
+  else if (msg.includes("fetch failed") || msg.includes("econnrefused") || msg.includes("network")) kind = "network";
+  return new LLMError(message, kind, provider, status, retryAfter);
+}
+
+const sleep = (ms: number) => new Promise((r) => setTimeout(r, ms));
+
+export async function withRetries<T>(fn: () => Promise<T>, classify: (error: unknown) => LLMError, maxRetries = 3): Promise<T> {
+  for (let attempt = 0; ; attempt++) {
+    try {
+      return await fn();
+    } catch (err) {
+      const error = classify(err);
+      if (!error.retryable || attempt >= maxRetries - 1) throw error;
+      await sleep(error.retryAfter ?? Math.min(1000 * Math.pow(2, attempt + 1), 30000));
+    }
+  }
+}
+
+export async function postJson(
+  url: string,
+  body: unknown,
+  headers: Record<string, string>,
+  timeoutMs: number
+): Promise<{ status: number; ok: boolean; headers: Headers; data: unknown; text: string }> {
+  const controller = new AbortController();
+  const timeout = setTimeout(() => controller.abort(), timeoutMs);
+  try {
+    const res = await fetch(url, {
+      method: "POST",
+      headers: { "Content-Type": "application/json", ...headers },
+      body: JSON.stringify(body),
+      signal: controller.signal,
+    });
+    const text = await res.text();
+    let data: unknown = null;
+    try {
+      data = text ? JSON.parse(text) : null;
+    } catch {
+      // Leave data null; callers fall back to the raw text
+    }
+    return { status: res.status, ok: res.ok, headers: res.headers, data, text };
+  } finally {
+    clearTimeout(timeout);
+  }
+}
+
+// POST that returns the raw response for streaming; non-2xx responses are thrown as classified errors
+export async function postStream(
+  provider: string,
+  url: string,
+  body: unknown,
+  headers: Record<string, string>,
+  timeoutMs: number,
+  refine?: (error: LLMError, data: unknown) => void
+): Promise<ReadableStream<Uint8Array>> {
+  const controller = new AbortController();
+  const timeout = setTimeout(() => controller.abort(), timeoutMs);
+  try {
+    const res = await fetch(url, {
+      method: "POST",
+      headers: { "Content-Type": "application/json", ...headers },
+      body: JSON.stringify(body),
+      signal: controller.signal,
+    });
+    if (!res.ok || !res.body) {
+      const text = await res.text();
+      let data: { error?: { message?: string } } | null = null;
+      try {
+        data = JSON.parse(text);
+      } catch {
+        // Non-JSON error body
+      }
+      const error = classifyHttpError(provider, res.status, data?.error?.message || text || `HTTP ${res.status}`, parseRetryAfter(res.headers.get("retry-after")));
+      refine?.(error, data);
+      throw error;
+    }
+    return res.body;
+  } finally {
+    // Only the wait for headers is bounded; the stream itself may run longer
+    clearTimeout(timeout);
+  }
+}
@@ -0,0 +1,148 @@
+import { getCircuitBreaker } from "../circuit-breaker";
+import { metrics } from "../metrics";
+import { ChatMessage, LLMError, LLMProviderClient, ProviderChatOptions, ProviderResponse, ServedBy, StreamEvent, TokenUsage } from "./provider";
+
+export interface LLMResponse extends ServedBy {
+  content: string;
+  latencyMs: number;
+  usage?: TokenUsage;
+}
+
+// Failures that say the provider itself is unhealthy, as opposed to this model or request
+const PROVIDER_FAILURE_KINDS: LLMError["kind"][] = ["network", "timeout", "server", "auth", "rate_limit"];
+
+// A model breaker also opens for a model the provider doesn't serve, but not
+// for a prompt that's too long or malformed, which the next request may not be
+function isModelFailure(error: LLMError): boolean {
+  return PROVIDER_FAILURE_KINDS.includes(error.kind) || (error.kind === "bad_request" && error.status === 404);
+}
+
+/**
+ * Ordered provider names to try. LLM_PROVIDERS ("groq,ollama,anthropic") takes
+ * precedence over LLM_DEFAULT_PROVIDER + LLM_FALLBACK_PROVIDER; an explicitly
+ * requested provider is always tried first.
+ */
+export function getProviderChain(preferred?: string, env: Record<string, string | undefined> = process.env): string[] {
+  const configured = env.LLM_PROVIDERS
+    ? env.LLM_PROVIDERS.split(",").map((p) => p.trim())
+    : [env.LLM_DEFAULT_PROVIDER || env.LLM_PROVIDER || "groq", env.LLM_FALLBACK_PROVIDER || ""];
+  return [...new Set([preferred || "", ...configured].filter(Boolean))];
+}
+
+export type StreamChunk = ServedBy & StreamEvent;
+
+type Resolver = (name: string) => LLMProviderClient;
+
+function recordUsage(served: ServedBy, usage: TokenUsage | undefined) {
+  if (!usage) return;
+  metrics.increment("llm_tokens", usage.promptTokens, { ...served, type: "prompt" });
+  metrics.increment("llm_tokens", usage.completionTokens, { ...served, type: "completion" });
+}
+
+function recordFailure(name: string, provider: LLMProviderClient, model: string, err: unknown): LLMError {
+  const error = provider.classifyError(err);
+  if (isModelFailure(error)) getCircuitBreaker(`llm:${name}:${model}`).onFailure();
+  if (PROVIDER_FAILURE_KINDS.includes(error.kind)) getCircuitBreaker(`llm:${name}`).onFailure();
+  metrics.increment("llm_requests", 1, { provider: name, model, status: "error" });
+  return error;
+}
+
+/**
+ * Try each provider in order, skipping any whose provider or provider/model
+ * breaker is open. Outage-type errors count against the provider breaker;
+ * those and missing models count against the model breaker.
+ */
+async function firstAvailable<T>(
+  chain: string[],
+  resolve: Resolver,
+  options: ProviderChatOptions | undefined,
+  call: (provider: LLMProviderClient) => Promise<T>
+): Promise<{ value: T; name: string; provider: LLMProviderClient; model: string; start: number }> {
+  const failures: string[] = [];
+  let lastError: LLMError | undefined;
+
+  for (const name of chain) {
+    let provider: LLMProviderClient;
+    try {
+      provider = resolve(name);
+    } catch (err) {
+      failures.push(`${name}: ${err instanceof Error ? err.message : "unavailable"}`);
+      continue;
+    }
+
+    const model = provider.model(options);
+    const providerBreaker = getCircuitBreaker(`llm:${name}`);
+    const modelBreaker = getCircuitBreaker(`llm:${name}:${model}`);
+    if (!providerBreaker.isAvailable() || !modelBreaker.isAvailable()) {
+      metrics.increment("llm_skipped", 1, { provider: name, model });
+      failures.push(`${name}/${model}: circuit open`);
+      continue;
+    }
+
+    const start = Date.now();
+    try {
+      const value = await call(provider);
+      providerBreaker.onSuccess();
+      modelBreaker.onSuccess();
+      return { value, name, provider, model, start };
+    } catch (err) {
+      const error = recordFailure(name, provider, model, err);
+      lastError = error;
+      failures.push(`${name}/${model}: ${error.kind} - ${error.message}`);
+      if (chain.length > 1) console.log(`LLM ${name}/${model} failed (${error.kind}), trying next provider`);
+    }
+  }
+
+  // A single attempted provider keeps its own error so callers see the real cause
+  if (failures.length === 1 && lastError) throw lastError;
+  throw new LLMError(`All LLM providers failed: ${failures.join("; ") || "no providers configured"}`, "unavailable", chain.join(","));
+}
+
+export async function routeChat(
+  messages: ChatMessage[],
+  options: ProviderChatOptions | undefined,
+  chain: string[],
+  resolve: Resolver
+): Promise<LLMResponse> {
+  const { value, name, model, start } = await firstAvailable(chain, resolve, options, (p) => p.chat(messages, options));
+  metrics.timing("llm_latency", start, { provider: name, model });
+  metrics.increment("llm_requests", 1, { provider: name, model, status: "success" });
+  recordUsage({ provider: name, model }, value.usage);
+  return { content: value.content, usage: value.usage, provider: name, model, latencyMs: Date.now() - start };
+}
+
+async function* singleChunk(response: Promise<ProviderResponse>): AsyncGenerator<StreamEvent> {
+  const { content, usage } = await response;
+  yield { type: "text", text: content };
+  if (usage) yield { type: "usage", usage };
+}
+
+/**
+ * Streaming variant of routeChat. Falling back to the next provider is only
+ * possible until the first chunk arrives; later failures are thrown.
+ */
+export async function* routeStream(
+  messages: ChatMessage[],
+  options: ProviderChatOptions | undefined,
+  chain: string[],
+  resolve: Resolver
+): AsyncGenerator<StreamChunk> {
+  const { value, name, provider, model, start } = await firstAvailable(chain, resolve, options, async (p) => {
+    const source = p.stream ? p.stream(messages, options) : singleChunk(p.chat(messages, options));
+    const iterator = source[Symbol.asyncIterator]();
+    return { iterator, first: await iterator.next() };
+  });
+
+  const { iterator, first } = value;
+  const served = { provider: name, model };
+  try {
+    for (let next = first; !next.done; next = await iterator.next()) {
+      if (next.value.type === "usage") recordUsage(served, next.value.usage);
+      yield { ...served, ...next.value };
+    }
+  } catch (err) {
+    throw recordFailure(name, provider, model, err);
+  }
+  metrics.timing("llm_latency", start, served);
+  metrics.increment("llm_requests", 1, { ...served, status: "success" });
+}
@@ -0,0 +1,9 @@
+import { z } from "zod";
+import { toJsonSchema as toJsonSchemaWith, type StructuredSchema } from "../../../supabase/functions/_shared/structured.ts";
+
+// The parse/repair logic lives with the edge functions so both runtimes share it
+export * from "../../../supabase/functions/_shared/structured.ts";
+
+export function toJsonSchema(schema: StructuredSchema<unknown>): Record<string, unknown> {
+  return toJsonSchemaWith(z, schema);
+}
@@ -0,0 +1,52 @@
+import type { TokenUsage } from "./provider";
+
+// USD per million tokens, [input, output]. Unlisted models (e.g. local Ollama) cost nothing.
+const DEFAULT_PRICING: Record<string, [number, number]> = {
+  "llama-3.1-8b-instant": [0.05, 0.08],
+  "llama-3.3-70b-versatile": [0.59, 0.79],
+  "gpt-4o-mini": [0.15, 0.6],
+  "gpt-4o": [2.5, 10],
+  "claude-3-5-haiku-latest": [0.8, 4],
+  "claude-3-5-sonnet-latest": [3, 15],
+};
+
+// Totals stored with review_history and security_scans rows
+export interface UsageTotals {
+  prompt_tokens: number;
+  completion_tokens: number;
+  cost_usd: number;
+}
+
+// "model=input/output,other=input/output", prices per million tokens
+export function parsePricing(value: string | undefined): Record<string, [number, number]> {
+  const pricing: Record<string, [number, number]> = {};
+  for (const entry of (value || "").split(",")) {
+    const [model, prices] = entry.split("=").map((s) => s.trim());
+    const [input, output] = (prices || "").split("/").map(Number);
+    if (model && Number.isFinite(input) && Number.isFinite(output)) pricing[model] = [input!, output!];
+  }
+  return pricing;
+}
+
+export function estimateCost(model: string, usage: TokenUsage, pricing = { ...DEFAULT_PRICING, ...parsePricing(process.env.LLM_PRICING) }): number {
+  const [input, output] = pricing[model] || [0, 0];
+  return (usage.promptTokens * input + usage.completionTokens * output) / 1_000_000;
+}
+
+export function emptyUsage(): UsageTotals {
+  return { prompt_tokens: 0, completion_tokens: 0, cost_usd: 0 };
+}
+
+// Add one LLM call's usage to a running total
+export function addUsage(totals: UsageTotals, model: string, usage: TokenUsage | undefined): UsageTotals {
+  if (!usage) return totals;
+  totals.prompt_tokens += usage.promptTokens;
+  totals.completion_tokens += usage.completionTokens;
+  totals.cost_usd = Math.round((totals.cost_usd + estimateCost(model, usage)) * 1e6) / 1e6;
+  return totals;
+}
+
+export function sumUsage(a: TokenUsage | undefined, b: TokenUsage | undefined): TokenUsage | undefined {
+  if (!a || !b) return a || b;
+  return { promptTokens: a.promptTokens + b.promptTokens, completionTokens: a.completionTokens + b.completionTokens };
+}
@@ -1,4 +1,4 @@
-import { createClient } from "./supabase/server";
+import { getStorage } from "./storage";
 
 interface NotifyOptions {
   type: "error" | "warning" | "info";
@@ -10,10 +10,8 @@ const SLACK_WEBHOOK = process.env.SLACK_WEBHOOK_URL;
 const DISCORD_WEBHOOK = process.env.DISCORD_WEBHOOK_URL;
 
 export async function notify({ type, message, metadata }: NotifyOptions): Promise<void> {
-  const supabase = await createClient();
-  
   // Log to database
-  await supabase.from("notifications").insert({
+  await getStorage().notifications.insert({
     type,
     channel: SLACK_WEBHOOK ? "slack" : DISCORD_WEBHOOK ? "discord" : "log",
     message,
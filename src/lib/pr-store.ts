@@ -3,41 +3,11 @@
  * Handles storing and retrieving pull requests from the database
  */
 
-import { createClient as createServerClient } from "./supabase/server";
+import { getStorage, type PullRequestRow } from "./storage";
 import { detectLLMGenerated, type PRData } from "./llm-detection";
 import { pr as githubPR } from "./github";
 
-export interface PullRequestRow {
-  id: string;
-  github_id: number;
-  repo_full_name: string;
-  number: number;
-  title: string;
-  body: string | null;
-  state: string;
-  draft: boolean;
-  author_login: string;
-  author_type: string | null;
-  author_id: number | null;
-  github_created_at: string | null;
-  github_updated_at: string | null;
-  github_merged_at: string | null;
-  additions: number;
-  deletions: number;
-  changed_files: number;
-  head_ref: string | null;
-  base_ref: string | null;
-  head_sha: string | null;
-  labels: string[];
-  html_url: string | null;
-  is_llm_generated: boolean;
-  llm_tool: string | null;
-  llm_confidence: number | null;
-  llm_detection_signals: Record<string, unknown> | null;
-  created_at: string;
-  updated_at: string;
-  synced_at: string;
-}
+export type { PullRequestRow } from './storage';
 
 export interface GitHubPullRequest {
   id: number;
@@ -79,7 +49,6 @@ export interface UpsertPRInput {
  */
 export async function upsertPullRequest(input: UpsertPRInput): Promise<PullRequestRow> {
   const { pr, repoFullName, commits } = input;
-  const supabase = await createServerClient();
 
   // Run LLM detection
   const detection = detectLLMGenerated({
@@ -122,14 +91,7 @@ export async function upsertPullRequest(input: UpsertPRInput): Promise<PullReque
     synced_at: new Date().toISOString(),
   };
 
-  const { data, error } = await supabase
-    .from('pull_requests')
-    .upsert(row, { onConflict: 'repo_full_name,number' })
-    .select()
-    .single();
-
-  if (error) throw error;
-  return data;
+  return getStorage().pullRequests.upsert(row);
 }
 
 /**
@@ -189,37 +151,7 @@ export async function getRepoPRs(
     offset?: number;
   }
 ): Promise<PullRequestRow[]> {
-  const supabase = await createServerClient();
-
-  let query = supabase
-    .from('pull_requests')
-    .select('*')
-    .eq('repo_full_name', repoFullName)
-    .order('github_created_at', { ascending: false });
-
-  if (options?.state && options.state !== 'all') {
-    if (options.state === 'merged') {
-      query = query.not('github_merged_at', 'is', null);
-    } else {
-      query = query.eq('state', options.state);
-    }
-  }
-
-  if (options?.llmOnly) {
-    query = query.eq('is_llm_generated', true);
-  }
-
-  if (options?.limit) {
-    query = query.limit(options.limit);
-  }
-
-  if (options?.offset) {
-    query = query.range(options.offset, options.offset + (options.limit || 50) - 1);
-  }
-
-  const { data, error } = await query;
-  if (error) throw error;
-  return data || [];
+  return getStorage().pullRequests.list({ ...options, repo: repoFullName });
 }
 
 /**
@@ -231,36 +163,7 @@ export async function getAllPRs(options?: {
   limit?: number;
   offset?: number;
 }): Promise<PullRequestRow[]> {
-  const supabase = await createServerClient();
-
-  let query = supabase
-    .from('pull_requests')
-    .select('*')
-    .order('github_created_at', { ascending: false });
-
-  if (options?.state && options.state !== 'all') {
-    if (options.state === 'merged') {
-      query = query.not('github_merged_at', 'is', null);
-    } else {
-      query = query.eq('state', options.state);
-    }
-  }
-
-  if (options?.llmOnly) {
-    query = query.eq('is_llm_generated', true);
-  }
-
-  if (options?.limit) {
-    query = query.limit(options.limit);
-  }
-
-  if (options?.offset) {
-    query = query.range(options.offset, options.offset + (options.limit || 50) - 1);
-  }
-
-  const { data, error } = await query;
-  if (error) throw error;
-  return data || [];
+  return getStorage().pullRequests.list(options);
 }
 
 /**
@@ -272,18 +175,7 @@ export async function getPRStats(repoFullName?: string): Promise<{
   byTool: Record<string, number>;
   byState: Record<string, number>;
 }> {
-  const supabase = await createServerClient();
-
-  // Build base query
-  let query = supabase.from('pull_requests').select('*');
-  if (repoFullName) {
-    query = query.eq('repo_full_name', repoFullName);
-  }
-
-  const { data, error } = await query;
-  if (error) throw error;
-
-  const prs = data || [];
+  const prs = await getStorage().pullRequests.list({ repo: repoFullName });
   const total = prs.length;
   const llmGenerated = prs.filter(pr => pr.is_llm_generated).length;
 
@@ -318,38 +210,12 @@ export async function getPRStats(repoFullName?: string): Promise<{
  * Get a single PR by repo and number
  */
 export async function getPR(repoFullName: string, number: number): Promise<PullRequestRow | null> {
-  const supabase = await createServerClient();
-
-  const { data, error } = await supabase
-    .from('pull_requests')
-    .select('*')
-    .eq('repo_full_name', repoFullName)
-    .eq('number', number)
-    .single();
-
-  if (error?.code === 'PGRST116') return null;
-  if (error) throw error;
-  return data;
+  return getStorage().pullRequests.get(repoFullName, number);
 }
 
 /**
  * Get distinct repos from stored PRs
  */
 export async function getStoredRepos(): Promise<string[]> {
-  const supabase = await createServerClient();
-
-  const { data, error } = await supabase
-    .from('pull_requests')
-    .select('repo_full_name')
-    .order('repo_full_name');
-
-  if (error) throw error;
-
-  // Get unique repo names
-  const repos = new Set<string>();
-  for (const row of data || []) {
-    repos.add(row.repo_full_name);
-  }
-
-  return Array.from(repos);
+  return getStorage().pullRequests.repos();
 }
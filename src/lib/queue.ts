@@ -1,97 +1,131 @@
-import { createClient } from "./supabase/server";
-
-export interface ReviewJob {
-  id: string;
-  repo_full_name: string;
-  pr_number: number;
-  owner: string;
-  repo: string;
-  status: "pending" | "processing" | "completed" | "failed";
-  attempts: number;
-  max_attempts: number;
-  analysis?: unknown;
-  error?: string;
-  created_at: string;
+import { randomUUID } from "crypto";
+import { getStorage, isUniqueViolation, withServiceRole, type ReviewJob } from "./storage";
+import { concludeCheck, createReviewCheck } from "./checks";
+
+export type { ReviewJob } from "./storage";
+
+// Identifies this process on the jobs it claims; set WORKER_ID to make it stable
+export const WORKER_ID = process.env.WORKER_ID || `app-${randomUUID().slice(0, 8)}`;
+// A claimed job returns to the queue if its lease isn't renewed within this time
+export const LEASE_SECONDS = parseInt(process.env.QUEUE_LEASE_SECONDS || "300", 10);
+// Default cap on running reviews per repo (repo_configs.max_concurrent_reviews overrides it)
+const REPO_CONCURRENCY = parseInt(process.env.QUEUE_REPO_CONCURRENCY || "2", 10);
+// A waiting job is promoted one priority level per this many minutes
+const AGING_MINUTES = parseInt(process.env.QUEUE_AGING_MINUTES || "15", 10);
+
+export const PRIORITY_LEVELS = { low: 0, medium: 1, high: 2, critical: 3 } as const;
+
+/** Queue priority from PR analysis; jobs without one sit in the middle. */
+export function jobPriority(analysis?: unknown): { priority: number; score: number } {
+  const { priority, score } = (analysis || {}) as { priority?: string; score?: number };
+  return {
+    priority: PRIORITY_LEVELS[priority as keyof typeof PRIORITY_LEVELS] ?? PRIORITY_LEVELS.medium,
+    score: typeof score === "number" ? Math.round(score) : 0,
+  };
 }
 
 const retryDelay = (attempt: number) => Math.min(30000 * Math.pow(4, attempt), 480000);
 
-export async function enqueueReview(owner: string, repo: string, prNumber: number, analysis?: unknown): Promise<ReviewJob> {
-  const supabase = await createClient();
+export async function enqueueReview(owner: string, repo: string, prNumber: number, analysis?: unknown, headSha?: string): Promise<ReviewJob> {
+  const { jobs } = getStorage();
   const fullName = `${owner}/${repo}`;
 
-  const { data: existing } = await supabase
-    .from("review_jobs")
-    .select("id")
-    .eq("repo_full_name", fullName)
-    .eq("pr_number", prNumber)
-    .in("status", ["pending", "processing"])
-    .single();
+  const active = await jobs.active(fullName, prNumber);
+
+  // Only a new commit is worth another review; jobs without a sha review whatever is current
+  if (active.some((j) => !headSha || !j.head_sha || j.head_sha === headSha)) {
+    throw new Error("Review already queued");
+  }
+
+  // The unique index on active jobs settles two deliveries racing past the check above
+  const data = await jobs.create({ repo_full_name: fullName, pr_number: prNumber, owner, repo, analysis, head_sha: headSha, ...jobPriority(analysis) })
+    .catch((err) => {
+      throw isUniqueViolation(err) ? new Error("Review already queued") : err;
+    });
+
+  // Pending jobs for older commits are replaced by this one. Running ones notice
+  // the new head themselves and stop before posting. Only the service role may
+  // update jobs, and this runs from webhooks too.
+  if (active.length) {
+    const superseded = await withServiceRole(() => jobs.supersedePending(active.map((j) => j.id), data.id, `Superseded by ${headSha}`));
+    for (const job of superseded) {
+      await concludeCheck(owner, repo, job.check_run_id, "skipped", "Review superseded", `A newer commit (${headSha!.slice(0, 7)}) was pushed and will be reviewed instead.`);
+    }
+  }
+
+  // Show the pending review on the PR straight away
+  const checkRunId = headSha ? await createReviewCheck(owner, repo, headSha) : null;
+  if (checkRunId) {
+    await withServiceRole(() => attachCheckRun(data.id, checkRunId));
+    data.check_run_id = checkRunId;
+  }
+  return data;
+}
 
-  if (existing) throw new Error("Review already queued");
+export async function attachCheckRun(jobId: string, checkRunId: number): Promise<void> {
+  await getStorage().jobs.attachCheckRun(jobId, checkRunId);
+}
 
-  const { data, error } = await supabase
-    .from("review_jobs")
-    .insert({ repo_full_name: fullName, pr_number: prNumber, owner, repo, analysis })
-    .select()
-    .single();
+export async function claimJob(workerId = WORKER_ID): Promise<ReviewJob | null> {
+  return getStorage().jobs.claim(workerId, {
+    leaseSeconds: LEASE_SECONDS,
+    repoLimit: REPO_CONCURRENCY,
+    agingMinutes: AGING_MINUTES,
+  });
+}
 
-  if (error) throw error;
-  return data;
+export async function heartbeatJob(jobId: string, workerId = WORKER_ID): Promise<boolean> {
+  return getStorage().jobs.heartbeat(jobId, workerId, LEASE_SECONDS);
 }
 
-export async function claimJob(): Promise<ReviewJob | null> {
-  const supabase = await createClient();
-  const now = new Date().toISOString();
-
-  const { data, error } = await supabase
-    .from("review_jobs")
-    .update({ status: "processing", started_at: now, updated_at: now })
-    .eq("status", "pending")
-    .or(`next_retry_at.is.null,next_retry_at.lte.${now}`)
-    .order("created_at", { ascending: true })
-    .limit(1)
-    .select()
-    .single();
-
-  if (error?.code === "PGRST116") return null;
-  if (error) throw error;
-  return data;
+/**
+ * Renew a lease every third of its length until stopped. onLost fires once if
+ * the job was taken back, so the caller can stop before posting a duplicate.
+ */
+export function startHeartbeat(beat: () => Promise<boolean>, intervalMs: number, onLost?: () => void): () => void {
+  let stopped = false;
+  const timer = setInterval(async () => {
+    const held = await beat().catch(() => true); // a failed heartbeat isn't proof the lease is gone
+    if (!held && !stopped) {
+      stopped = true;
+      clearInterval(timer);
+      onLost?.();
+    }
+  }, intervalMs);
+  return () => {
+    stopped = true;
+    clearInterval(timer);
+  };
 }
 
-export async function completeJob(jobId: string): Promise<void> {
-  const supabase = await createClient();
-  const now = new Date().toISOString();
-  await supabase.from("review_jobs").update({ status: "completed", completed_at: now, updated_at: now }).eq("id", jobId);
+export function keepLease(jobId: string, onLost?: () => void): () => void {
+  return startHeartbeat(() => heartbeatJob(jobId), (LEASE_SECONDS * 1000) / 3, onLost);
 }
 
-export async function failJob(jobId: string, error: string, attempts: number, maxAttempts: number): Promise<boolean> {
-  const supabase = await createClient();
+export async function completeJob(jobId: string, workerId = WORKER_ID): Promise<void> {
+  await getStorage().jobs.release(jobId, workerId, { status: "completed", completed_at: new Date().toISOString() });
+}
+
+/** Drop a job whose commit is no longer the PR head; a newer job covers the PR. */
+export async function supersedeJob(jobId: string, reason: string, workerId = WORKER_ID): Promise<void> {
+  await getStorage().jobs.release(jobId, workerId, { status: "superseded", error: reason });
+}
+
+export async function failJob(jobId: string, error: string, attempts: number, maxAttempts: number, workerId = WORKER_ID): Promise<boolean> {
   const shouldRetry = attempts < maxAttempts;
-  const now = new Date().toISOString();
 
-  await supabase.from("review_jobs").update({
+  await getStorage().jobs.release(jobId, workerId, {
     status: shouldRetry ? "pending" : "failed",
     error,
     attempts,
-    updated_at: now,
     next_retry_at: shouldRetry ? new Date(Date.now() + retryDelay(attempts)).toISOString() : null,
-  }).eq("id", jobId);
+  });
 
   return !shouldRetry;
 }
 
 export async function getJobStats() {
-  const supabase = await createClient();
   const today = new Date();
   today.setHours(0, 0, 0, 0);
-
-  const [pending, processing, failed, completed] = await Promise.all([
-    supabase.from("review_jobs").select("id", { count: "exact" }).eq("status", "pending"),
-    supabase.from("review_jobs").select("id", { count: "exact" }).eq("status", "processing"),
-    supabase.from("review_jobs").select("id", { count: "exact" }).eq("status", "failed"),
-    supabase.from("review_jobs").select("id", { count: "exact" }).eq("status", "completed").gte("completed_at", today.toISOString()),
-  ]);
-
-  return { pending: pending.count || 0, processing: processing.count || 0, failed: failed.count || 0, completed_today: completed.count || 0 };
+  return getStorage().jobs.counts(today);
 }
@@ -0,0 +1,116 @@
+/**
+ * Repo Sync Module
+ * Keeps repo_configs in step with GitHub App installations and repository
+ * lifecycle events (rename, transfer, archive, delete)
+ */
+
+import { getStorage, withServiceRole } from "./storage";
+
+interface RepoRef {
+  full_name: string;
+}
+
+export interface RepoEventPayload {
+  action: string;
+  installation?: { id: number };
+  repositories?: RepoRef[];
+  repositories_added?: RepoRef[];
+  repositories_removed?: RepoRef[];
+  repository?: RepoRef & { name: string; owner: { login: string } };
+  changes?: {
+    repository?: { name?: { from: string } };
+    owner?: { from: { user?: { login: string }; organization?: { login: string } } };
+  };
+}
+
+export type RepoChange =
+  | { type: "register"; repos: string[]; installationId?: number }
+  | { type: "disable"; repos: string[]; reason: string; installationId?: number }
+  | { type: "rename"; from: string; to: string };
+
+export const REPO_LIFECYCLE_EVENTS = ["installation", "installation_repositories", "repository"];
+
+const names = (repos?: RepoRef[]) => (repos || []).map((r) => r.full_name);
+
+/** What a webhook event means for repo_configs; empty for actions we don't track. */
+export function repoChangesFor(event: string, body: RepoEventPayload): RepoChange[] {
+  const installationId = body.installation?.id;
+
+  if (event === "installation") {
+    if (["created", "unsuspend"].includes(body.action)) {
+      return [{ type: "register", repos: names(body.repositories), installationId }];
+    }
+    if (["deleted", "suspend"].includes(body.action)) {
+      return [{ type: "disable", repos: names(body.repositories), reason: `installation ${body.action}`, installationId }];
+    }
+    return [];
+  }
+
+  if (event === "installation_repositories") {
+    const changes: RepoChange[] = [];
+    if (body.repositories_added?.length) {
+      changes.push({ type: "register", repos: names(body.repositories_added), installationId });
+    }
+    if (body.repositories_removed?.length) {
+      changes.push({ type: "disable", repos: names(body.repositories_removed), reason: "removed from installation" });
+    }
+    return changes;
+  }
+
+  if (event === "repository" && body.repository) {
+    const repo = body.repository;
+    switch (body.action) {
+      case "renamed": {
+        const from = body.changes?.repository?.name?.from;
+        return from ? [{ type: "rename", from: `${repo.owner.login}/${from}`, to: repo.full_name }] : [];
+      }
+      case "transferred": {
+        const previous = body.changes?.owner?.from;
+        const owner = previous?.organization?.login || previous?.user?.login;
+        return owner ? [{ type: "rename", from: `${owner}/${repo.name}`, to: repo.full_name }] : [];
+      }
+      case "archived":
+      case "deleted":
+        return [{ type: "disable", repos: [repo.full_name], reason: `repository ${body.action}` }];
+      case "unarchived":
+        return [{ type: "register", repos: [repo.full_name], installationId }];
+    }
+  }
+
+  return [];
+}
+
+async function applyChange(change: RepoChange): Promise<void> {
+  const { configs } = getStorage();
+
+  if (change.type === "rename") {
+    await configs.rename(change.from, change.to);
+    return;
+  }
+
+  if (change.type === "register") {
+    if (!change.repos.length) return;
+    // New repos start enabled; existing ones keep the team's choice unless we switched them off
+    const saved = await configs.upsert(change.repos.map((full_name) => ({
+      full_name,
+      ...(change.installationId && { installation_id: change.installationId }),
+    })));
+    const autoDisabled = saved.filter((c) => c.disabled_reason).map((c) => c.full_name);
+    if (autoDisabled.length) await configs.update({ full_names: autoDisabled }, { enabled: true, disabled_reason: null });
+    return;
+  }
+
+  // Uninstall payloads may omit the repo list, so also match on the installation
+  const update = { enabled: false, disabled_reason: change.reason };
+  if (change.repos.length) await configs.update({ full_names: change.repos }, update);
+  if (change.installationId) await configs.update({ installation_id: change.installationId }, update);
+}
+
+// Webhooks aren't anyone's session, and RLS only lets the service role write repo_configs
+export async function applyRepoEvent(event: string, body: RepoEventPayload): Promise<RepoChange[]> {
+  const changes = repoChangesFor(event, body);
+  await withServiceRole(async () => {
+    for (const change of changes) await applyChange(change);
+  });
+  return changes;
+}
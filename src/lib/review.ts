@@ -1,48 +1,98 @@
-import { chat } from "./llm";
-import { getPullRequest, getPullRequestDiff, createReview, getCompareCommits, getPullRequestFiles } from "./github";
-import { parseDiff, summarizeFiles, truncateDiff, filterIgnoredPaths } from "./review/analyzer";
-import { SYSTEM_PROMPT, buildReviewPrompt, INCREMENTAL_SYSTEM_PROMPT, buildIncrementalPrompt } from "./review/prompts";
+import { addUsage, complete, emptyUsage, StructuredOutputError, type UsageTotals } from "./llm";
+import { getPullRequest, getPullRequestDiff, createReview, getCompareCommits, getPullRequestFiles, statuses } from "./github";
+import { recordPostedComments } from "./feedback-store";
+import { reviewResponseSchema } from "./review/schema";
 import {
+  analyzePR,
   CodeReviewResult,
+  generateReview,
+  LineComment,
+  openFindings,
+  publishReview,
+  PRContext,
+  PullRequestInfo,
+  RepoConfig,
   ReviewCategory,
-  parseCategory,
-  parseSeverity,
-  FileWalkthrough,
-} from "./review/models";
-import { createClient } from "./supabase/server";
-import { analyzePR, getDepthPrompt, PRContext, ReviewDecision } from "./analysis";
-
-export type { CodeReviewResult as ReviewResult } from "./review/models";
-export type { ReviewDecision } from "./analysis";
-
-interface RepoConfig {
-  categories?: string[];
-  ignore_paths?: string[];
-  custom_instructions?: string;
+  ReviewDecision,
+  ReviewLearning,
+  ReviewRuntime,
+} from "./review/engine";
+import { getStorage } from "./storage";
+import { enabledAnalyzers, runStaticAnalysis } from "./static-analysis";
+
+export type { CodeReviewResult as ReviewResult } from "./review/engine";
+export type { ReviewDecision } from "./review/engine";
+
+// Diff budget per LLM call, and the most calls a single review may make
+const REVIEW_CHUNK_TOKENS = parseInt(process.env.REVIEW_CHUNK_TOKENS || "2000", 10);
+const REVIEW_MAX_PASSES = parseInt(process.env.REVIEW_MAX_PASSES || "10", 10);
+
+// headSha pins the review to the commit it was requested for (see StaleReviewError)
+// signal is aborted by the worker once its lease on the job is gone
+type ReviewOptions = { depth?: "quick" | "standard" | "deep"; focus_areas?: string[]; headSha?: string | null; signal?: AbortSignal };
+
+// The commit the PR was last reviewed at, and the findings still open there
+async function getLastReview(fullName: string, prNumber: number): Promise<{ sha: string | null; findings: LineComment[] }> {
+  const latest = await getStorage().history.latest(fullName, prNumber).catch(() => null);
+  return { sha: latest?.head_sha || null, findings: openFindings(latest?.result) };
 }
 
-async function getLastReviewedSha(fullName: string, prNumber: number): Promise<string | null> {
-  const supabase = await createClient();
-  const { data } = await supabase
-    .from("review_history")
-    .select("head_sha")
-    .eq("repo_full_name", fullName)
-    .eq("pr_number", prNumber)
-    .eq("status", "completed")
-    .order("created_at", { ascending: false })
-    .limit(1)
-    .single();
-  return data?.head_sha || null;
+async function getRepoLearnings(fullName: string): Promise<ReviewLearning[]> {
+  try {
+    return await getStorage().learnings.active(fullName, 200);
+  } catch {
+    return [];
+  }
 }
 
 async function getRepoConfig(fullName: string): Promise<RepoConfig> {
-  const supabase = await createClient();
-  const { data } = await supabase
-    .from("repo_configs")
-    .select("categories, ignore_paths, custom_instructions")
-    .eq("full_name", fullName)
-    .single();
-  return data || {};
+  const config = await getStorage().configs.get(fullName).catch(() => null);
+  if (!config) return {};
+  return {
+    categories: config.categories ?? undefined,
+    ignore_paths: config.ignore_paths ?? undefined,
+    custom_instructions: config.custom_instructions ?? undefined,
+    category_weights: config.category_weights ?? undefined,
+    severity_policy: config.severity_policy,
+  };
+}
+
+// Wire the shared review engine to the app's GitHub client and LLM provider chain
+async function createRuntime(
+  owner: string,
+  repo: string,
+  prNumber: number,
+  usage: UsageTotals,
+  lastReviewedSha?: string | null,
+  config?: RepoConfig,
+  previousFindings: LineComment[] = []
+): Promise<ReviewRuntime> {
+  return {
+    github: {
+      pullRequest: () => getPullRequest(owner, repo, prNumber) as Promise<PullRequestInfo>,
+      diff: () => getPullRequestDiff(owner, repo, prNumber),
+      compare: (base, head) => getCompareCommits(owner, repo, base, head),
+      createReview: (body, event, comments) => createReview(owner, repo, prNumber, body, event, comments) as Promise<{ id?: number }>,
+      setStatus: (sha, state, context, description) => statuses.create(owner, repo, sha, { state, context, description }),
+    },
+    complete: async (prompt) => {
+      try {
+        const result = await complete(prompt, { useReviewModel: true, schema: reviewResponseSchema });
+        addUsage(usage, result.model, result.usage);
+        return { review: result.content, servedBy: { provider: result.provider, model: result.model } };
+      } catch (err) {
+        if (!(err instanceof StructuredOutputError)) throw err;
+        return { review: null };
+      }
+    },
+    config: config || {},
+    learnings: await getRepoLearnings(`${owner}/${repo}`),
+    lastReviewedSha,
+    previousFindings,
+    ...(enabledAnalyzers().length > 0 && {
+      analyze: (paths: string[], headSha: string) => runStaticAnalysis(owner, repo, headSha, paths),
+    }),
+  };
 }
 
 export async function reviewPullRequest(
@@ -56,153 +106,17 @@ export async function reviewPullRequest(
   ],
   lastReviewedSha?: string | null,
   config?: RepoConfig,
-  options?: { depth?: "quick" | "standard" | "deep"; focus_areas?: string[] }
+  options?: ReviewOptions
 ): Promise<CodeReviewResult & { headSha: string; isIncremental: boolean }> {
-  const prData = await getPullRequest(owner, repo, prNumber) as any;
-  const headSha = prData.head.sha;
-
-  let diff: string;
-  let isIncremental = false;
-
-  if (lastReviewedSha && lastReviewedSha !== headSha) {
-    try {
-      diff = await getCompareCommits(owner, repo, lastReviewedSha, headSha);
-      isIncremental = true;
-    } catch {
-      diff = await getPullRequestDiff(owner, repo, prNumber);
-    }
-  } else {
-    diff = await getPullRequestDiff(owner, repo, prNumber);
-  }
-
-  let parsedFiles = parseDiff(diff);
-  
-  // Filter ignored paths
-  if (config?.ignore_paths?.length) {
-    parsedFiles = filterIgnoredPaths(parsedFiles, config.ignore_paths);
-  }
-
-  const filesSummary = summarizeFiles(parsedFiles);
-  const truncatedDiff = truncateDiff(diff, 2000);
-
-  // Build prompt with custom instructions and depth
-  let systemPrompt = isIncremental ? INCREMENTAL_SYSTEM_PROMPT : SYSTEM_PROMPT;
-  if (config?.custom_instructions) {
-    systemPrompt += `\n\n## Custom Instructions\n${config.custom_instructions}`;
-  }
-  if (options?.depth || options?.focus_areas?.length) {
-    systemPrompt += "\n\n" + getDepthPrompt(options.depth || "standard", options.focus_areas || []);
-  }
-
-  const prompt = isIncremental
-    ? buildIncrementalPrompt(prData.title, filesSummary, truncatedDiff, categories)
-    : buildReviewPrompt(prData.title, prData.body || "", filesSummary, truncatedDiff, categories);
-
-  const response = await chat(`${systemPrompt}\n\n${prompt}`, { useReviewModel: true });
-
-  try {
-    let json = response.trim();
-    if (json.includes("```json")) {
-      json = json.split("```json")[1]!.split("```")[0]!;
-    } else if (json.includes("```")) {
-      json = json.split("```")[1]!.split("```")[0]!;
-    }
-
-    const parsed = JSON.parse(json.trim());
-
-    const lineComments = (parsed.line_comments || []).map((c: Record<string, unknown>) => ({
-      path: String(c.path || ""),
-      line: Number(c.line || 0),
-      body: String(c.body || ""),
-      severity: parseSeverity(String(c.severity || "medium")),
-      category: parseCategory(String(c.category || "other")),
-      start_line: c.start_line ? Number(c.start_line) : undefined,
-      suggestion: c.suggestion ? String(c.suggestion) : undefined,
-    }));
-
-    const walkthrough: FileWalkthrough[] = (parsed.walkthrough || []).map((w: Record<string, unknown>) => ({
-      path: String(w.path || ""),
-      summary: String(w.summary || ""),
-      changes: Array.isArray(w.changes) ? w.changes.map(String) : [],
-    }));
-
-    return {
-      summary: {
-        overview: parsed.summary?.overview || "",
-        changes_description: parsed.summary?.changes_description || "",
-        risk_assessment: parsed.summary?.risk_assessment || "Unknown",
-        recommendations: parsed.summary?.recommendations || [],
-        praise: parsed.summary?.praise || [],
-      },
-      walkthrough,
-      line_comments: lineComments,
-      approval_recommendation: parsed.approval_recommendation || "comment",
-      headSha,
-      isIncremental,
-    };
-  } catch {
-    return {
-      summary: {
-        overview: "Review completed but parsing failed",
-        changes_description: "See raw response",
-        risk_assessment: "Unknown",
-        recommendations: ["Manual review recommended"],
-      },
-      walkthrough: [],
-      line_comments: [],
-      approval_recommendation: "comment",
-      headSha,
-      isIncremental,
-    };
-  }
-}
-
-function formatReviewBody(review: CodeReviewResult, isIncremental: boolean): string {
-  const sections: string[] = [];
-
-  sections.push(isIncremental ? "## 🔄 Incremental Review\n" : "## 🤖 AI Code Review\n");
-
-  if (isIncremental) {
-    sections.push("*Reviewing only new changes since last review*\n");
-  }
-
-  sections.push(`### Summary\n${review.summary.overview}\n`);
-  sections.push(`**Changes:** ${review.summary.changes_description}\n`);
-  sections.push(`**Risk Level:** ${review.summary.risk_assessment}\n`);
-
-  if (review.summary.praise?.length) {
-    sections.push("### ✨ What's Good\n");
-    sections.push(review.summary.praise.map((p) => `- ${p}`).join("\n") + "\n");
-  }
-
-  if (review.walkthrough.length > 0) {
-    sections.push("### 📝 Walkthrough\n");
-    sections.push("<details><summary>File changes</summary>\n");
-    for (const file of review.walkthrough) {
-      sections.push(`\n**${file.path}**\n${file.summary}`);
-      if (file.changes.length > 0) {
-        sections.push(file.changes.map((c) => `- ${c}`).join("\n"));
-      }
-    }
-    sections.push("\n</details>\n");
-  }
-
-  if (review.summary.recommendations.length > 0) {
-    sections.push("### 📋 Recommendations\n");
-    sections.push(review.summary.recommendations.map((r) => `- ${r}`).join("\n") + "\n");
-  }
-
-  const criticalCount = review.line_comments.filter((c) => c.severity === "critical").length;
-  const highCount = review.line_comments.filter((c) => c.severity === "high").length;
-  const otherCount = review.line_comments.length - criticalCount - highCount;
-
-  sections.push("---");
-  sections.push(`📊 **${review.line_comments.length} comments** `);
-  if (criticalCount > 0) sections.push(`| 🔴 ${criticalCount} critical `);
-  if (highCount > 0) sections.push(`| 🟠 ${highCount} high `);
-  if (otherCount > 0) sections.push(`| 🟡 ${otherCount} other`);
-
-  return sections.join("\n");
+  const usage = emptyUsage();
+  const runtime = await createRuntime(owner, repo, prNumber, usage, lastReviewedSha, config);
+  const { review, headSha, isIncremental } = await generateReview(runtime, {
+    categories,
+    ...options,
+    chunkTokens: REVIEW_CHUNK_TOKENS,
+    maxPasses: REVIEW_MAX_PASSES,
+  });
+  return { ...review, usage, headSha, isIncremental };
 }
 
 export async function reviewAndPost(
@@ -210,11 +124,11 @@ export async function reviewAndPost(
   repo: string,
   prNumber: number,
   categories?: ReviewCategory[],
-  options?: { depth?: "quick" | "standard" | "deep"; focus_areas?: string[] }
+  options?: ReviewOptions
 ): Promise<{ review: CodeReviewResult; posted: boolean; headSha: string; isIncremental: boolean; analysis?: ReviewDecision }> {
   const fullName = `${owner}/${repo}`;
-  const [lastReviewedSha, config] = await Promise.all([
-    getLastReviewedSha(fullName, prNumber),
+  const [lastReview, config] = await Promise.all([
+    getLastReview(fullName, prNumber),
     getRepoConfig(fullName),
   ]);
 
@@ -237,41 +151,29 @@ export async function reviewAndPost(
         files: prFiles.map((f: any) => f.filename),
       };
       analysis = analyzePR(ctx);
-      reviewOptions = { depth: analysis.depth, focus_areas: analysis.focus_areas };
+      reviewOptions = { ...options, depth: analysis.depth, focus_areas: analysis.focus_areas };
     } catch { /* use defaults */ }
   }
 
-  const reviewCategories = categories || 
-    (config.categories?.map((c) => c as ReviewCategory)) ||
-    [ReviewCategory.SECURITY, ReviewCategory.BUG, ReviewCategory.PERFORMANCE];
-
-  const result = await reviewPullRequest(owner, repo, prNumber, reviewCategories, lastReviewedSha, config, reviewOptions);
-  const { headSha, isIncremental, ...review } = result;
-
-  const body = formatReviewBody(review, isIncremental);
-
-  const eventMap = {
-    approve: "APPROVE" as const,
-    request_changes: "REQUEST_CHANGES" as const,
-    comment: "COMMENT" as const,
-  };
-
-  const comments = review.line_comments
-    .filter((c) => c.path && c.line > 0)
-    .map((c) => {
-      let commentBody = `**[${c.severity.toString().toUpperCase()}]** ${c.body}`;
-      if (c.suggestion) {
-        commentBody += `\n\n\`\`\`suggestion\n${c.suggestion}\n\`\`\``;
-      }
-      return { path: c.path, line: c.line, body: commentBody };
-    });
-
-  try {
-    await createReview(owner, repo, prNumber, body, eventMap[review.approval_recommendation], comments);
-  } catch {
-    // If posting with line comments fails, post without them
-    await createReview(owner, repo, prNumber, body, "COMMENT", []);
+  const usage = emptyUsage();
+  const runtime = await createRuntime(owner, repo, prNumber, usage, lastReview.sha, config, lastReview.findings);
+  const generated = await generateReview(runtime, {
+    categories,
+    ...reviewOptions,
+    chunkTokens: REVIEW_CHUNK_TOKENS,
+    maxPasses: REVIEW_MAX_PASSES,
+  });
+  const review = { ...generated.review, usage };
+
+  // Another worker owns the job now and will post its own review
+  options?.signal?.throwIfAborted();
+  const { reviewId, placed } = await publishReview(runtime, { ...generated, review });
+  if (reviewId && placed.length) {
+    // Track posted comments so reactions can feed back into learnings
+    await recordPostedComments(owner, repo, prNumber, reviewId, placed).catch((e) =>
+      console.error("Failed to record posted comments:", e)
+    );
   }
 
-  return { review, posted: true, headSha, isIncremental, analysis };
+  return { review, posted: true, headSha: generated.headSha, isIncremental: generated.isIncremental, analysis };
 }
@@ -0,0 +1,2 @@
+// The review engine lives with the edge functions so both runtimes import the same code
+export * from "../../../supabase/functions/_shared/review/index.ts";
@@ -0,0 +1,6 @@
+import { z } from "zod";
+import { defineReviewSchema, type ReviewResponse } from "./engine";
+
+export const reviewResponseSchema: z.ZodType<ReviewResponse> = defineReviewSchema(z);
+
+export type { ReviewResponse };
@@ -0,0 +1,103 @@
+import { gzipSync } from "zlib";
+import { codeScanning, repos } from "./github";
+import { getStorage, type SecurityScan } from "./storage";
+import { audit } from "./audit";
+import { sarifProvenance, sarifToFindings, scanToSarif, scoreFindings } from "./review/engine";
+
+/**
+ * Security scans as SARIF: export for GitHub code scanning and other
+ * consumers, and import of other tools' logs as scans of their own.
+ */
+
+// Largest log accepted for import; GitHub takes at most 10 MB gzipped
+export const MAX_SARIF_BYTES = 20 * 1024 * 1024;
+const GITHUB_SARIF_LIMIT = 10 * 1024 * 1024;
+
+const REPO_PATTERN = /^[\w.-]+\/[\w.-]+$/;
+
+export const isRepoName = (name: unknown): name is string => typeof name === "string" && REPO_PATTERN.test(name);
+
+/** A scan by id, or the latest scan of a repo. */
+export async function findScan({ id, repo }: { id?: string | null; repo?: string | null }): Promise<SecurityScan | null> {
+  const { scans } = getStorage();
+  if (id) return scans.get(id);
+  if (repo) return (await scans.list({ repo, limit: 1 })).scans[0] || null;
+  return null;
+}
+
+export function scanSarif(scan: SecurityScan): Record<string, unknown> {
+  return scanToSarif({
+    repo_full_name: scan.repo_full_name,
+    // Scans from older versions kept their findings in `findings`
+    issues: scan.issues?.length ? scan.issues : scan.findings || [],
+    scan_metadata: scan.scan_metadata,
+  });
+}
+
+/**
+ * Store a SARIF log as a scan of `repo` (or the repo its provenance names),
+ * scored like our own scans. Throws on logs that aren't SARIF 2.1.0.
+ */
+export async function importSarif(log: unknown, repo?: string | null, actorId?: string): Promise<SecurityScan> {
+  const findings = sarifToFindings(log);
+  const provenance = sarifProvenance(log);
+  const fullName = repo || provenance.repo;
+  if (!isRepoName(fullName)) throw new Error("Repository (owner/repo) required: pass repo or include versionControlProvenance in the log");
+
+  const tools = [...new Set(findings.map((f) => f.tool || "sarif"))];
+  const { score, grade, threat_level, by_severity } = scoreFindings(findings);
+  const { critical: c, high: h, medium: m, low: l } = by_severity;
+
+  const scan = await getStorage().scans.insert({
+    repo_full_name: fullName,
+    security_score: score,
+    issues: findings,
+    summary: `Imported ${findings.length} findings from ${tools.join(", ") || "SARIF"}: ${c} critical, ${h} high, ${m} medium, ${l} low.`,
+    files_scanned: new Set(findings.map((f) => f.file)).size,
+    scan_metadata: {
+      grade,
+      threat_level,
+      by_severity,
+      source: "sarif",
+      tools,
+      ...(provenance.commit_sha && { commit_sha: provenance.commit_sha }),
+      ...(provenance.branch && { branch: provenance.branch }),
+    },
+  });
+
+  await audit({
+    action: "scan.imported",
+    actor_id: actorId,
+    resource_type: "security_scan",
+    resource_id: scan.id,
+    metadata: { repo: fullName, findings: findings.length, tools },
+  });
+  return scan;
+}
+
+/**
+ * Upload a scan to GitHub code scanning, against `branch` or the branch and
+ * commit the scan recorded (falling back to the default branch's head).
+ */
+export async function uploadScanToGitHub(scan: SecurityScan, branch?: string, actorId?: string): Promise<{ id: string; url: string; ref: string; commit_sha: string }> {
+  const [owner, repo] = scan.repo_full_name.split("/") as [string, string];
+  const meta = (scan.scan_metadata || {}) as { branch?: string; commit_sha?: string };
+
+  const target = branch || meta.branch || (await repos.get(owner, repo)).default_branch;
+  // The recorded commit is only right for the branch that was scanned
+  const commitSha = !branch && meta.commit_sha ? meta.commit_sha : (await repos.commit(owner, repo, target)).sha;
+  const ref = `refs/heads/${target}`;
+
+  const sarif = gzipSync(JSON.stringify(scanSarif(scan))).toString("base64");
+  if (sarif.length > GITHUB_SARIF_LIMIT * 4 / 3) throw new Error("SARIF log exceeds GitHub's 10 MB upload limit");
+
+  const result = await codeScanning.uploadSarif(owner, repo, { commit_sha: commitSha, ref, sarif });
+  await audit({
+    action: "scan.uploaded",
+    actor_id: actorId,
+    resource_type: "security_scan",
+    resource_id: scan.id,
+    metadata: { repo: scan.repo_full_name, ref, commit_sha: commitSha, sarif_id: result.id },
+  });
+  return { ...result, ref, commit_sha: commitSha };
+}
@@ -0,0 +1,63 @@
+// Server-sent events helpers, usable from route handlers and the browser
+
+export interface SSEMessage {
+  event: string;
+  data: string;
+}
+
+export function encodeSSE(event: string, data: unknown): string {
+  const payload = typeof data === "string" ? data : JSON.stringify(data);
+  return `event: ${event}\n${payload.split("\n").map((line) => `data: ${line}`).join("\n")}\n\n`;
+}
+
+// Split a byte stream into lines, handling chunks that end mid-line
+export async function* readLines(body: ReadableStream<Uint8Array>): AsyncGenerator<string> {
+  const reader = body.getReader();
+  const decoder = new TextDecoder();
+  let buffer = "";
+  try {
+    while (true) {
+      const { done, value } = await reader.read();
+      if (done) break;
+      buffer += decoder.decode(value, { stream: true });
+      let newline: number;
+      while ((newline = buffer.indexOf("\n")) >= 0) {
+        yield buffer.slice(0, newline).replace(/\r$/, "");
+        buffer = buffer.slice(newline + 1);
+      }
+    }
+    buffer += decoder.decode();
+    if (buffer) yield buffer.replace(/\r$/, "");
+  } finally {
+    reader.releaseLock();
+  }
+}
+
+export async function* parseSSE(body: ReadableStream<Uint8Array>): AsyncGenerator<SSEMessage> {
+  let event = "message";
+  let data: string[] = [];
+  for await (const line of readLines(body)) {
+    if (line === "") {
+      if (data.length) yield { event, data: data.join("\n") };
+      event = "message";
+      data = [];
+    } else if (line.startsWith("event:")) {
+      event = line.slice(6).trim();
+    } else if (line.startsWith("data:")) {
+      data.push(line.slice(5).replace(/^ /, ""));
+    }
+    // Comments (":") and id/retry fields are ignored
+  }
+  if (data.length) yield { event, data: data.join("\n") };
+}
+
+export function sseResponse(stream: ReadableStream<Uint8Array>): Response {
+  return new Response(stream, {
+    headers: {
+      "Content-Type": "text/event-stream",
+      "Cache-Control": "no-cache, no-transform",
+      Connection: "keep-alive",
+      "X-Accel-Buffering": "no",
+    },
+  });
+}
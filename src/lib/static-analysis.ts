@@ -0,0 +1,217 @@
+import { execFile } from "child_process";
+import { mkdir, mkdtemp, readFile, rm, writeFile } from "fs/promises";
+import { tmpdir } from "os";
+import { dirname, join } from "path";
+import { getRepoToken } from "./github-app";
+import {
+  parseEslint,
+  parseGitleaks,
+  parseRuff,
+  parseSemgrep,
+  type AnalyzerFinding,
+} from "./review/engine";
+
+/**
+ * Runs the configured linters and scanners on a shallow checkout of the PR
+ * head. Tools must be installed on the host; the checkout has no project
+ * dependencies installed, so ESLint plugins have to be available globally.
+ *
+ * The checkout is untrusted code. No tool may execute it: ESLint runs with our
+ * config instead of the repo's (which is JavaScript), Ruff ignores the repo's
+ * settings, gitleaks uses our rules and allowlist, and tools that build the
+ * project (cargo clippy) aren't offered. Nothing is written inside the checkout.
+ * Tools also get none of our environment beyond what they need to run.
+ */
+
+const ANALYZER_TIMEOUT_MS = parseInt(process.env.ANALYZER_TIMEOUT_MS || "120000", 10);
+const MAX_OUTPUT_BYTES = 32 * 1024 * 1024;
+
+export interface Analyzer {
+  name: string;
+  // Changed files this tool can say something about
+  matches(path: string): boolean;
+  run(dir: string, paths: string[]): Promise<AnalyzerFinding[]>;
+}
+
+const TOOL_ENV_KEYS = ["PATH", "LANG", "LC_ALL", "TZ"];
+
+// Keys, tokens and database URLs stay out of reach of the tools; HOME is a
+// scratch directory next to the checkout so user-level configs aren't read either
+function toolEnv(cwd: string): NodeJS.ProcessEnv {
+  const env: NodeJS.ProcessEnv = { NO_COLOR: "1", HOME: join(dirname(cwd), "home") };
+  for (const key of TOOL_ENV_KEYS) if (process.env[key]) env[key] = process.env[key];
+  return env;
+}
+
+// Linters exit nonzero when they find something; only a missing tool or a timeout is a failure
+function run(command: string, args: string[], cwd: string, timeout = ANALYZER_TIMEOUT_MS): Promise<string> {
+  return new Promise((resolve, reject) => {
+    execFile(command, args, { cwd, timeout, maxBuffer: MAX_OUTPUT_BYTES, env: toolEnv(cwd) }, (err, stdout, stderr) => {
+      if (err && (typeof err.code !== "number" || err.killed)) {
+        reject(new Error(`${command} failed: ${err.killed ? "timed out" : stderr.trim().slice(0, 500) || err.message}`));
+        return;
+      }
+      resolve(stdout);
+    });
+  });
+}
+
+const extension = (...exts: string[]) => (path: string) => exts.some((e) => path.endsWith(e));
+
+const JAVASCRIPT = extension(".js", ".jsx", ".mjs", ".cjs");
+const TYPESCRIPT = extension(".ts", ".tsx", ".mts", ".cts");
+
+// Core rules only, so it works without plugins; ESLINT_CONFIG can point at a
+// server-side config that adds typescript-eslint and the like
+const DEFAULT_ESLINT_CONFIG = `export default [{
+  files: ["**/*.{js,jsx,mjs,cjs}"],
+  languageOptions: { ecmaVersion: "latest", sourceType: "module", parserOptions: { ecmaFeatures: { jsx: true } } },
+  linterOptions: { noInlineConfig: true },
+  rules: {
+    "no-eval": "error",
+    "no-implied-eval": "error",
+    "no-new-func": "error",
+    "no-script-url": "error",
+    "no-debugger": "warn",
+    "no-dupe-keys": "error",
+    "no-unsafe-finally": "error",
+    "no-unreachable": "warn",
+    "no-self-assign": "warn",
+  },
+}];
+`;
+
+// gitleaks' built-in rules. Passed explicitly, since without --config gitleaks
+// loads the .gitleaks.toml at the root of the checkout, which could allowlist the PR's own secrets
+const DEFAULT_GITLEAKS_CONFIG = `[extend]
+useDefault = true
+`;
+
+// Semgrep won't build the "auto" config with metrics off, so name a registry ruleset
+export const DEFAULT_SEMGREP_CONFIG = "p/default";
+
+const defaultConfigs = new Map<string, Promise<string>>();
+
+// Written once per process, outside any checkout
+function defaultConfig(file: string, contents: string): Promise<string> {
+  let path = defaultConfigs.get(file);
+  if (!path) {
+    path = mkdtemp(join(tmpdir(), "review-config-")).then(async (dir) => {
+      await writeFile(join(dir, file), contents);
+      return join(dir, file);
+    });
+    defaultConfigs.set(file, path);
+  }
+  return path;
+}
+
+function eslintConfig(): Promise<string> {
+  if (process.env.ESLINT_CONFIG) return Promise.resolve(process.env.ESLINT_CONFIG);
+  return defaultConfig("eslint.config.mjs", DEFAULT_ESLINT_CONFIG);
+}
+
+function gitleaksConfig(): Promise<string> {
+  if (process.env.GITLEAKS_CONFIG) return Promise.resolve(process.env.GITLEAKS_CONFIG);
+  return defaultConfig("gitleaks.toml", DEFAULT_GITLEAKS_CONFIG);
+}
+
+export function semgrepArgs(paths: string[], env: Record<string, string | undefined> = process.env): string[] {
+  return ["scan", "--json", "--quiet", "--metrics", "off", "--config", env.SEMGREP_CONFIG || DEFAULT_SEMGREP_CONFIG, "--", ...paths];
+}
+
+// The ignore path points next to our config so the PR's .gitleaksignore isn't read either
+export function gitleaksArgs(dir: string, config: string, report: string): string[] {
+  return [
+    "detect", "--no-git", "--no-banner", "--source", dir, "--config", config, "--gitleaks-ignore-path", dirname(config),
+    "--report-format", "json", "--report-path", report, "--exit-code", "0",
+  ];
+}
+
+export const ANALYZERS: Record<string, Analyzer> = {
+  eslint: {
+    name: "eslint",
+    // The default config only parses JavaScript
+    matches: (path) => JAVASCRIPT(path) || (!!process.env.ESLINT_CONFIG && TYPESCRIPT(path)),
+    run: async (dir, paths) => parseEslint(
+      await run("eslint", [
+        "--no-config-lookup", "--config", await eslintConfig(),
+        "--format", "json", "--no-error-on-unmatched-pattern", "--", ...paths,
+      ], dir),
+      dir
+    ),
+  },
+  semgrep: {
+    name: "semgrep",
+    matches: () => true,
+    run: async (dir, paths) => parseSemgrep(
+      await run("semgrep", semgrepArgs(paths), dir),
+      dir
+    ),
+  },
+  ruff: {
+    name: "ruff",
+    matches: extension(".py", ".pyi"),
+    run: async (dir, paths) =>
+      parseRuff(await run("ruff", ["check", "--isolated", "--output-format", "json", "--exit-zero", "--", ...paths], dir), dir),
+  },
+  gitleaks: {
+    name: "gitleaks",
+    matches: () => true,
+    run: async (dir) => {
+      // Next to the checkout, not in it, so a symlink in the PR can't redirect the write or the read
+      const report = join(dirname(dir), "gitleaks-report.json");
+      await run("gitleaks", gitleaksArgs(dir, await gitleaksConfig(), report), dir);
+      return parseGitleaks(await readFile(report, "utf8").catch(() => "[]"), dir);
+    },
+  },
+};
+
+/** Analyzers named in STATIC_ANALYZERS (comma separated); none by default. */
+export function enabledAnalyzers(env: Record<string, string | undefined> = process.env): Analyzer[] {
+  const names = (env.STATIC_ANALYZERS || "").split(",").map((n) => n.trim().toLowerCase()).filter(Boolean);
+  return names.flatMap((name) => {
+    const analyzer = ANALYZERS[name];
+    if (!analyzer) console.warn(`Unknown static analyzer "${name}" in STATIC_ANALYZERS`);
+    return analyzer ? [analyzer] : [];
+  });
+}
+
+async function checkout(owner: string, repo: string, sha: string, dir: string): Promise<void> {
+  const token = await getRepoToken(owner, repo);
+  // Token goes in a header so it never appears in the remote URL or git's errors
+  const auth = `AUTHORIZATION: basic ${Buffer.from(`x-access-token:${token}`).toString("base64")}`;
+  await run("git", ["init", "--quiet"], dir);
+  await run("git", ["-c", `http.extraHeader=${auth}`, "fetch", "--quiet", "--depth", "1", `https://github.com/${owner}/${repo}.git`, sha], dir);
+  await run("git", ["checkout", "--quiet", "FETCH_HEAD"], dir);
+}
+
+/**
+ * Check out the head and run each enabled analyzer on the changed files it
+ * handles. A tool that fails is logged and skipped; the checkout is always removed.
+ */
+export async function runStaticAnalysis(
+  owner: string,
+  repo: string,
+  headSha: string,
+  paths: string[],
+  analyzers = enabledAnalyzers()
+): Promise<AnalyzerFinding[]> {
+  const applicable = analyzers.filter((a) => paths.some(a.matches));
+  if (!applicable.length) return [];
+
+  const root = await mkdtemp(join(tmpdir(), "review-analysis-"));
+  const dir = join(root, "src");
+  try {
+    await mkdir(dir);
+    await mkdir(join(root, "home"));
+    await checkout(owner, repo, headSha, dir);
+    const results = await Promise.allSettled(applicable.map((a) => a.run(dir, paths.filter(a.matches))));
+    return results.flatMap((result, i) => {
+      if (result.status === "fulfilled") return result.value;
+      console.error(`Static analyzer ${applicable[i]!.name} failed on ${owner}/${repo}:`, result.reason);
+      return [];
+    });
+  } finally {
+    await rm(root, { recursive: true, force: true }).catch(() => {});
+  }
+}
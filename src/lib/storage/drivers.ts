@@ -0,0 +1,125 @@
+import { mkdirSync } from "fs";
+import { dirname } from "path";
+import type { Row, SqlDriver } from "./sql";
+
+/**
+ * Database drivers for the SQL backend. `pg` and `better-sqlite3` are optional
+ * and only loaded when their backend is selected, so Supabase deployments
+ * don't need either installed.
+ */
+
+async function load(name: string): Promise<any> {
+  try {
+    // A variable specifier keeps the bundler from resolving the package at build time
+    const mod = await import(/* webpackIgnore: true */ name);
+    return mod.default ?? mod;
+  } catch {
+    throw new Error(`The "${name}" package is required for this storage backend. Install it with: npm install ${name}`);
+  }
+}
+
+// Rewrite ? placeholders to $1, $2, ... (queries never contain a literal ?)
+export function numberPlaceholders(sql: string): string {
+  let n = 0;
+  return sql.replace(/\?/g, () => `$${++n}`);
+}
+
+export async function postgresDriver(connectionString: string): Promise<SqlDriver> {
+  const pg = await load("pg");
+  const pool = new pg.Pool({ connectionString, max: Number(process.env.DATABASE_POOL_SIZE) || 10 });
+
+  const wrap = (client: { query: (sql: string, params?: unknown[]) => Promise<{ rows: Row[] }> }): SqlDriver => ({
+    dialect: "postgres",
+    async query<T>(sql: string, params: unknown[] = []) {
+      const { rows } = await client.query(numberPlaceholders(sql), params);
+      return rows as T[];
+    },
+    async transaction(fn) {
+      return fn(this);
+    },
+    async close() {},
+  });
+
+  return {
+    ...wrap(pool),
+    async transaction(fn) {
+      const client = await pool.connect();
+      try {
+        await client.query("BEGIN");
+        const result = await fn(wrap(client));
+        await client.query("COMMIT");
+        return result;
+      } catch (err) {
+        await client.query("ROLLBACK");
+        throw err;
+      } finally {
+        client.release();
+      }
+    },
+    async close() {
+      await pool.end();
+    },
+  };
+}
+
+// SQLite only binds numbers, strings, bigints, buffers and null
+export function sqliteParam(value: unknown): unknown {
+  if (value === undefined) return null;
+  if (typeof value === "boolean") return value ? 1 : 0;
+  if (value instanceof Date) return value.toISOString();
+  return value;
+}
+
+export async function sqliteDriver(path: string): Promise<SqlDriver> {
+  const Database = await load("better-sqlite3");
+  if (path !== ":memory:") mkdirSync(dirname(path), { recursive: true });
+  const db = new Database(path);
+  db.pragma("journal_mode = WAL");
+  db.pragma("busy_timeout = 5000");
+
+  // better-sqlite3 is synchronous, but transaction callbacks await between
+  // statements; queue transactions so two never interleave on the connection
+  let lock: Promise<unknown> = Promise.resolve();
+
+  const query = async <T>(sql: string, params: unknown[] = []): Promise<T[]> => {
+    const statement = db.prepare(sql);
+    const values = params.map(sqliteParam);
+    return (statement.reader ? statement.all(values) : (statement.run(values), [])) as T[];
+  };
+
+  const tx: SqlDriver = {
+    dialect: "sqlite",
+    query,
+    async transaction(fn) {
+      return fn(tx);
+    },
+    async close() {},
+  };
+
+  return {
+    dialect: "sqlite",
+    async query<T>(sql: string, params?: unknown[]) {
+      await lock;
+      return query<T>(sql, params);
+    },
+    async transaction(fn) {
+      const run = lock.then(async () => {
+        db.exec("BEGIN IMMEDIATE");
+        try {
+          const result = await fn(tx);
+          db.exec("COMMIT");
+          return result;
+        } catch (err) {
+          db.exec("ROLLBACK");
+          throw err;
+        }
+      });
+      lock = run.catch(() => undefined);
+      return run;
+    },
+    async close() {
+      await lock;
+      db.close();
+    },
+  };
+}
@@ -0,0 +1,51 @@
+import { postgresDriver, sqliteDriver } from "./drivers";
+import { createSqlStorage } from "./sql";
+import { supabaseStorage } from "./supabase";
+import type { Storage } from "./types";
+
+export * from "./types";
+export { createSqlStorage, rankJobs, type SqlDriver } from "./sql";
+export { hasServiceRole, withServiceRole } from "./supabase";
+
+const DEFAULT_SQLITE_PATH = "./data/foodshare.db";
+
+const globalForStorage = globalThis as unknown as { __storage?: Storage };
+
+/**
+ * Build the backend named by STORAGE_BACKEND:
+ *   supabase (default) - the Supabase project from NEXT_PUBLIC_SUPABASE_URL
+ *   postgres           - any Postgres database at DATABASE_URL
+ *   sqlite             - a local file at SQLITE_PATH
+ */
+export function createStorage(env: Record<string, string | undefined> = process.env): Storage {
+  const backend = (env.STORAGE_BACKEND || "supabase").toLowerCase();
+  switch (backend) {
+    case "supabase":
+      return supabaseStorage;
+    case "postgres": {
+      const url = env.DATABASE_URL;
+      if (!url) throw new Error("DATABASE_URL is required when STORAGE_BACKEND=postgres");
+      return createSqlStorage("postgres", () => postgresDriver(url));
+    }
+    case "sqlite":
+      return createSqlStorage("sqlite", () => sqliteDriver(env.SQLITE_PATH || DEFAULT_SQLITE_PATH));
+    default:
+      throw new Error(`Unknown STORAGE_BACKEND "${backend}" (expected supabase, postgres or sqlite)`);
+  }
+}
+
+// One instance per process; SQL backends hold a connection pool
+export function getStorage(): Storage {
+  return (globalForStorage.__storage ||= createStorage());
+}
+
+// PostgREST and pg report a unique index conflict as 23505, better-sqlite3 by its own code
+export function isUniqueViolation(err: unknown): boolean {
+  const { code } = (err || {}) as { code?: string };
+  return code === "23505" || code === "SQLITE_CONSTRAINT_UNIQUE";
+}
+
+// Swap the backend, e.g. for tests
+export function setStorage(storage: Storage | undefined): void {
+  globalForStorage.__storage = storage;
+}
@@ -0,0 +1,274 @@
+/**
+ * Tables for the direct SQL backends. This is the subset of the Supabase schema
+ * the storage layer uses, written once for both Postgres and SQLite. Ids and
+ * timestamps are generated in the app, so no database functions are needed.
+ */
+
+export type Dialect = "postgres" | "sqlite";
+
+// Per-table column kinds that need converting on the way in and out
+export const COLUMNS: Record<string, { json?: string[]; bool?: string[]; num?: string[] }> = {
+  review_jobs: { json: ["analysis", "error_history"], num: ["check_run_id"] },
+  review_history: { json: ["result"], bool: ["is_incremental"], num: ["cost_usd"] },
+  repo_configs: {
+    json: ["categories", "ignore_paths", "ignore_patterns", "category_weights", "severity_policy"],
+    bool: ["enabled", "auto_review"],
+    num: ["monthly_token_budget", "monthly_cost_budget", "installation_id"],
+  },
+  pull_requests: {
+    json: ["labels", "llm_detection_signals"],
+    bool: ["draft", "is_llm_generated"],
+    num: ["github_id", "author_id", "llm_confidence"],
+  },
+  security_scans: { json: ["issues", "scan_metadata", "findings"], num: ["check_run_id", "cost_usd"] },
+  review_learnings: {},
+  audit_logs: { json: ["metadata"] },
+  notifications: { json: ["metadata"] },
+  review_comments: { num: ["github_review_id", "github_comment_id"] },
+  review_feedback: { num: ["github_comment_id"] },
+  review_jobs_dlq: { json: ["analysis", "error_history", "metadata"] },
+  tool_audit_logs: { json: ["params"], bool: ["success"] },
+};
+
+const TABLES = `
+CREATE TABLE IF NOT EXISTS review_jobs (
+  id TEXT PRIMARY KEY,
+  repo_full_name TEXT NOT NULL,
+  pr_number INTEGER NOT NULL,
+  owner TEXT NOT NULL,
+  repo TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'pending',
+  attempts INTEGER NOT NULL DEFAULT 0,
+  max_attempts INTEGER NOT NULL DEFAULT 3,
+  analysis {JSON},
+  error TEXT,
+  error_history {JSON} NOT NULL DEFAULT '[]',
+  priority INTEGER NOT NULL DEFAULT 1,
+  score INTEGER NOT NULL DEFAULT 0,
+  head_sha TEXT,
+  superseded_by TEXT,
+  check_run_id {BIGINT},
+  worker_id TEXT,
+  lease_expires_at {TIMESTAMP},
+  next_retry_at {TIMESTAMP},
+  started_at {TIMESTAMP},
+  completed_at {TIMESTAMP},
+  created_at {TIMESTAMP} NOT NULL,
+  updated_at {TIMESTAMP} NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_review_jobs_status ON review_jobs(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_review_jobs_pr ON review_jobs(repo_full_name, pr_number);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_review_jobs_active_head ON review_jobs(repo_full_name, pr_number, head_sha)
+  WHERE status IN ('pending', 'processing');
+
+CREATE TABLE IF NOT EXISTS review_history (
+  id TEXT PRIMARY KEY,
+  repo_full_name TEXT NOT NULL,
+  pr_number INTEGER NOT NULL,
+  status TEXT NOT NULL,
+  result {JSON},
+  head_sha TEXT,
+  is_incremental {BOOL} NOT NULL DEFAULT {FALSE},
+  prompt_tokens INTEGER NOT NULL DEFAULT 0,
+  completion_tokens INTEGER NOT NULL DEFAULT 0,
+  cost_usd {REAL} NOT NULL DEFAULT 0,
+  created_at {TIMESTAMP} NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_review_history_pr ON review_history(repo_full_name, pr_number, created_at);
+
+CREATE TABLE IF NOT EXISTS repo_configs (
+  id TEXT PRIMARY KEY,
+  full_name TEXT NOT NULL UNIQUE,
+  repo_full_name TEXT,
+  enabled {BOOL} NOT NULL DEFAULT {TRUE},
+  auto_review {BOOL} NOT NULL DEFAULT {TRUE},
+  review_depth TEXT DEFAULT 'standard',
+  categories {JSON},
+  ignore_paths {JSON},
+  ignore_patterns {JSON},
+  custom_instructions TEXT,
+  category_weights {JSON},
+  severity_policy {JSON},
+  monthly_token_budget {BIGINT},
+  monthly_cost_budget {REAL},
+  over_budget_action TEXT DEFAULT 'downgrade',
+  budget_notified_at {TIMESTAMP},
+  max_concurrent_reviews INTEGER,
+  installation_id {BIGINT},
+  disabled_reason TEXT,
+  created_by TEXT,
+  created_at {TIMESTAMP} NOT NULL,
+  updated_at {TIMESTAMP} NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS pull_requests (
+  id TEXT PRIMARY KEY,
+  github_id {BIGINT} NOT NULL,
+  repo_full_name TEXT NOT NULL,
+  number INTEGER NOT NULL,
+  title TEXT NOT NULL,
+  body TEXT,
+  state TEXT NOT NULL DEFAULT 'open',
+  draft {BOOL} NOT NULL DEFAULT {FALSE},
+  author_login TEXT NOT NULL,
+  author_type TEXT,
+  author_id {BIGINT},
+  github_created_at {TIMESTAMP},
+  github_updated_at {TIMESTAMP},
+  github_merged_at {TIMESTAMP},
+  additions INTEGER NOT NULL DEFAULT 0,
+  deletions INTEGER NOT NULL DEFAULT 0,
+  changed_files INTEGER NOT NULL DEFAULT 0,
+  head_ref TEXT,
+  base_ref TEXT,
+  head_sha TEXT,
+  labels {JSON},
+  html_url TEXT,
+  is_llm_generated {BOOL} NOT NULL DEFAULT {FALSE},
+  llm_tool TEXT,
+  llm_confidence {REAL},
+  llm_detection_signals {JSON},
+  created_at {TIMESTAMP} NOT NULL,
+  updated_at {TIMESTAMP} NOT NULL,
+  synced_at {TIMESTAMP},
+  UNIQUE (repo_full_name, number)
+);
+
+CREATE TABLE IF NOT EXISTS security_scans (
+  id TEXT PRIMARY KEY,
+  repo_full_name TEXT NOT NULL,
+  security_score INTEGER,
+  issues {JSON},
+  summary TEXT,
+  files_scanned INTEGER NOT NULL DEFAULT 0,
+  scan_metadata {JSON},
+  prompt_tokens INTEGER NOT NULL DEFAULT 0,
+  completion_tokens INTEGER NOT NULL DEFAULT 0,
+  cost_usd {REAL} NOT NULL DEFAULT 0,
+  check_run_id {BIGINT},
+  score INTEGER,
+  grade TEXT,
+  critical_count INTEGER,
+  high_count INTEGER,
+  medium_count INTEGER,
+  findings {JSON},
+  created_at {TIMESTAMP} NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_security_scans_repo ON security_scans(repo_full_name, created_at);
+
+CREATE TABLE IF NOT EXISTS review_learnings (
+  id TEXT PRIMARY KEY,
+  repo_full_name TEXT NOT NULL,
+  pattern TEXT NOT NULL,
+  learning TEXT NOT NULL,
+  category TEXT,
+  source TEXT DEFAULT 'manual',
+  status TEXT DEFAULT 'active',
+  created_at {TIMESTAMP} NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_learnings_repo ON review_learnings(repo_full_name);
+
+CREATE TABLE IF NOT EXISTS audit_logs (
+  id TEXT PRIMARY KEY,
+  action TEXT NOT NULL,
+  actor_id TEXT,
+  resource_type TEXT,
+  resource_id TEXT,
+  metadata {JSON},
+  ip_address TEXT,
+  user_agent TEXT,
+  created_at {TIMESTAMP} NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_created ON audit_logs(created_at);
+
+CREATE TABLE IF NOT EXISTS notifications (
+  id TEXT PRIMARY KEY,
+  type TEXT NOT NULL,
+  channel TEXT NOT NULL,
+  message TEXT NOT NULL,
+  metadata {JSON},
+  sent_at {TIMESTAMP} NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS review_comments (
+  id TEXT PRIMARY KEY,
+  repo_full_name TEXT NOT NULL,
+  pr_number INTEGER NOT NULL,
+  github_review_id {BIGINT},
+  github_comment_id {BIGINT} NOT NULL UNIQUE,
+  path TEXT NOT NULL,
+  line INTEGER,
+  category TEXT,
+  severity TEXT,
+  source TEXT,
+  body TEXT,
+  created_at {TIMESTAMP} NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_review_comments_repo ON review_comments(repo_full_name, pr_number);
+CREATE INDEX IF NOT EXISTS idx_review_comments_review ON review_comments(github_review_id);
+
+CREATE TABLE IF NOT EXISTS review_feedback (
+  id TEXT PRIMARY KEY,
+  repo_full_name TEXT NOT NULL,
+  github_comment_id {BIGINT} NOT NULL,
+  signal TEXT NOT NULL,
+  count INTEGER NOT NULL DEFAULT 1,
+  note TEXT,
+  created_at {TIMESTAMP} NOT NULL,
+  updated_at {TIMESTAMP} NOT NULL,
+  UNIQUE (github_comment_id, signal)
+);
+CREATE INDEX IF NOT EXISTS idx_review_feedback_repo ON review_feedback(repo_full_name);
+
+CREATE TABLE IF NOT EXISTS review_jobs_dlq (
+  id TEXT PRIMARY KEY,
+  original_job_id TEXT NOT NULL,
+  repo_full_name TEXT NOT NULL,
+  pr_number INTEGER NOT NULL,
+  owner TEXT NOT NULL,
+  repo TEXT NOT NULL,
+  attempts INTEGER NOT NULL,
+  error TEXT,
+  analysis {JSON},
+  head_sha TEXT,
+  error_history {JSON} NOT NULL DEFAULT '[]',
+  original_created_at {TIMESTAMP} NOT NULL,
+  moved_to_dlq_at {TIMESTAMP} NOT NULL,
+  replay_count INTEGER NOT NULL DEFAULT 0,
+  replayed_at {TIMESTAMP},
+  replayed_job_id TEXT,
+  metadata {JSON}
+);
+CREATE INDEX IF NOT EXISTS idx_dlq_repo_pr ON review_jobs_dlq(repo_full_name, pr_number);
+CREATE INDEX IF NOT EXISTS idx_dlq_moved_at ON review_jobs_dlq(moved_to_dlq_at);
+
+CREATE TABLE IF NOT EXISTS tool_audit_logs (
+  id TEXT PRIMARY KEY,
+  correlation_id TEXT NOT NULL,
+  conversation_id TEXT,
+  step INTEGER,
+  tool_name TEXT NOT NULL,
+  user_id TEXT,
+  ip_address TEXT,
+  params {JSON},
+  success {BOOL} NOT NULL,
+  error_message TEXT,
+  duration_ms INTEGER,
+  created_at {TIMESTAMP} NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_tool_audit_created ON tool_audit_logs(created_at);
+`;
+
+const TYPES: Record<Dialect, Record<string, string>> = {
+  postgres: { JSON: "JSONB", TIMESTAMP: "TIMESTAMPTZ", BOOL: "BOOLEAN", TRUE: "TRUE", FALSE: "FALSE", BIGINT: "BIGINT", REAL: "DOUBLE PRECISION" },
+  sqlite: { JSON: "TEXT", TIMESTAMP: "TEXT", BOOL: "INTEGER", TRUE: "1", FALSE: "0", BIGINT: "INTEGER", REAL: "REAL" },
+};
+
+/** CREATE statements for a dialect, one per array entry. */
+export function schemaStatements(dialect: Dialect): string[] {
+  return TABLES
+    .replace(/\{(\w+)\}/g, (_, type: string) => TYPES[dialect][type]!)
+    .split(";")
+    .map((s) => s.trim())
+    .filter(Boolean);
+}
@@ -0,0 +1,689 @@
+import { randomUUID } from "crypto";
+import { COLUMNS, schemaStatements, type Dialect } from "./schema";
+import type {
+  AuditLogStore, ClaimOptions, DlqFilter, DlqStore, FeedbackStore, FeedbackWithComment, JobCounts, LearningStore,
+  NotificationStore, PullRequestStore, RepoConfigMatch, RepoConfigRow, RepoConfigStore, ReviewHistoryStore, ReviewJob,
+  ReviewJobStore, ScanStore, Storage, ToolAuditStore,
+} from "./types";
+
+/**
+ * Storage on a plain Postgres or SQLite database. Queries are written once
+ * with `?` placeholders; drivers translate them for their database.
+ */
+
+export type Row = Record<string, unknown>;
+
+export interface SqlDriver {
+  dialect: Dialect;
+  query<T = Row>(sql: string, params?: unknown[]): Promise<T[]>;
+  // Runs fn on one connection inside BEGIN/COMMIT, rolling back if it throws
+  transaction<T>(fn: (tx: SqlDriver) => Promise<T>): Promise<T>;
+  close(): Promise<void>;
+}
+
+// A processing job without a lease (claimed by an older worker) expires after this
+const LEGACY_LEASE_MS = 10 * 60 * 1000;
+// Jobs out of retries stay visible in the queue this long before moving to the DLQ
+const DLQ_AFTER_MS = 7 * 24 * 60 * 60 * 1000;
+// Tables rename_repo() moves to a repo's new name; the job tables also carry owner and repo
+const RENAMED_TABLES = ["review_history", "pull_requests", "security_scans", "review_comments", "review_feedback", "review_learnings"];
+const RENAMED_JOB_TABLES = ["review_jobs", "review_jobs_dlq"];
+
+const iso = (date: Date) => date.toISOString();
+
+/** Convert values for writing: JSON columns are serialised, everything else passes through. */
+export function encodeRow(table: string, row: Row): Row {
+  const json = COLUMNS[table]?.json || [];
+  const out: Row = {};
+  for (const [key, value] of Object.entries(row)) {
+    if (value === undefined) continue;
+    out[key] = json.includes(key) && value !== null ? JSON.stringify(value) : value;
+  }
+  return out;
+}
+
+/** Convert a row read back from either database into the shape Supabase returns. */
+export function decodeRow<T>(table: string, row: Row): T {
+  const { json = [], bool = [], num = [] } = COLUMNS[table] || {};
+  const out: Row = {};
+  for (const [key, value] of Object.entries(row)) {
+    if (value instanceof Date) out[key] = iso(value);
+    else if (json.includes(key) && typeof value === "string") out[key] = JSON.parse(value);
+    else if (bool.includes(key) && typeof value === "number") out[key] = value !== 0;
+    else if (num.includes(key) && (typeof value === "string" || typeof value === "bigint")) out[key] = Number(value);
+    else out[key] = value;
+  }
+  return out as T;
+}
+
+// WHERE clause from conditions whose value is set
+export function where(conditions: Record<string, unknown>): { clause: string; params: unknown[] } {
+  const set = Object.entries(conditions).filter(([, value]) => value !== undefined && value !== null && value !== "");
+  return {
+    clause: set.length ? `WHERE ${set.map(([sql]) => sql).join(" AND ")}` : "",
+    params: set.map(([, value]) => value),
+  };
+}
+
+function configMatch(match: RepoConfigMatch): { sql: string; params: unknown[] } {
+  if ("id" in match) return { sql: "id = ?", params: [match.id] };
+  if ("full_name" in match) return { sql: "full_name = ?", params: [match.full_name] };
+  if ("full_names" in match) {
+    return match.full_names.length
+      ? { sql: `full_name IN (${match.full_names.map(() => "?").join(", ")})`, params: match.full_names }
+      : { sql: "1 = 0", params: [] };
+  }
+  return { sql: "installation_id = ?", params: [match.installation_id] };
+}
+
+export function dlqMatch(filter: DlqFilter): { sql: string; params: unknown[] } {
+  const conditions: string[] = [];
+  const params: unknown[] = [];
+  const add = (sql: string, ...values: unknown[]) => {
+    conditions.push(sql);
+    params.push(...values);
+  };
+  if (filter.ids?.length) add(`id IN (${filter.ids.map(() => "?").join(", ")})`, ...filter.ids);
+  if (filter.repo) add("repo_full_name = ?", filter.repo);
+  if (filter.pr) add("pr_number = ?", filter.pr);
+  if (filter.error) add("LOWER(error) LIKE ?", `%${filter.error.toLowerCase()}%`);
+  if (filter.before) add("moved_to_dlq_at < ?", filter.before);
+  if (filter.replayed === true) add("replayed_at IS NOT NULL");
+  if (filter.replayed === false) add("replayed_at IS NULL");
+  return { sql: conditions.length ? conditions.join(" AND ") : "1 = 1", params };
+}
+
+export interface RankedJob {
+  id: string;
+  repo_full_name: string;
+  priority: number;
+  score: number;
+  created_at: string;
+}
+
+/**
+ * Order ready jobs the way claim_review_job does: priority plus one level per
+ * agingMinutes waited, then score, then age. Repos at their cap are left out.
+ */
+export function rankJobs(
+  jobs: RankedJob[],
+  running: Map<string, number>,
+  caps: Map<string, number>,
+  options: Pick<ClaimOptions, "repoLimit" | "agingMinutes">,
+  now = new Date()
+): RankedJob[] {
+  const agingMs = 60000 * Math.max(options.agingMinutes, 1);
+  const effective = (j: RankedJob) => j.priority + (now.getTime() - new Date(j.created_at).getTime()) / agingMs;
+  return jobs
+    .filter((j) => (running.get(j.repo_full_name) || 0) < (caps.get(j.repo_full_name) ?? options.repoLimit))
+    .sort((a, b) => effective(b) - effective(a) || b.score - a.score || a.created_at.localeCompare(b.created_at));
+}
+
+export function createSqlStorage(dialect: Dialect, connect: () => Promise<SqlDriver>): Storage {
+  let connection: Promise<SqlDriver> | null = null;
+
+  // Connect and create tables on first use
+  const db = () => {
+    connection ||= connect().then(async (driver) => {
+      for (const statement of schemaStatements(driver.dialect)) await driver.query(statement);
+      return driver;
+    }).catch((err) => {
+      connection = null;
+      throw err;
+    });
+    return connection;
+  };
+
+  const select = async <T>(table: string, sql: string, params: unknown[] = []) =>
+    (await (await db()).query(sql, params)).map((row) => decodeRow<T>(table, row));
+
+  async function insert<T>(conn: SqlDriver, table: string, row: Row): Promise<T> {
+    const values = encodeRow(table, { id: randomUUID(), ...row });
+    const keys = Object.keys(values);
+    const [inserted] = await conn.query(
+      `INSERT INTO ${table} (${keys.join(", ")}) VALUES (${keys.map(() => "?").join(", ")}) RETURNING *`,
+      Object.values(values)
+    );
+    return decodeRow<T>(table, inserted!);
+  }
+
+  // Insert, or update every column but the key and creation time when the row exists
+  async function upsert<T>(conn: SqlDriver, table: string, row: Row, conflict: string[]): Promise<T> {
+    const values = encodeRow(table, { id: randomUUID(), ...row });
+    const keys = Object.keys(values);
+    const updates = keys.filter((k) => !["id", "created_at", ...conflict].includes(k));
+    const [saved] = await conn.query(
+      `INSERT INTO ${table} (${keys.join(", ")}) VALUES (${keys.map(() => "?").join(", ")})
+       ON CONFLICT (${conflict.join(", ")}) DO UPDATE SET ${updates.map((k) => `${k} = excluded.${k}`).join(", ")}
+       RETURNING *`,
+      Object.values(values)
+    );
+    return decodeRow<T>(table, saved!);
+  }
+
+  async function update<T>(conn: SqlDriver, table: string, patch: Row, where: string, params: unknown[]): Promise<T[]> {
+    const values = encodeRow(table, patch);
+    const keys = Object.keys(values);
+    if (!keys.length) return [];
+    const rows = await conn.query(
+      `UPDATE ${table} SET ${keys.map((k) => `${k} = ?`).join(", ")} WHERE ${where} RETURNING *`,
+      [...Object.values(values), ...params]
+    );
+    return rows.map((row) => decodeRow<T>(table, row));
+  }
+
+  // Mirrors the record_job_error trigger: every error a job hits is kept
+  const withError = (job: Row, error: string, attempts: unknown) => error === job.error ? { error } : {
+    error,
+    error_history: [
+      ...((decodeRow<Row>("review_jobs", job).error_history as unknown[]) || []),
+      { at: iso(new Date()), attempt: attempts, error, worker_id: job.worker_id ?? null },
+    ],
+  };
+
+  async function requeueExpired(tx: SqlDriver, now: Date) {
+    const expired = await tx.query(
+      `SELECT * FROM review_jobs WHERE status = 'processing'
+         AND (lease_expires_at < ? OR (lease_expires_at IS NULL AND started_at < ?))`,
+      [iso(now), iso(new Date(now.getTime() - LEGACY_LEASE_MS))]
+    );
+    for (const job of expired) {
+      const attempts = Number(job.attempts) + 1;
+      const patch = {
+        status: attempts >= Number(job.max_attempts) ? "failed" : "pending",
+        attempts,
+        ...withError(job, `Lease expired on worker ${job.worker_id || "unknown"}`, attempts),
+        worker_id: null,
+        lease_expires_at: null,
+        updated_at: iso(now),
+      };
+      await update(tx, "review_jobs", patch, "id = ?", [job.id]);
+      // A job that kept crashing its worker goes straight to the DLQ
+      if (patch.status === "failed") await deadLetter(tx, { ...decodeRow<Row>("review_jobs", job), ...patch }, now);
+    }
+  }
+
+  async function deadLetter(tx: SqlDriver, job: Row, now: Date) {
+    await insert(tx, "review_jobs_dlq", {
+      original_job_id: job.id,
+      repo_full_name: job.repo_full_name,
+      pr_number: job.pr_number,
+      owner: job.owner,
+      repo: job.repo,
+      attempts: job.attempts,
+      error: job.error,
+      analysis: job.analysis,
+      head_sha: job.head_sha,
+      error_history: job.error_history || [],
+      original_created_at: job.created_at,
+      moved_to_dlq_at: iso(now),
+      metadata: { status: job.status, max_attempts: job.max_attempts, started_at: job.started_at ?? null, completed_at: job.completed_at ?? null },
+    });
+    await tx.query("DELETE FROM review_jobs WHERE id = ?", [job.id]);
+  }
+
+  // Mirrors the move_to_dlq cron job
+  async function moveToDlq(tx: SqlDriver, now: Date) {
+    const dead = await tx.query(
+      "SELECT * FROM review_jobs WHERE status = 'failed' AND attempts >= max_attempts AND updated_at < ?",
+      [iso(new Date(now.getTime() - DLQ_AFTER_MS))]
+    );
+    for (const row of dead) await deadLetter(tx, decodeRow<Row>("review_jobs", row), now);
+  }
+
+  const repoSearch = (search?: string) => search && `%${search.toLowerCase()}%`;
+
+  const jobs: ReviewJobStore = {
+    async list(filter) {
+      const { clause, params } = where({ "status = ?": filter.status, "LOWER(repo_full_name) LIKE ?": repoSearch(filter.search) });
+      return select("review_jobs",
+        `SELECT * FROM review_jobs ${clause} ORDER BY updated_at DESC LIMIT ?`,
+        [...params, filter.limit]);
+    },
+
+    async active(repoFullName, prNumber) {
+      return select("review_jobs",
+        `SELECT id, head_sha FROM review_jobs WHERE repo_full_name = ? AND pr_number = ? AND status IN ('pending', 'processing')`,
+        [repoFullName, prNumber]);
+    },
+
+    async create(job) {
+      const now = iso(new Date());
+      return insert(await db(), "review_jobs", { ...job, status: "pending", created_at: now, updated_at: now });
+    },
+
+    async supersedePending(ids, supersededBy, error) {
+      if (!ids.length) return [];
+      const rows = await update<ReviewJob>(await db(), "review_jobs",
+        { status: "superseded", superseded_by: supersededBy, error, updated_at: iso(new Date()) },
+        `id IN (${ids.map(() => "?").join(", ")}) AND status = 'pending'`, ids);
+      return rows.map((j) => ({ id: j.id, check_run_id: j.check_run_id }));
+    },
+
+    async attachCheckRun(jobId, checkRunId) {
+      await update(await db(), "review_jobs", { check_run_id: checkRunId }, "id = ?", [jobId]);
+    },
+
+    async claim(workerId, options) {
+      return (await db()).transaction(async (tx) => {
+        const now = new Date();
+        await requeueExpired(tx, now);
+        await moveToDlq(tx, now);
+
+        const ready = await tx.query<RankedJob>(
+          `SELECT id, repo_full_name, priority, score, created_at FROM review_jobs
+           WHERE status = 'pending' AND (next_retry_at IS NULL OR next_retry_at <= ?)
+           ORDER BY created_at LIMIT 1000`,
+          [iso(now)]
+        );
+        if (!ready.length) return null;
+
+        const running = await tx.query<{ repo_full_name: string; n: unknown }>(
+          `SELECT repo_full_name, COUNT(*) AS n FROM review_jobs WHERE status = 'processing' GROUP BY repo_full_name`
+        );
+        const caps = await tx.query<{ full_name: string; max_concurrent_reviews: unknown }>(
+          `SELECT full_name, max_concurrent_reviews FROM repo_configs WHERE max_concurrent_reviews IS NOT NULL`
+        );
+        const ranked = rankJobs(
+          ready.map((j) => decodeRow<RankedJob>("review_jobs", j)),
+          new Map(running.map((r) => [r.repo_full_name, Number(r.n)])),
+          new Map(caps.map((c) => [c.full_name, Number(c.max_concurrent_reviews)])),
+          options,
+          now
+        );
+
+        // Another worker may take a job between the read and the update; move on to the next
+        for (const candidate of ranked) {
+          const [job] = await update<ReviewJob>(tx, "review_jobs", {
+            status: "processing",
+            worker_id: workerId,
+            lease_expires_at: iso(new Date(now.getTime() + options.leaseSeconds * 1000)),
+            started_at: iso(now),
+            updated_at: iso(now),
+          }, "id = ? AND status = 'pending'", [candidate.id]);
+          if (job) return job;
+        }
+        return null;
+      });
+    },
+
+    async heartbeat(jobId, workerId, leaseSeconds) {
+      const now = new Date();
+      const rows = await update(await db(), "review_jobs",
+        { lease_expires_at: iso(new Date(now.getTime() + leaseSeconds * 1000)), updated_at: iso(now) },
+        "id = ? AND worker_id = ? AND status = 'processing'", [jobId, workerId]);
+      return rows.length > 0;
+    },
+
+    async release(jobId, workerId, release) {
+      await (await db()).transaction(async (tx) => {
+        const [job] = await tx.query("SELECT * FROM review_jobs WHERE id = ? AND worker_id = ?", [jobId, workerId]);
+        if (!job) return;
+        await update(tx, "review_jobs", {
+          ...release,
+          ...(release.error && withError(job, release.error, release.attempts ?? job.attempts)),
+          updated_at: iso(new Date()),
+          worker_id: null,
+          lease_expires_at: null,
+        }, "id = ? AND worker_id = ?", [jobId, workerId]);
+      });
+    },
+
+    async counts(completedSince): Promise<JobCounts> {
+      const conn = await db();
+      const byStatus = await conn.query<{ status: string; n: unknown }>(
+        "SELECT status, COUNT(*) AS n FROM review_jobs WHERE status IN ('pending', 'processing', 'failed') GROUP BY status"
+      );
+      const [completed] = await conn.query<{ n: unknown }>(
+        "SELECT COUNT(*) AS n FROM review_jobs WHERE status = 'completed' AND completed_at >= ?", [iso(completedSince)]
+      );
+      const count = (status: string) => Number(byStatus.find((r) => r.status === status)?.n || 0);
+      return { pending: count("pending"), processing: count("processing"), failed: count("failed"), completed_today: Number(completed?.n || 0) };
+    },
+
+    async retryFailed(search) {
+      const rows = await update(await db(), "review_jobs",
+        { status: "pending", attempts: 0, error: null, next_retry_at: null, updated_at: iso(new Date()) },
+        search ? "status = 'failed' AND LOWER(repo_full_name) LIKE ?" : "status = 'failed'",
+        search ? [repoSearch(search)] : []);
+      return rows.length;
+    },
+
+    async clear(status) {
+      const rows = await (await db()).query("DELETE FROM review_jobs WHERE status = ? RETURNING id", [status]);
+      return rows.length;
+    },
+  };
+
+  const history: ReviewHistoryStore = {
+    async insert(entry) {
+      await insert(await db(), "review_history", { ...entry, created_at: iso(new Date()) });
+    },
+
+    async list(filter = {}) {
+      const { clause, params } = where({
+        "repo_full_name = ?": filter.repo,
+        "LOWER(repo_full_name) LIKE ?": repoSearch(filter.search),
+        "pr_number = ?": filter.pr,
+        "status = ?": filter.status,
+        "created_at >= ?": filter.since && iso(filter.since),
+      });
+      return select("review_history",
+        `SELECT * FROM review_history ${clause} ORDER BY created_at DESC LIMIT ?`,
+        [...params, filter.limit || 50]);
+    },
+
+    async count() {
+      const [row] = await (await db()).query<{ n: unknown }>("SELECT COUNT(*) AS n FROM review_history");
+      return Number(row?.n || 0);
+    },
+
+    async get(id) {
+      const [row] = await select<any>("review_history", "SELECT * FROM review_history WHERE id = ?", [id]);
+      return row || null;
+    },
+
+    async remove(id) {
+      await (await db()).query("DELETE FROM review_history WHERE id = ?", [id]);
+    },
+
+    async latest(repoFullName, prNumber) {
+      const [row] = await select<any>("review_history",
+        `SELECT * FROM review_history WHERE repo_full_name = ? AND pr_number = ? AND status = 'completed'
+         ORDER BY created_at DESC LIMIT 1`,
+        [repoFullName, prNumber]);
+      return row || null;
+    },
+
+    async usageSince(repoFullName, since) {
+      const [row] = await (await db()).query<Row>(
+        `SELECT COALESCE(SUM(prompt_tokens), 0) AS prompt_tokens, COALESCE(SUM(completion_tokens), 0) AS completion_tokens,
+                COALESCE(SUM(cost_usd), 0) AS cost_usd
+         FROM (
+           SELECT prompt_tokens, completion_tokens, cost_usd FROM review_history WHERE repo_full_name = ? AND created_at >= ?
+           UNION ALL
+           SELECT prompt_tokens, completion_tokens, cost_usd FROM security_scans WHERE repo_full_name = ? AND created_at >= ?
+         ) u`,
+        [repoFullName, iso(since), repoFullName, iso(since)]
+      );
+      return {
+        prompt_tokens: Number(row?.prompt_tokens) || 0,
+        completion_tokens: Number(row?.completion_tokens) || 0,
+        cost_usd: Number(row?.cost_usd) || 0,
+      };
+    },
+  };
+
+  const configs: RepoConfigStore = {
+    async get(fullName) {
+      const [row] = await select<any>("repo_configs", "SELECT * FROM repo_configs WHERE full_name = ?", [fullName]);
+      return row || null;
+    },
+
+    async list() {
+      return select("repo_configs", "SELECT * FROM repo_configs ORDER BY full_name");
+    },
+
+    async search(query) {
+      const pattern = `%${query.toLowerCase()}%`;
+      return select("repo_configs",
+        "SELECT * FROM repo_configs WHERE LOWER(full_name) LIKE ? OR LOWER(repo_full_name) LIKE ? ORDER BY full_name",
+        [pattern, pattern]);
+    },
+
+    async create(config) {
+      const now = iso(new Date());
+      return insert(await db(), "repo_configs", { enabled: true, auto_review: true, ...config, created_at: now, updated_at: now });
+    },
+
+    async upsert(rows) {
+      const conn = await db();
+      const now = iso(new Date());
+      const saved: RepoConfigRow[] = [];
+      for (const config of rows) {
+        saved.push(await upsert(conn, "repo_configs", { created_at: now, ...config, updated_at: now }, ["full_name"]));
+      }
+      return saved;
+    },
+
+    async update(match, patch) {
+      const { sql, params } = configMatch(match);
+      return update<RepoConfigRow>(await db(), "repo_configs", { ...patch, updated_at: iso(new Date()) }, sql, params);
+    },
+
+    async remove(match) {
+      const { sql, params } = configMatch(match);
+      const rows = await (await db()).query(`DELETE FROM repo_configs WHERE ${sql} RETURNING id`, params);
+      return rows.length;
+    },
+
+    async rename(from, to) {
+      const [owner, repo] = to.split("/");
+      await (await db()).transaction(async (tx) => {
+        // The old config holds the team's settings; one under the new name was only registered by an installation event
+        const [old] = await tx.query("SELECT id, installation_id FROM repo_configs WHERE full_name = ?", [from]);
+        if (old) {
+          const [registered] = await tx.query("SELECT installation_id FROM repo_configs WHERE full_name = ?", [to]);
+          await tx.query("DELETE FROM repo_configs WHERE full_name = ?", [to]);
+          await update(tx, "repo_configs", {
+            full_name: to,
+            repo_full_name: to,
+            installation_id: old.installation_id ?? registered?.installation_id,
+            updated_at: iso(new Date()),
+          }, "id = ?", [old.id]);
+        }
+        for (const table of RENAMED_TABLES) {
+          await tx.query(`UPDATE ${table} SET repo_full_name = ? WHERE repo_full_name = ?`, [to, from]);
+        }
+        for (const table of RENAMED_JOB_TABLES) {
+          await tx.query(`UPDATE ${table} SET repo_full_name = ?, owner = ?, repo = ? WHERE repo_full_name = ?`, [to, owner, repo, from]);
+        }
+      });
+    },
+  };
+
+  const pullRequests: PullRequestStore = {
+    async upsert(pr) {
+      const now = iso(new Date());
+      return upsert(await db(), "pull_requests", { ...pr, created_at: now, updated_at: now }, ["repo_full_name", "number"]);
+    },
+
+    async list(filter = {}) {
+      const { clause, params } = where({
+        "repo_full_name = ?": filter.repo,
+        "state = ?": filter.state === "all" || filter.state === "merged" ? undefined : filter.state,
+        "is_llm_generated = ?": filter.llmOnly || undefined,
+      });
+      const merged = filter.state === "merged" ? `${clause ? `${clause} AND` : "WHERE"} github_merged_at IS NOT NULL` : clause;
+
+      let sql = `SELECT * FROM pull_requests ${merged} ORDER BY github_created_at DESC`;
+      if (filter.limit || filter.offset) {
+        sql += " LIMIT ? OFFSET ?";
+        params.push(filter.limit || 50, filter.offset || 0);
+      }
+      return select("pull_requests", sql, params);
+    },
+
+    async get(repoFullName, number) {
+      const [row] = await select<any>("pull_requests", "SELECT * FROM pull_requests WHERE repo_full_name = ? AND number = ?", [repoFullName, number]);
+      return row || null;
+    },
+
+    async repos() {
+      const rows = await (await db()).query<{ repo_full_name: string }>(
+        "SELECT DISTINCT repo_full_name FROM pull_requests ORDER BY repo_full_name"
+      );
+      return rows.map((r) => r.repo_full_name);
+    },
+  };
+
+  const scans: ScanStore = {
+    async insert(scan) {
+      return insert(await db(), "security_scans", { ...scan, created_at: iso(new Date()) });
+    },
+
+    async get(id) {
+      const [row] = await select<any>("security_scans", "SELECT * FROM security_scans WHERE id = ?", [id]);
+      return row || null;
+    },
+
+    async list(filter = {}) {
+      const { clause, params } = where({
+        "repo_full_name = ?": filter.repo,
+        "LOWER(repo_full_name) LIKE ?": filter.search && `%${filter.search.toLowerCase()}%`,
+        "grade = ?": filter.grade,
+      });
+      const [count] = await (await db()).query<{ n: unknown }>(`SELECT COUNT(*) AS n FROM security_scans ${clause}`, params);
+      const rows = await select<any>("security_scans",
+        `SELECT * FROM security_scans ${clause} ORDER BY created_at DESC LIMIT ? OFFSET ?`,
+        [...params, filter.limit || 20, filter.offset || 0]);
+      return { scans: rows, total: Number(count?.n || 0) };
+    },
+
+    async repoCount() {
+      const [row] = await (await db()).query<{ n: unknown }>("SELECT COUNT(DISTINCT repo_full_name) AS n FROM security_scans");
+      return Number(row?.n || 0);
+    },
+  };
+
+  const learnings: LearningStore = {
+    async active(repoFullName, limit = 200) {
+      return select("review_learnings",
+        `SELECT id, repo_full_name, pattern, learning, category, created_at FROM review_learnings
+         WHERE repo_full_name = ? AND (status IS NULL OR status = 'active') ORDER BY created_at DESC LIMIT ?`,
+        [repoFullName, limit]);
+    },
+
+    async list(filter = {}) {
+      const { clause, params } = where({ "repo_full_name = ?": filter.repo, "status = ?": filter.status });
+      return select("review_learnings",
+        `SELECT * FROM review_learnings ${clause} ORDER BY created_at DESC LIMIT ?`,
+        [...params, filter.limit || 100]);
+    },
+
+    async create(learning) {
+      return insert(await db(), "review_learnings", { status: "active", source: "manual", ...learning, created_at: iso(new Date()) });
+    },
+
+    async update(id, patch) {
+      const [row] = await update<any>(await db(), "review_learnings", patch, "id = ?", [id]);
+      return row || null;
+    },
+
+    async remove(id) {
+      await (await db()).query("DELETE FROM review_learnings WHERE id = ?", [id]);
+    },
+  };
+
+  const audit: AuditLogStore = {
+    async insert(entry) {
+      await insert(await db(), "audit_logs", { ...entry, created_at: entry.created_at || iso(new Date()) });
+    },
+
+    async list(filter = {}) {
+      const { clause, params } = where({
+        "action = ?": filter.action,
+        "actor_id = ?": filter.actor_id,
+        "resource_type = ?": filter.resource_type,
+        "created_at >= ?": filter.from && iso(filter.from),
+        "created_at <= ?": filter.to && iso(filter.to),
+      });
+      return select("audit_logs",
+        `SELECT * FROM audit_logs ${clause} ORDER BY created_at DESC LIMIT ?`,
+        [...params, filter.limit || 100]);
+    },
+  };
+
+  const notifications: NotificationStore = {
+    async insert(notification) {
+      await insert(await db(), "notifications", { ...notification, sent_at: iso(new Date()) });
+    },
+  };
+
+  const feedback: FeedbackStore = {
+    async recordComments(comments) {
+      const conn = await db();
+      const now = iso(new Date());
+      for (const comment of comments) {
+        await upsert(conn, "review_comments", { created_at: now, ...comment }, ["github_comment_id"]);
+      }
+    },
+
+    async comments(filter) {
+      const { clause, params } = where({
+        "repo_full_name = ?": filter.repo,
+        "pr_number = ?": filter.pr,
+        "github_review_id = ?": filter.reviewId,
+        "github_comment_id = ?": filter.commentId,
+      });
+      return select("review_comments", `SELECT * FROM review_comments ${clause}`, params);
+    },
+
+    async upsertSignal(signal) {
+      const now = iso(new Date());
+      await upsert(await db(), "review_feedback", { created_at: now, ...signal, updated_at: now }, ["github_comment_id", "signal"]);
+    },
+
+    async signals(repoFullName) {
+      const rows = await (await db()).query<FeedbackWithComment>(
+        `SELECT f.signal, f.count, f.note, c.category, c.path, c.source
+         FROM review_feedback f LEFT JOIN review_comments c ON c.github_comment_id = f.github_comment_id
+         WHERE f.repo_full_name = ?`,
+        [repoFullName]
+      );
+      return rows.map((r) => ({ ...r, count: Number(r.count) }));
+    },
+  };
+
+  const dlq: DlqStore = {
+    async list(filter) {
+      const { sql, params } = dlqMatch(filter);
+      const [count] = await (await db()).query<{ n: unknown }>(`SELECT COUNT(*) AS n FROM review_jobs_dlq WHERE ${sql}`, params);
+      const entries = await select<any>("review_jobs_dlq",
+        `SELECT * FROM review_jobs_dlq WHERE ${sql} ORDER BY moved_to_dlq_at DESC LIMIT ?`,
+        [...params, filter.limit]);
+      return { entries, total: Number(count?.n || 0) };
+    },
+
+    async get(id) {
+      const [row] = await select<any>("review_jobs_dlq", "SELECT * FROM review_jobs_dlq WHERE id = ?", [id]);
+      return row || null;
+    },
+
+    async markReplayed(id, jobId, replayCount) {
+      await update(await db(), "review_jobs_dlq",
+        { replayed_at: iso(new Date()), replayed_job_id: jobId, replay_count: replayCount }, "id = ?", [id]);
+    },
+
+    async remove(filter) {
+      const { sql, params } = dlqMatch(filter);
+      const rows = await (await db()).query(`DELETE FROM review_jobs_dlq WHERE ${sql} RETURNING id`, params);
+      return rows.length;
+    },
+  };
+
+  const toolAudit: ToolAuditStore = {
+    async insert(entries) {
+      const conn = await db();
+      for (const entry of entries) await insert(conn, "tool_audit_logs", { ...entry });
+    },
+  };
+
+  return {
+    backend: dialect,
+    jobs,
+    history,
+    configs,
+    pullRequests,
+    scans,
+    learnings,
+    audit,
+    notifications,
+    feedback,
+    dlq,
+    toolAudit,
+    async ping() {
+      await (await db()).query("SELECT 1");
+    },
+  };
+}
@@ -0,0 +1,570 @@
+import { AsyncLocalStorage } from "async_hooks";
+import { createClient, createServiceClient } from "../supabase/server";
+import type {
+  AuditLogStore, DlqFilter, DlqStore, FeedbackStore, JobCounts, LearningStore, NotificationStore, PullRequestFilter,
+  PullRequestStore, RepoConfigMatch, RepoConfigStore, ReviewHistoryStore, ReviewJobStore, ScanStore, Storage, ToolAuditStore,
+} from "./types";
+
+// PostgREST's "no rows" error from .single()
+const NOT_FOUND = "PGRST116";
+
+const serviceScope = new AsyncLocalStorage<boolean>();
+
+/**
+ * Run work with the service role: the queue worker, and writes made on behalf
+ * of signed GitHub webhooks. Everything else acts as the user of the request.
+ */
+export function withServiceRole<T>(fn: () => Promise<T>): Promise<T> {
+  return serviceScope.run(true, fn);
+}
+
+export function hasServiceRole(): boolean {
+  return serviceScope.getStore() === true;
+}
+
+const client = async () => (serviceScope.getStore() ? createServiceClient() : createClient());
+
+function applyPrFilter(query: any, filter: PullRequestFilter): any {
+  let q = query;
+  if (filter.repo) q = q.eq("repo_full_name", filter.repo);
+  if (filter.state && filter.state !== "all") {
+    q = filter.state === "merged" ? q.not("github_merged_at", "is", null) : q.eq("state", filter.state);
+  }
+  if (filter.llmOnly) q = q.eq("is_llm_generated", true);
+  if (filter.limit) q = q.limit(filter.limit);
+  if (filter.offset) q = q.range(filter.offset, filter.offset + (filter.limit || 50) - 1);
+  return q;
+}
+
+function applyDlqFilter(query: any, filter: DlqFilter): any {
+  let q = query;
+  if (filter.ids?.length) q = q.in("id", filter.ids);
+  if (filter.repo) q = q.eq("repo_full_name", filter.repo);
+  if (filter.pr) q = q.eq("pr_number", filter.pr);
+  if (filter.error) q = q.ilike("error", `%${filter.error}%`);
+  if (filter.before) q = q.lt("moved_to_dlq_at", filter.before);
+  if (filter.replayed === true) q = q.not("replayed_at", "is", null);
+  if (filter.replayed === false) q = q.is("replayed_at", null);
+  return q;
+}
+
+function applyConfigMatch(query: any, match: RepoConfigMatch): any {
+  if ("id" in match) return query.eq("id", match.id);
+  if ("full_name" in match) return query.eq("full_name", match.full_name);
+  if ("full_names" in match) return query.in("full_name", match.full_names);
+  return query.eq("installation_id", match.installation_id);
+}
+
+const jobs: ReviewJobStore = {
+  async list(filter) {
+    const supabase = await client();
+    let query = supabase.from("review_jobs").select("*").order("updated_at", { ascending: false }).limit(filter.limit);
+    if (filter.status) query = query.eq("status", filter.status);
+    if (filter.search) query = query.ilike("repo_full_name", `%${filter.search}%`);
+    const { data, error } = await query;
+    if (error) throw error;
+    return data || [];
+  },
+
+  async active(repoFullName, prNumber) {
+    const supabase = await client();
+    const { data } = await supabase
+      .from("review_jobs")
+      .select("id, head_sha")
+      .eq("repo_full_name", repoFullName)
+      .eq("pr_number", prNumber)
+      .in("status", ["pending", "processing"]);
+    return data || [];
+  },
+
+  async create(job) {
+    const supabase = await client();
+    const { data, error } = await supabase.from("review_jobs").insert(job).select().single();
+    if (error) throw error;
+    return data;
+  },
+
+  async supersedePending(ids, supersededBy, error) {
+    if (!ids.length) return [];
+    const supabase = await client();
+    const { data } = await supabase
+      .from("review_jobs")
+      .update({ status: "superseded", superseded_by: supersededBy, error, updated_at: new Date().toISOString() })
+      .in("id", ids)
+      .eq("status", "pending")
+      .select("id, check_run_id");
+    return data || [];
+  },
+
+  async attachCheckRun(jobId, checkRunId) {
+    const supabase = await client();
+    await supabase.from("review_jobs").update({ check_run_id: checkRunId }).eq("id", jobId);
+  },
+
+  async claim(workerId, options) {
+    const supabase = await client();
+    const { data, error } = await supabase.rpc("claim_review_job", {
+      p_worker_id: workerId,
+      p_lease_seconds: options.leaseSeconds,
+      p_repo_limit: options.repoLimit,
+      p_aging_minutes: options.agingMinutes,
+    });
+    if (error) throw error;
+    return data?.[0] ?? null;
+  },
+
+  async heartbeat(jobId, workerId, leaseSeconds) {
+    const supabase = await client();
+    const { data, error } = await supabase.rpc("heartbeat_review_job", { p_job_id: jobId, p_worker_id: workerId, p_lease_seconds: leaseSeconds });
+    if (error) throw error;
+    return data === true;
+  },
+
+  async release(jobId, workerId, release) {
+    const supabase = await client();
+    await supabase.from("review_jobs")
+      .update({ ...release, updated_at: new Date().toISOString(), worker_id: null, lease_expires_at: null })
+      .eq("id", jobId)
+      .eq("worker_id", workerId);
+  },
+
+  async counts(completedSince): Promise<JobCounts> {
+    const supabase = await client();
+    const [pending, processing, failed, completed] = await Promise.all([
+      supabase.from("review_jobs").select("id", { count: "exact" }).eq("status", "pending"),
+      supabase.from("review_jobs").select("id", { count: "exact" }).eq("status", "processing"),
+      supabase.from("review_jobs").select("id", { count: "exact" }).eq("status", "failed"),
+      supabase.from("review_jobs").select("id", { count: "exact" }).eq("status", "completed").gte("completed_at", completedSince.toISOString()),
+    ]);
+    return { pending: pending.count || 0, processing: processing.count || 0, failed: failed.count || 0, completed_today: completed.count || 0 };
+  },
+
+  async retryFailed(search) {
+    const supabase = await client();
+    let query = supabase
+      .from("review_jobs")
+      .update({ status: "pending", attempts: 0, error: null, next_retry_at: null, updated_at: new Date().toISOString() })
+      .eq("status", "failed");
+    if (search) query = query.ilike("repo_full_name", `%${search}%`);
+    const { data, error } = await query.select("id");
+    if (error) throw error;
+    return data?.length || 0;
+  },
+
+  async clear(status) {
+    const supabase = await client();
+    const { data, error } = await supabase.from("review_jobs").delete().eq("status", status).select("id");
+    if (error) throw error;
+    return data?.length || 0;
+  },
+};
+
+const history: ReviewHistoryStore = {
+  async insert(entry) {
+    const supabase = await client();
+    await supabase.from("review_history").insert(entry);
+  },
+
+  async list(filter = {}) {
+    const supabase = await client();
+    let query = supabase.from("review_history").select("*").order("created_at", { ascending: false }).limit(filter.limit || 50);
+    if (filter.repo) query = query.eq("repo_full_name", filter.repo);
+    if (filter.search) query = query.ilike("repo_full_name", `%${filter.search}%`);
+    if (filter.pr) query = query.eq("pr_number", filter.pr);
+    if (filter.status) query = query.eq("status", filter.status);
+    if (filter.since) query = query.gte("created_at", filter.since.toISOString());
+    const { data, error } = await query;
+    if (error) throw error;
+    return data || [];
+  },
+
+  async count() {
+    const supabase = await client();
+    const { count, error } = await supabase.from("review_history").select("id", { count: "exact", head: true });
+    if (error) throw error;
+    return count || 0;
+  },
+
+  async get(id) {
+    const supabase = await client();
+    const { data, error } = await supabase.from("review_history").select("*").eq("id", id).maybeSingle();
+    if (error) throw error;
+    return data;
+  },
+
+  async remove(id) {
+    const supabase = await client();
+    const { error } = await supabase.from("review_history").delete().eq("id", id);
+    if (error) throw error;
+  },
+
+  async latest(repoFullName, prNumber) {
+    const supabase = await client();
+    const { data, error } = await supabase
+      .from("review_history")
+      .select("*")
+      .eq("repo_full_name", repoFullName)
+      .eq("pr_number", prNumber)
+      .eq("status", "completed")
+      .order("created_at", { ascending: false })
+      .limit(1)
+      .maybeSingle();
+    if (error) throw error;
+    return data;
+  },
+
+  async usageSince(repoFullName, since) {
+    const supabase = await client();
+    const { data, error } = await supabase.rpc("repo_usage_since", { p_repo: repoFullName, p_since: since.toISOString() });
+    if (error) throw error;
+    const row = Array.isArray(data) ? data[0] : data;
+    return {
+      prompt_tokens: Number(row?.prompt_tokens) || 0,
+      completion_tokens: Number(row?.completion_tokens) || 0,
+      cost_usd: Number(row?.cost_usd) || 0,
+    };
+  },
+};
+
+const configs: RepoConfigStore = {
+  async get(fullName) {
+    const supabase = await client();
+    const { data, error } = await supabase.from("repo_configs").select("*").eq("full_name", fullName).single();
+    if (error?.code === NOT_FOUND) return null;
+    if (error) throw error;
+    return data;
+  },
+
+  async list() {
+    const supabase = await client();
+    const { data, error } = await supabase.from("repo_configs").select("*").order("full_name");
+    if (error) throw error;
+    return data || [];
+  },
+
+  async search(query) {
+    const supabase = await client();
+    const { data, error } = await supabase
+      .from("repo_configs")
+      .select("*")
+      .or(`full_name.ilike.%${query}%,repo_full_name.ilike.%${query}%`)
+      .order("full_name");
+    if (error) throw error;
+    return data || [];
+  },
+
+  async create(config) {
+    const supabase = await client();
+    const { data, error } = await supabase.from("repo_configs").insert(config).select().single();
+    if (error) throw error;
+    return data;
+  },
+
+  async upsert(configs) {
+    if (!configs.length) return [];
+    const supabase = await client();
+    const now = new Date().toISOString();
+    const { data, error } = await supabase
+      .from("repo_configs")
+      .upsert(configs.map((c) => ({ ...c, updated_at: now })), { onConflict: "full_name" })
+      .select();
+    if (error) throw error;
+    return data || [];
+  },
+
+  async update(match, patch) {
+    const supabase = await client();
+    const { data, error } = await applyConfigMatch(
+      supabase.from("repo_configs").update({ ...patch, updated_at: new Date().toISOString() }),
+      match
+    ).select();
+    if (error) throw error;
+    return data || [];
+  },
+
+  async remove(match) {
+    const supabase = await client();
+    const { data, error } = await applyConfigMatch(supabase.from("repo_configs").delete(), match).select("id");
+    if (error) throw error;
+    return data?.length || 0;
+  },
+
+  async rename(from, to) {
+    const supabase = await client();
+    const { error } = await supabase.rpc("rename_repo", { p_old: from, p_new: to });
+    if (error) throw error;
+  },
+};
+
+const pullRequests: PullRequestStore = {
+  async upsert(pr) {
+    const supabase = await client();
+    const { data, error } = await supabase
+      .from("pull_requests")
+      .upsert(pr, { onConflict: "repo_full_name,number" })
+      .select()
+      .single();
+    if (error) throw error;
+    return data;
+  },
+
+  async list(filter = {}) {
+    const supabase = await client();
+    const { data, error } = await applyPrFilter(
+      supabase.from("pull_requests").select("*").order("github_created_at", { ascending: false }),
+      filter
+    );
+    if (error) throw error;
+    return data || [];
+  },
+
+  async get(repoFullName, number) {
+    const supabase = await client();
+    const { data, error } = await supabase
+      .from("pull_requests")
+      .select("*")
+      .eq("repo_full_name", repoFullName)
+      .eq("number", number)
+      .single();
+    if (error?.code === NOT_FOUND) return null;
+    if (error) throw error;
+    return data;
+  },
+
+  async repos() {
+    const supabase = await client();
+    const { data, error } = await supabase.from("pull_requests").select("repo_full_name").order("repo_full_name");
+    if (error) throw error;
+    return [...new Set((data || []).map((row) => row.repo_full_name as string))];
+  },
+};
+
+const scans: ScanStore = {
+  async insert(scan) {
+    const supabase = await client();
+    const { data, error } = await supabase.from("security_scans").insert(scan).select().single();
+    if (error) throw error;
+    return data;
+  },
+
+  async get(id) {
+    const supabase = await client();
+    const { data, error } = await supabase.from("security_scans").select("*").eq("id", id).maybeSingle();
+    if (error) throw error;
+    return data;
+  },
+
+  async list(filter = {}) {
+    const supabase = await client();
+    const limit = filter.limit || 20;
+    const offset = filter.offset || 0;
+    let query = supabase
+      .from("security_scans")
+      .select("*", { count: "exact" })
+      .order("created_at", { ascending: false })
+      .range(offset, offset + limit - 1);
+    if (filter.repo) query = query.eq("repo_full_name", filter.repo);
+    if (filter.search) query = query.ilike("repo_full_name", `%${filter.search}%`);
+    if (filter.grade) query = query.eq("grade", filter.grade);
+    const { data, error, count } = await query;
+    if (error) throw error;
+    return { scans: data || [], total: count || 0 };
+  },
+
+  async repoCount() {
+    const supabase = await client();
+    const { data } = await supabase.from("security_scans").select("repo_full_name");
+    return new Set(data?.map((r) => r.repo_full_name)).size;
+  },
+};
+
+const learnings: LearningStore = {
+  async active(repoFullName, limit = 200) {
+    const supabase = await client();
+    const { data } = await supabase
+      .from("review_learnings")
+      .select("id, repo_full_name, pattern, learning, category, created_at")
+      .eq("repo_full_name", repoFullName)
+      .or("status.is.null,status.eq.active")
+      .order("created_at", { ascending: false })
+      .limit(limit);
+    return data || [];
+  },
+
+  async list(filter = {}) {
+    const supabase = await client();
+    let query = supabase.from("review_learnings").select("*").order("created_at", { ascending: false });
+    if (filter.repo) query = query.eq("repo_full_name", filter.repo);
+    if (filter.status) query = query.eq("status", filter.status);
+    const { data, error } = await query.limit(filter.limit || 100);
+    if (error) throw error;
+    return data || [];
+  },
+
+  async create(learning) {
+    const supabase = await client();
+    const { data, error } = await supabase.from("review_learnings").insert(learning).select().single();
+    if (error) throw error;
+    return data;
+  },
+
+  async update(id, patch) {
+    const supabase = await client();
+    const { data, error } = await supabase.from("review_learnings").update(patch).eq("id", id).select();
+    if (error) throw error;
+    return data?.[0] ?? null;
+  },
+
+  async remove(id) {
+    const supabase = await client();
+    const { error } = await supabase.from("review_learnings").delete().eq("id", id);
+    if (error) throw error;
+  },
+};
+
+const audit: AuditLogStore = {
+  async insert(entry) {
+    const supabase = await client();
+    await supabase.from("audit_logs").insert({ ...entry, created_at: entry.created_at || new Date().toISOString() });
+  },
+
+  async list(filter = {}) {
+    const supabase = await client();
+    let query = supabase
+      .from("audit_logs")
+      .select("*")
+      .order("created_at", { ascending: false })
+      .limit(filter.limit || 100);
+    if (filter.action) query = query.eq("action", filter.action);
+    if (filter.actor_id) query = query.eq("actor_id", filter.actor_id);
+    if (filter.resource_type) query = query.eq("resource_type", filter.resource_type);
+    if (filter.from) query = query.gte("created_at", filter.from.toISOString());
+    if (filter.to) query = query.lte("created_at", filter.to.toISOString());
+    const { data } = await query;
+    return data || [];
+  },
+};
+
+const notifications: NotificationStore = {
+  async insert(notification) {
+    const supabase = await client();
+    await supabase.from("notifications").insert(notification);
+  },
+};
+
+const feedback: FeedbackStore = {
+  async recordComments(comments) {
+    if (!comments.length) return;
+    const supabase = await client();
+    const { error } = await supabase.from("review_comments").upsert(comments, { onConflict: "github_comment_id" });
+    if (error) throw error;
+  },
+
+  async comments(filter) {
+    const supabase = await client();
+    let query = supabase.from("review_comments").select("*");
+    if (filter.repo) query = query.eq("repo_full_name", filter.repo);
+    if (filter.pr) query = query.eq("pr_number", filter.pr);
+    if (filter.reviewId) query = query.eq("github_review_id", filter.reviewId);
+    if (filter.commentId) query = query.eq("github_comment_id", filter.commentId);
+    const { data, error } = await query;
+    if (error) throw error;
+    return data || [];
+  },
+
+  async upsertSignal(signal) {
+    const supabase = await client();
+    const { error } = await supabase
+      .from("review_feedback")
+      .upsert({ ...signal, updated_at: new Date().toISOString() }, { onConflict: "github_comment_id,signal" });
+    if (error) throw error;
+  },
+
+  async signals(repoFullName) {
+    const supabase = await client();
+    const { data, error } = await supabase
+      .from("review_feedback")
+      .select("signal, count, note, review_comments(category, path, source)")
+      .eq("repo_full_name", repoFullName);
+    if (error) throw error;
+    return (data || []).map((r) => {
+      const comment = (Array.isArray(r.review_comments) ? r.review_comments[0] : r.review_comments) as
+        { category: string | null; path: string; source?: string | null } | null;
+      return {
+        signal: r.signal,
+        count: r.count,
+        note: r.note,
+        category: comment?.category ?? null,
+        path: comment?.path ?? null,
+        source: comment?.source ?? null,
+      };
+    });
+  },
+};
+
+const dlq: DlqStore = {
+  async list(filter) {
+    const supabase = await client();
+    const { data, error, count } = await applyDlqFilter(
+      supabase.from("review_jobs_dlq").select("*", { count: "exact" }),
+      filter
+    )
+      .order("moved_to_dlq_at", { ascending: false })
+      .limit(filter.limit);
+    if (error) throw error;
+    return { entries: data || [], total: count || 0 };
+  },
+
+  async get(id) {
+    const supabase = await client();
+    const { data, error } = await supabase.from("review_jobs_dlq").select("*").eq("id", id).single();
+    if (error?.code === NOT_FOUND) return null;
+    if (error) throw error;
+    return data;
+  },
+
+  async markReplayed(id, jobId, replayCount) {
+    const supabase = await client();
+    const { error } = await supabase
+      .from("review_jobs_dlq")
+      .update({ replayed_at: new Date().toISOString(), replayed_job_id: jobId, replay_count: replayCount })
+      .eq("id", id);
+    if (error) throw error;
+  },
+
+  async remove(filter) {
+    const supabase = await client();
+    const { data, error } = await applyDlqFilter(supabase.from("review_jobs_dlq").delete(), filter).select("id");
+    if (error) throw error;
+    return data?.length || 0;
+  },
+};
+
+const toolAudit: ToolAuditStore = {
+  async insert(entries) {
+    if (!entries.length) return;
+    const supabase = await client();
+    const { error } = await supabase.from("tool_audit_logs").insert(entries);
+    if (error) throw error;
+  },
+};
+
+export const supabaseStorage: Storage = {
+  backend: "supabase",
+  jobs,
+  history,
+  configs,
+  pullRequests,
+  scans,
+  learnings,
+  audit,
+  notifications,
+  feedback,
+  dlq,
+  toolAudit,
+  async ping() {
+    const supabase = await client();
+    const { error } = await supabase.from("repo_configs").select("id").limit(1);
+    if (error) throw error;
+  },
+};
@@ -0,0 +1,425 @@
+import type { UsageTotals } from "../llm/usage";
+import type { ReviewLearning, SeverityPolicy } from "../review/engine";
+
+/**
+ * Storage repositories. Modules talk to these instead of a database client,
+ * so the same code runs on Supabase or on a plain Postgres/SQLite database.
+ */
+
+export type JobStatus = "pending" | "processing" | "completed" | "failed" | "superseded";
+
+export interface ReviewJob {
+  id: string;
+  repo_full_name: string;
+  pr_number: number;
+  owner: string;
+  repo: string;
+  status: JobStatus;
+  attempts: number;
+  max_attempts: number;
+  analysis?: unknown;
+  priority: number;
+  score: number;
+  error?: string;
+  check_run_id?: number | null;
+  updated_at?: string;
+  head_sha?: string | null;
+  superseded_by?: string | null;
+  worker_id?: string | null;
+  lease_expires_at?: string | null;
+  created_at: string;
+}
+
+export interface NewReviewJob {
+  repo_full_name: string;
+  pr_number: number;
+  owner: string;
+  repo: string;
+  analysis?: unknown;
+  head_sha?: string | null;
+  error_history?: unknown[]; // carried over when a DLQ entry is replayed
+  priority: number;
+  score: number;
+}
+
+// How a worker hands back a job it holds; the lease is always cleared
+export interface JobRelease {
+  status: JobStatus;
+  error?: string | null;
+  attempts?: number;
+  next_retry_at?: string | null;
+  completed_at?: string | null;
+}
+
+export interface ClaimOptions {
+  leaseSeconds: number;
+  repoLimit: number; // default per-repo cap; repo_configs.max_concurrent_reviews overrides it
+  agingMinutes: number; // a waiting job gains one priority level per this many minutes
+}
+
+export interface JobCounts {
+  pending: number;
+  processing: number;
+  failed: number;
+  completed_today: number;
+}
+
+export interface ReviewJobFilter {
+  status?: JobStatus;
+  search?: string; // substring of the repo name
+  limit: number;
+}
+
+export interface ReviewJobStore {
+  // Most recently updated first
+  list(filter: ReviewJobFilter): Promise<ReviewJob[]>;
+  active(repoFullName: string, prNumber: number): Promise<Pick<ReviewJob, "id" | "head_sha">[]>;
+  // Fails with a unique violation while another job for the same commit is active
+  create(job: NewReviewJob): Promise<ReviewJob>;
+  // Mark still-pending jobs as replaced by a newer one; returns the ones changed
+  supersedePending(ids: string[], supersededBy: string, error: string): Promise<Pick<ReviewJob, "id" | "check_run_id">[]>;
+  attachCheckRun(jobId: string, checkRunId: number): Promise<void>;
+  claim(workerId: string, options: ClaimOptions): Promise<ReviewJob | null>;
+  heartbeat(jobId: string, workerId: string, leaseSeconds: number): Promise<boolean>;
+  // Only applies while workerId still holds the job
+  release(jobId: string, workerId: string, release: JobRelease): Promise<void>;
+  counts(completedSince: Date): Promise<JobCounts>;
+  // Put failed jobs back in the queue with fresh retries; returns how many
+  retryFailed(search?: string): Promise<number>;
+  // Delete finished jobs; returns how many
+  clear(status: "completed" | "failed"): Promise<number>;
+}
+
+export interface ReviewHistoryEntry extends UsageTotals {
+  id: string;
+  repo_full_name: string;
+  pr_number: number;
+  status: string;
+  result: unknown;
+  head_sha: string | null;
+  is_incremental: boolean;
+  created_at: string;
+}
+
+export type NewReviewHistory = Omit<ReviewHistoryEntry, "id" | "created_at">;
+
+export interface ReviewHistoryFilter {
+  repo?: string; // exact name
+  search?: string; // substring of the name
+  pr?: number;
+  status?: string;
+  since?: Date;
+  limit?: number;
+}
+
+export interface ReviewHistoryStore {
+  insert(entry: NewReviewHistory): Promise<void>;
+  // Newest first
+  list(filter?: ReviewHistoryFilter): Promise<ReviewHistoryEntry[]>;
+  count(): Promise<number>;
+  get(id: string): Promise<ReviewHistoryEntry | null>;
+  remove(id: string): Promise<void>;
+  // The newest completed review of a PR
+  latest(repoFullName: string, prNumber: number): Promise<ReviewHistoryEntry | null>;
+  // LLM usage of reviews and scans for a repo since a point in time
+  usageSince(repoFullName: string, since: Date): Promise<UsageTotals>;
+}
+
+export interface RepoConfigRow {
+  id: string;
+  full_name: string;
+  repo_full_name?: string | null;
+  enabled: boolean;
+  auto_review: boolean;
+  review_depth?: string | null;
+  categories?: string[] | null;
+  ignore_paths?: string[] | null;
+  ignore_patterns?: string[] | null;
+  custom_instructions?: string | null;
+  category_weights?: Record<string, number> | null;
+  severity_policy?: SeverityPolicy | null;
+  monthly_token_budget?: number | null;
+  monthly_cost_budget?: number | null;
+  over_budget_action?: "downgrade" | "skip" | null;
+  budget_notified_at?: string | null;
+  max_concurrent_reviews?: number | null;
+  installation_id?: number | null;
+  disabled_reason?: string | null;
+  created_by?: string | null;
+  created_at?: string;
+  updated_at?: string;
+}
+
+export type RepoConfigInput = Partial<Omit<RepoConfigRow, "id" | "created_at">>;
+
+// Which configs an update or delete applies to
+export type RepoConfigMatch = { id: string } | { full_name: string } | { full_names: string[] } | { installation_id: number };
+
+export interface RepoConfigStore {
+  get(fullName: string): Promise<RepoConfigRow | null>;
+  list(): Promise<RepoConfigRow[]>;
+  // Case-insensitive substring match on the repo name, for chat tools
+  search(query: string): Promise<RepoConfigRow[]>;
+  create(config: RepoConfigInput & { full_name: string }): Promise<RepoConfigRow>;
+  // Insert or update by full_name
+  upsert(configs: (RepoConfigInput & { full_name: string })[]): Promise<RepoConfigRow[]>;
+  // Returns the updated rows
+  update(match: RepoConfigMatch, patch: RepoConfigInput): Promise<RepoConfigRow[]>;
+  remove(match: RepoConfigMatch): Promise<number>;
+  // Move a repo's config and history to its new name after a rename or transfer
+  rename(from: string, to: string): Promise<void>;
+}
+
+export interface PullRequestRow {
+  id: string;
+  github_id: number;
+  repo_full_name: string;
+  number: number;
+  title: string;
+  body: string | null;
+  state: string;
+  draft: boolean;
+  author_login: string;
+  author_type: string | null;
+  author_id: number | null;
+  github_created_at: string | null;
+  github_updated_at: string | null;
+  github_merged_at: string | null;
+  additions: number;
+  deletions: number;
+  changed_files: number;
+  head_ref: string | null;
+  base_ref: string | null;
+  head_sha: string | null;
+  labels: string[];
+  html_url: string | null;
+  is_llm_generated: boolean;
+  llm_tool: string | null;
+  llm_confidence: number | null;
+  llm_detection_signals: Record<string, unknown> | null;
+  created_at: string;
+  updated_at: string;
+  synced_at: string;
+}
+
+export type NewPullRequest = Omit<PullRequestRow, "id" | "created_at" | "updated_at">;
+
+export interface PullRequestFilter {
+  repo?: string;
+  state?: "open" | "closed" | "merged" | "all";
+  llmOnly?: boolean;
+  limit?: number;
+  offset?: number;
+}
+
+export interface PullRequestStore {
+  // Insert or update by (repo_full_name, number)
+  upsert(pr: NewPullRequest): Promise<PullRequestRow>;
+  list(filter?: PullRequestFilter): Promise<PullRequestRow[]>;
+  get(repoFullName: string, number: number): Promise<PullRequestRow | null>;
+  repos(): Promise<string[]>;
+}
+
+export interface SecurityScan extends Partial<UsageTotals> {
+  id: string;
+  repo_full_name: string;
+  security_score: number | null;
+  issues: unknown[];
+  summary: string | null;
+  files_scanned: number;
+  scan_metadata?: Record<string, unknown> | null;
+  check_run_id?: number | null;
+  created_at: string;
+  // Columns from older scan versions
+  score?: number | null;
+  grade?: string | null;
+  critical_count?: number | null;
+  high_count?: number | null;
+  medium_count?: number | null;
+  findings?: unknown[] | null;
+}
+
+export type NewSecurityScan = Omit<SecurityScan, "id" | "created_at">;
+
+export interface ScanFilter {
+  repo?: string; // exact name
+  search?: string; // substring of the name
+  grade?: string;
+  limit?: number;
+  offset?: number;
+}
+
+export interface ScanStore {
+  insert(scan: NewSecurityScan): Promise<SecurityScan>;
+  get(id: string): Promise<SecurityScan | null>;
+  list(filter?: ScanFilter): Promise<{ scans: SecurityScan[]; total: number }>;
+  repoCount(): Promise<number>;
+}
+
+export interface LearningRow extends ReviewLearning {
+  source?: string | null; // manual, feedback
+  status?: string | null; // active, proposed, rejected
+}
+
+export interface LearningStore {
+  // Learnings applied to reviews of a repo, newest first
+  active(repoFullName: string, limit?: number): Promise<ReviewLearning[]>;
+  list(filter?: { repo?: string; status?: string; limit?: number }): Promise<LearningRow[]>;
+  create(learning: Omit<LearningRow, "id" | "created_at">): Promise<LearningRow>;
+  update(id: string, patch: Partial<Pick<LearningRow, "status" | "learning" | "pattern" | "category">>): Promise<LearningRow | null>;
+  remove(id: string): Promise<void>;
+}
+
+export interface AuditLogRow {
+  id?: string;
+  action: string;
+  actor_id?: string | null;
+  resource_type?: string | null;
+  resource_id?: string | null;
+  metadata?: Record<string, unknown> | null;
+  ip_address?: string | null;
+  user_agent?: string | null;
+  created_at?: string;
+}
+
+export interface AuditLogFilter {
+  action?: string;
+  actor_id?: string;
+  resource_type?: string;
+  from?: Date;
+  to?: Date;
+  limit?: number;
+}
+
+export interface AuditLogStore {
+  insert(entry: AuditLogRow): Promise<void>;
+  list(filter?: AuditLogFilter): Promise<AuditLogRow[]>;
+}
+
+export interface ReviewCommentRow {
+  id?: string;
+  repo_full_name: string;
+  pr_number: number;
+  github_review_id: number | null;
+  github_comment_id: number;
+  path: string;
+  line: number | null;
+  category: string | null;
+  severity: string | null;
+  source: string | null; // analyzer that reported it; null for the model
+  body: string | null;
+  created_at?: string;
+}
+
+export interface FeedbackSignalRow {
+  repo_full_name: string;
+  github_comment_id: number;
+  signal: string; // thumbs_up, thumbs_down, resolved, wont_fix, dismissed
+  count: number;
+  note?: string | null;
+  updated_at?: string;
+}
+
+// A signal with the comment it was given on
+export interface FeedbackWithComment {
+  signal: string;
+  count: number;
+  note: string | null;
+  category: string | null;
+  path: string | null;
+  source: string | null;
+}
+
+export interface FeedbackStore {
+  // Insert or update by github_comment_id
+  recordComments(comments: ReviewCommentRow[]): Promise<void>;
+  comments(filter: { repo?: string; pr?: number; reviewId?: number; commentId?: number }): Promise<ReviewCommentRow[]>;
+  // Insert or update by (github_comment_id, signal)
+  upsertSignal(signal: FeedbackSignalRow): Promise<void>;
+  signals(repoFullName: string): Promise<FeedbackWithComment[]>;
+}
+
+export interface JobError {
+  at: string;
+  attempt: number;
+  error: string;
+  worker_id?: string | null;
+}
+
+export interface DlqEntry {
+  id: string;
+  original_job_id: string;
+  repo_full_name: string;
+  pr_number: number;
+  owner: string;
+  repo: string;
+  attempts: number;
+  error: string | null;
+  analysis: unknown;
+  head_sha: string | null;
+  error_history: JobError[];
+  original_created_at: string;
+  moved_to_dlq_at: string;
+  replay_count: number;
+  replayed_at: string | null;
+  replayed_job_id: string | null;
+  metadata: Record<string, unknown> | null;
+}
+
+export interface DlqFilter {
+  ids?: string[];
+  repo?: string;
+  pr?: number;
+  error?: string; // substring of the last error
+  before?: string; // moved to the DLQ before this time
+  replayed?: boolean;
+  limit?: number;
+}
+
+export interface DlqStore {
+  // Newest first, with the count of every match
+  list(filter: DlqFilter & { limit: number }): Promise<{ entries: DlqEntry[]; total: number }>;
+  get(id: string): Promise<DlqEntry | null>;
+  markReplayed(id: string, jobId: string, replayCount: number): Promise<void>;
+  // Returns how many entries were deleted
+  remove(filter: DlqFilter): Promise<number>;
+}
+
+export interface ToolAuditRow {
+  correlation_id: string;
+  conversation_id?: string | null;
+  step?: number | null;
+  tool_name: string;
+  user_id?: string | null;
+  ip_address?: string | null;
+  params: Record<string, string>;
+  success: boolean;
+  error_message?: string | null;
+  duration_ms?: number | null;
+  created_at: string;
+}
+
+export interface ToolAuditStore {
+  insert(entries: ToolAuditRow[]): Promise<void>;
+}
+
+export interface NotificationStore {
+  insert(notification: { type: string; channel: string; message: string; metadata?: Record<string, unknown> }): Promise<void>;
+}
+
+export interface Storage {
+  backend: "supabase" | "postgres" | "sqlite";
+  jobs: ReviewJobStore;
+  history: ReviewHistoryStore;
+  configs: RepoConfigStore;
+  pullRequests: PullRequestStore;
+  scans: ScanStore;
+  learnings: LearningStore;
+  audit: AuditLogStore;
+  notifications: NotificationStore;
+  feedback: FeedbackStore;
+  dlq: DlqStore;
+  toolAudit: ToolAuditStore;
+  // Cheap round trip for health checks
+  ping(): Promise<void>;
+}
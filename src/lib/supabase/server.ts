@@ -1,8 +1,11 @@
 import { createServerClient } from '@supabase/ssr'
 import { cookies } from 'next/headers'
 
+// Acts as the signed-in user. Throws outside a request; background work
+// has to ask for createServiceClient() instead.
 export async function createClient() {
   const cookieStore = await cookies()
+
   return createServerClient(
     process.env.NEXT_PUBLIC_SUPABASE_URL!,
     process.env.NEXT_PUBLIC_SUPABASE_ANON_KEY!,
@@ -18,3 +21,13 @@ export async function createClient() {
     }
   )
 }
+
+// Bypasses RLS. Only for the queue worker, through withServiceRole() in storage
+export function createServiceClient() {
+  const key = process.env.SUPABASE_SERVICE_ROLE_KEY
+  if (!key) throw new Error('SUPABASE_SERVICE_ROLE_KEY is required to run the worker against Supabase')
+
+  return createServerClient(process.env.NEXT_PUBLIC_SUPABASE_URL!, key, {
+    cookies: { getAll: () => [], setAll: () => {} },
+  })
+}
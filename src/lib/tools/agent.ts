@@ -0,0 +1,97 @@
+import type { ChatMessage, ProviderChatOptions, ServedBy, TokenUsage, ToolCallRequest, ToolSpec } from "@/lib/llm/provider";
+import type { StreamChunk } from "@/lib/llm/router";
+import { sumUsage } from "@/lib/llm/usage";
+import { ToolResult } from "./types";
+
+export const DEFAULT_MAX_STEPS = 5;
+
+export type ToolLoopEvent =
+  | { type: "token"; text: string }
+  | { type: "tool_call"; step: number; id: string; tool: string; params: Record<string, unknown> }
+  | { type: "tool_result"; step: number; id: string; tool: string; success: boolean; error?: string; duration?: number; cached?: boolean };
+
+export interface ToolStep {
+  step: number;
+  tool: string;
+  params: Record<string, unknown>;
+  success: boolean;
+  error?: string;
+  duration?: number;
+}
+
+export interface ToolLoopOptions {
+  tools: ToolSpec[];
+  stream: (messages: ChatMessage[], options: ProviderChatOptions) => AsyncIterable<StreamChunk>;
+  execute: (call: ToolCallRequest, step: number) => Promise<ToolResult>;
+  maxSteps?: number;
+  temperature?: number;
+  onEvent?: (event: ToolLoopEvent) => void;
+}
+
+export interface ToolLoopResult {
+  response: string;
+  steps: ToolStep[];
+  servedBy?: ServedBy;
+  usage?: TokenUsage; // summed over every step
+  stepLimitReached: boolean;
+}
+
+const STEP_LIMIT_NOTE = "Tool step limit reached. Answer with the information gathered so far without calling more tools.";
+
+/**
+ * Run a chat turn with native tool calling. Each step streams one model
+ * response; any tool calls it makes are executed in order and their results
+ * fed back as tool messages before the next step. After maxSteps rounds of
+ * tool calls the model is asked to answer without tools.
+ */
+export async function runToolLoop(messages: ChatMessage[], options: ToolLoopOptions): Promise<ToolLoopResult> {
+  const { tools, stream, execute, maxSteps = DEFAULT_MAX_STEPS, temperature, onEvent } = options;
+  const conversation = [...messages];
+  const steps: ToolStep[] = [];
+  let response = "";
+  let servedBy: ServedBy | undefined;
+  let usage: TokenUsage | undefined;
+
+  const emitText = (text: string, first: boolean) => {
+    // Separate text from consecutive model turns
+    const prefix = first && response.trim() && !response.endsWith("\n") ? "\n\n" : "";
+    response += prefix + text;
+    onEvent?.({ type: "token", text: prefix + text });
+  };
+
+  for (let step = 1; ; step++) {
+    const limitReached = step > maxSteps;
+    const turn = limitReached ? [...conversation, { role: "system" as const, content: STEP_LIMIT_NOTE }] : conversation;
+
+    let content = "";
+    const calls: ToolCallRequest[] = [];
+    for await (const chunk of stream(turn, { temperature, ...(!limitReached && { tools }) })) {
+      servedBy = { provider: chunk.provider, model: chunk.model };
+      if (chunk.type === "text") {
+        emitText(chunk.text, !content);
+        content += chunk.text;
+      } else if (chunk.type === "usage") {
+        usage = sumUsage(usage, chunk.usage);
+      } else if (!limitReached) {
+        calls.push(chunk.call);
+      }
+    }
+
+    if (!calls.length) return { response: response.trim(), steps, servedBy, usage, stepLimitReached: limitReached };
+
+    conversation.push({ role: "assistant", content, toolCalls: calls });
+    for (const call of calls) {
+      onEvent?.({ type: "tool_call", step, id: call.id, tool: call.name, params: call.arguments });
+      const result = await execute(call, step);
+      const duration = result.metadata?.duration;
+      steps.push({ step, tool: call.name, params: call.arguments, success: result.success, error: result.error, duration });
+      onEvent?.({ type: "tool_result", step, id: call.id, tool: call.name, success: result.success, error: result.error, duration, cached: result.metadata?.cacheHit });
+      conversation.push({
+        role: "tool",
+        toolCallId: call.id,
+        name: call.name,
+        content: result.success ? result.data || "(no output)" : `Error: ${result.error || "Command failed"}`,
+      });
+    }
+  }
+}
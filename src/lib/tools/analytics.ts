@@ -1,6 +1,10 @@
 import { z } from "zod";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
+import { getJobStats } from "@/lib/queue";
 import { Tool, ToolResult, daysSchema, toolError } from "./types";
+import { reviewScore } from "./utils";
+
+const message = (err: unknown) => (err instanceof Error ? err.message : String(err));
 
 export const analyticsTools: Tool[] = [
   {
@@ -12,33 +16,35 @@ export const analyticsTools: Tool[] = [
     params: [],
     schema: z.object({}),
     execute: async (_, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
-      
-      const [reviews, scans, repos, queue, recentScores] = await Promise.all([
-        supabase.from("reviews").select("id", { count: "exact", head: true }),
-        supabase.from("security_scans").select("id", { count: "exact", head: true }),
-        supabase.from("repo_configs").select("id, enabled"),
-        supabase.from("review_queue").select("status"),
-        supabase.from("reviews").select("score").not("score", "is", null).order("created_at", { ascending: false }).limit(100),
-      ]);
-      
-      const queueData = queue.data || [];
-      const pending = queueData.filter(j => j.status === "pending").length;
-      const processing = queueData.filter(j => j.status === "processing").length;
-      const failed = queueData.filter(j => j.status === "failed").length;
-      
-      const scores = recentScores.data?.map(r => r.score).filter((s): s is number => s != null) || [];
+      const { history, scans, configs } = getStorage();
+      
+      let results;
+      try {
+        results = await Promise.all([
+          history.count(),
+          scans.list({ limit: 1 }),
+          configs.list(),
+          getJobStats(),
+          history.list({ limit: 100 }),
+        ]);
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", message(err));
+      }
+      
+      const [reviews, { total: scanCount }, repos, { pending, processing, failed }, recent] = results;
+      
+      const scores = recent.map(r => reviewScore(r.result));
       const avg = scores.length ? Math.round(scores.reduce((a, b) => a + b, 0) / scores.length) : 0;
       
-      const enabledRepos = repos.data?.filter(r => r.enabled).length || 0;
+      const enabledRepos = repos.filter(r => r.enabled).length;
       const healthStatus = failed > 10 ? "⚠️ Degraded" : processing > 20 ? "⚡ High Load" : "✓ Healthy";
       
       const output = `**Platform Statistics**
 
 📊 **Overview**
-• Total Reviews: ${reviews.count?.toLocaleString() || 0}
-• Total Scans: ${scans.count?.toLocaleString() || 0}
-• Repositories: ${repos.count || 0} (${enabledRepos} active)
+• Total Reviews: ${reviews.toLocaleString()}
+• Total Scans: ${scanCount.toLocaleString()}
+• Repositories: ${repos.length} (${enabledRepos} active)
 • Avg Score: ${avg}/100
 
 ⚙️ **Queue Status**
@@ -66,23 +72,18 @@ export const analyticsTools: Tool[] = [
       days: daysSchema.optional(),
     }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
       const days = Math.min(parseInt(params.days || "7"), 365);
-      const since = new Date(Date.now() - days * 24 * 60 * 60 * 1000).toISOString();
+      const since = new Date(Date.now() - days * 24 * 60 * 60 * 1000);
       
-      let query = supabase
-        .from("reviews")
-        .select("repo_full_name, score, created_at")
-        .gte("created_at", since)
-        .order("created_at", { ascending: false });
+      let data;
+      try {
+        data = await getStorage().history.list({ search: params.repo, since, limit: 5000 });
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", message(err));
+      }
+      if (!data.length) return { success: true, data: `No reviews in the last ${days} days.` };
       
-      if (params.repo) query = query.ilike("repo_full_name", `%${params.repo}%`);
-      
-      const { data, error } = await query;
-      if (error) return toolError("INTERNAL_ERROR", error.message);
-      if (!data?.length) return { success: true, data: `No reviews in the last ${days} days.` };
-      
-      const scores = data.map(r => r.score).filter((s): s is number => s != null);
+      const scores = data.map(r => reviewScore(r.result));
       const avg = scores.length ? Math.round(scores.reduce((a, b) => a + b, 0) / scores.length) : 0;
       const min = scores.length ? Math.min(...scores) : 0;
       const max = scores.length ? Math.max(...scores) : 0;
@@ -91,7 +92,7 @@ export const analyticsTools: Tool[] = [
       data.forEach(r => {
         const name = r.repo_full_name;
         if (!byRepo[name]) byRepo[name] = [];
-        if (r.score != null) byRepo[name].push(r.score);
+        byRepo[name].push(reviewScore(r.result));
       });
       
       const repoStats = Object.entries(byRepo)
@@ -121,18 +122,16 @@ ${repoStats.slice(0, 10).map(r => `• ${r.repo}: ${r.count} reviews, avg ${r.av
     params: [],
     schema: z.object({}),
     execute: async (_, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
-      const { data, error } = await supabase
-        .from("review_queue")
-        .select("*")
-        .order("created_at", { ascending: false })
-        .limit(20);
-      
-      if (error) return toolError("INTERNAL_ERROR", error.message);
-      if (!data?.length) return { success: true, data: "Queue is empty." };
+      let data;
+      try {
+        data = await getStorage().jobs.list({ limit: 20 });
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", message(err));
+      }
+      if (!data.length) return { success: true, data: "Queue is empty." };
       
       const counts = { pending: 0, processing: 0, failed: 0, completed: 0 };
-      data.forEach(j => { counts[j.status as keyof typeof counts]++; });
+      data.forEach(j => { if (j.status in counts) counts[j.status as keyof typeof counts]++; });
       
       const output = `**Queue Status**
 • Pending: ${counts.pending} | Processing: ${counts.processing} | Failed: ${counts.failed} | Completed: ${counts.completed}
@@ -157,19 +156,12 @@ ${data.slice(0, 12).map(j => {
     ],
     schema: z.object({ repo: z.string().optional() }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
-      
-      let query = supabase
-        .from("review_queue")
-        .update({ status: "pending", attempts: 0, error: null, updated_at: new Date().toISOString() })
-        .eq("status", "failed");
-      
-      if (params.repo) query = query.ilike("repo_full_name", `%${params.repo}%`);
-      
-      const { data, error } = await query.select("id");
-      if (error) return toolError("INTERNAL_ERROR", error.message);
-      
-      const count = data?.length || 0;
+      let count;
+      try {
+        count = await getStorage().jobs.retryFailed(params.repo);
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", message(err));
+      }
       return {
         success: true,
         data: count > 0 
@@ -193,17 +185,12 @@ ${data.slice(0, 12).map(j => {
       confirm: z.literal("yes"),
     }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
-      
-      const { data, error } = await supabase
-        .from("review_queue")
-        .delete()
-        .eq("status", params.status)
-        .select("id");
-      
-      if (error) return toolError("INTERNAL_ERROR", error.message);
-      
-      const count = data?.length || 0;
+      let count;
+      try {
+        count = await getStorage().jobs.clear(params.status as "completed" | "failed");
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", message(err));
+      }
       return {
         success: true,
         data: `✓ Cleared ${count} ${params.status} job${count !== 1 ? 's' : ''} from queue`,
@@ -0,0 +1,135 @@
+import { z } from "zod";
+import { getDlqEntry, hasFilter, listDlq, parseDlqFilter, purgeDlq, replayDlq, type DlqEntry } from "@/lib/dlq";
+import { Tool, ToolResult, confirmSchema, idSchema, limitSchema, prSchema, repoSchema, toolError } from "./types";
+
+const age = (iso: string) => {
+  const hours = Math.floor((Date.now() - new Date(iso).getTime()) / 3600000);
+  return hours < 48 ? `${hours}h ago` : `${Math.floor(hours / 24)}d ago`;
+};
+
+const summary = (e: DlqEntry) =>
+  `• \`${e.id.slice(0, 8)}\` ${e.repo_full_name} PR#${e.pr_number} (${e.attempts} attempts, ${age(e.moved_to_dlq_at)})${e.replayed_at ? " ↻ replayed" : ""}` +
+  (e.error ? `\n  └ ${e.error.slice(0, 80)}` : "");
+
+const filterParams = [
+  { name: "repo", required: false, description: "Filter by repository (owner/repo)", type: "string" as const },
+  { name: "pr", required: false, description: "Filter by PR number", type: "number" as const },
+  { name: "error", required: false, description: "Filter by error text", type: "string" as const },
+];
+
+const filterSchema = {
+  repo: repoSchema.optional(),
+  pr: prSchema.optional(),
+  error: z.string().optional(),
+};
+
+// Tools take short ids from the list output, so resolve a prefix to the full entry
+async function findEntry(id: string): Promise<DlqEntry | null> {
+  if (id.length === 36) return getDlqEntry(id);
+  const { entries } = await listDlq({ limit: 500 });
+  const matches = entries.filter((e) => e.id.startsWith(id));
+  return matches.length === 1 ? matches[0]! : null;
+}
+
+export const dlqTools: Tool[] = [
+  {
+    name: "dlq",
+    description: "List dead letter queue entries",
+    category: "queue",
+    permission: "read",
+    cacheTtl: 10,
+    params: [
+      ...filterParams,
+      { name: "limit", required: false, description: "Max entries (1-100)", type: "number", default: "10" },
+    ],
+    schema: z.object({ ...filterSchema, limit: limitSchema.optional() }),
+    execute: async (params, ctx): Promise<ToolResult> => {
+      const { entries, total } = await listDlq(parseDlqFilter({ ...params, limit: params.limit || "10" }));
+      if (!entries.length) return { success: true, data: "Dead letter queue is empty." };
+
+      const output = `**Dead Letter Queue** (${entries.length} of ${total})\n\n${entries.map(summary).join("\n")}`;
+      return { success: true, data: output, metadata: { duration: Date.now() - ctx.startTime, recordsAffected: entries.length } };
+    }
+  },
+  {
+    name: "dlq-inspect",
+    description: "Show a DLQ entry with its full error history",
+    category: "queue",
+    permission: "read",
+    params: [{ name: "id", required: true, description: "Entry id (or prefix)", type: "string" }],
+    schema: z.object({ id: idSchema }),
+    execute: async (params, ctx): Promise<ToolResult> => {
+      const entry = await findEntry(params.id!);
+      if (!entry) return toolError("NOT_FOUND", `No single DLQ entry matches ${params.id}`);
+
+      const history = entry.error_history?.length
+        ? entry.error_history.map((h) => `${h.attempt}. ${new Date(h.at).toLocaleString()}${h.worker_id ? ` [${h.worker_id}]` : ""}\n   ${h.error}`).join("\n")
+        : entry.error || "No errors recorded";
+
+      const output = `**${entry.repo_full_name} PR#${entry.pr_number}**
+• Entry: \`${entry.id}\`
+• Job: \`${entry.original_job_id}\`${entry.head_sha ? `\n• Commit: ${entry.head_sha.slice(0, 7)}` : ""}
+• Attempts: ${entry.attempts}
+• Queued: ${age(entry.original_created_at)} | Dead-lettered: ${age(entry.moved_to_dlq_at)}
+• Replays: ${entry.replay_count}${entry.replayed_job_id ? ` (last job \`${entry.replayed_job_id}\`)` : ""}
+
+**Error History**
+${history}`;
+      return { success: true, data: output, metadata: { duration: Date.now() - ctx.startTime } };
+    }
+  },
+  {
+    name: "dlq-replay",
+    description: "Replay DLQ entries as new review jobs",
+    category: "queue",
+    permission: "write",
+    params: [
+      { name: "id", required: false, description: "Entry id (or prefix); omit to replay by filter", type: "string" },
+      ...filterParams,
+    ],
+    schema: z.object({ id: idSchema.optional(), ...filterSchema }),
+    execute: async (params, ctx): Promise<ToolResult> => {
+      let filter = parseDlqFilter(params);
+      if (params.id) {
+        const entry = await findEntry(params.id);
+        if (!entry) return toolError("NOT_FOUND", `No single DLQ entry matches ${params.id}`);
+        filter = { ids: [entry.id], replayed: entry.replayed_at !== null };
+      } else if (!hasFilter(filter)) {
+        return toolError("VALIDATION_ERROR", "Pass an id, repo, pr or error to choose entries to replay");
+      }
+
+      const { replayed, skipped } = await replayDlq(filter, ctx.userId);
+      if (!replayed.length && !skipped.length) return { success: true, data: "No matching entries to replay." };
+
+      const output = [
+        replayed.length ? `✓ Replayed ${replayed.length} entr${replayed.length === 1 ? "y" : "ies"}` : "",
+        ...skipped.map((s) => `✗ \`${s.id.slice(0, 8)}\`: ${s.reason}`),
+      ].filter(Boolean).join("\n");
+      return { success: true, data: output, metadata: { duration: Date.now() - ctx.startTime, recordsAffected: replayed.length } };
+    }
+  },
+  {
+    name: "dlq-purge",
+    description: "Delete DLQ entries",
+    category: "queue",
+    permission: "admin",
+    params: [
+      ...filterParams,
+      { name: "days", required: false, description: "Only entries older than this many days", type: "number" },
+      { name: "confirm", required: true, description: "Type 'yes' to confirm", type: "string" },
+    ],
+    schema: z.object({ ...filterSchema, days: z.coerce.number().int().min(1).optional(), confirm: confirmSchema }),
+    execute: async (params, ctx): Promise<ToolResult> => {
+      const before = params.days ? new Date(Date.now() - Number(params.days) * 86400000).toISOString() : undefined;
+      const filter = parseDlqFilter({ repo: params.repo, pr: params.pr, error: params.error, before });
+      if (!hasFilter(filter)) return toolError("VALIDATION_ERROR", "Pass repo, pr, error or days to choose entries to purge");
+
+      const count = await purgeDlq(filter, ctx.userId);
+      return {
+        success: true,
+        data: `✓ Purged ${count} DLQ entr${count === 1 ? "y" : "ies"}`,
+        metadata: { duration: Date.now() - ctx.startTime, recordsAffected: count }
+      };
+    }
+  },
+];
@@ -1,5 +1,6 @@
 import { z } from "zod";
 import { Tool, ToolResult, repoSchema, toolError } from "./types";
+import { getRepoToken } from "@/lib/github-app";
 
 export const githubTools: Tool[] = [
   {
@@ -14,8 +15,13 @@ export const githubTools: Tool[] = [
     ],
     schema: z.object({ repo: repoSchema }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const token = process.env.GITHUB_TOKEN;
-      if (!token) return toolError("INTERNAL_ERROR", "GitHub token not configured");
+      const [owner, repo] = params.repo.split("/") as [string, string];
+      let token: string;
+      try {
+        token = await getRepoToken(owner, repo);
+      } catch (e) {
+        return toolError("INTERNAL_ERROR", e instanceof Error ? e.message : "GitHub token not configured");
+      }
       
       try {
         const controller = new AbortController();
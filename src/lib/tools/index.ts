@@ -4,10 +4,12 @@ import { reviewTools } from "./reviews";
 import { securityTools } from "./security";
 import { repoTools } from "./repos";
 import { analyticsTools } from "./analytics";
+import { dlqTools } from "./dlq";
 import { githubTools } from "./github";
 import { getCached, setCache, cacheKey, audit, hasPermission, toolsHealthCheck } from "./utils";
 import { logger } from "@/lib/logger";
 import { rateLimit } from "@/lib/rate-limit";
+import type { ToolSpec } from "@/lib/llm/provider";
 
 // Combine all tools
 const allTools: Tool[] = [
@@ -15,6 +17,7 @@ const allTools: Tool[] = [
   ...securityTools,
   ...repoTools,
   ...analyticsTools,
+  ...dlqTools,
   ...githubTools,
 ];
 
@@ -77,6 +80,27 @@ export const toolDefinitions: ToolDefinition[] = tools.map(t => ({
   permission: t.permission,
 }));
 
+// Function specs for native tool-calling APIs, limited to what the caller may run
+export function toolFunctionSpecs(permissions: Permission[] = ["read", "write"]): ToolSpec[] {
+  return tools
+    .filter(t => t.name !== "help" && hasPermission(t.permission, permissions))
+    .map(t => ({
+      name: t.name,
+      description: t.description,
+      parameters: z.toJSONSchema(t.schema, { io: "input", unrepresentable: "any" }) as Record<string, unknown>,
+    }));
+}
+
+// Model-supplied arguments are JSON values; tools validate string params with their own schema
+export function toToolArgs(args: Record<string, unknown>): Record<string, string> {
+  const out: Record<string, string> = {};
+  for (const [k, v] of Object.entries(args)) {
+    if (v === null || v === undefined) continue;
+    out[k] = typeof v === "object" ? JSON.stringify(v) : String(v);
+  }
+  return out;
+}
+
 // Per-tool rate limiters
 const toolLimiters = new Map<string, ReturnType<typeof rateLimit>>();
 
@@ -112,6 +136,8 @@ export async function executeTool(
       tool: name,
       userId: ctx.userId,
       ip: ctx.ip,
+      conversationId: ctx.conversationId,
+      step: ctx.step,
       params: args,
       success: false,
       error: "Permission denied",
@@ -174,6 +200,8 @@ export async function executeTool(
       tool: name,
       userId: ctx.userId,
       ip: ctx.ip,
+      conversationId: ctx.conversationId,
+      step: ctx.step,
       params: args,
       success: result.success,
       error: result.error,
@@ -195,6 +223,8 @@ export async function executeTool(
       tool: name,
       userId: ctx.userId,
       ip: ctx.ip,
+      conversationId: ctx.conversationId,
+      step: ctx.step,
       params: args,
       success: false,
       error: errorMsg,
@@ -1,5 +1,5 @@
 import { z } from "zod";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage, type RepoConfigInput } from "@/lib/storage";
 import { Tool, ToolResult, repoSchema, depthSchema, toolError } from "./types";
 
 export const repoTools: Tool[] = [
@@ -12,14 +12,13 @@ export const repoTools: Tool[] = [
     params: [],
     schema: z.object({}),
     execute: async (_, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
-      const { data, error } = await supabase
-        .from("repo_configs")
-        .select("*")
-        .order("full_name");
-      
-      if (error) return toolError("INTERNAL_ERROR", error.message);
-      if (!data?.length) return { success: true, data: "No repositories configured." };
+      let data;
+      try {
+        data = await getStorage().configs.list();
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", err instanceof Error ? err.message : String(err));
+      }
+      if (!data.length) return { success: true, data: "No repositories configured." };
       
       const enabled = data.filter(r => r.enabled).length;
       const output = `**${data.length} Repositories** (${enabled} enabled)\n\n` + data.map(r => {
@@ -51,30 +50,27 @@ export const repoTools: Tool[] = [
       auto: z.string().optional(),
     }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
-      
-      // Check if already exists (check both column names)
-      const { data: existing } = await supabase
-        .from("repo_configs")
-        .select("id")
-        .or(`full_name.eq.${params.repo},repo_full_name.eq.${params.repo}`)
-        .single();
-      
-      if (existing) {
-        return toolError("CONFLICT", `Repository ${params.repo} already configured`);
+      const { configs } = getStorage();
+      
+      try {
+        // Check if already exists (check both column names)
+        const matches = await configs.search(params.repo);
+        if (matches.some(r => r.full_name === params.repo || r.repo_full_name === params.repo)) {
+          return toolError("CONFLICT", `Repository ${params.repo} already configured`);
+        }
+        
+        await configs.create({
+          full_name: params.repo,
+          repo_full_name: params.repo,
+          enabled: true,
+          review_depth: params.depth || "standard",
+          auto_review: params.auto === "true",
+          created_by: ctx.userId,
+        });
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", err instanceof Error ? err.message : String(err));
       }
       
-      const { error } = await supabase.from("repo_configs").insert({
-        full_name: params.repo,
-        repo_full_name: params.repo,
-        enabled: true,
-        review_depth: params.depth || "standard",
-        auto_review: params.auto === "true",
-        created_by: ctx.userId,
-      });
-      
-      if (error) return toolError("INTERNAL_ERROR", error.message);
-      
       return {
         success: true,
         data: `✓ Repository added\n• Name: ${params.repo}\n• Depth: ${params.depth || 'standard'}\n• Auto-review: ${params.auto === 'true' ? 'enabled' : 'disabled'}`,
@@ -100,29 +96,32 @@ export const repoTools: Tool[] = [
       auto: z.string().optional(),
     }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
-      const updates: Record<string, unknown> = { updated_at: new Date().toISOString() };
+      const { configs } = getStorage();
+      const updates: RepoConfigInput = {};
       
-      if (params.enabled !== undefined) updates.enabled = params.enabled === "true";
+      if (params.enabled !== undefined) {
+        updates.enabled = params.enabled === "true";
+        updates.disabled_reason = null; // the team's choice now, not an automatic disable
+      }
       if (params.depth) updates.review_depth = params.depth;
       if (params.auto !== undefined) updates.auto_review = params.auto === "true";
       
-      if (Object.keys(updates).length === 1) {
+      if (Object.keys(updates).length === 0) {
         return toolError("VALIDATION_ERROR", "No updates specified. Use: enabled, depth, or auto");
       }
       
-      // Try both column names for matching
-      const { data, error } = await supabase
-        .from("repo_configs")
-        .update(updates)
-        .or(`full_name.ilike.%${params.repo}%,repo_full_name.ilike.%${params.repo}%`)
-        .select("full_name, repo_full_name");
-      
-      if (error) return toolError("INTERNAL_ERROR", error.message);
-      if (!data?.length) return toolError("NOT_FOUND", "Repository not found");
+      // Matches either column name
+      let data;
+      try {
+        data = await configs.search(params.repo);
+        await configs.update({ full_names: data.map(r => r.full_name) }, updates);
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", err instanceof Error ? err.message : String(err));
+      }
+      if (!data.length) return toolError("NOT_FOUND", "Repository not found");
       
       const repoName = data[0]?.repo_full_name || data[0]?.full_name || params.repo;
-      const changes = Object.entries(updates).filter(([k]) => k !== "updated_at");
+      const changes = Object.entries(updates);
       
       return {
         success: true,
@@ -145,16 +144,16 @@ export const repoTools: Tool[] = [
       confirm: z.literal("yes"),
     }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
-      
-      const { data, error } = await supabase
-        .from("repo_configs")
-        .delete()
-        .or(`full_name.ilike.%${params.repo}%,repo_full_name.ilike.%${params.repo}%`)
-        .select("full_name, repo_full_name");
-      
-      if (error) return toolError("INTERNAL_ERROR", error.message);
-      if (!data?.length) return toolError("NOT_FOUND", "Repository not found");
+      const { configs } = getStorage();
+      
+      let data;
+      try {
+        data = await configs.search(params.repo);
+        await configs.remove({ full_names: data.map(r => r.full_name) });
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", err instanceof Error ? err.message : String(err));
+      }
+      if (!data.length) return toolError("NOT_FOUND", "Repository not found");
       
       return {
         success: true,
@@ -1,6 +1,19 @@
 import { z } from "zod";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage, type ReviewHistoryEntry } from "@/lib/storage";
+import { enqueueReview } from "@/lib/queue";
+import type { CodeReviewResult } from "@/lib/review/engine";
 import { Tool, ToolResult, limitSchema, statusSchema, depthSchema, repoSchema, prSchema, toolError } from "./types";
+import { reviewScore } from "./utils";
+
+const message = (err: unknown) => (err instanceof Error ? err.message : String(err));
+
+// Tools take short ids from the list output, so resolve a prefix to the full entry
+async function findReview(id: string): Promise<ReviewHistoryEntry | null> {
+  const { history } = getStorage();
+  if (id.length === 36) return history.get(id);
+  const matches = (await history.list({ limit: 500 })).filter((r) => r.id.startsWith(id));
+  return matches.length === 1 ? matches[0]! : null;
+}
 
 export const reviewTools: Tool[] = [
   {
@@ -20,24 +33,18 @@ export const reviewTools: Tool[] = [
       status: statusSchema.optional(),
     }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
       const limit = Math.min(parseInt(params.limit || "10"), 100);
       
-      let query = supabase
-        .from("reviews")
-        .select("id, repo_full_name, pr_number, summary, score, status, created_at")
-        .order("created_at", { ascending: false })
-        .limit(limit);
-      
-      if (params.repo) query = query.ilike("repo_full_name", `%${params.repo}%`);
-      if (params.status) query = query.eq("status", params.status);
-      
-      const { data, error } = await query;
-      if (error) return toolError("INTERNAL_ERROR", error.message);
-      if (!data?.length) return { success: true, data: "No reviews found." };
+      let data;
+      try {
+        data = await getStorage().history.list({ search: params.repo, status: params.status, limit });
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", message(err));
+      }
+      if (!data.length) return { success: true, data: "No reviews found." };
       
       const output = `Found ${data.length} reviews:\n` + data.map(r => 
-        `• [${r.id.slice(0,8)}] ${r.repo_full_name} PR#${r.pr_number}: ${r.score ?? '-'}/100 (${r.status})`
+        `• [${r.id.slice(0,8)}] ${r.repo_full_name} PR#${r.pr_number}: ${reviewScore(r.result)}/100 (${r.status})`
       ).join("\n");
       
       return { success: true, data: output, metadata: { duration: Date.now() - ctx.startTime, recordsAffected: data.length } };
@@ -54,26 +61,24 @@ export const reviewTools: Tool[] = [
     ],
     schema: z.object({ id: z.string().min(4, "Review ID required (min 4 chars)") }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
-      const { data, error } = await supabase
-        .from("reviews")
-        .select("*")
-        .ilike("id", `${params.id}%`)
-        .limit(1)
-        .single();
-      
-      if (error?.code === "PGRST116") return toolError("NOT_FOUND", "Review not found");
-      if (error) return toolError("INTERNAL_ERROR", error.message);
+      let data;
+      try {
+        data = await findReview(params.id!);
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", message(err));
+      }
+      if (!data) return toolError("NOT_FOUND", "Review not found");
       
-      const comments = (data.comments as Array<{body: string; severity: string; category: string}>) || [];
+      const result = data.result as Partial<CodeReviewResult> | null;
+      const comments = result?.line_comments || [];
       const output = `**Review ${data.id.slice(0,8)}**
 • Repository: ${data.repo_full_name}
 • PR: #${data.pr_number}
-• Score: ${data.score ?? 'N/A'}/100
+• Score: ${reviewScore(result)}/100
 • Status: ${data.status}
 • Created: ${new Date(data.created_at).toLocaleString()}
 
-**Summary:** ${data.summary || 'No summary'}
+**Summary:** ${result?.summary?.overview || 'No summary'}
 
 **Issues Found:** ${comments.length}
 ${comments.slice(0, 5).map(c => `  [${c.severity}] ${c.body?.slice(0, 100)}`).join("\n")}${comments.length > 5 ? `\n  ... and ${comments.length - 5} more` : ''}`;
@@ -98,39 +103,19 @@ ${comments.slice(0, 5).map(c => `  [${c.severity}] ${c.body?.slice(0, 100)}`).jo
       depth: depthSchema.optional(),
     }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
       const prNum = parseInt(params.pr || "0");
       
       if (!prNum) return toolError("VALIDATION_ERROR", "Invalid PR number");
       
-      // Check for existing pending/processing job
-      const { data: existing } = await supabase
-        .from("review_queue")
-        .select("id, status")
-        .eq("repo_full_name", params.repo)
-        .eq("pr_number", prNum)
-        .in("status", ["pending", "processing"])
-        .single();
-      
-      if (existing) {
-        return toolError("CONFLICT", `Review already ${existing.status} (job: ${existing.id.slice(0,8)})`);
+      const [owner, repo] = params.repo!.split("/") as [string, string];
+      let data;
+      try {
+        data = await enqueueReview(owner, repo, prNum, { depth: params.depth || "standard", requested_by: ctx.userId });
+      } catch (err) {
+        if (message(err) === "Review already queued") return toolError("CONFLICT", "Review already queued for this PR");
+        return toolError("INTERNAL_ERROR", message(err));
       }
       
-      const { data, error } = await supabase
-        .from("review_queue")
-        .insert({
-          repo_full_name: params.repo,
-          pr_number: prNum,
-          status: "pending",
-          depth: params.depth || "standard",
-          attempts: 0,
-          requested_by: ctx.userId,
-        })
-        .select("id")
-        .single();
-      
-      if (error) return toolError("INTERNAL_ERROR", error.message);
-      
       return {
         success: true,
         data: `✓ Review queued successfully\n• Repository: ${params.repo}\n• PR: #${prNum}\n• Depth: ${params.depth || 'standard'}\n• Job ID: ${data.id.slice(0,8)}`,
@@ -1,5 +1,7 @@
 import { z } from "zod";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage, type SecurityScan } from "@/lib/storage";
+import { findScan, uploadScanToGitHub } from "@/lib/sarif";
+import type { ScanFinding } from "@/lib/review/engine";
 import { Tool, ToolResult, limitSchema, gradeSchema, repoSchema, toolError } from "./types";
 
 export const securityTools: Tool[] = [
@@ -20,21 +22,15 @@ export const securityTools: Tool[] = [
       grade: gradeSchema.optional(),
     }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
       const limit = Math.min(parseInt(params.limit || "10"), 100);
       
-      let query = supabase
-        .from("security_scans")
-        .select("id, repo_full_name, score, security_score, grade, critical_count, high_count, medium_count, created_at")
-        .order("created_at", { ascending: false })
-        .limit(limit);
-      
-      if (params.repo) query = query.ilike("repo_full_name", `%${params.repo}%`);
-      if (params.grade) query = query.eq("grade", params.grade.toUpperCase());
-      
-      const { data, error } = await query;
-      if (error) return toolError("INTERNAL_ERROR", error.message);
-      if (!data?.length) return { success: true, data: "No scans found." };
+      let data: SecurityScan[];
+      try {
+        ({ scans: data } = await getStorage().scans.list({ search: params.repo, grade: params.grade?.toUpperCase(), limit }));
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", err instanceof Error ? err.message : String(err));
+      }
+      if (!data.length) return { success: true, data: "No scans found." };
       
       const output = `Found ${data.length} scans:\n` + data.map(s => {
         const score = s.score ?? s.security_score ?? 0;
@@ -56,33 +52,61 @@ export const securityTools: Tool[] = [
     ],
     schema: z.object({ repo: z.string().min(1, "Repository name required") }),
     execute: async (params, ctx): Promise<ToolResult> => {
-      const supabase = await createClient();
-      const { data, error } = await supabase
-        .from("security_scans")
-        .select("*")
-        .ilike("repo_full_name", `%${params.repo}%`)
-        .order("created_at", { ascending: false })
-        .limit(1)
-        .single();
-      
-      if (error?.code === "PGRST116") return toolError("NOT_FOUND", "No scan found for this repository");
-      if (error) return toolError("INTERNAL_ERROR", error.message);
+      let data: SecurityScan | undefined;
+      try {
+        [data] = (await getStorage().scans.list({ search: params.repo, limit: 1 })).scans;
+      } catch (err) {
+        return toolError("INTERNAL_ERROR", err instanceof Error ? err.message : String(err));
+      }
+      if (!data) return toolError("NOT_FOUND", "No scan found for this repository");
       
       const score = data.score ?? data.security_score ?? 0;
       const grade = data.grade || (score >= 90 ? 'A' : score >= 80 ? 'B' : score >= 70 ? 'C' : score >= 60 ? 'D' : 'F');
-      const findings = (data.findings || data.issues || []) as Array<{title: string; severity: string; description: string}>;
+      const findings = (data.findings || data.issues || []) as Array<ScanFinding & { description?: string }>;
+      const tools = (data.scan_metadata?.tools as string[] | undefined)?.join(", ");
+      const coverage = data.scan_metadata?.coverage as { total_files: number; scanned_files: number; percent: number } | undefined;
       
       const output = `**Security Scan: ${data.repo_full_name}**
 • Grade: ${grade} (${score}/100)
 • Critical: ${data.critical_count || 0} | High: ${data.high_count || 0} | Medium: ${data.medium_count || 0}
-• Scanned: ${new Date(data.created_at).toLocaleString()}
+• Scanned: ${new Date(data.created_at).toLocaleString()}${tools ? `\n• Imported from: ${tools}` : ""}${coverage ? `\n• Coverage: ${coverage.scanned_files} of ${coverage.total_files} files (${coverage.percent}%)` : ""}
 
 **Top Findings:**
-${findings.slice(0, 8).map(f => `  [${(f.severity || 'medium').toUpperCase()}] ${f.title || f.description?.slice(0, 60) || 'Issue'}`).join("\n") || "  No findings"}`;
+${findings.slice(0, 8).map(f => {
+  const location = f.file ? ` (${f.file}${f.line ? `:${f.line}` : ""})` : "";
+  const source = f.tool ? ` [${f.tool}]` : "";
+  return `  [${(f.severity || 'medium').toUpperCase()}] ${f.title || f.description?.slice(0, 60) || 'Issue'}${location}${source}`;
+}).join("\n") || "  No findings"}`;
       
       return { success: true, data: output, metadata: { duration: Date.now() - ctx.startTime } };
     }
   },
+  {
+    name: "upload-scan",
+    description: "Upload the latest security scan of a repository to GitHub code scanning as SARIF",
+    category: "security",
+    permission: "write",
+    rateLimit: { max: 5, windowMs: 60000 },
+    params: [
+      { name: "repo", required: true, description: "Repository (owner/repo)", type: "string" },
+      { name: "branch", required: false, description: "Branch to report against (default: the scanned branch)", type: "string" },
+    ],
+    schema: z.object({ repo: repoSchema, branch: z.string().regex(/^[\w./-]+$/, "Invalid branch").optional() }),
+    execute: async (params, ctx): Promise<ToolResult> => {
+      try {
+        const scan = await findScan({ repo: params.repo });
+        if (!scan) return toolError("NOT_FOUND", "No scan found for this repository");
+        const result = await uploadScanToGitHub(scan, params.branch, ctx.userId);
+        return {
+          success: true,
+          data: `✓ Scan uploaded to GitHub code scanning\n• Repository: ${scan.repo_full_name}\n• Ref: ${result.ref} @ ${result.commit_sha.slice(0, 7)}\n• Upload: ${result.id}`,
+          metadata: { duration: Date.now() - ctx.startTime },
+        };
+      } catch (err) {
+        return toolError("EXTERNAL_ERROR", err instanceof Error ? err.message : String(err));
+      }
+    }
+  },
   {
     name: "trigger-scan",
     description: "Trigger a security scan for a repository",
@@ -125,7 +149,7 @@ ${findings.slice(0, 8).map(f => `  [${(f.severity || 'medium').toUpperCase()}] $
         
         return {
           success: true,
-          data: `✓ Security scan triggered\n• Repository: ${repo}\n• Status: Processing\n• ETA: 1-2 minutes`,
+          data: `✓ Security scan triggered\n• Repository: ${repo}\n• Status: Processing\n• ETA: a few minutes; large repositories are scanned in batches that resume every 10 minutes`,
           metadata: { duration: Date.now() - ctx.startTime }
         };
       } catch (e) {
@@ -18,6 +18,8 @@ async function testTools() {
     { name: "remove-repo", args: {}, expectError: true },
     { name: "remove-repo", args: { repo: "test" }, expectError: true }, // missing confirm
     { name: "clear-queue", args: {}, expectError: true },
+    { name: "dlq-inspect", args: {}, expectError: true },
+    { name: "dlq-purge", args: { repo: "owner/repo" }, expectError: true }, // missing confirm
     
     // Should pass - valid params or no required params
     { name: "help", args: {}, expectError: false },
@@ -28,6 +30,7 @@ async function testTools() {
     { name: "scans", args: {}, expectError: false },
     { name: "trends", args: {}, expectError: false },
     { name: "retry", args: {}, expectError: false },
+    { name: "dlq", args: {}, expectError: false },
   ];
   
   let passed = 0;
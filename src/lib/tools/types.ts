@@ -30,6 +30,8 @@ export interface ToolContext {
   startTime: number;
   permissions: Permission[];
   ip?: string;
+  conversationId?: string; // chat conversation that triggered the call
+  step?: number; // tool-calling step within the conversation turn
 }
 
 export interface ToolResult {
@@ -73,6 +75,8 @@ export interface AuditEntry {
   success: boolean;
   error?: string;
   duration: number;
+  conversationId?: string;
+  step?: number;
 }
 
 // Validation schemas - Zod v4 syntax
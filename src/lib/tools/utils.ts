@@ -1,6 +1,7 @@
 import { AuditEntry } from "./types";
 import { logger } from "@/lib/logger";
-import { createClient } from "@/lib/supabase/server";
+import { getStorage } from "@/lib/storage";
+import { openFindings, scoreFindings } from "@/lib/review/engine";
 
 // In-memory cache with TTL
 const cache = new Map<string, { data: string; expires: number }>();
@@ -53,10 +54,11 @@ async function flushAuditBuffer(): Promise<void> {
   const entries = auditBuffer.splice(0, auditBuffer.length);
   
   try {
-    const supabase = await createClient();
-    await supabase.from("tool_audit_logs").insert(
+    await getStorage().toolAudit.insert(
       entries.map(e => ({
         correlation_id: e.correlationId,
+        conversation_id: e.conversationId,
+        step: e.step,
         tool_name: e.tool,
         user_id: e.userId,
         ip_address: e.ip,
@@ -86,6 +88,11 @@ function sanitizeParams(params: Record<string, string>): Record<string, string>
   return sanitized;
 }
 
+// Reviews store no score of their own, so grade their open findings the way scans are graded
+export function reviewScore(result: unknown): number {
+  return scoreFindings(openFindings(result)).score;
+}
+
 // Permission check
 export function hasPermission(required: string, granted: string[]): boolean {
   const hierarchy = { read: 1, write: 2, admin: 3 };
@@ -102,9 +109,8 @@ export async function toolsHealthCheck(): Promise<{ healthy: boolean; details: R
   };
   
   try {
-    const supabase = await createClient();
-    const { error } = await supabase.from("repo_configs").select("id").limit(1);
-    checks.database = error ? "error" : "ok";
+    await getStorage().ping();
+    checks.database = "ok";
   } catch {
     checks.database = "error";
   }
@@ -0,0 +1,232 @@
+import { attachCheckRun, claimJob, completeJob, failJob, keepLease, supersedeJob, WORKER_ID, type ReviewJob } from "./queue";
+import { reviewAndPost } from "./review";
+import { notifyReviewFailed, notifyReviewCompleted } from "./notify";
+import { getStorage, withServiceRole } from "./storage";
+import { ReviewCategory, StaleReviewError } from "./review/engine";
+import { checkRepoBudget, usageColumns } from "./budget";
+import { completeReviewCheck, concludeCheck, startReviewCheck } from "./checks";
+
+/**
+ * Review job processing, shared by the /api/worker batch route and the
+ * embedded worker that polls the queue inside the Node server.
+ */
+
+export interface JobOutcome {
+  key: string;
+  status: "processed" | "skipped" | "failed";
+  error?: string;
+}
+
+// Jobs aren't tied to any user's session, so they run with the service role
+export async function processJob(job: ReviewJob, workerId = WORKER_ID): Promise<JobOutcome> {
+  return withServiceRole(() => runJob(job, workerId));
+}
+
+async function runJob(job: ReviewJob, workerId: string): Promise<JobOutcome> {
+  const key = `${job.repo_full_name}#${job.pr_number}`;
+  // Once the lease is gone the job belongs to whoever re-claims it: stop
+  // before posting and leave the job and its check run to them
+  const lease = new AbortController();
+  const stopHeartbeat = keepLease(job.id, () => {
+    console.warn(`Lease lost for ${key}, stopping before another worker retries it`);
+    lease.abort();
+  });
+  const checkRunId = await startReviewCheck(job.owner, job.repo, job.pr_number, job.check_run_id);
+  if (checkRunId && !job.check_run_id) await attachCheckRun(job.id, checkRunId);
+  try {
+    const storage = getStorage();
+    const config = await storage.configs.get(job.repo_full_name).catch(() => null);
+
+    const budget = await checkRepoBudget(job.repo_full_name);
+    if (budget.action === "skip") {
+      await concludeCheck(job.owner, job.repo, checkRunId, "skipped", "Review skipped", `Monthly LLM budget exceeded: ${budget.reason}`);
+      await completeJob(job.id, workerId);
+      return { key, status: "skipped" };
+    }
+
+    const categories = (config?.categories || ["security", "bug", "performance"]).map((c: string) => c as ReviewCategory);
+    const analysis = job.analysis as { depth?: string; focus_areas?: string[] } | undefined;
+    const options = {
+      headSha: job.head_sha,
+      depth: analysis?.depth as "quick" | "standard" | "deep" | undefined,
+      focus_areas: analysis?.focus_areas,
+      signal: lease.signal,
+    };
+    if (budget.action === "downgrade") options.depth = "quick";
+
+    const result = await reviewAndPost(job.owner, job.repo, job.pr_number, categories, options);
+
+    await storage.history.insert({
+      repo_full_name: job.repo_full_name,
+      pr_number: job.pr_number,
+      status: "completed",
+      result: { ...result.review, _analysis: job.analysis, ...(budget.action === "downgrade" && { _budget: budget.reason }) },
+      head_sha: result.headSha,
+      is_incremental: result.isIncremental,
+      ...usageColumns(result.review.usage),
+    });
+
+    await completeReviewCheck(job.owner, job.repo, checkRunId, result.review);
+    await completeJob(job.id, workerId);
+    await notifyReviewCompleted(job.repo_full_name, job.pr_number, result.review.line_comments?.length || 0);
+    return { key, status: "processed" };
+  } catch (err) {
+    if (lease.signal.aborted) return { key, status: "skipped" };
+    if (err instanceof StaleReviewError) {
+      // A newer push is queued (or will be polled); this review would describe old code
+      await supersedeJob(job.id, err.message, workerId);
+      await concludeCheck(job.owner, job.repo, checkRunId, "skipped", "Review superseded", `${err.message}; the new head will be reviewed instead.`);
+      return { key, status: "skipped" };
+    }
+    const error = err instanceof Error ? err.message : "Unknown error";
+    const permanentlyFailed = await failJob(job.id, error, job.attempts + 1, job.max_attempts, workerId);
+    if (permanentlyFailed) {
+      await concludeCheck(job.owner, job.repo, checkRunId, "neutral", "Review failed", error);
+    }
+    await notifyReviewFailed(job.repo_full_name, job.pr_number, error, job.attempts + 1, !permanentlyFailed);
+    return { key, status: "failed", error };
+  } finally {
+    stopHeartbeat();
+  }
+}
+
+export interface WorkerOptions {
+  workerId?: string;
+  concurrency?: number;
+  pollIntervalMs?: number;
+  // Injected in tests; default to the queue and processJob
+  claim?: (workerId: string) => Promise<ReviewJob | null>;
+  process?: (job: ReviewJob, workerId: string) => Promise<JobOutcome>;
+}
+
+export interface WorkerStatus {
+  workerId: string;
+  concurrency: number;
+  running: boolean;
+  active: string[];
+  processed: number;
+  skipped: number;
+  failed: number;
+  startedAt: string;
+}
+
+export interface EmbeddedWorker {
+  status(): WorkerStatus;
+  // Skip the rest of the poll interval, e.g. right after a job is enqueued
+  wake(): void;
+  // Stop claiming and wait for in-flight reviews to finish
+  stop(): Promise<void>;
+}
+
+export const WORKER_CONCURRENCY = parseInt(process.env.WORKER_CONCURRENCY || "2", 10);
+export const WORKER_POLL_INTERVAL_MS = parseInt(process.env.WORKER_POLL_INTERVAL_MS || "5000", 10);
+
+/**
+ * Poll the queue with `concurrency` slots. Each slot claims one job at a time,
+ * so the per-repo cap and priorities are still decided by claim_review_job.
+ */
+export function startWorker(options: WorkerOptions = {}): EmbeddedWorker {
+  const workerId = options.workerId || WORKER_ID;
+  const concurrency = Math.max(1, options.concurrency ?? WORKER_CONCURRENCY);
+  const pollIntervalMs = options.pollIntervalMs ?? WORKER_POLL_INTERVAL_MS;
+  const claim = options.claim || claimJob;
+  const run = options.process || processJob;
+
+  const counts = { processed: 0, skipped: 0, failed: 0 };
+  const active = new Map<string, string>();
+  const startedAt = new Date().toISOString();
+  let stopping = false;
+  let waiters: (() => void)[] = [];
+
+  const wake = () => {
+    const pending = waiters;
+    waiters = [];
+    pending.forEach((resolve) => resolve());
+  };
+
+  const idle = () => new Promise<void>((resolve) => {
+    const timer = setTimeout(done, pollIntervalMs);
+    function done() {
+      clearTimeout(timer);
+      waiters = waiters.filter((w) => w !== done);
+      resolve();
+    }
+    waiters.push(done);
+  });
+
+  async function slot() {
+    while (!stopping) {
+      let job: ReviewJob | null = null;
+      try {
+        job = await claim(workerId);
+      } catch (err) {
+        console.error(`Worker ${workerId} failed to claim a job:`, err);
+      }
+      if (!job) {
+        if (!stopping) await idle();
+        continue;
+      }
+
+      active.set(job.id, `${job.repo_full_name}#${job.pr_number}`);
+      try {
+        const outcome = await run(job, workerId);
+        counts[outcome.status]++;
+        if (outcome.error) console.error(`Review failed for ${outcome.key}: ${outcome.error}`);
+      } catch (err) {
+        counts.failed++;
+        console.error(`Worker ${workerId} crashed on job ${job.id}:`, err);
+      } finally {
+        active.delete(job.id);
+      }
+    }
+  }
+
+  const slots = Array.from({ length: concurrency }, () => withServiceRole(slot));
+
+  return {
+    status: () => ({ workerId, concurrency, running: !stopping, active: [...active.values()], ...counts, startedAt }),
+    wake,
+    stop: async () => {
+      stopping = true;
+      wake();
+      await Promise.all(slots);
+    },
+  };
+}
+
+// Route handlers and instrumentation are bundled separately, so the running
+// worker lives on globalThis for both to find
+const globalWorker = globalThis as typeof globalThis & { __reviewWorker?: EmbeddedWorker };
+
+export function getEmbeddedWorker(): EmbeddedWorker | undefined {
+  return globalWorker.__reviewWorker;
+}
+
+/**
+ * Run the worker inside this server until SIGTERM/SIGINT. In-flight reviews
+ * get WORKER_SHUTDOWN_TIMEOUT_MS to finish; anything still running after that
+ * keeps its lease until expiry and is then re-queued for another worker.
+ */
+export function startEmbeddedWorker(): EmbeddedWorker {
+  if (globalWorker.__reviewWorker) return globalWorker.__reviewWorker;
+
+  const worker = startWorker();
+  globalWorker.__reviewWorker = worker;
+  const { workerId, concurrency } = worker.status();
+  console.log(`Embedded review worker ${workerId} started with ${concurrency} slots`);
+
+  const timeoutMs = parseInt(process.env.WORKER_SHUTDOWN_TIMEOUT_MS || "25000", 10);
+  let shuttingDown = false;
+  const shutdown = async (signal: string) => {
+    if (shuttingDown) return;
+    shuttingDown = true;
+    const { active } = worker.status();
+    console.log(`${signal} received, waiting for ${active.length} running reviews`);
+    await Promise.race([worker.stop(), new Promise((resolve) => setTimeout(resolve, timeoutMs))]);
+    process.exit(0);
+  };
+  process.once("SIGTERM", () => shutdown("SIGTERM"));
+  process.once("SIGINT", () => shutdown("SIGINT"));
+
+  return worker;
+}
@@ -0,0 +1,123 @@
+// Check runs for the edge functions; mirrors src/lib/checks.ts
+import { githubAuthHeaders, isGitHubAppConfigured } from "./github.ts";
+
+export const REVIEW_CHECK_NAME = "AI Code Review";
+export const SCAN_CHECK_NAME = "AI Security Scan";
+const ANNOTATIONS_PER_REQUEST = 50;
+
+export type CheckConclusion = "success" | "failure" | "neutral" | "skipped";
+
+export interface Annotation {
+  path: string;
+  start_line: number;
+  end_line: number;
+  annotation_level: "notice" | "warning" | "failure";
+  title?: string;
+  message: string;
+  raw_details?: string;
+}
+
+export interface CheckFinding {
+  path?: string | null;
+  line?: number | null;
+  start_line?: number | null;
+  severity?: string | null;
+  title?: string;
+  body: string;
+  suggestion?: string | null;
+}
+
+// Writing check runs needs a GitHub App; a PAT gets 403
+export const checksEnabled = () => isGitHubAppConfigured() && Deno.env.get("GITHUB_CHECKS") !== "false";
+
+export function conclusionFor(findings: CheckFinding[]): CheckConclusion {
+  const severities = findings.map((f) => (f.severity || "").toLowerCase());
+  if (severities.includes("critical")) return "failure";
+  if (severities.includes("high")) return "neutral";
+  return "success";
+}
+
+export function toAnnotations(findings: CheckFinding[]): Annotation[] {
+  return findings
+    .filter((f) => f.path && f.line && f.line > 0)
+    .map((f) => {
+      const severity = (f.severity || "info").toLowerCase();
+      return {
+        path: f.path!,
+        start_line: f.start_line && f.start_line < f.line! ? f.start_line : f.line!,
+        end_line: f.line!,
+        annotation_level: severity === "critical" || severity === "high" ? "failure" : severity === "medium" ? "warning" : "notice",
+        title: f.title ? `[${severity.toUpperCase()}] ${f.title}` : `[${severity.toUpperCase()}]`,
+        message: f.body.slice(0, 60000),
+        ...(f.suggestion && { raw_details: f.suggestion.slice(0, 60000) }),
+      };
+    });
+}
+
+async function checkRequest(owner: string, repo: string, path: string, method: string, body: Record<string, unknown>) {
+  const endpoint = `/repos/${owner}/${repo}/check-runs${path}`;
+  const res = await fetch(`https://api.github.com${endpoint}`, {
+    method,
+    headers: { ...(await githubAuthHeaders(endpoint)), Accept: "application/vnd.github+json", "User-Agent": "foodshare-ai-bot" },
+    body: JSON.stringify(body),
+  });
+  if (!res.ok) throw new Error(`GitHub check run ${res.status}: ${await res.text().catch(() => res.statusText)}`);
+  return res.json() as Promise<{ id: number }>;
+}
+
+/** Create a check run; returns null when checks are off or GitHub refuses. */
+export async function createCheck(
+  owner: string,
+  repo: string,
+  headSha: string,
+  name = REVIEW_CHECK_NAME,
+  status: "queued" | "in_progress" = "queued",
+): Promise<number | null> {
+  if (!checksEnabled()) return null;
+  try {
+    const { id } = await checkRequest(owner, repo, "", "POST", {
+      name,
+      head_sha: headSha,
+      status,
+      output: { title: status === "queued" ? "Queued" : "In progress", summary: "Results will appear here when the run finishes." },
+    });
+    return id;
+  } catch (err) {
+    console.error(`Failed to create check run for ${owner}/${repo}: ${err}`);
+    return null;
+  }
+}
+
+export async function startCheck(owner: string, repo: string, checkRunId: number): Promise<void> {
+  await checkRequest(owner, repo, `/${checkRunId}`, "PATCH", {
+    status: "in_progress",
+    output: { title: "In progress", summary: "Results will appear here when the run finishes." },
+  }).catch((err) => console.error(`Failed to update check run ${checkRunId}: ${err}`));
+}
+
+/** Finish a check, appending annotations 50 per request; the last request sets the conclusion. */
+export async function completeCheck(
+  owner: string,
+  repo: string,
+  checkRunId: number | null | undefined,
+  conclusion: CheckConclusion,
+  output: { title: string; summary: string },
+  annotations: Annotation[] = [],
+): Promise<void> {
+  if (!checkRunId) return;
+  const batches: Annotation[][] = [];
+  for (let i = 0; i < annotations.length; i += ANNOTATIONS_PER_REQUEST) batches.push(annotations.slice(i, i + ANNOTATIONS_PER_REQUEST));
+  if (!batches.length) batches.push([]);
+
+  try {
+    for (let i = 0; i < batches.length; i++) {
+      const last = i === batches.length - 1;
+      await checkRequest(owner, repo, `/${checkRunId}`, "PATCH", {
+        ...(last && { status: "completed", conclusion }),
+        output: { ...output, annotations: batches[i] },
+      });
+    }
+  } catch (err) {
+    console.error(`Failed to complete check run ${checkRunId}: ${err}`);
+  }
+}
@@ -0,0 +1,121 @@
+// GitHub auth for the edge functions; mirrors src/lib/github-app.ts.
+// With GITHUB_APP_ID and GITHUB_APP_PRIVATE_KEY set, repo requests use the
+// repo's installation token, otherwise GITHUB_TOKEN.
+import type { SupabaseClient } from "https://esm.sh/@supabase/supabase-js@2";
+
+const GITHUB_API = "https://api.github.com";
+const APP_ID = Deno.env.get("GITHUB_APP_ID") || "";
+const RAW_KEY = Deno.env.get("GITHUB_APP_PRIVATE_KEY") || "";
+const DEFAULT_INSTALLATION = parseInt(Deno.env.get("GITHUB_APP_INSTALLATION_ID") || "", 10) || undefined;
+const GITHUB_TOKEN = Deno.env.get("GITHUB_TOKEN") || "";
+
+const TOKEN_REFRESH_MARGIN_MS = 5 * 60 * 1000;
+const JWT_TTL_SECONDS = 540;
+
+export const isGitHubAppConfigured = () => Boolean(APP_ID && RAW_KEY);
+export const hasGitHubAuth = () => isGitHubAppConfigured() || Boolean(GITHUB_TOKEN);
+
+function derLength(len: number): number[] {
+  if (len < 0x80) return [len];
+  const bytes: number[] = [];
+  for (let n = len; n > 0; n >>= 8) bytes.unshift(n & 0xff);
+  return [0x80 | bytes.length, ...bytes];
+}
+
+// GitHub hands out PKCS#1 keys but WebCrypto only imports PKCS#8, so wrap them
+function pemToPkcs8(pem: string): Uint8Array {
+  const body = pem.replace(/-----[^-]+-----/g, "").replace(/\s+/g, "");
+  const der = Uint8Array.from(atob(body), (c) => c.charCodeAt(0));
+  if (pem.includes("BEGIN PRIVATE KEY")) return der;
+
+  const algorithm = [0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01, 0x05, 0x00];
+  const octets = [0x04, ...derLength(der.length), ...der];
+  const inner = [0x02, 0x01, 0x00, ...algorithm, ...octets];
+  return new Uint8Array([0x30, ...derLength(inner.length), ...inner]);
+}
+
+const base64url = (bytes: Uint8Array) =>
+  btoa(String.fromCharCode(...bytes)).replace(/\+/g, "-").replace(/\//g, "_").replace(/=+$/, "");
+const encodeJson = (value: unknown) => base64url(new TextEncoder().encode(JSON.stringify(value)));
+
+let signingKey: Promise<CryptoKey> | null = null;
+let cachedJwt: { token: string; expiresAt: number } | null = null;
+
+async function appJwt(): Promise<string> {
+  if (cachedJwt && cachedJwt.expiresAt > Date.now() + 60_000) return cachedJwt.token;
+
+  const pem = RAW_KEY.includes("BEGIN") ? RAW_KEY.replace(/\\n/g, "\n") : atob(RAW_KEY);
+  signingKey ??= crypto.subtle.importKey("pkcs8", pemToPkcs8(pem), { name: "RSASSA-PKCS1-v1_5", hash: "SHA-256" }, false, ["sign"]);
+
+  const iat = Math.floor(Date.now() / 1000) - 60;
+  const unsigned = `${encodeJson({ alg: "RS256", typ: "JWT" })}.${encodeJson({ iat, exp: iat + 60 + JWT_TTL_SECONDS, iss: APP_ID })}`;
+  const signature = await crypto.subtle.sign("RSASSA-PKCS1-v1_5", await signingKey, new TextEncoder().encode(unsigned));
+  const token = `${unsigned}.${base64url(new Uint8Array(signature))}`;
+  cachedJwt = { token, expiresAt: Date.now() + JWT_TTL_SECONDS * 1000 };
+  return token;
+}
+
+async function appRequest<T>(endpoint: string, init?: RequestInit): Promise<T> {
+  const res = await fetch(`${GITHUB_API}${endpoint}`, {
+    ...init,
+    headers: { Authorization: `Bearer ${await appJwt()}`, Accept: "application/vnd.github+json", "User-Agent": "foodshare-ai-bot" },
+  });
+  if (!res.ok) throw new Error(`GitHub App API ${res.status} ${endpoint}`);
+  return res.json();
+}
+
+const tokenCache = new Map<number, { token: string; expiresAt: number }>();
+const installationCache = new Map<string, number>();
+
+async function installationToken(installationId: number): Promise<string> {
+  const cached = tokenCache.get(installationId);
+  if (cached && cached.expiresAt - TOKEN_REFRESH_MARGIN_MS > Date.now()) return cached.token;
+  const { token, expires_at } = await appRequest<{ token: string; expires_at: string }>(
+    `/app/installations/${installationId}/access_tokens`,
+    { method: "POST" },
+  );
+  tokenCache.set(installationId, { token, expiresAt: new Date(expires_at).getTime() });
+  return token;
+}
+
+async function resolveInstallation(owner: string, repo: string, supabase?: SupabaseClient): Promise<number | undefined> {
+  const fullName = `${owner}/${repo}`;
+  const key = fullName.toLowerCase();
+  const cached = installationCache.get(key);
+  if (cached) return cached;
+
+  let installationId: number | undefined;
+  if (supabase) {
+    const { data } = await supabase.from("repo_configs").select("installation_id").eq("full_name", fullName).single();
+    installationId = data?.installation_id || undefined;
+  }
+  if (!installationId) {
+    installationId = await appRequest<{ id: number }>(`/repos/${owner}/${repo}/installation`)
+      .then((r) => r.id)
+      .catch(() => undefined);
+  }
+  if (installationId) installationCache.set(key, installationId);
+  return installationId;
+}
+
+/** Token for a repo: its installation's when the app covers it, else GITHUB_TOKEN, else the default installation. */
+export async function repoToken(owner: string, repo: string, supabase?: SupabaseClient): Promise<string> {
+  if (isGitHubAppConfigured()) {
+    const installationId = await resolveInstallation(owner, repo, supabase);
+    if (installationId) return installationToken(installationId);
+  }
+  if (GITHUB_TOKEN) return GITHUB_TOKEN;
+  if (isGitHubAppConfigured() && DEFAULT_INSTALLATION) return installationToken(DEFAULT_INSTALLATION);
+  throw new Error(`No GitHub credentials for ${owner}/${repo}`);
+}
+
+/** Auth header for a GitHub API path; repo paths get that repo's token. */
+export async function githubAuthHeaders(endpoint: string, supabase?: SupabaseClient): Promise<Record<string, string>> {
+  const match = endpoint.match(/^\/repos\/([^/]+)\/([^/?]+)/);
+  const token = match
+    ? await repoToken(match[1], match[2], supabase)
+    : isGitHubAppConfigured() && !GITHUB_TOKEN && DEFAULT_INSTALLATION
+      ? await installationToken(DEFAULT_INSTALLATION)
+      : GITHUB_TOKEN;
+  return token ? { Authorization: `Bearer ${token}` } : {};
+}
@@ -1,4 +1,9 @@
 import Groq from "https://esm.sh/groq-sdk@0.37.0";
+import { z } from "https://esm.sh/zod@4.3.5";
+import { buildRepairPrompt, parseStructured, StructuredOutputError, toJsonSchema } from "./structured.ts";
+import type { TokenUsage } from "./usage.ts";
+
+export { StructuredOutputError };
 
 export type LLMProvider = "groq" | "ollama";
 
@@ -11,6 +16,15 @@ export interface ChatOptions {
   useReviewModel?: boolean;
   maxRetries?: number;
   timeout?: number;
+  jsonMode?: boolean;
+  format?: "json" | Record<string, unknown>;
+  // Called with the model and token usage of every completion, including repair attempts
+  onUsage?: (model: string, usage: TokenUsage) => void;
+}
+
+export interface StructuredChatOptions<T> extends ChatOptions {
+  schema: z.ZodType<T>;
+  repairAttempts?: number;
 }
 
 const env = (key: string) => Deno.env.get(key) || "";
@@ -53,7 +67,11 @@ async function chatWithGroq(prompt: string, options?: ChatOptions): Promise<stri
         messages,
         temperature: options?.temperature ?? 0.1,
         max_tokens: options?.maxTokens ?? 4096,
+        ...(options?.jsonMode && { response_format: { type: "json_object" as const } }),
       });
+      if (response.usage) {
+        options?.onUsage?.(model, { promptTokens: response.usage.prompt_tokens ?? 0, completionTokens: response.usage.completion_tokens ?? 0 });
+      }
       return response.choices[0]?.message?.content || "";
     } catch (e) {
       const { isRateLimit, retryAfter } = parseRateLimit(e);
@@ -96,17 +114,22 @@ async function chatWithOllama(prompt: string, options?: ChatOptions): Promise<st
         model,
         messages,
         stream: false,
+        ...(options?.format && { format: options.format }),
         options: { temperature: options?.temperature ?? 0.1, num_ctx: 8192 },
       }),
     });
     if (!res.ok) throw new Error(`Ollama ${res.status}: ${await res.text()}`);
-    return (await res.json()).message?.content || "";
+    const data = await res.json();
+    if (data.prompt_eval_count !== undefined || data.eval_count !== undefined) {
+      options?.onUsage?.(model, { promptTokens: data.prompt_eval_count ?? 0, completionTokens: data.eval_count ?? 0 });
+    }
+    return data.message?.content || "";
   } finally {
     clearTimeout(timeout);
   }
 }
 
-export async function chat(prompt: string, options?: ChatOptions): Promise<string> {
+async function chatText(prompt: string, options?: ChatOptions): Promise<string> {
   const provider = options?.provider || (env("LLM_PROVIDER") as LLMProvider) || "groq";
 
   if (provider === "ollama") {
@@ -124,6 +147,33 @@ export async function chat(prompt: string, options?: ChatOptions): Promise<strin
   return chatWithGroq(prompt, options);
 }
 
+async function chatStructured<T>(prompt: string, options: StructuredChatOptions<T>): Promise<T> {
+  const { schema, repairAttempts = 1, ...rest } = options;
+  const jsonSchema = toJsonSchema(z, schema);
+  // Groq's JSON mode only accepts a top-level object
+  const providerOptions: ChatOptions = { ...rest, jsonMode: jsonSchema.type === "object", format: jsonSchema };
+
+  let raw = await chatText(prompt, providerOptions);
+  let result = parseStructured(raw, schema);
+
+  for (let attempt = 0; !result.success && attempt < repairAttempts; attempt++) {
+    console.log(`Structured output invalid, repair attempt ${attempt + 1}/${repairAttempts}`);
+    raw = await chatText(buildRepairPrompt(prompt, raw, result.error), providerOptions);
+    result = parseStructured(raw, schema);
+  }
+
+  if (!result.success) throw new StructuredOutputError(raw, result.error);
+  return result.data;
+}
+
+// With a Zod schema, returns the validated object instead of text
+export async function chat<T>(prompt: string, options: StructuredChatOptions<T>): Promise<T>;
+export async function chat(prompt: string, options?: ChatOptions): Promise<string>;
+export async function chat<T>(prompt: string, options?: ChatOptions | StructuredChatOptions<T>): Promise<T | string> {
+  if (options && "schema" in options) return chatStructured(prompt, options);
+  return chatText(prompt, options);
+}
+
 export function getLLMStatus() {
   const provider = (env("LLM_PROVIDER") as LLMProvider) || "groq";
   return {
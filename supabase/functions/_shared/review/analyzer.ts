@@ -104,17 +104,17 @@ export function summarizeFiles(files: FileDiff[]): string {
   return lines.length > 0 ? lines.join("\n") : "No files changed";
 }
 
+export function matchesPathPattern(path: string, pattern: string): boolean {
+  if (pattern.includes("*")) {
+    const regex = new RegExp("^" + pattern.replace(/[.+?^${}()|[\]\\]/g, "\\$&").replace(/\*/g, ".*") + "$");
+    return regex.test(path);
+  }
+  return path.startsWith(pattern) || path.includes(pattern);
+}
+
 export function filterIgnoredPaths(files: FileDiff[], ignorePaths: string[]): FileDiff[] {
   if (!ignorePaths.length) return files;
-  return files.filter((f) => {
-    return !ignorePaths.some((pattern) => {
-      if (pattern.includes("*")) {
-        const regex = new RegExp("^" + pattern.replace(/\*/g, ".*") + "$");
-        return regex.test(f.path);
-      }
-      return f.path.startsWith(pattern) || f.path.includes(pattern);
-    });
-  });
+  return files.filter((f) => !ignorePaths.some((pattern) => matchesPathPattern(f.path, pattern)));
 }
 
 // Files to skip entirely (auto-generated, lock files)
@@ -217,3 +217,131 @@ export function truncateDiff(diff: string, maxTokens: number = 1000): string {
 
   return truncated + `\n\n... [truncated - ${skippedCount} more files not shown]`;
 }
+
+export interface DiffBatch {
+  files: FileDiff[];
+  diff: string;
+  tokens: number;
+}
+
+// Rough token estimate (~4 chars per token)
+export function estimateTokens(text: string): number {
+  return Math.ceil(text.length / 4);
+}
+
+function renderFileDiff(path: string, hunks: DiffHunk[]): string {
+  return [`diff --git a/${path} b/${path}`, ...hunks.map((h) => h.content)].join("\n");
+}
+
+/**
+ * Split a hunk into consecutive hunks of at most maxChars each, on line
+ * boundaries, with headers renumbered so line comments still map. A single
+ * line longer than maxChars is the only thing ever cut.
+ */
+export function splitHunk(hunk: DiffHunk, maxChars: number): DiffHunk[] {
+  const [header = "", ...lines] = hunk.content.split("\n");
+  const context = header.replace(HUNK_PATTERN, "");
+  const parts: DiffHunk[] = [];
+  let body: string[] = [];
+  let size = 0;
+  let oldLine = hunk.oldStart;
+  let newLine = hunk.newStart;
+  let oldCount = 0;
+  let newCount = 0;
+
+  const flush = () => {
+    if (!body.length) return;
+    parts.push({
+      path: hunk.path,
+      oldStart: oldLine,
+      oldCount,
+      newStart: newLine,
+      newCount,
+      content: [`@@ -${oldLine},${oldCount} +${newLine},${newCount} @@${context}`, ...body].join("\n"),
+    });
+    oldLine += oldCount;
+    newLine += newCount;
+    body = [];
+    size = 0;
+    oldCount = 0;
+    newCount = 0;
+  };
+
+  // Room for the longest header a part can get
+  const budget = Math.max(1, maxChars - header.length - 24);
+  for (const raw of lines) {
+    const line = raw.length > budget ? raw.slice(0, Math.max(0, budget - 24)) + " ... [line truncated]" : raw;
+    if (size + line.length + 1 > budget) flush();
+    body.push(line);
+    size += line.length + 1;
+    if (line.startsWith("+")) newCount++;
+    else if (line.startsWith("-")) oldCount++;
+    else if (!line.startsWith("\\")) {
+      oldCount++;
+      newCount++;
+    }
+  }
+  flush();
+  return parts;
+}
+
+/**
+ * Split parsed files into batches that each fit in maxTokens. Files are kept
+ * whole where possible; oversized files are split on hunk boundaries, and an
+ * oversized hunk is split on line boundaries.
+ */
+export function chunkDiff(files: FileDiff[], maxTokens: number = 2000): DiffBatch[] {
+  const maxChars = maxTokens * 4;
+  const ordered = files
+    .filter((f) => !shouldSkipFile(f.path) && f.hunks.length > 0)
+    .sort((a, b) => getFilePriority(a.path) - getFilePriority(b.path));
+
+  // Break files into pieces that individually fit
+  const pieces: { file: FileDiff; text: string }[] = [];
+  for (const file of ordered) {
+    const whole = renderFileDiff(file.path, file.hunks);
+    if (whole.length <= maxChars) {
+      pieces.push({ file, text: whole });
+      continue;
+    }
+
+    let group: DiffHunk[] = [];
+    const flush = () => {
+      if (!group.length) return;
+      pieces.push({ file: { ...file, hunks: group }, text: renderFileDiff(file.path, group) });
+      group = [];
+    };
+    for (const hunk of file.hunks) {
+      if (renderFileDiff(file.path, [...group, hunk]).length > maxChars) flush();
+      if (renderFileDiff(file.path, [hunk]).length > maxChars) {
+        for (const part of splitHunk(hunk, maxChars - renderFileDiff(file.path, []).length - 1)) {
+          group = [part];
+          flush();
+        }
+        continue;
+      }
+      group.push(hunk);
+    }
+    flush();
+  }
+
+  // Pack pieces into batches
+  const batches: DiffBatch[] = [];
+  let current: { file: FileDiff; text: string }[] = [];
+  let size = 0;
+  const close = () => {
+    if (!current.length) return;
+    const diff = current.map((p) => p.text).join("\n");
+    batches.push({ files: current.map((p) => p.file), diff, tokens: estimateTokens(diff) });
+    current = [];
+    size = 0;
+  };
+  for (const piece of pieces) {
+    if (size + piece.text.length + 1 > maxChars) close();
+    current.push(piece);
+    size += piece.text.length + 1;
+  }
+  close();
+
+  return batches;
+}
@@ -0,0 +1,395 @@
+// The review pipeline used by both the Next.js app and the edge worker. Each
+// runtime supplies GitHub access, stored repo state and an LLM call through
+// ReviewRuntime; everything that shapes the review itself lives here.
+import { chunkDiff, filterIgnoredPaths, parseDiff, summarizeFiles, type FileDiff } from "./analyzer.ts";
+import { buildBatchSummary, buildIncrementalPrompt, buildReviewPrompt, INCREMENTAL_SYSTEM_PROMPT, SYSTEM_PROMPT } from "./prompts.ts";
+import { mergeReviewResults } from "./merge.ts";
+import { placeComments, type PlacedComment, type PlacementResult } from "./placement.ts";
+import { buildLearningsPrompt, selectLearnings, toAppliedLearnings, type ReviewLearning } from "./learnings.ts";
+import { buildNoisyCategoriesPrompt, filterNoisyComments } from "./feedback.ts";
+import { carryOpenFindings, describePolicy, evaluatePolicy, POLICY_STATUS_CONTEXT, type SeverityPolicy } from "./policy.ts";
+import { getDepthPrompt } from "./analysis.ts";
+import { mergeSecretComments, redactSecrets, scanDiffSecrets } from "./secrets.ts";
+import { buildStaticAnalysisPrompt, dedupeFindings, findingsOnChangedLines, mergeAnalyzerComments, type AnalyzerFinding } from "./static-analysis.ts";
+import { ReviewCategory, type CodeReviewResult, type LineComment, type ServedBy } from "./models.ts";
+import type { ReviewResponse } from "./schema.ts";
+
+export type ReviewDepth = "quick" | "standard" | "deep";
+export type ReviewEvent = "APPROVE" | "REQUEST_CHANGES" | "COMMENT";
+
+export const DEFAULT_CATEGORIES = [ReviewCategory.SECURITY, ReviewCategory.BUG, ReviewCategory.PERFORMANCE];
+
+// Diff budget per LLM call, and the most calls a single review may make
+export const DEFAULT_CHUNK_TOKENS = 2000;
+export const DEFAULT_MAX_PASSES = 10;
+
+export const REVIEW_EVENTS: Record<CodeReviewResult["approval_recommendation"], ReviewEvent> = {
+  approve: "APPROVE",
+  request_changes: "REQUEST_CHANGES",
+  comment: "COMMENT",
+};
+
+export interface RepoConfig {
+  categories?: string[];
+  ignore_paths?: string[];
+  custom_instructions?: string;
+  category_weights?: Record<string, number>;
+  severity_policy?: SeverityPolicy | null;
+}
+
+export interface PullRequestInfo {
+  title: string;
+  body?: string | null;
+  head: { sha: string };
+}
+
+export interface ReviewCommentInput {
+  path: string;
+  line: number;
+  body: string;
+  side?: "LEFT" | "RIGHT";
+  start_line?: number;
+  start_side?: "LEFT" | "RIGHT";
+}
+
+export interface ReviewRuntime {
+  github: {
+    pullRequest(): Promise<PullRequestInfo>;
+    diff(): Promise<string>;
+    compare(base: string, head: string): Promise<string>;
+    createReview(body: string, event: ReviewEvent, comments: ReviewCommentInput[]): Promise<{ id?: number }>;
+    setStatus(sha: string, state: "success" | "failure", context: string, description: string): Promise<unknown>;
+  };
+  // One structured completion; review is null when the output never validated
+  complete(prompt: string): Promise<{ review: ReviewResponse | null; servedBy?: ServedBy }>;
+  config: RepoConfig;
+  learnings: ReviewLearning[];
+  lastReviewedSha?: string | null;
+  // Findings still open as of lastReviewedSha, so an incremental review doesn't forget them
+  previousFindings?: LineComment[];
+  // Linters and scanners run on a checkout of the head; omitted where tools can't run
+  analyze?(paths: string[], headSha: string): Promise<AnalyzerFinding[]>;
+}
+
+export interface ReviewOptions {
+  categories?: ReviewCategory[];
+  depth?: ReviewDepth;
+  focus_areas?: string[];
+  chunkTokens?: number;
+  maxPasses?: number;
+  // The commit the review was requested for; the review stops if the PR has moved past it
+  headSha?: string | null;
+}
+
+export interface GeneratedReview {
+  review: CodeReviewResult;
+  headSha: string;
+  isIncremental: boolean;
+  diff: string;
+}
+
+/** The PR head moved on, so this review would describe outdated code. */
+export class StaleReviewError extends Error {
+  constructor(public reviewedSha: string, public currentSha: string) {
+    super(`PR head moved from ${reviewedSha.slice(0, 7)} to ${currentSha.slice(0, 7)}`);
+    this.name = "StaleReviewError";
+  }
+}
+
+export function parseFailedResult(): CodeReviewResult {
+  return {
+    summary: {
+      overview: "Review failed: the model did not return a valid review after repair attempts",
+      changes_description: "Unavailable",
+      risk_assessment: "Unknown",
+      recommendations: ["Manual review recommended"],
+    },
+    walkthrough: [],
+    line_comments: [],
+    approval_recommendation: "comment",
+  };
+}
+
+function uniqueServedBy(entries: ServedBy[]): ServedBy[] {
+  const seen = new Map(entries.map((e) => [`${e.provider}/${e.model}`, e]));
+  return [...seen.values()];
+}
+
+/**
+ * When the repo has a severity policy, it, not the model, decides whether the
+ * PR is blocked, over every finding still open. Without one the model's verdict stands.
+ */
+export function applyPolicy(review: CodeReviewResult, policy?: SeverityPolicy | null): CodeReviewResult {
+  if (!policy) return review;
+  const result = evaluatePolicy(review.open_findings || review.line_comments, policy);
+  review.policy = result;
+  if (!result.passed) review.approval_recommendation = "request_changes";
+  else if (review.approval_recommendation === "request_changes") review.approval_recommendation = "comment";
+  return review;
+}
+
+/**
+ * Review a PR: incremental when it was reviewed before, split into batches when
+ * the diff is too big for one call, then filtered and checked against policy.
+ */
+export async function generateReview(runtime: ReviewRuntime, options: ReviewOptions = {}): Promise<GeneratedReview> {
+  const { config, github } = runtime;
+  const categories = options.categories || (config.categories?.map((c) => c as ReviewCategory)) || DEFAULT_CATEGORIES;
+  const maxPasses = options.maxPasses ?? DEFAULT_MAX_PASSES;
+
+  const pr = await github.pullRequest();
+  const headSha = pr.head.sha;
+  if (options.headSha && options.headSha !== headSha) throw new StaleReviewError(options.headSha, headSha);
+
+  let diff: string;
+  let isIncremental = false;
+  if (runtime.lastReviewedSha && runtime.lastReviewedSha !== headSha) {
+    try {
+      diff = await github.compare(runtime.lastReviewedSha, headSha);
+      isIncremental = true;
+    } catch {
+      diff = await github.diff();
+    }
+  } else {
+    diff = await github.diff();
+  }
+
+  let parsedFiles = parseDiff(diff);
+  // Before ignore_paths, so findings in ignored files still move with their lines
+  const carried = isIncremental ? carryOpenFindings(runtime.previousFindings || [], parsedFiles) : [];
+  if (config.ignore_paths?.length) {
+    parsedFiles = filterIgnoredPaths(parsedFiles, config.ignore_paths);
+  }
+
+  // Secrets are found by rule, not by the model, and never leave in the prompt
+  const secrets = scanDiffSecrets(parsedFiles);
+  parsedFiles = secrets.files;
+
+  // Analyzer findings count only on lines this diff adds
+  let findings: AnalyzerFinding[] = [];
+  if (runtime.analyze && parsedFiles.length) {
+    try {
+      const reported = await runtime.analyze(parsedFiles.filter((f) => f.additions > 0).map((f) => f.path), headSha);
+      findings = dedupeFindings(findingsOnChangedLines(reported, parsedFiles));
+    } catch (err) {
+      console.error("Static analysis failed:", err);
+    }
+  }
+
+  const filesSummary = summarizeFiles(parsedFiles);
+  const batches = chunkDiff(parsedFiles, options.chunkTokens ?? DEFAULT_CHUNK_TOKENS);
+  const reviewedBatches = batches.slice(0, maxPasses);
+  const learnings = selectLearnings(runtime.learnings, parsedFiles.map((f) => f.path), categories);
+
+  // Build prompt with custom instructions, learnings and depth
+  let systemPrompt = isIncremental ? INCREMENTAL_SYSTEM_PROMPT : SYSTEM_PROMPT;
+  if (config.custom_instructions) {
+    systemPrompt += `\n\n## Custom Instructions\n${config.custom_instructions}`;
+  }
+  if (learnings.length) {
+    systemPrompt += "\n\n" + buildLearningsPrompt(learnings);
+  }
+  const noisyPrompt = buildNoisyCategoriesPrompt(config.category_weights);
+  if (noisyPrompt) {
+    systemPrompt += "\n\n" + noisyPrompt;
+  }
+  if (options.depth || options.focus_areas?.length) {
+    systemPrompt += "\n\n" + getDepthPrompt(options.depth || "standard", options.focus_areas || []);
+  }
+
+  const buildPrompt = (summary: string, diffContent: string, files: FileDiff[]) => {
+    const prompt = isIncremental
+      ? buildIncrementalPrompt(redactSecrets(pr.title), summary, diffContent, categories)
+      : buildReviewPrompt(redactSecrets(pr.title), redactSecrets(pr.body || ""), summary, diffContent, categories);
+    // Only the findings for this batch's files
+    const paths = new Set(files.map((f) => f.path));
+    const analysis = buildStaticAnalysisPrompt(findings.filter((f) => paths.has(f.path)));
+    return analysis ? `${prompt}\n\n${analysis}` : prompt;
+  };
+
+  let review: CodeReviewResult;
+  const servedBy: ServedBy[] = [];
+
+  if (reviewedBatches.length <= 1) {
+    const result = await runtime.complete(`${systemPrompt}\n\n${buildPrompt(filesSummary, reviewedBatches[0]?.diff || "", reviewedBatches[0]?.files || [])}`);
+    review = result.review || parseFailedResult();
+    if (result.servedBy) servedBy.push(result.servedBy);
+  } else {
+    // Map: review each batch on its own. Reduce: merge into one result.
+    const results: CodeReviewResult[] = [];
+    const failedPaths: string[] = [];
+
+    for (const [i, batch] of reviewedBatches.entries()) {
+      const summary = buildBatchSummary(i + 1, reviewedBatches.length, summarizeFiles(batch.files), filesSummary);
+      try {
+        const result = await runtime.complete(`${systemPrompt}\n\n${buildPrompt(summary, batch.diff, batch.files)}`);
+        if (result.servedBy) servedBy.push(result.servedBy);
+        if (!result.review) throw new Error("invalid review output");
+        results.push(result.review);
+      } catch {
+        failedPaths.push(...batch.files.map((f) => f.path));
+      }
+    }
+
+    review = results.length ? mergeReviewResults(results) : parseFailedResult();
+
+    if (results.length && failedPaths.length) {
+      review.summary.recommendations.push(
+        `Review failed for part of this PR, manual review recommended: ${[...new Set(failedPaths)].join(", ")}`
+      );
+    }
+  }
+
+  const skippedPaths = [...new Set(batches.slice(maxPasses).flatMap((b) => b.files.map((f) => f.path)))];
+  if (skippedPaths.length) {
+    review.summary.recommendations.push(
+      `${skippedPaths.length} files were not reviewed (pass limit reached): ${skippedPaths.slice(0, 20).join(", ")}`
+    );
+  }
+
+  const lineComments = mergeSecretComments(
+    mergeAnalyzerComments(filterNoisyComments(review.line_comments, config.category_weights), findings),
+    secrets.findings
+  );
+  review = applyPolicy({
+    ...review,
+    line_comments: lineComments,
+    open_findings: [...carried, ...lineComments],
+    applied_learnings: toAppliedLearnings(learnings),
+    served_by: uniqueServedBy(servedBy),
+  }, config.severity_policy);
+  // A leaked secret blocks the PR whatever the model or the policy says
+  if (secrets.findings.length) review.approval_recommendation = "request_changes";
+
+  return { review, headSha, isIncremental, diff };
+}
+
+export function formatReviewBody(review: CodeReviewResult, isIncremental: boolean, unplaced: LineComment[] = []): string {
+  const sections: string[] = [];
+
+  sections.push(isIncremental ? "## 🔄 Incremental Review\n" : "## 🤖 AI Code Review\n");
+
+  if (isIncremental) {
+    sections.push("*Reviewing only new changes since last review*\n");
+  }
+
+  sections.push(`### Summary\n${review.summary.overview}\n`);
+  sections.push(`**Changes:** ${review.summary.changes_description}\n`);
+  sections.push(`**Risk Level:** ${review.summary.risk_assessment}\n`);
+
+  if (review.summary.praise?.length) {
+    sections.push("### ✨ What's Good\n");
+    sections.push(review.summary.praise.map((p) => `- ${p}`).join("\n") + "\n");
+  }
+
+  if (review.walkthrough.length > 0) {
+    sections.push("### 📝 Walkthrough\n");
+    sections.push("<details><summary>File changes</summary>\n");
+    for (const file of review.walkthrough) {
+      sections.push(`\n**${file.path}**\n${file.summary}`);
+      if (file.changes.length > 0) {
+        sections.push(file.changes.map((c) => `- ${c}`).join("\n"));
+      }
+    }
+    sections.push("\n</details>\n");
+  }
+
+  if (review.summary.recommendations.length > 0) {
+    sections.push("### 📋 Recommendations\n");
+    sections.push(review.summary.recommendations.map((r) => `- ${r}`).join("\n") + "\n");
+  }
+
+  if (review.policy && !review.policy.passed) {
+    sections.push("### ⛔ Merge Blocked by Policy\n");
+    sections.push(review.policy.violations.map((v) => `- ${v}`).join("\n") + "\n");
+    const earlier = (review.open_findings?.length || 0) - review.line_comments.length;
+    if (earlier > 0) sections.push(`*Includes ${earlier} finding${earlier === 1 ? "" : "s"} from earlier reviews that are still open*\n`);
+  }
+
+  if (unplaced.length > 0) {
+    sections.push("### 📌 Comments Outside the Diff\n");
+    sections.push(unplaced.map((c) => {
+      const location = c.path ? `\`${c.path}${c.line > 0 ? `:${c.line}` : ""}\`` : "General";
+      return `- **[${c.severity.toString().toUpperCase()}]** ${location} - ${c.body}${c.source ? ` _(${c.source})_` : ""}`;
+    }).join("\n") + "\n");
+  }
+
+  const criticalCount = review.line_comments.filter((c) => c.severity === "critical").length;
+  const highCount = review.line_comments.filter((c) => c.severity === "high").length;
+  const otherCount = review.line_comments.length - criticalCount - highCount;
+
+  sections.push("---");
+  sections.push(`📊 **${review.line_comments.length} comments** `);
+  if (criticalCount > 0) sections.push(`| 🔴 ${criticalCount} critical `);
+  if (highCount > 0) sections.push(`| 🟠 ${highCount} high `);
+  if (otherCount > 0) sections.push(`| 🟡 ${otherCount} other`);
+
+  if (review.served_by?.length) {
+    sections.push(`\n<sub>Reviewed with ${review.served_by.map((s) => `${s.provider}/${s.model}`).join(", ")}</sub>`);
+  }
+
+  return sections.join("\n");
+}
+
+export function toReviewComments(placed: PlacedComment[]): ReviewCommentInput[] {
+  return placed.map((c) => {
+    let commentBody = `**[${c.severity.toString().toUpperCase()}]** ${c.body}`;
+    // A suggestion replaces the commented lines, so it's only safe where the model aimed it
+    if (c.suggestion && !c.snapped) {
+      commentBody += `\n\n\`\`\`suggestion\n${c.suggestion}\n\`\`\``;
+    }
+    if (c.source) commentBody += `\n\n<sub>Reported by ${c.source}</sub>`;
+    return {
+      path: c.path,
+      line: c.line,
+      side: "RIGHT" as const,
+      ...(c.start_line && { start_line: c.start_line, start_side: "RIGHT" as const }),
+      body: commentBody,
+    };
+  });
+}
+
+/**
+ * Post the review with inline comments on diff lines, and the policy result as
+ * a commit status. Returns the posted review id and the comments placed inline.
+ * Throws StaleReviewError without posting if new commits arrived meanwhile.
+ */
+export async function publishReview(
+  runtime: ReviewRuntime,
+  { review, headSha, isIncremental, diff }: GeneratedReview
+): Promise<{ reviewId?: number; placed: PlacedComment[] }> {
+  const { github } = runtime;
+
+  // Reviewing can take minutes; never post against a head that has since changed
+  const current = await github.pullRequest();
+  if (current.head.sha !== headSha) throw new StaleReviewError(headSha, current.head.sha);
+
+  // Comments must land on lines in the PR diff, or GitHub rejects the whole review
+  let placement: PlacementResult = { placed: [], unplaced: review.line_comments };
+  if (review.line_comments.length) {
+    try {
+      placement = placeComments(review.line_comments, parseDiff(isIncremental ? await github.diff() : diff));
+    } catch { /* post everything in the body */ }
+  }
+
+  let posted: { reviewId?: number; placed: PlacedComment[] };
+  try {
+    const result = await github.createReview(
+      formatReviewBody(review, isIncremental, placement.unplaced),
+      REVIEW_EVENTS[review.approval_recommendation],
+      toReviewComments(placement.placed)
+    );
+    posted = { reviewId: result?.id, placed: placement.placed };
+  } catch {
+    // If GitHub still rejects the inline comments, keep them in the body
+    await github.createReview(formatReviewBody(review, isIncremental, review.line_comments), "COMMENT", []);
+    posted = { placed: [] };
+  }
+
+  if (review.policy) {
+    await github.setStatus(headSha, review.policy.passed ? "success" : "failure", POLICY_STATUS_CONTEXT, describePolicy(review.policy))
+      .catch((e) => console.error("Failed to publish policy status:", e));
+  }
+
+  return posted;
+}
@@ -0,0 +1,134 @@
+import { LineComment, Severity } from "./models.ts";
+
+export type FeedbackSignal = "thumbs_up" | "thumbs_down" | "resolved" | "wont_fix" | "dismissed";
+
+const POSITIVE_SIGNALS: FeedbackSignal[] = ["thumbs_up", "resolved"];
+const NEGATIVE_SIGNALS: FeedbackSignal[] = ["thumbs_down", "wont_fix", "dismissed"];
+
+// Below this weight a category is treated as noisy for the repo
+export const NOISY_CATEGORY_WEIGHT = 0.5;
+const MIN_SIGNALS = 3;
+
+const WONT_FIX_PATTERN = /\bwon'?t\s*fix\b|\bwontfix\b|\bfalse\s+positive\b|\bby\s+design\b|\bintentional(ly)?\b|\bnot\s+(an?\s+)?(issue|problem|applicable|relevant)\b/i;
+// Only a short reply that leads with it, so "is this fixed upstream?" doesn't count
+const RESOLVED_PATTERN = /^(?:(?:good catch|thanks|thank you|ok(?:ay)?|yes|yep)\b[\s,.!-]*)?(?:resolved|fixed|addressed|done)\b/i;
+const MAX_RESOLVED_REPLY = 200;
+
+export function classifyReply(body: string): FeedbackSignal | null {
+  if (!body) return null;
+  if (WONT_FIX_PATTERN.test(body)) return "wont_fix";
+  const reply = body.trim();
+  if (reply.length <= MAX_RESOLVED_REPLY && !reply.includes("?") && RESOLVED_PATTERN.test(reply)) return "resolved";
+  return null;
+}
+
+export interface FeedbackRow {
+  signal: FeedbackSignal;
+  count: number;
+  category: string | null;
+  path: string;
+  note?: string | null;
+}
+
+export interface CategoryStats {
+  positive: number;
+  negative: number;
+}
+
+export function summarizeFeedback(rows: FeedbackRow[]): Record<string, CategoryStats> {
+  const stats: Record<string, CategoryStats> = {};
+  for (const row of rows) {
+    const category = row.category || "other";
+    const entry = stats[category] || (stats[category] = { positive: 0, negative: 0 });
+    if (POSITIVE_SIGNALS.includes(row.signal)) entry.positive += row.count;
+    else if (NEGATIVE_SIGNALS.includes(row.signal)) entry.negative += row.count;
+  }
+  return stats;
+}
+
+// Laplace-smoothed approval rate scaled so that 50%+ approval keeps full weight
+export function computeCategoryWeights(
+  stats: Record<string, CategoryStats>,
+  minSignals: number = MIN_SIGNALS
+): Record<string, number> {
+  const weights: Record<string, number> = {};
+  for (const [category, { positive, negative }] of Object.entries(stats)) {
+    if (positive + negative < minSignals) continue;
+    const rate = (positive + 1) / (positive + negative + 2);
+    weights[category] = Math.round(Math.min(1, rate * 2) * 100) / 100;
+  }
+  return weights;
+}
+
+export function noisyCategories(weights: Record<string, number> | undefined): string[] {
+  return Object.entries(weights || {})
+    .filter(([, w]) => w < NOISY_CATEGORY_WEIGHT)
+    .map(([c]) => c);
+}
+
+// Drop low-impact comments in categories the team keeps rejecting
+export function filterNoisyComments(
+  comments: LineComment[],
+  weights: Record<string, number> | undefined
+): LineComment[] {
+  const noisy = noisyCategories(weights);
+  if (!noisy.length) return comments;
+  return comments.filter(
+    (c) => !noisy.includes(String(c.category)) || c.severity === Severity.CRITICAL || c.severity === Severity.HIGH
+  );
+}
+
+export function buildNoisyCategoriesPrompt(weights: Record<string, number> | undefined): string {
+  const noisy = noisyCategories(weights);
+  if (!noisy.length) return "";
+  return `## Noisy Categories
+The team usually rejects comments in these categories: ${noisy.join(", ")}.
+Only raise them for high or critical issues.`;
+}
+
+export interface ProposedLearning {
+  pattern: string;
+  category: string | null;
+  learning: string;
+}
+
+function scopeFor(path: string): string {
+  const dir = path.includes("/") ? path.slice(0, path.lastIndexOf("/")) : "";
+  return dir ? `${dir}/*` : path;
+}
+
+export function proposeLearnings(
+  rows: FeedbackRow[],
+  weights: Record<string, number>,
+  existing: { pattern: string; category?: string | null }[]
+): ProposedLearning[] {
+  const seen = new Set(existing.map((l) => `${l.pattern}|${l.category || ""}`));
+  const proposals: ProposedLearning[] = [];
+  const add = (p: ProposedLearning) => {
+    const key = `${p.pattern}|${p.category || ""}`;
+    if (seen.has(key)) return;
+    seen.add(key);
+    proposals.push(p);
+  };
+
+  // Explicit rejections with a reason become path-scoped learnings
+  for (const row of rows) {
+    if (row.signal !== "wont_fix" || !row.note?.trim()) continue;
+    const reason = row.note.trim().replace(/\s+/g, " ").slice(0, 200);
+    add({
+      pattern: scopeFor(row.path),
+      category: row.category,
+      learning: `Team declined ${row.category || "this"} feedback here: "${reason}"`,
+    });
+  }
+
+  for (const category of noisyCategories(weights)) {
+    add({
+      pattern: "*",
+      category,
+      learning: `Comments in the "${category}" category are usually rejected in this repo. Only raise ${category} issues that are clearly high severity.`,
+    });
+  }
+
+  return proposals;
+}
@@ -0,0 +1,15 @@
+// Review engine shared by the Next.js app (via src/lib/review/engine.ts) and the edge worker
+export * from "./models.ts";
+export * from "./analyzer.ts";
+export * from "./analysis.ts";
+export * from "./prompts.ts";
+export * from "./merge.ts";
+export * from "./placement.ts";
+export * from "./learnings.ts";
+export * from "./feedback.ts";
+export * from "./policy.ts";
+export * from "./schema.ts";
+export * from "./secrets.ts";
+export * from "./static-analysis.ts";
+export * from "./scans.ts";
+export * from "./engine.ts";
@@ -0,0 +1,67 @@
+import { matchesPathPattern, estimateTokens } from "./analyzer.ts";
+import { AppliedLearning } from "./models.ts";
+
+export interface ReviewLearning {
+  id: string;
+  repo_full_name: string;
+  pattern: string;
+  learning: string;
+  category?: string | null;
+  created_at?: string;
+}
+
+// Rough budget for the learnings section of the system prompt
+export const LEARNINGS_TOKEN_BUDGET = 400;
+
+function isWildcard(pattern: string): boolean {
+  return !pattern || pattern === "*" || pattern === "**" || pattern === "**/*";
+}
+
+function formatLearning(l: ReviewLearning): string {
+  const scope = isWildcard(l.pattern) ? "" : ` (\`${l.pattern}\`)`;
+  const category = l.category ? `[${l.category}] ` : "";
+  return `- ${category}${l.learning.trim()}${scope}`;
+}
+
+// Pick learnings relevant to the changed files and review categories, most specific first,
+// stopping once the token budget is spent
+export function selectLearnings(
+  learnings: ReviewLearning[],
+  paths: string[],
+  categories: string[],
+  maxTokens: number = LEARNINGS_TOKEN_BUDGET
+): ReviewLearning[] {
+  const matching = learnings.filter((l) => {
+    if (!l.learning?.trim()) return false;
+    if (l.category && categories.length && !categories.includes(l.category)) return false;
+    if (isWildcard(l.pattern)) return true;
+    return paths.some((p) => matchesPathPattern(p, l.pattern));
+  });
+
+  // Path-scoped learnings beat repo-wide ones; otherwise keep the incoming (newest first) order
+  const ranked = matching
+    .map((l, i) => ({ l, i, specific: isWildcard(l.pattern) ? 1 : 0 }))
+    .sort((a, b) => a.specific - b.specific || a.i - b.i)
+    .map(({ l }) => l);
+
+  const selected: ReviewLearning[] = [];
+  let used = 0;
+  for (const l of ranked) {
+    const cost = estimateTokens(formatLearning(l));
+    if (used + cost > maxTokens) continue;
+    selected.push(l);
+    used += cost;
+  }
+  return selected;
+}
+
+export function buildLearningsPrompt(learnings: ReviewLearning[]): string {
+  if (!learnings.length) return "";
+  return `## Team Learnings
+These were recorded from earlier reviews of this repository. Follow them and don't flag what they say is intentional.
+${learnings.map(formatLearning).join("\n")}`;
+}
+
+export function toAppliedLearnings(learnings: ReviewLearning[]): AppliedLearning[] {
+  return learnings.map(({ id, pattern, category }) => ({ id, pattern, category }));
+}
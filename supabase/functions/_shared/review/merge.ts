@@ -0,0 +1,92 @@
+import { CodeReviewResult, FileWalkthrough, LineComment } from "./models.ts";
+
+const SEVERITY_RANK: Record<string, number> = { critical: 4, high: 3, medium: 2, low: 1, info: 0 };
+const APPROVAL_RANK: Record<CodeReviewResult["approval_recommendation"], number> = {
+  approve: 0,
+  comment: 1,
+  request_changes: 2,
+};
+const RISK_LEVELS = ["low", "medium", "high", "critical"];
+
+function severityRank(severity: string): number {
+  return SEVERITY_RANK[String(severity).toLowerCase()] ?? 2;
+}
+
+function riskRank(assessment: string): number {
+  const lower = assessment.toLowerCase();
+  let rank = -1;
+  RISK_LEVELS.forEach((level, i) => {
+    if (lower.startsWith(level) || lower.includes(`${level} -`) || lower.includes(`${level}:`)) rank = Math.max(rank, i);
+  });
+  return rank;
+}
+
+function uniqueStrings(values: (string | undefined)[]): string[] {
+  const seen = new Set<string>();
+  const result: string[] = [];
+  for (const v of values) {
+    const key = v?.trim().toLowerCase();
+    if (!key || seen.has(key)) continue;
+    seen.add(key);
+    result.push(v!.trim());
+  }
+  return result;
+}
+
+// One comment per path/line/category, keeping the most severe
+export function dedupeLineComments(comments: LineComment[]): LineComment[] {
+  const byKey = new Map<string, LineComment>();
+  for (const c of comments) {
+    const key = `${c.path}:${c.line}:${c.category}`;
+    const existing = byKey.get(key);
+    if (!existing || severityRank(String(c.severity)) > severityRank(String(existing.severity))) {
+      byKey.set(key, c);
+    }
+  }
+  return [...byKey.values()].sort(
+    (a, b) => severityRank(String(b.severity)) - severityRank(String(a.severity)) || a.path.localeCompare(b.path) || a.line - b.line
+  );
+}
+
+function mergeWalkthrough(entries: FileWalkthrough[]): FileWalkthrough[] {
+  const byPath = new Map<string, FileWalkthrough>();
+  for (const w of entries) {
+    const existing = byPath.get(w.path);
+    if (!existing) {
+      byPath.set(w.path, { ...w, changes: [...w.changes] });
+      continue;
+    }
+    existing.summary = uniqueStrings([existing.summary, w.summary]).join(" ");
+    existing.changes = uniqueStrings([...existing.changes, ...w.changes]);
+  }
+  return [...byPath.values()];
+}
+
+/**
+ * Reduce step for multi-pass reviews: combine per-batch results into one
+ * CodeReviewResult with deduplicated comments and the most cautious verdict
+ */
+export function mergeReviewResults(results: CodeReviewResult[]): CodeReviewResult {
+  if (results.length === 1) return results[0]!;
+
+  const risk = results
+    .map((r) => r.summary.risk_assessment)
+    .reduce((worst, r) => (riskRank(r) > riskRank(worst) ? r : worst), "Unknown");
+
+  const approval = results
+    .map((r) => r.approval_recommendation)
+    .reduce((worst, a) => (APPROVAL_RANK[a] > APPROVAL_RANK[worst] ? a : worst), "approve" as CodeReviewResult["approval_recommendation"]);
+
+  return {
+    summary: {
+      overview: uniqueStrings(results.map((r) => r.summary.overview)).join(" "),
+      changes_description: uniqueStrings(results.map((r) => r.summary.changes_description)).join(" "),
+      risk_assessment: risk,
+      recommendations: uniqueStrings(results.flatMap((r) => r.summary.recommendations)),
+      praise: uniqueStrings(results.flatMap((r) => r.summary.praise || [])),
+    },
+    walkthrough: mergeWalkthrough(results.flatMap((r) => r.walkthrough)),
+    line_comments: dedupeLineComments(results.flatMap((r) => r.line_comments)),
+    approval_recommendation: approval,
+  };
+}
@@ -1,3 +1,19 @@
+// Review result shapes shared by the Next.js app and the edge worker.
+// Runtime-neutral: no imports outside this directory.
+import type { PolicyResult } from "./policy.ts";
+
+// Same shapes as ServedBy and UsageTotals in the app's LLM layer
+export interface ServedBy {
+  provider: string;
+  model: string;
+}
+
+export interface UsageTotals {
+  prompt_tokens: number;
+  completion_tokens: number;
+  cost_usd: number;
+}
+
 export enum Severity {
   CRITICAL = "critical",
   HIGH = "high",
@@ -32,6 +48,7 @@ export interface LineComment {
   category: ReviewCategory | string;
   start_line?: number;
   suggestion?: string;
+  source?: string; // analyzer that reported it; unset for the model's own comments
 }
 
 export interface FileWalkthrough {
@@ -48,11 +65,23 @@ export interface ReviewSummary {
   praise?: string[];
 }
 
+export interface AppliedLearning {
+  id: string;
+  pattern: string;
+  category?: string | null;
+}
+
 export interface CodeReviewResult {
   summary: ReviewSummary;
   walkthrough: FileWalkthrough[];
   line_comments: LineComment[];
   approval_recommendation: "approve" | "request_changes" | "comment";
+  applied_learnings?: AppliedLearning[];
+  served_by?: ServedBy[]; // provider/model pairs that produced the review
+  usage?: UsageTotals; // tokens and estimated cost across every LLM call
+  policy?: PolicyResult; // repo severity policy over open_findings
+  // This review's comments plus earlier ones an incremental review left untouched
+  open_findings?: LineComment[];
 }
 
 export interface ReviewRequest {
@@ -0,0 +1,107 @@
+import { FileDiff } from "./analyzer.ts";
+import { LineComment } from "./models.ts";
+
+// How far (in lines) a comment may be moved to land on a line GitHub accepts
+export const MAX_SNAP_DISTANCE = 10;
+
+interface HunkLines {
+  added: number[];
+  commentable: number[]; // added + context lines on the new side
+}
+
+export interface PlacedComment extends LineComment {
+  snapped?: boolean;
+}
+
+export interface PlacementResult {
+  placed: PlacedComment[];
+  unplaced: LineComment[];
+}
+
+// New-side line numbers for each hunk, which is what GitHub accepts for side=RIGHT
+export function getHunkLines(file: FileDiff): HunkLines[] {
+  return file.hunks.map((hunk) => {
+    const added: number[] = [];
+    const commentable: number[] = [];
+    let line = hunk.newStart;
+    const end = hunk.newStart + hunk.newCount;
+    for (const raw of hunk.content.split("\n").slice(1)) {
+      if (line >= end) break;
+      if (raw.startsWith("+")) {
+        added.push(line);
+        commentable.push(line);
+        line++;
+      } else if (raw.startsWith(" ") || raw === "") {
+        commentable.push(line);
+        line++;
+      }
+      // "-" lines and "\ No newline" markers don't exist on the new side
+    }
+    return { added, commentable };
+  });
+}
+
+function normalizePath(path: string): string {
+  return path.trim().replace(/^\.?\//, "").replace(/^[ab]\//, "");
+}
+
+function findFile(files: FileDiff[], path: string): FileDiff | undefined {
+  const wanted = normalizePath(path);
+  const exact = files.find((f) => f.path === wanted);
+  if (exact) return exact;
+  const candidates = files.filter((f) => f.path.endsWith(`/${wanted}`) || wanted.endsWith(`/${f.path}`));
+  return candidates.length === 1 ? candidates[0] : undefined;
+}
+
+function nearest(lines: number[], target: number): number | undefined {
+  let best: number | undefined;
+  for (const l of lines) {
+    if (best === undefined || Math.abs(l - target) < Math.abs(best - target)) best = l;
+  }
+  return best;
+}
+
+/**
+ * Check each comment against the diff hunks. Comments on lines outside the
+ * diff are snapped to the nearest changed line when close enough, otherwise
+ * returned as unplaced so they can go in the review body instead.
+ */
+export function placeComments(comments: LineComment[], files: FileDiff[]): PlacementResult {
+  const placed: PlacedComment[] = [];
+  const unplaced: LineComment[] = [];
+
+  for (const comment of comments) {
+    const file = comment.path ? findFile(files, comment.path) : undefined;
+    if (!file || !(comment.line > 0)) {
+      unplaced.push(comment);
+      continue;
+    }
+
+    const hunks = getHunkLines(file);
+    let hunk = hunks.find((h) => h.commentable.includes(comment.line));
+    let line = comment.line;
+    let snapped = false;
+
+    if (!hunk) {
+      // Prefer added lines, fall back to context lines
+      const candidates = hunks.flatMap((h) => h.added.length ? h.added : h.commentable);
+      const target = nearest(candidates, comment.line);
+      if (target === undefined || Math.abs(target - comment.line) > MAX_SNAP_DISTANCE) {
+        unplaced.push(comment);
+        continue;
+      }
+      line = target;
+      snapped = true;
+      hunk = hunks.find((h) => h.commentable.includes(target))!;
+    }
+
+    // Multi-line ranges must start earlier in the same hunk
+    const startLine = comment.start_line && comment.start_line < line && hunk.commentable.includes(comment.start_line)
+      ? comment.start_line
+      : undefined;
+
+    placed.push({ ...comment, path: file.path, line, start_line: startLine, ...(snapped && { snapped }) });
+  }
+
+  return { placed, unplaced };
+}
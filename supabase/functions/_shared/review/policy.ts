@@ -0,0 +1,110 @@
+import { LineComment } from "./models.ts";
+import type { FileDiff } from "./analyzer.ts";
+
+/**
+ * Per-repo merge policy, stored as repo_configs.severity_policy. Limits are
+ * the most comments of that severity a PR may carry, so 0 blocks on any.
+ */
+export interface SeverityPolicy {
+  max_critical?: number;
+  max_high?: number;
+  max_medium?: number;
+  block_categories?: string[]; // any comment in these categories blocks
+}
+
+export interface PolicyResult {
+  passed: boolean;
+  violations: string[];
+  counts: Record<string, number>;
+}
+
+export const DEFAULT_POLICY: SeverityPolicy = { max_critical: 0 };
+
+export const POLICY_STATUS_CONTEXT = "ai-review/policy";
+
+const LIMITS = ["max_critical", "max_high", "max_medium"] as const;
+
+/** Validate a policy from user input; throws with a readable message. */
+export function parsePolicy(input: unknown): SeverityPolicy {
+  if (!input || typeof input !== "object" || Array.isArray(input)) throw new Error("severity_policy must be an object");
+  const raw = input as Record<string, unknown>;
+  const policy: SeverityPolicy = {};
+
+  for (const key of LIMITS) {
+    const value = raw[key];
+    if (value === undefined || value === null) continue;
+    if (typeof value !== "number" || !Number.isInteger(value) || value < 0) {
+      throw new Error(`severity_policy.${key} must be a non-negative integer`);
+    }
+    policy[key] = value;
+  }
+
+  if (raw.block_categories !== undefined) {
+    if (!Array.isArray(raw.block_categories) || !raw.block_categories.every((c) => typeof c === "string")) {
+      throw new Error("severity_policy.block_categories must be a list of categories");
+    }
+    policy.block_categories = raw.block_categories.map((c: string) => c.toLowerCase());
+  }
+
+  const unknown = Object.keys(raw).filter((k) => !(LIMITS as readonly string[]).includes(k) && k !== "block_categories");
+  if (unknown.length) throw new Error(`Unknown severity_policy keys: ${unknown.join(", ")}`);
+  return policy;
+}
+
+export function evaluatePolicy(comments: LineComment[], policy: SeverityPolicy = DEFAULT_POLICY): PolicyResult {
+  const counts: Record<string, number> = { critical: 0, high: 0, medium: 0, low: 0, info: 0 };
+  for (const c of comments) {
+    const severity = String(c.severity || "info").toLowerCase();
+    counts[severity] = (counts[severity] || 0) + 1;
+  }
+
+  const violations: string[] = [];
+  for (const key of LIMITS) {
+    const limit = policy[key];
+    const severity = key.replace("max_", "");
+    const count = counts[severity] || 0;
+    if (limit !== undefined && count > limit) {
+      violations.push(limit === 0 ? `${count} ${severity} (none allowed)` : `${count} ${severity} (max ${limit})`);
+    }
+  }
+
+  for (const category of policy.block_categories || []) {
+    const count = comments.filter((c) => String(c.category).toLowerCase() === category).length;
+    if (count) violations.push(`${count} ${category} finding${count === 1 ? "" : "s"}`);
+  }
+
+  return { passed: violations.length === 0, violations, counts };
+}
+
+/** One-line summary for the commit status (GitHub truncates at 140 chars). */
+export function describePolicy(result: PolicyResult): string {
+  const text = result.passed ? "Review policy passed" : `Blocked: ${result.violations.join(", ")}`;
+  return text.length > 140 ? `${text.slice(0, 137)}...` : text;
+}
+
+/** The findings still open as of a stored review; older rows only kept their own comments. */
+export function openFindings(result: unknown): LineComment[] {
+  const review = result as { open_findings?: LineComment[]; line_comments?: LineComment[] } | null | undefined;
+  return review?.open_findings || review?.line_comments || [];
+}
+
+/**
+ * Earlier findings that stay open after an incremental diff. Findings on lines
+ * a hunk covers were shown to the new pass, which reports them again if they
+ * still apply, so they're dropped; the rest move with the lines around them.
+ */
+export function carryOpenFindings(previous: LineComment[], files: FileDiff[]): LineComment[] {
+  return previous.flatMap((finding) => {
+    const hunks = files.find((f) => f.path === finding.path)?.hunks || [];
+    if (!hunks.length || finding.line <= 0) return [finding];
+
+    const start = finding.start_line && finding.start_line < finding.line ? finding.start_line : finding.line;
+    let shift = 0;
+    for (const hunk of hunks) {
+      const end = hunk.oldStart + hunk.oldCount - 1;
+      if (hunk.oldCount > 0 && hunk.oldStart <= finding.line && end >= start) return [];
+      if (hunk.oldCount > 0 ? end < start : hunk.oldStart < start) shift += hunk.newCount - hunk.oldCount;
+    }
+    return [{ ...finding, line: finding.line + shift, ...(finding.start_line && { start_line: finding.start_line + shift }) }];
+  });
+}
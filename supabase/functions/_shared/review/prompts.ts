@@ -1,4 +1,4 @@
-import { ReviewCategory } from "./models";
+import { ReviewCategory } from "./models.ts";
 
 export enum Language {
   TYPESCRIPT = "typescript",
@@ -569,3 +569,18 @@ export function buildIncrementalPrompt(
     .replace("{diff_content}", diffContent)
     .replace("{review_focus}", focusParts.length > 0 ? focusParts.join("\n") : "General code quality review");
 }
+
+// Files summary for one pass of a multi-pass review
+export function buildBatchSummary(
+  pass: number,
+  totalPasses: number,
+  batchFilesSummary: string,
+  allFilesSummary: string
+): string {
+  return `${batchFilesSummary}
+
+## Review Pass ${pass} of ${totalPasses}
+This PR is too large for one pass. Only comment on the files in the diff below.
+For context, the full PR changes:
+${allFilesSummary}`;
+}
@@ -0,0 +1,351 @@
+// Security scan findings: scoring, and SARIF 2.1.0 in both directions so scans
+// can be pushed to GitHub code scanning and other tools' results imported.
+import { parseSarif } from "./static-analysis.ts";
+import { ReviewCategory } from "./models.ts";
+
+// One entry in security_scans.issues, as SCAN_PROMPT asks for it
+export interface ScanFinding {
+  severity: string;
+  type?: string | null; // security, bug, quality
+  title: string;
+  file?: string | null;
+  line?: number | null;
+  problem?: string | null;
+  fix?: string | null;
+  cwe?: string | null;
+  // Set on findings imported from another tool
+  tool?: string | null;
+  rule?: string | null;
+  help_url?: string | null;
+}
+
+export interface ScanScore {
+  score: number;
+  grade: string;
+  threat_level: string;
+  by_severity: { critical: number; high: number; medium: number; low: number };
+}
+
+export const SCAN_TOOL_NAME = "FoodShare Scan";
+
+export function scoreFindings(findings: Pick<ScanFinding, "severity">[]): ScanScore {
+  const count = (severity: string) => findings.filter((f) => f.severity === severity).length;
+  const c = count("critical");
+  const h = count("high");
+  const m = count("medium");
+  const l = count("low");
+  const score = Math.max(0, Math.min(100, 100 - c * 25 - h * 15 - m * 5 - l * 2));
+  return {
+    score,
+    grade: score >= 90 ? "A" : score >= 80 ? "B" : score >= 70 ? "C" : score >= 60 ? "D" : "F",
+    threat_level: c > 0 ? "CRITICAL" : h > 0 ? "HIGH" : m > 0 ? "MEDIUM" : l > 0 ? "LOW" : "SAFE",
+    by_severity: { critical: c, high: h, medium: m, low: l },
+  };
+}
+
+const SARIF_SCHEMA = "https://json.schemastore.org/sarif-2.1.0.json";
+
+const LEVELS: Record<string, string> = { critical: "error", high: "error", medium: "warning", low: "note", info: "note" };
+
+// GitHub ranks security alerts by this CVSS-style score
+const SECURITY_SEVERITY: Record<string, string> = { critical: "9.5", high: "8.0", medium: "5.5", low: "2.0", info: "0.0" };
+
+export interface SarifScan {
+  repo_full_name: string;
+  issues: unknown[];
+  scan_metadata?: Record<string, unknown> | null;
+}
+
+const slug = (text: string) => text.toLowerCase().replace(/[^a-z0-9]+/g, "-").replace(/^-|-$/g, "").slice(0, 60) || "issue";
+
+function ruleId(f: ScanFinding): string {
+  if (f.rule) return f.rule;
+  if (f.cwe) return `${f.type || "security"}/${f.cwe.toLowerCase()}`;
+  return `${f.type || "issue"}/${slug(f.title)}`;
+}
+
+/** A SARIF 2.1.0 log for a scan: one run per tool, ours plus any imported ones. */
+export function scanToSarif(scan: SarifScan): Record<string, unknown> {
+  const byTool = new Map<string, ScanFinding[]>();
+  for (const issue of scan.issues as ScanFinding[]) {
+    if (!issue?.title) continue;
+    const tool = issue.tool || SCAN_TOOL_NAME;
+    byTool.set(tool, [...(byTool.get(tool) || []), issue]);
+  }
+  if (!byTool.size) byTool.set(SCAN_TOOL_NAME, []);
+
+  const meta = scan.scan_metadata || {};
+  const provenance = {
+    repositoryUri: `https://github.com/${scan.repo_full_name}`,
+    ...(typeof meta.commit_sha === "string" && { revisionId: meta.commit_sha }),
+    ...(typeof meta.branch === "string" && { branch: meta.branch }),
+  };
+
+  const runs = [...byTool].map(([tool, findings]) => {
+    const rules: Record<string, unknown>[] = [];
+    const ruleIndex = new Map<string, number>();
+    const results = findings.map((f) => {
+      const id = ruleId(f);
+      const severity = String(f.severity || "medium").toLowerCase();
+      if (!ruleIndex.has(id)) {
+        ruleIndex.set(id, rules.length);
+        const security = f.type === "security" || !!f.cwe;
+        rules.push({
+          id,
+          shortDescription: { text: f.title },
+          ...(f.help_url && { helpUri: f.help_url }),
+          properties: {
+            tags: [f.type || "quality", ...(security && f.type !== "security" ? ["security"] : []), ...(f.cwe ? [`external/cwe/${f.cwe.toLowerCase()}`] : [])],
+            ...(security && { "security-severity": SECURITY_SEVERITY[severity] || "5.5" }),
+          },
+        });
+      }
+      const text = [f.title, f.problem, f.fix && `Fix: ${f.fix}`].filter(Boolean).join("\n\n");
+      return {
+        ruleId: id,
+        ruleIndex: ruleIndex.get(id),
+        level: LEVELS[severity] || "warning",
+        message: { text },
+        ...(f.file && {
+          locations: [{
+            physicalLocation: {
+              artifactLocation: { uri: f.file.replace(/^\.?\//, ""), uriBaseId: "%SRCROOT%" },
+              region: { startLine: Math.max(1, f.line || 1) },
+            },
+          }],
+        }),
+        properties: { severity, ...(f.cwe && { cwe: f.cwe }) },
+      };
+    });
+    return {
+      tool: { driver: { name: tool, rules } },
+      versionControlProvenance: [provenance],
+      results,
+    };
+  });
+
+  return { $schema: SARIF_SCHEMA, version: "2.1.0", runs };
+}
+
+/** Throws unless the value looks like a SARIF 2.1.0 log. */
+export function assertSarif(log: unknown): asserts log is { version: string; runs: unknown[] } {
+  const candidate = log as { version?: unknown; runs?: unknown } | null;
+  if (!candidate || typeof candidate !== "object" || candidate.version !== "2.1.0" || !Array.isArray(candidate.runs)) {
+    throw new Error("Not a SARIF 2.1.0 log");
+  }
+}
+
+export interface SarifProvenance {
+  repo: string | null; // owner/repo
+  commit_sha?: string;
+  branch?: string;
+}
+
+/** The GitHub repo, commit and branch a log's version control provenance names. */
+export function sarifProvenance(log: unknown): SarifProvenance {
+  for (const run of (log as { runs?: any[] })?.runs || []) {
+    for (const vcs of run?.versionControlProvenance || []) {
+      const match = String(vcs?.repositoryUri || "").match(/github\.com[/:]([\w.-]+\/[\w.-]+?)(?:\.git)?\/?$/);
+      if (!match) continue;
+      return {
+        repo: match[1]!,
+        ...(typeof vcs.revisionId === "string" && { commit_sha: vcs.revisionId }),
+        ...(typeof vcs.branch === "string" && { branch: vcs.branch.replace(/^refs\/heads\//, "") }),
+      };
+    }
+  }
+  return { repo: null };
+}
+
+const FINDING_TYPES: Partial<Record<string, string>> = { [ReviewCategory.SECURITY]: "security", [ReviewCategory.BUG]: "bug" };
+
+/** Scan findings from a SARIF log, keeping the reporting tool and rule. Results without a location are dropped. */
+export function sarifToFindings(log: unknown, root = ""): ScanFinding[] {
+  assertSarif(log);
+  return parseSarif(log, root).map((f) => {
+    const [title = f.rule] = f.message.split("\n");
+    return {
+      severity: f.severity,
+      type: FINDING_TYPES[f.category] || "quality",
+      title: title.slice(0, 200),
+      file: f.path,
+      line: f.line,
+      problem: f.message,
+      cwe: f.cwe || null,
+      tool: f.tool === SCAN_TOOL_NAME.toLowerCase() ? null : f.tool,
+      rule: f.rule,
+      help_url: f.help_url || null,
+    };
+  });
+}
+
+// Full-repository scans walk the tree in batches that fit the model's context,
+// one LLM call per batch, and keep each file's outcome so a run can resume
+// across invocations and report the coverage it actually got.
+
+export interface TreeEntry {
+  path: string;
+  type: string;
+  size?: number;
+  sha?: string;
+}
+
+export interface PlannedScanFile {
+  path: string;
+  blob_sha: string | null;
+  size: number;
+  priority: number;
+  status: "pending" | "skipped";
+  skip_reason?: string;
+}
+
+const SOURCE_FILE = /\.(ts|tsx|js|jsx|py|go|rs|java|rb|php|swift|kt|cs|c|cpp|h)$/i;
+const IGNORED_PATH = /(node_modules|vendor|dist|build|\.min\.|__tests__|__mocks__|\.test\.|\.spec\.|__pycache__|\.next|\.git|coverage)/i;
+// Smaller files are stubs and re-exports, not worth a slot in a batch
+const MIN_FILE_BYTES = 50;
+
+/** Lower goes first: security-sensitive files, then entry points, data and config. */
+export function scanPriority(path: string): number {
+  if (/auth|login|password|secret|token|api|admin|payment|crypto|session/i.test(path)) return 0;
+  if (/route|controller|handler|middleware|service/i.test(path)) return 1;
+  if (/database|model|schema|migration/i.test(path)) return 2;
+  if (/config|env|setting/i.test(path)) return 3;
+  return 10;
+}
+
+/**
+ * The source files of a tree in scan order. Files over `maxFileBytes` or past
+ * `maxFiles` are kept as skipped, with the reason, so coverage counts them.
+ */
+export function planScanFiles(tree: TreeEntry[], { maxFileBytes, maxFiles }: { maxFileBytes: number; maxFiles: number }): PlannedScanFile[] {
+  const files = tree
+    .filter((f) => f.type === "blob" && (f.size ?? 0) > MIN_FILE_BYTES && SOURCE_FILE.test(f.path) && !IGNORED_PATH.test(f.path))
+    .map((f) => ({ path: f.path, blob_sha: f.sha || null, size: f.size ?? 0, priority: scanPriority(f.path) }))
+    .sort((a, b) => a.priority - b.priority || a.path.localeCompare(b.path));
+
+  let planned = 0;
+  return files.map((f): PlannedScanFile => {
+    if (f.size > maxFileBytes) return { ...f, status: "skipped", skip_reason: "too large" };
+    if (planned >= maxFiles) return { ...f, status: "skipped", skip_reason: "file limit" };
+    planned++;
+    return { ...f, status: "pending" };
+  });
+}
+
+export interface ScanChunk {
+  path: string;
+  start_line: number;
+  end_line: number;
+  text: string; // line-numbered
+}
+
+// Minified or generated lines would eat a batch on their own
+const MAX_LINE_CHARS = 500;
+
+/** A file as line-numbered chunks of at most `maxChars`, so findings can cite real lines. */
+export function chunkFile(path: string, content: string, maxChars: number): ScanChunk[] {
+  const lines = content.split("\n");
+  const width = String(lines.length).length;
+  const chunks: ScanChunk[] = [];
+  let current: string[] = [];
+  let size = 0;
+  let start = 1;
+
+  lines.forEach((raw, i) => {
+    const text = raw.length > MAX_LINE_CHARS ? `${raw.slice(0, MAX_LINE_CHARS)} …` : raw;
+    const line = `${String(i + 1).padStart(width)}| ${text}`;
+    if (current.length && size + line.length + 1 > maxChars) {
+      chunks.push({ path, start_line: start, end_line: i, text: current.join("\n") });
+      current = [];
+      size = 0;
+      start = i + 1;
+    }
+    current.push(line);
+    size += line.length + 1;
+  });
+  if (current.length) chunks.push({ path, start_line: start, end_line: lines.length, text: current.join("\n") });
+  return chunks;
+}
+
+const chunkHeader = (c: ScanChunk) => `=== ${c.path} (lines ${c.start_line}-${c.end_line}) ===`;
+
+/** Chunks packed in order into batches of at most `maxChars` of prompt text each. */
+export function packScanBatches(chunks: ScanChunk[], maxChars: number): ScanChunk[][] {
+  const batches: ScanChunk[][] = [];
+  let current: ScanChunk[] = [];
+  let size = 0;
+  for (const chunk of chunks) {
+    const length = chunkHeader(chunk).length + chunk.text.length + 3;
+    if (current.length && size + length > maxChars) {
+      batches.push(current);
+      current = [];
+      size = 0;
+    }
+    current.push(chunk);
+    size += length;
+  }
+  if (current.length) batches.push(current);
+  return batches;
+}
+
+export function formatScanBatch(batch: ScanChunk[]): string {
+  return batch.map((c) => `${chunkHeader(c)}\n${c.text}`).join("\n\n");
+}
+
+/**
+ * A batch's findings grouped by the file they belong to, with `file` set to
+ * its full path. The model sometimes shortens or drops paths: a suffix match
+ * counts, and in a single-file batch everything belongs to that file.
+ * Findings naming no file of the batch are dropped.
+ */
+export function assignFindings<T extends Pick<ScanFinding, "file">>(findings: T[], paths: string[]): Map<string, T[]> {
+  const byPath = new Map(paths.map((p) => [p, [] as T[]]));
+  for (const finding of findings) {
+    const named = (finding.file || "").trim().replace(/^\.?\//, "");
+    const path = paths.find((p) => p === named)
+      || (named ? paths.find((p) => p.endsWith(`/${named}`) || named.endsWith(`/${p}`)) : undefined)
+      || (paths.length === 1 ? paths[0] : undefined);
+    if (path) byPath.get(path)!.push({ ...finding, file: path });
+  }
+  return byPath;
+}
+
+export interface ScanCoverage {
+  total_files: number;
+  scanned_files: number;
+  skipped_files: number;
+  failed_files: number;
+  pending_files: number;
+  percent: number;
+  skipped_reasons: Record<string, number>;
+}
+
+export function scanCoverage(files: { status: string; skip_reason?: string | null }[]): ScanCoverage {
+  const count = (status: string) => files.filter((f) => f.status === status).length;
+  const skipped_reasons: Record<string, number> = {};
+  for (const f of files) {
+    if (f.status === "skipped") skipped_reasons[f.skip_reason || "other"] = (skipped_reasons[f.skip_reason || "other"] || 0) + 1;
+  }
+  const scanned = count("scanned");
+  return {
+    total_files: files.length,
+    scanned_files: scanned,
+    skipped_files: count("skipped"),
+    failed_files: count("failed"),
+    pending_files: count("pending"),
+    percent: files.length ? Math.floor((scanned / files.length) * 1000) / 10 : 0,
+    skipped_reasons,
+  };
+}
+
+/** "Scanned 180 of 200 source files (90%): 15 skipped (too large), 5 failed." */
+export function coverageSummary(coverage: ScanCoverage): string {
+  const { total_files: total, scanned_files: scanned, skipped_files: skipped, failed_files: failed, pending_files: pending } = coverage;
+  const reasons = Object.entries(coverage.skipped_reasons).map(([reason, n]) => `${n} ${reason}`).join(", ");
+  const gaps = [
+    skipped && `${skipped} skipped${reasons ? ` (${reasons})` : ""}`,
+    failed && `${failed} failed`,
+    pending && `${pending} not reached`,
+  ].filter(Boolean);
+  return `Scanned ${scanned} of ${total} source files (${coverage.percent}%)${gaps.length ? `: ${gaps.join(", ")}` : ""}.`;
+}
@@ -0,0 +1,45 @@
+import { parseCategory, parseSeverity } from "./models.ts";
+import type { CodeReviewResult } from "./models.ts";
+
+// The app imports zod from npm and the edge worker from esm.sh, so the schema is
+// written once here and built with whichever zod the runtime passes in.
+// deno-lint-ignore no-explicit-any
+type Zod = any;
+
+export type ReviewResponse = Pick<CodeReviewResult, "summary" | "walkthrough" | "line_comments" | "approval_recommendation">;
+
+// Matches the JSON format requested by SYSTEM_PROMPT / INCREMENTAL_SYSTEM_PROMPT
+export function defineReviewSchema(z: Zod) {
+  // LLMs often send null for optional fields; treat it as absent
+  const optionalString = z.string().nullish().transform((v: string | null | undefined) => v || undefined);
+  const stringList = z.array(z.coerce.string()).nullish().transform((v: string[] | null | undefined) => v ?? []);
+
+  const lineCommentSchema = z.object({
+    path: z.string(),
+    line: z.coerce.number().int(),
+    body: z.string(),
+    severity: z.string().default("medium").transform(parseSeverity),
+    category: z.string().default("other").transform(parseCategory),
+    start_line: z.coerce.number().int().positive().nullish().catch(undefined).transform((v: number | null | undefined) => v ?? undefined),
+    suggestion: optionalString,
+  });
+
+  const fileWalkthroughSchema = z.object({
+    path: z.string(),
+    summary: z.string().default(""),
+    changes: stringList,
+  });
+
+  return z.object({
+    summary: z.object({
+      overview: z.string().default(""),
+      changes_description: z.string().default(""),
+      risk_assessment: z.string().default("Unknown"),
+      recommendations: stringList,
+      praise: stringList,
+    }),
+    walkthrough: z.array(fileWalkthroughSchema).nullish().transform((v: unknown[] | null | undefined) => v ?? []),
+    line_comments: z.array(lineCommentSchema).nullish().transform((v: unknown[] | null | undefined) => v ?? []),
+    approval_recommendation: z.enum(["approve", "request_changes", "comment"]).catch("comment"),
+  });
+}
@@ -0,0 +1,247 @@
+import type { DiffHunk, FileDiff } from "./analyzer.ts";
+import { ReviewCategory, Severity, type LineComment } from "./models.ts";
+
+/**
+ * Rule-based secret detection. Runs before the LLM sees anything: findings
+ * become fixed-wording critical comments, and every match is redacted from
+ * the diff, PR text and scanned files so credentials never leave the worker.
+ */
+
+export interface SecretRule {
+  id: string;
+  name: string;
+  pattern: RegExp;
+  // Which capture group holds the secret; the whole match by default
+  group?: number;
+  // Extra check on the captured value, e.g. entropy for generic assignments
+  accept?: (value: string) => boolean;
+}
+
+export interface SecretFinding {
+  path: string;
+  line: number;
+  rule: string;
+  name: string;
+}
+
+interface SecretMatch {
+  rule: SecretRule;
+  start: number;
+  end: number;
+}
+
+// Shannon entropy in bits per character
+export function entropy(value: string): number {
+  const counts = new Map<string, number>();
+  for (const ch of value) counts.set(ch, (counts.get(ch) || 0) + 1);
+  let bits = 0;
+  for (const n of counts.values()) {
+    const p = n / value.length;
+    bits -= p * Math.log2(p);
+  }
+  return bits;
+}
+
+const PLACEHOLDER = /^(?:<[^>]*>|\$\{[^}]*\}|\{\{[^}]*\}\}|changeme|change_me|example\w*|your[_-]\w*|x{4,}|\*{3,}|\.{3,}|null|none|true|false)$/i;
+
+const looksRandom = (value: string, minBits: number) =>
+  !PLACEHOLDER.test(value) && entropy(value) >= minBits && /\d/.test(value) && /[a-zA-Z]/.test(value);
+
+// Specific provider formats first so overlapping generic rules don't claim the match
+export const SECRET_RULES: SecretRule[] = [
+  { id: "private-key", name: "private key", pattern: /-----BEGIN (?:[A-Z0-9]+ )*PRIVATE KEY(?: BLOCK)?-----/ },
+  { id: "aws-access-key", name: "AWS access key", pattern: /\b(?:AKIA|ASIA)[0-9A-Z]{16}\b/ },
+  { id: "github-token", name: "GitHub token", pattern: /\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})\b/ },
+  { id: "anthropic-key", name: "Anthropic API key", pattern: /\bsk-ant-[A-Za-z0-9_-]{20,}/ },
+  { id: "openai-key", name: "OpenAI API key", pattern: /\bsk-(?:proj-|svcacct-)?[A-Za-z0-9_-]{20,}/ },
+  { id: "groq-key", name: "Groq API key", pattern: /\bgsk_[A-Za-z0-9]{20,}\b/ },
+  { id: "stripe-key", name: "Stripe secret key", pattern: /\b[rs]k_(?:live|test)_[A-Za-z0-9]{16,}\b/ },
+  { id: "slack-token", name: "Slack token", pattern: /\bxox[abprs]-[A-Za-z0-9-]{10,}\b/ },
+  { id: "google-api-key", name: "Google API key", pattern: /\bAIza[0-9A-Za-z_-]{35}\b/ },
+  { id: "jwt", name: "JSON Web Token", pattern: /\beyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}/ },
+  {
+    id: "connection-string",
+    name: "database password",
+    pattern: /\b(?:postgres(?:ql)?|mysql|mongodb(?:\+srv)?|redis|amqps?):\/\/[^:/\s"'@]+:([^@\s"']{6,})@/,
+    group: 1,
+    accept: (v) => !PLACEHOLDER.test(v),
+  },
+  {
+    id: "generic-secret",
+    name: "hard-coded secret",
+    pattern: /(?:secret|token|passw(?:or)?d|pwd|api[_-]?key|access[_-]?key|private[_-]?key|client[_-]?secret)[\w-]*["']?\s*[:=]\s*["'`]?([A-Za-z0-9+/=_\-.~]{16,})/i,
+    group: 1,
+    accept: (v) => looksRandom(v, 3.5),
+  },
+  {
+    id: "high-entropy",
+    name: "high-entropy string",
+    pattern: /["'`]([A-Za-z0-9+/=_-]{32,})["'`]/,
+    group: 1,
+    accept: (v) => looksRandom(v, 4.5) && /[A-Z]/.test(v) && /[a-z]/.test(v),
+  },
+];
+
+const ENV_FILE = /(?:^|\/)\.env(?:\.[\w-]+)?$/;
+const ENV_TEMPLATE = /\.(?:example|sample|template|dist|defaults)$/;
+const ENV_ASSIGNMENT = /^\s*(?:export\s+)?([A-Za-z_][A-Za-z0-9_]*)\s*=\s*(["']?)(.+?)\2\s*$/;
+const SENSITIVE_KEY = /SECRET|TOKEN|PASSW|PWD|API_?KEY|PRIVATE|CREDENTIAL|AUTH|DSN/i;
+const ENV_RULE: SecretRule = { id: "env-file", name: ".env secret", pattern: /$^/ };
+
+export function isEnvFile(path: string): boolean {
+  return ENV_FILE.test(path) && !ENV_TEMPLATE.test(path);
+}
+
+// Lock files are full of integrity hashes that look random but aren't secret
+const SKIP_FILES = /(?:^|\/)(?:package-lock\.json|yarn\.lock|pnpm-lock\.yaml|Cargo\.lock|poetry\.lock|Gemfile\.lock|composer\.lock|go\.sum)$/;
+
+const redacted = (rule: SecretRule) => `[REDACTED:${rule.id}]`;
+
+function matchLine(text: string, envFile: boolean): SecretMatch[] {
+  const matches: SecretMatch[] = [];
+  const overlaps = (start: number, end: number) => matches.some((m) => start < m.end && end > m.start);
+
+  for (const rule of SECRET_RULES) {
+    const global = new RegExp(rule.pattern.source, rule.pattern.flags.includes("g") ? rule.pattern.flags : rule.pattern.flags + "g");
+    for (const m of text.matchAll(global)) {
+      const value = rule.group ? m[rule.group] : m[0];
+      if (!value || (rule.accept && !rule.accept(value))) continue;
+      const start = m.index! + (rule.group ? m[0].indexOf(value) : 0);
+      const end = start + value.length;
+      if (!overlaps(start, end)) matches.push({ rule, start, end });
+    }
+  }
+
+  // Committed .env files: flag sensitive keys and random-looking values that no rule recognised
+  if (envFile && !matches.length) {
+    const m = text.match(ENV_ASSIGNMENT);
+    const value = m?.[3]?.trim();
+    if (m && value && !PLACEHOLDER.test(value) && (SENSITIVE_KEY.test(m[1]!) || looksRandom(value, 3))) {
+      const start = text.lastIndexOf(value);
+      matches.push({ rule: ENV_RULE, start, end: start + value.length });
+    }
+  }
+
+  return matches.sort((a, b) => a.start - b.start);
+}
+
+function redactMatches(text: string, matches: SecretMatch[]): string {
+  let out = text;
+  for (const m of [...matches].sort((a, b) => b.start - a.start)) {
+    out = out.slice(0, m.start) + redacted(m.rule) + out.slice(m.end);
+  }
+  return out;
+}
+
+/**
+ * Scan lines of one file. `added` says which lines count as new (all of them
+ * for whole files); only those produce findings, but every match is redacted.
+ */
+function scanLines(
+  path: string,
+  lines: { text: string; line: number | null; added: boolean }[]
+): { lines: string[]; findings: SecretFinding[] } {
+  const envFile = isEnvFile(path);
+  const findings: SecretFinding[] = [];
+  let inKey = false;
+
+  const out = lines.map(({ text, line, added }) => {
+    if (inKey) {
+      // Key material between BEGIN and END is redacted wholesale
+      if (/-----END [A-Z0-9 ]*PRIVATE KEY(?: BLOCK)?-----/.test(text)) inKey = false;
+      return text.trim() ? "[REDACTED:private-key]" : text;
+    }
+
+    const matches = matchLine(text, envFile);
+    if (matches.some((m) => m.rule.id === "private-key") && !/-----END /.test(text)) inKey = true;
+    if (!matches.length) return text;
+
+    if (added && line !== null) {
+      for (const rule of new Set(matches.map((m) => m.rule))) {
+        findings.push({ path, line, rule: rule.id, name: rule.name });
+      }
+    }
+    return redactMatches(text, matches);
+  });
+
+  return { lines: out, findings };
+}
+
+function scanHunk(hunk: DiffHunk): { hunk: DiffHunk; findings: SecretFinding[] } {
+  const [header, ...body] = hunk.content.split("\n");
+  let line = hunk.newStart;
+  const entries = body.map((raw) => {
+    const prefix = raw[0] ?? "";
+    const entry = { text: raw.slice(1), line: null as number | null, added: prefix === "+" };
+    if (prefix === "+" || prefix === " " || raw === "") entry.line = line++;
+    return { prefix, entry };
+  });
+
+  const { lines, findings } = scanLines(hunk.path, entries.map((e) => e.entry));
+  const content = [header, ...lines.map((text, i) => {
+    const prefix = entries[i]!.prefix;
+    // "\ No newline" markers and blank context lines pass through untouched
+    return prefix === "\\" || body[i] === "" ? body[i]! : prefix + text;
+  })].join("\n");
+
+  return { hunk: { ...hunk, content }, findings };
+}
+
+/** Redact secrets from parsed diff files and report the ones added by this change. */
+export function scanDiffSecrets(files: FileDiff[]): { files: FileDiff[]; findings: SecretFinding[] } {
+  const findings: SecretFinding[] = [];
+  const scanned = files.map((file) => {
+    if (SKIP_FILES.test(file.path)) return file;
+    const hunks = file.hunks.map((h) => {
+      const result = scanHunk(h);
+      findings.push(...result.findings);
+      return result.hunk;
+    });
+    return { ...file, hunks };
+  });
+  return { files: scanned, findings };
+}
+
+/** Redact and report secrets in a whole file, as read from the repo. */
+export function scanFileSecrets(path: string, content: string): { content: string; findings: SecretFinding[] } {
+  if (SKIP_FILES.test(path)) return { content, findings: [] };
+  const { lines, findings } = scanLines(path, content.split("\n").map((text, i) => ({ text, line: i + 1, added: true })));
+  return { content: lines.join("\n"), findings };
+}
+
+/** Redact secrets from free text such as a PR title or description. */
+export function redactSecrets(text: string): string {
+  return scanFileSecrets("", text).content;
+}
+
+export const SECRET_COMMENT =
+  "This line contains what looks like a live credential. Treat it as compromised: rotate it, remove it from the code, and load it from the environment or a secret manager. Deleting it in a later commit does not remove it from git history.";
+
+/** Fixed-wording critical comments for secret findings, one per line. */
+export function secretComments(findings: SecretFinding[]): LineComment[] {
+  const byLine = new Map<string, SecretFinding[]>();
+  for (const f of findings) {
+    const key = `${f.path}:${f.line}`;
+    byLine.set(key, [...(byLine.get(key) || []), f]);
+  }
+  return [...byLine.values()].map((group) => {
+    const names = [...new Set(group.map((f) => f.name))];
+    return {
+      path: group[0]!.path,
+      line: group[0]!.line,
+      severity: Severity.CRITICAL,
+      category: ReviewCategory.SECURITY,
+      body: `**Secret detected: ${names.join(", ")}.** ${SECRET_COMMENT}`,
+    };
+  });
+}
+
+/** Secret comments first; LLM security comments on the same lines are dropped as duplicates. */
+export function mergeSecretComments(comments: LineComment[], findings: SecretFinding[]): LineComment[] {
+  if (!findings.length) return comments;
+  const flagged = new Set(findings.map((f) => `${f.path}:${f.line}`));
+  return [
+    ...secretComments(findings),
+    ...comments.filter((c) => !(c.category === ReviewCategory.SECURITY && flagged.has(`${c.path}:${c.line}`))),
+  ];
+}
@@ -0,0 +1,279 @@
+// Findings from linters and scanners (ESLint, Semgrep, Ruff, clippy, gitleaks)
+// run on a checkout of the PR head. Parsing, filtering and merging live here;
+// running the tools is left to the runtime, since the edge worker can't.
+import type { FileDiff } from "./analyzer.ts";
+import { getHunkLines } from "./placement.ts";
+import { ReviewCategory, Severity, type LineComment } from "./models.ts";
+import { redactSecrets } from "./secrets.ts";
+
+export interface AnalyzerFinding {
+  tool: string;
+  rule: string;
+  path: string;
+  line: number;
+  end_line?: number;
+  severity: Severity;
+  category: ReviewCategory;
+  message: string;
+  help_url?: string;
+  cwe?: string; // e.g. CWE-89
+}
+
+// Most findings a review prompt carries; the rest are still posted
+export const MAX_PROMPT_FINDINGS = 40;
+
+const SEVERITY_RANK: Record<string, number> = { critical: 4, high: 3, medium: 2, low: 1, info: 0 };
+
+/** Repo-relative path from a tool's absolute path or file:// URI. */
+export function relativePath(path: string, root = ""): string {
+  let p = decodeURIComponent(path.replace(/^file:\/\//, ""));
+  const base = root.replace(/\/+$/, "");
+  if (base && p.startsWith(`${base}/`)) p = p.slice(base.length + 1);
+  return p.replace(/^\.?\//, "");
+}
+
+function json(output: string | unknown): any {
+  if (typeof output !== "string") return output;
+  return output.trim() ? JSON.parse(output) : null;
+}
+
+function categoryFromTags(tags: unknown, fallback: ReviewCategory): ReviewCategory {
+  const text = (Array.isArray(tags) ? tags : [tags]).join(" ").toLowerCase();
+  if (/security|cwe|owasp|secret/.test(text)) return ReviewCategory.SECURITY;
+  if (/performance/.test(text)) return ReviewCategory.PERFORMANCE;
+  if (/correctness|bug|error/.test(text)) return ReviewCategory.BUG;
+  return fallback;
+}
+
+/** First CWE id in tags or metadata, normalized to `CWE-<n>`. */
+export function findCwe(values: unknown): string | undefined {
+  const match = [values].flat(2).join(" ").match(/cwe[-_/:\s]*(\d+)/i);
+  return match ? `CWE-${match[1]}` : undefined;
+}
+
+const SARIF_LEVELS: Record<string, Severity> = { error: Severity.HIGH, warning: Severity.MEDIUM, note: Severity.LOW, none: Severity.INFO };
+
+// GitHub's convention for CVSS-style scores in SARIF rule properties
+function securitySeverity(score: unknown): Severity | undefined {
+  const n = Number(score);
+  if (!Number.isFinite(n) || score === undefined || score === null || score === "") return undefined;
+  if (n >= 9) return Severity.CRITICAL;
+  if (n >= 7) return Severity.HIGH;
+  if (n >= 4) return Severity.MEDIUM;
+  return Severity.LOW;
+}
+
+/** SARIF 2.1.0, as written by Semgrep, gitleaks, ESLint's SARIF formatter and most scanners. */
+export function parseSarif(output: string | unknown, root = ""): AnalyzerFinding[] {
+  const log = json(output);
+  const findings: AnalyzerFinding[] = [];
+  for (const run of log?.runs || []) {
+    const driver = run.tool?.driver || {};
+    const tool = String(driver.name || "sarif").toLowerCase();
+    const rules = new Map<string, any>((driver.rules || []).map((r: any) => [r.id, r]));
+    for (const result of run.results || []) {
+      const location = result.locations?.[0]?.physicalLocation;
+      const uri = location?.artifactLocation?.uri;
+      const line = location?.region?.startLine;
+      if (!uri || !line) continue;
+      const rule = rules.get(result.ruleId) || driver.rules?.[result.ruleIndex] || {};
+      const props = { ...rule.properties, ...result.properties };
+      const level = result.level || rule.defaultConfiguration?.level || "warning";
+      findings.push({
+        tool,
+        rule: result.ruleId || rule.id || "unknown",
+        path: relativePath(uri, root),
+        line,
+        end_line: location.region.endLine,
+        severity: tool === "gitleaks"
+          ? Severity.CRITICAL
+          : securitySeverity(props["security-severity"]) || SARIF_LEVELS[level] || Severity.MEDIUM,
+        category: tool === "gitleaks" ? ReviewCategory.SECURITY : categoryFromTags(props.tags, ReviewCategory.BEST_PRACTICES),
+        message: result.message?.text || rule.shortDescription?.text || result.ruleId || "",
+        help_url: rule.helpUri,
+        cwe: findCwe([props.cwe, props.tags]),
+      });
+    }
+  }
+  return findings;
+}
+
+/** `eslint --format json` */
+export function parseEslint(output: string | unknown, root = ""): AnalyzerFinding[] {
+  const files = json(output) || [];
+  return files.flatMap((file: any) => (file.messages || []).filter((m: any) => m.line).map((m: any): AnalyzerFinding => ({
+    tool: "eslint",
+    rule: m.ruleId || "parse-error",
+    path: relativePath(file.filePath, root),
+    line: m.line,
+    end_line: m.endLine,
+    // Without a rule id the file didn't parse
+    severity: !m.ruleId ? Severity.HIGH : m.severity === 2 ? Severity.MEDIUM : Severity.LOW,
+    category: !m.ruleId
+      ? ReviewCategory.BUG
+      : categoryFromTags(m.ruleId.split("/")[0], ReviewCategory.BEST_PRACTICES),
+    message: m.message,
+  })));
+}
+
+const SEMGREP_SEVERITIES: Record<string, Severity> = { ERROR: Severity.HIGH, WARNING: Severity.MEDIUM, INFO: Severity.LOW };
+
+/** `semgrep scan --json` */
+export function parseSemgrep(output: string | unknown, root = ""): AnalyzerFinding[] {
+  const report = json(output);
+  return (report?.results || []).map((r: any): AnalyzerFinding => {
+    const metadata = r.extra?.metadata || {};
+    const references = Array.isArray(metadata.references) ? metadata.references : [];
+    return {
+      tool: "semgrep",
+      rule: r.check_id,
+      path: relativePath(r.path, root),
+      line: r.start?.line,
+      end_line: r.end?.line,
+      severity: SEMGREP_SEVERITIES[r.extra?.severity] || Severity.MEDIUM,
+      category: categoryFromTags([metadata.category, metadata.cwe].flat(), ReviewCategory.BEST_PRACTICES),
+      message: r.extra?.message || r.check_id,
+      help_url: metadata.source || references[0],
+      cwe: findCwe(metadata.cwe),
+    };
+  }).filter((f: AnalyzerFinding) => f.line);
+}
+
+/** `ruff check --output-format json` */
+export function parseRuff(output: string | unknown, root = ""): AnalyzerFinding[] {
+  const diagnostics = json(output) || [];
+  return diagnostics.filter((d: any) => d.location?.row).map((d: any): AnalyzerFinding => {
+    const code: string = d.code || "syntax-error";
+    // S = flake8-bandit, F = pyflakes, E9 = syntax errors
+    const security = code.startsWith("S");
+    const bug = code.startsWith("F") || code.startsWith("E9") || !d.code;
+    return {
+      tool: "ruff",
+      rule: code,
+      path: relativePath(d.filename, root),
+      line: d.location.row,
+      end_line: d.end_location?.row,
+      severity: !d.code || code.startsWith("E9") ? Severity.HIGH : security || bug ? Severity.MEDIUM : Severity.LOW,
+      category: security ? ReviewCategory.SECURITY : bug ? ReviewCategory.BUG : ReviewCategory.STYLE,
+      message: d.message,
+      help_url: d.url || undefined,
+    };
+  });
+}
+
+/** `cargo clippy --message-format=json`, one JSON message per line. */
+export function parseClippy(output: string, root = ""): AnalyzerFinding[] {
+  const findings: AnalyzerFinding[] = [];
+  for (const raw of output.split("\n")) {
+    if (!raw.startsWith("{")) continue;
+    let msg: any;
+    try {
+      msg = JSON.parse(raw);
+    } catch {
+      continue;
+    }
+    if (msg.reason !== "compiler-message" || !["error", "warning"].includes(msg.message?.level)) continue;
+    const span = (msg.message.spans || []).find((s: any) => s.is_primary);
+    if (!span) continue;
+    const code: string = msg.message.code?.code || (msg.message.level === "error" ? "compile-error" : "warning");
+    findings.push({
+      tool: "clippy",
+      rule: code,
+      path: relativePath(span.file_name, root),
+      line: span.line_start,
+      end_line: span.line_end,
+      severity: msg.message.level === "error" ? Severity.HIGH : Severity.LOW,
+      category: msg.message.level === "error" || code.startsWith("clippy::correctness")
+        ? ReviewCategory.BUG
+        : code.startsWith("clippy::") ? ReviewCategory.BEST_PRACTICES : ReviewCategory.BUG,
+      message: msg.message.message,
+    });
+  }
+  return findings;
+}
+
+/** `gitleaks --report-format json`. The secret itself is never copied. */
+export function parseGitleaks(output: string | unknown, root = ""): AnalyzerFinding[] {
+  const leaks = json(output) || [];
+  return leaks.filter((l: any) => l.File && l.StartLine).map((l: any): AnalyzerFinding => ({
+    tool: "gitleaks",
+    rule: l.RuleID || "secret",
+    path: relativePath(l.File, root),
+    line: l.StartLine,
+    end_line: l.EndLine,
+    severity: Severity.CRITICAL,
+    category: ReviewCategory.SECURITY,
+    message: `Possible secret: ${l.Description || l.RuleID}`,
+  }));
+}
+
+/**
+ * Keep findings that touch lines the PR adds, moved onto the first added line
+ * of their range. Findings in files outside the diff are dropped.
+ */
+export function findingsOnChangedLines(findings: AnalyzerFinding[], files: FileDiff[]): AnalyzerFinding[] {
+  const added = new Map(files.map((f) => [f.path, new Set(getHunkLines(f).flatMap((h) => h.added))]));
+  const kept: AnalyzerFinding[] = [];
+  for (const finding of findings) {
+    const lines = added.get(finding.path);
+    if (!lines) continue;
+    const end = Math.max(finding.line, finding.end_line || finding.line);
+    for (let line = finding.line; line <= end; line++) {
+      if (lines.has(line)) {
+        kept.push({ ...finding, line });
+        break;
+      }
+    }
+  }
+  return kept;
+}
+
+const normalizeMessage = (message: string) => message.toLowerCase().replace(/[^a-z0-9]+/g, " ").trim();
+
+/** One finding per tool/rule/line, and none repeating another tool's message on the same line. */
+export function dedupeFindings(findings: AnalyzerFinding[]): AnalyzerFinding[] {
+  const seen = new Set<string>();
+  const result: AnalyzerFinding[] = [];
+  const sorted = [...findings].sort((a, b) => (SEVERITY_RANK[b.severity] ?? 2) - (SEVERITY_RANK[a.severity] ?? 2));
+  for (const f of sorted) {
+    const keys = [`${f.tool}:${f.rule}:${f.path}:${f.line}`, `${f.path}:${f.line}:${normalizeMessage(f.message)}`];
+    if (keys.some((k) => seen.has(k))) continue;
+    keys.forEach((k) => seen.add(k));
+    result.push(f);
+  }
+  return result;
+}
+
+/** Prompt section listing analyzer findings, so the model can build on them instead of repeating them. */
+export function buildStaticAnalysisPrompt(findings: AnalyzerFinding[]): string {
+  if (!findings.length) return "";
+  const shown = findings.slice(0, MAX_PROMPT_FINDINGS);
+  const lines = shown.map((f) => `- ${f.path}:${f.line} [${f.tool} ${f.rule}, ${f.severity}] ${redactSecrets(f.message)}`);
+  if (findings.length > shown.length) lines.push(`- ...and ${findings.length - shown.length} more`);
+  return `## Static Analysis Findings
+These were reported by linters and scanners on the changed lines and will be posted separately.
+Do not repeat them. Use them as context: explain impact or point out related problems the tools missed.
+${lines.join("\n")}`;
+}
+
+/** Line comments for analyzer findings, attributed to the tool that reported them. */
+export function analyzerComments(findings: AnalyzerFinding[]): LineComment[] {
+  return findings.map((f) => ({
+    path: f.path,
+    line: f.line,
+    severity: f.severity,
+    category: f.category,
+    body: `${redactSecrets(f.message)} (\`${f.rule}\`)${f.help_url ? ` - [docs](${f.help_url})` : ""}`,
+    source: f.tool,
+  }));
+}
+
+/** Analyzer comments first; model comments repeating one (same line and category) are dropped. */
+export function mergeAnalyzerComments(comments: LineComment[], findings: AnalyzerFinding[]): LineComment[] {
+  if (!findings.length) return comments;
+  const covered = new Set(findings.map((f) => `${f.path}:${f.line}:${f.category}`));
+  return [
+    ...analyzerComments(findings),
+    ...comments.filter((c) => !covered.has(`${c.path}:${c.line}:${c.category}`)),
+  ];
+}
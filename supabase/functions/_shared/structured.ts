@@ -0,0 +1,76 @@
+// Shared by the app (re-exported from src/lib/llm/structured.ts) and the edge
+// functions. They load zod from npm and esm.sh respectively, so schemas are typed
+// by shape here and toJsonSchema is handed whichever zod the runtime has.
+// deno-lint-ignore no-explicit-any
+type Zod = any;
+
+export interface SchemaIssues {
+  issues: { path: PropertyKey[]; message: string }[];
+}
+
+export interface StructuredSchema<T> {
+  safeParse(data: unknown): { success: true; data: T } | { success: false; error: SchemaIssues };
+}
+
+export type StructuredParseResult<T> =
+  | { success: true; data: T }
+  | { success: false; error: string };
+
+export class StructuredOutputError extends Error {
+  constructor(public raw: string, public validationErrors: string) {
+    super(`LLM output failed schema validation: ${validationErrors}`);
+    this.name = "StructuredOutputError";
+  }
+}
+
+// JSON Schema for provider JSON modes. Uses the input side so transforms and defaults don't break generation.
+export function toJsonSchema(z: Zod, schema: StructuredSchema<unknown>): Record<string, unknown> {
+  return z.toJSONSchema(schema, { io: "input", unrepresentable: "any" }) as Record<string, unknown>;
+}
+
+// Pull the JSON payload out of a response that may be wrapped in prose or code fences
+export function extractJson(text: string): string {
+  const trimmed = text.trim();
+  const fenced = trimmed.match(/```(?:json)?\s*\n?([\s\S]*?)```/);
+  const body = (fenced ? fenced[1]! : trimmed).trim();
+  if (body.startsWith("{") || body.startsWith("[")) return body;
+
+  const starts = [body.indexOf("{"), body.indexOf("[")].filter((i) => i >= 0);
+  if (!starts.length) return body;
+  const start = Math.min(...starts);
+  const end = Math.max(body.lastIndexOf("}"), body.lastIndexOf("]"));
+  return end > start ? body.slice(start, end + 1) : body;
+}
+
+export function formatIssues(error: SchemaIssues): string {
+  return error.issues
+    .slice(0, 20)
+    .map((issue) => `- ${issue.path.length ? issue.path.map(String).join(".") : "(root)"}: ${issue.message}`)
+    .join("\n");
+}
+
+export function parseStructured<T>(raw: string, schema: StructuredSchema<T>): StructuredParseResult<T> {
+  let json: unknown;
+  try {
+    json = JSON.parse(extractJson(raw));
+  } catch (err) {
+    return { success: false, error: `- (root): invalid JSON (${err instanceof Error ? err.message : "parse error"})` };
+  }
+  const result = schema.safeParse(json);
+  return result.success ? { success: true, data: result.data } : { success: false, error: formatIssues(result.error) };
+}
+
+export function buildRepairPrompt(prompt: string, raw: string, errors: string): string {
+  return `${prompt}
+
+## Previous Response
+Your previous response did not match the required JSON format:
+\`\`\`
+${raw.slice(0, 4000)}
+\`\`\`
+
+## Validation Errors
+${errors}
+
+Respond again with ONLY valid JSON that fixes these errors. No prose, no code fences.`;
+}
@@ -0,0 +1,94 @@
+// Mirrors src/lib/llm/usage.ts and src/lib/budget.ts for the edge functions
+import type { SupabaseClient } from "https://esm.sh/@supabase/supabase-js@2";
+
+export interface TokenUsage {
+  promptTokens: number;
+  completionTokens: number;
+}
+
+export interface UsageTotals {
+  prompt_tokens: number;
+  completion_tokens: number;
+  cost_usd: number;
+}
+
+// USD per million tokens, [input, output]. Unlisted models (e.g. local Ollama) cost nothing.
+const DEFAULT_PRICING: Record<string, [number, number]> = {
+  "llama-3.1-8b-instant": [0.05, 0.08],
+  "llama-3.3-70b-versatile": [0.59, 0.79],
+};
+
+function pricing(): Record<string, [number, number]> {
+  const table = { ...DEFAULT_PRICING };
+  for (const entry of (Deno.env.get("LLM_PRICING") || "").split(",")) {
+    const [model, prices] = entry.split("=").map((s) => s.trim());
+    const [input, output] = (prices || "").split("/").map(Number);
+    if (model && Number.isFinite(input) && Number.isFinite(output)) table[model] = [input, output];
+  }
+  return table;
+}
+
+export function emptyUsage(): UsageTotals {
+  return { prompt_tokens: 0, completion_tokens: 0, cost_usd: 0 };
+}
+
+export function addUsage(totals: UsageTotals, model: string, usage: TokenUsage | undefined): UsageTotals {
+  if (!usage) return totals;
+  const [input, output] = pricing()[model] || [0, 0];
+  totals.prompt_tokens += usage.promptTokens;
+  totals.completion_tokens += usage.completionTokens;
+  totals.cost_usd = Math.round((totals.cost_usd + (usage.promptTokens * input + usage.completionTokens * output) / 1_000_000) * 1e6) / 1e6;
+  return totals;
+}
+
+export interface BudgetDecision {
+  action: "allow" | "downgrade" | "skip";
+  reason?: string;
+}
+
+/**
+ * Same rules as checkRepoBudget() in the app: repos over their monthly token or
+ * cost budget are downgraded or skipped, with one notification per month.
+ */
+export async function checkRepoBudget(supabase: SupabaseClient, fullName: string): Promise<BudgetDecision> {
+  const { data: budget } = await supabase
+    .from("repo_configs")
+    .select("monthly_token_budget, monthly_cost_budget, over_budget_action, budget_notified_at")
+    .eq("full_name", fullName)
+    .single();
+
+  if (!budget?.monthly_token_budget && !budget?.monthly_cost_budget) return { action: "allow" };
+
+  const now = new Date();
+  const monthStart = new Date(Date.UTC(now.getUTCFullYear(), now.getUTCMonth(), 1));
+  const { data, error } = await supabase.rpc("repo_usage_since", { p_repo: fullName, p_since: monthStart.toISOString() });
+  if (error) {
+    console.error(`Budget check failed for ${fullName}: ${error.message}`);
+    return { action: "allow" };
+  }
+
+  const row = Array.isArray(data) ? data[0] : data;
+  const tokens = Number(row?.prompt_tokens || 0) + Number(row?.completion_tokens || 0);
+  const cost = Number(row?.cost_usd || 0);
+  const reasons: string[] = [];
+  if (budget.monthly_token_budget && tokens >= budget.monthly_token_budget) {
+    reasons.push(`${tokens} of ${budget.monthly_token_budget} tokens used`);
+  }
+  if (budget.monthly_cost_budget && cost >= Number(budget.monthly_cost_budget)) {
+    reasons.push(`$${cost.toFixed(2)} of $${Number(budget.monthly_cost_budget).toFixed(2)} spent`);
+  }
+  if (!reasons.length) return { action: "allow" };
+
+  const decision: BudgetDecision = { action: budget.over_budget_action === "skip" ? "skip" : "downgrade", reason: reasons.join(", ") };
+  const alreadyNotified = budget.budget_notified_at && new Date(budget.budget_notified_at) >= monthStart;
+  if (!alreadyNotified) {
+    await supabase.from("notifications").insert({
+      type: "warning",
+      channel: "log",
+      message: `Monthly LLM budget exceeded, auto-reviews ${decision.action === "skip" ? "skipped" : "downgraded to quick"} for ${fullName}`,
+      metadata: { repo: fullName, usage: decision.reason, action: decision.action },
+    });
+    await supabase.from("repo_configs").update({ budget_notified_at: now.toISOString() }).eq("full_name", fullName);
+  }
+  return decision;
+}
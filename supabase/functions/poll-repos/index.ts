@@ -1,13 +1,14 @@
 import { serve } from "https://deno.land/std@0.168.0/http/server.ts";
 import { createClient } from "https://esm.sh/@supabase/supabase-js@2";
+import { hasGitHubAuth, repoToken } from "../_shared/github.ts";
+import { createCheck } from "../_shared/checks.ts";
 
 const env = (key: string) => Deno.env.get(key) || "";
-const GITHUB_TOKEN = env("GITHUB_TOKEN");
 const SUPABASE_URL = env("SUPABASE_URL");
 const SUPABASE_SERVICE_KEY = env("SUPABASE_SERVICE_ROLE_KEY");
 const CRON_SECRET = env("CRON_SECRET");
 
-if (!GITHUB_TOKEN || !SUPABASE_URL || !SUPABASE_SERVICE_KEY) {
+if (!hasGitHubAuth() || !SUPABASE_URL || !SUPABASE_SERVICE_KEY) {
   throw new Error("Missing required environment variables");
 }
 
@@ -69,7 +70,7 @@ async function processRepo(repo: any, exclusions: any, supabase: any): Promise<{
       `https://api.github.com/repos/${owner}/${repoName}/pulls?state=open&per_page=10`,
       { 
         headers: { 
-          Authorization: `Bearer ${GITHUB_TOKEN}`, 
+          Authorization: `Bearer ${await repoToken(owner, repoName, supabase)}`, 
           Accept: "application/vnd.github+json",
           "User-Agent": "foodshare-ai-bot"
         } 
@@ -95,6 +96,7 @@ async function processRepo(repo: any, exclusions: any, supabase: any): Promise<{
         pr_number: pr.number,
         owner,
         repo: repoName,
+        head_sha: pr.head.sha,
         analysis: { 
           depth: (pr.additions + pr.deletions) > 500 ? "deep" : "standard",
           title: pr.title,
@@ -105,11 +107,15 @@ async function processRepo(repo: any, exclusions: any, supabase: any): Promise<{
     }
 
     if (jobs.length > 0) {
-      const { error } = await supabase.from("review_jobs").insert(jobs);
+      const { data: inserted, error } = await supabase.from("review_jobs").insert(jobs).select("id, head_sha");
       if (error) {
         errors.push(`${repo.full_name}: DB insert failed`);
       } else {
         queued.push(...jobs.map(j => `${j.repo_full_name}#${j.pr_number}`));
+        for (const job of inserted || []) {
+          const checkRunId = await createCheck(owner, repoName, job.head_sha);
+          if (checkRunId) await supabase.from("review_jobs").update({ check_run_id: checkRunId }).eq("id", job.id);
+        }
       }
     }
   } catch (err) {
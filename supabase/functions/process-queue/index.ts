@@ -1,8 +1,22 @@
 import { serve } from "https://deno.land/std@0.168.0/http/server.ts";
 import { createClient } from "https://esm.sh/@supabase/supabase-js@2";
-import { chat, getLLMStatus } from "../_shared/llm.ts";
+import { z } from "https://esm.sh/zod@4.3.5";
+import { chat, getLLMStatus, StructuredOutputError } from "../_shared/llm.ts";
+import { addUsage, checkRepoBudget, emptyUsage, type TokenUsage } from "../_shared/usage.ts";
+import { githubAuthHeaders } from "../_shared/github.ts";
+import { completeCheck, conclusionFor, createCheck, startCheck, toAnnotations } from "../_shared/checks.ts";
+import {
+  defineReviewSchema,
+  describePolicy,
+  generateReview,
+  openFindings,
+  publishReview,
+  StaleReviewError,
+  type RepoConfig,
+  type ReviewResponse,
+  type ReviewRuntime,
+} from "../_shared/review/index.ts";
 
-const GITHUB_TOKEN = Deno.env.get("GITHUB_TOKEN")!;
 const SUPABASE_URL = Deno.env.get("SUPABASE_URL")!;
 const SUPABASE_SERVICE_KEY = Deno.env.get("SUPABASE_SERVICE_ROLE_KEY")!;
 const CRON_SECRET = Deno.env.get("CRON_SECRET") || "";
@@ -10,6 +24,9 @@ const CRON_SECRET = Deno.env.get("CRON_SECRET") || "";
 const MAX_RETRIES = 3;
 const BASE_DELAY = 1000;
 const RATE_LIMIT_DELAY = 300000;
+const LEASE_SECONDS = 300;
+const REPO_CONCURRENCY = parseInt(Deno.env.get("QUEUE_REPO_CONCURRENCY") || "2", 10);
+const AGING_MINUTES = parseInt(Deno.env.get("QUEUE_AGING_MINUTES") || "15", 10);
 
 interface JobResult {
   success: boolean;
@@ -28,32 +45,12 @@ function calculateBackoff(attempt: number, isRateLimit = false): number {
   return BASE_DELAY * Math.pow(2, attempt - 1);
 }
 
-const REVIEW_PROMPT = `You are an elite security auditor. Find ALL vulnerabilities.
-
-## OWASP TOP 10:
-A01: Broken Access Control  A02: Cryptographic Failures  A03: Injection
-A04: Insecure Design  A05: Security Misconfiguration  A06: Vulnerable Components
-A07: Auth Failures  A08: Data Integrity Failures  A09: Logging Failures  A10: SSRF
-
-## RESPONSE FORMAT (strict JSON):
-{
-  "security_score": <0-100>,
-  "threat_level": "<CRITICAL|HIGH|MEDIUM|LOW|SAFE>",
-  "owasp_violations": ["A01"],
-  "summary": {"overview": "<assessment>", "critical_findings": [], "recommendations": []},
-  "vulnerabilities": [{"type": "<category>", "severity": "<critical|high|medium|low>", "path": "<file>", "line": <n>, "description": "<issue>", "fix": "<solution>"}],
-  "line_comments": [{"path": "<file>", "line": <n>, "body": "<issue>", "severity": "<level>", "suggestion": "<fix>"}],
-  "approval_recommendation": "<approve|request_changes|comment>",
-  "auto_mergeable": <true if score >= 90>
-}
-Return ONLY valid JSON.`;
-
 async function ghFetch(endpoint: string, options?: RequestInit): Promise<Response> {
   try {
     const res = await fetch(`https://api.github.com${endpoint}`, {
       ...options,
       headers: { 
-        Authorization: `Bearer ${GITHUB_TOKEN}`, 
+        ...(await githubAuthHeaders(endpoint)), 
         Accept: "application/vnd.github+json", 
         ...options?.headers 
       },
@@ -78,88 +75,133 @@ async function ghFetch(endpoint: string, options?: RequestInit): Promise<Respons
   }
 }
 
-async function processJob(supabase: ReturnType<typeof createClient>, job: Record<string, unknown>): Promise<JobResult> {
+const reviewResponseSchema: z.ZodType<ReviewResponse> = defineReviewSchema(z);
+const DIFF_ACCEPT = { Accept: "application/vnd.github.v3.diff" };
+
+// Wire the shared review engine to this function's GitHub client, Supabase and LLM
+async function createRuntime(
+  supabase: ReturnType<typeof createClient>,
+  job: Record<string, unknown>,
+  onUsage: (model: string, usage: TokenUsage) => void
+): Promise<ReviewRuntime> {
+  const base = `/repos/${job.owner}/${job.repo}`;
+  const fullName = job.repo_full_name as string;
+
+  const [{ data: config }, { data: learnings }, { data: last }] = await Promise.all([
+    supabase.from("repo_configs")
+      .select("categories, ignore_paths, custom_instructions, category_weights, severity_policy")
+      .eq("full_name", fullName)
+      .maybeSingle(),
+    supabase.from("review_learnings")
+      .select("id, repo_full_name, pattern, learning, category, created_at")
+      .eq("repo_full_name", fullName)
+      .or("status.is.null,status.eq.active")
+      .order("created_at", { ascending: false })
+      .limit(200),
+    supabase.from("review_history")
+      .select("head_sha, result")
+      .eq("repo_full_name", fullName)
+      .eq("pr_number", job.pr_number)
+      .eq("status", "completed")
+      .order("created_at", { ascending: false })
+      .limit(1)
+      .maybeSingle(),
+  ]);
+
+  return {
+    github: {
+      pullRequest: async () => (await ghFetch(`${base}/pulls/${job.pr_number}`)).json(),
+      diff: async () => (await ghFetch(`${base}/pulls/${job.pr_number}`, { headers: DIFF_ACCEPT })).text(),
+      compare: async (baseSha, headSha) => (await ghFetch(`${base}/compare/${baseSha}...${headSha}`, { headers: DIFF_ACCEPT })).text(),
+      createReview: async (body, event, comments) => (await ghFetch(`${base}/pulls/${job.pr_number}/reviews`, {
+        method: "POST",
+        body: JSON.stringify({ body, event, ...(comments.length && { comments }) }),
+      })).json(),
+      setStatus: (sha, state, context, description) => ghFetch(`${base}/statuses/${sha}`, {
+        method: "POST",
+        body: JSON.stringify({ state, context, description }),
+      }),
+    },
+    complete: async (prompt) => {
+      try {
+        return { review: await chat(prompt, { useReviewModel: true, schema: reviewResponseSchema, onUsage }) };
+      } catch (err) {
+        if (!(err instanceof StructuredOutputError)) throw err;
+        log('warn', `Invalid LLM response for ${fullName}#${job.pr_number}`, { error: err.validationErrors });
+        return { review: null };
+      }
+    },
+    config: (config || {}) as RepoConfig,
+    learnings: learnings || [],
+    lastReviewedSha: last?.head_sha || null,
+    previousFindings: openFindings(last?.result),
+  };
+}
+
+// Job updates only land while workerId still holds the lease; lease is aborted once it doesn't
+async function processJob(supabase: ReturnType<typeof createClient>, job: Record<string, unknown>, workerId: string, lease: AbortSignal): Promise<JobResult> {
   const jobId = `${job.repo_full_name}#${job.pr_number}`;
   log('info', `Processing job ${jobId}`, { jobId: job.id });
+  const owner = job.owner as string;
+  const repo = job.repo as string;
+  let checkRunId = (job.check_run_id as number | null) || null;
 
   try {
-    // Fetch PR diff
-    const diffRes = await ghFetch(`/repos/${job.owner}/${job.repo}/pulls/${job.pr_number}`, {
-      headers: { Accept: "application/vnd.github.v3.diff" },
-    });
-    const diff = await diffRes.text();
-    const truncatedDiff = diff.length > 20000 ? diff.slice(0, 20000) + "\n...[truncated]" : diff;
+    if (checkRunId) await startCheck(owner, repo, checkRunId);
+
+    const budget = await checkRepoBudget(supabase, job.repo_full_name as string);
+    if (budget.action === "skip") {
+      log('warn', `Skipping ${jobId}: over monthly budget`, { usage: budget.reason });
+      await completeCheck(owner, repo, checkRunId, "skipped", { title: "Review skipped", summary: `Monthly LLM budget exceeded: ${budget.reason}` });
+      await supabase.from("review_jobs")
+        .update({ status: "completed", completed_at: new Date().toISOString(), worker_id: null, lease_expires_at: null })
+        .eq("id", job.id)
+        .eq("worker_id", workerId);
+      return { success: true };
+    }
 
     // Fetch PR details
     const prRes = await ghFetch(`/repos/${job.owner}/${job.repo}/pulls/${job.pr_number}`);
     const pr = await prRes.json();
 
+    if (!checkRunId) {
+      checkRunId = await createCheck(owner, repo, pr.head.sha, undefined, "in_progress");
+      if (checkRunId) await supabase.from("review_jobs").update({ check_run_id: checkRunId }).eq("id", job.id);
+    }
+
     if (pr.state !== "open") {
       log('info', `PR ${jobId} is not open, marking as completed`);
+      await completeCheck(owner, repo, checkRunId, "skipped", { title: "Review skipped", summary: "The pull request is no longer open." });
       await supabase.from("review_jobs")
-        .update({ status: "completed", completed_at: new Date().toISOString() })
-        .eq("id", job.id);
+        .update({ status: "completed", completed_at: new Date().toISOString(), worker_id: null, lease_expires_at: null })
+        .eq("id", job.id)
+        .eq("worker_id", workerId);
       return { success: true };
     }
 
-    // Generate review using LLM
+    // Generate review using LLM; over-budget repos get a single quick pass
     log('info', `Generating review for ${jobId}`);
-    const content = await chat(
-      `${REVIEW_PROMPT}\n\n## PR: ${pr.title}\n## Description: ${pr.body || "None"}\n\n## Code Diff:\n\`\`\`diff\n${truncatedDiff}\n\`\`\``,
-      { useReviewModel: true, systemPrompt: "You are an elite security auditor. Be thorough." }
-    );
-
-    // Parse review response
-    let review: Record<string, unknown>;
-    try {
-      let json = content;
-      if (json.includes("```json")) json = json.split("```json")[1].split("```")[0];
-      else if (json.includes("```")) json = json.split("```")[1].split("```")[0];
-      review = JSON.parse(json.trim());
-    } catch (parseErr) {
-      log('warn', `Failed to parse LLM response for ${jobId}`, { error: parseErr instanceof Error ? parseErr.message : 'Unknown' });
-      review = { summary: { overview: "Review completed" }, line_comments: [] };
-    }
-
-    // Format review content
-    const threatEmoji: Record<string, string> = { CRITICAL: "🚨", HIGH: "🔴", MEDIUM: "🟠", LOW: "🟡", SAFE: "🟢" };
-    const threatLevel = (review.threat_level as string) || "MEDIUM";
-    
-    let body = `## 🛡️ Security Audit\n\n| Metric | Value |\n|--------|-------|\n`;
-    body += `| **Score** | ${review.security_score ?? "N/A"}/100 |\n`;
-    body += `| **Threat** | ${threatEmoji[threatLevel] || "⚪"} ${threatLevel} |\n`;
-    body += `| **OWASP** | ${(review.owasp_violations as string[])?.join(", ") || "None"} |\n\n`;
-    body += `${(review.summary as Record<string, unknown>)?.overview || "Review completed"}\n`;
-
-    const summaryObj = review.summary as Record<string, unknown> | undefined;
-    if ((summaryObj?.critical_findings as string[])?.length) {
-      body += `\n### 🚨 Critical\n${(summaryObj.critical_findings as string[]).map((f: string) => `- ${f}`).join("\n")}\n`;
-    }
-    if ((summaryObj?.recommendations as string[])?.length) {
-      body += `\n### 📋 Recommendations\n${(summaryObj.recommendations as string[]).map((r: string) => `- ${r}`).join("\n")}`;
-    }
-
-    const comments = ((review.line_comments as Array<Record<string, unknown>>) || [])
-      .filter((c) => c.path && (c.line as number) > 0)
-      .slice(0, 10)
-      .map((c) => ({ path: c.path, line: c.line, body: `**[${((c.severity as string) || "info").toUpperCase()}]** ${c.body}` }));
-
-    const event = threatLevel === "CRITICAL" ? "REQUEST_CHANGES" : (review.auto_mergeable ? "APPROVE" : "COMMENT");
-
-    // Submit review to GitHub
-    try {
-      await ghFetch(`/repos/${job.owner}/${job.repo}/pulls/${job.pr_number}/reviews`, {
-        method: "POST",
-        body: JSON.stringify({ body, event, comments: comments.length > 0 ? comments : undefined }),
-      });
-      log('info', `Review submitted for ${jobId}`, { event, commentsCount: comments.length });
-    } catch (reviewErr) {
-      log('warn', `Failed to submit review with comments for ${jobId}, retrying without comments`);
-      await ghFetch(`/repos/${job.owner}/${job.repo}/pulls/${job.pr_number}/reviews`, {
-        method: "POST",
-        body: JSON.stringify({ body, event: "COMMENT" }),
-      });
-    }
+    const usage = emptyUsage();
+    const runtime = await createRuntime(supabase, job, (model, u) => addUsage(usage, model, u));
+    const generated = await generateReview(runtime, {
+      headSha: job.head_sha as string | null,
+      ...(budget.action === "downgrade" && { depth: "quick" as const, maxPasses: 1 }),
+    });
+    const review = { ...generated.review, usage };
+
+    // Another worker owns the job now and will post its own review
+    lease.throwIfAborted();
+    const { placed } = await publishReview(runtime, { ...generated, review });
+    log('info', `Review submitted for ${jobId}`, { event: review.approval_recommendation, commentsCount: placed.length });
+
+    // Every finding becomes an annotation, not just the ones placed inline. Repos
+    // without a severity policy conclude on the findings' severities instead.
+    const policy = review.policy;
+    const conclusion = policy ? (policy.passed ? "success" : "failure") : conclusionFor(review.line_comments);
+    await completeCheck(owner, repo, checkRunId, conclusion, {
+      title: `${review.line_comments.length} comments${policy ? `: ${describePolicy(policy)}` : ""}`.slice(0, 255),
+      summary: review.summary.overview,
+    }, toAnnotations(review.line_comments));
 
     // Save to history
     await supabase.from("review_history").insert({
@@ -167,18 +209,35 @@ async function processJob(supabase: ReturnType<typeof createClient>, job: Record
       pr_number: job.pr_number,
       status: "completed",
       result: review,
-      head_sha: pr.head.sha,
+      head_sha: generated.headSha,
+      ...usage,
     });
 
     // Mark job as completed
     await supabase.from("review_jobs")
-      .update({ status: "completed", completed_at: new Date().toISOString() })
-      .eq("id", job.id);
+      .update({ status: "completed", completed_at: new Date().toISOString(), worker_id: null, lease_expires_at: null })
+      .eq("id", job.id)
+      .eq("worker_id", workerId);
 
     log('info', `Job ${jobId} completed successfully`);
     return { success: true };
 
   } catch (err) {
+    if (lease.aborted) {
+      // The job and its check run belong to whoever re-claimed it
+      log('warn', `Stopped ${jobId} after losing its lease`);
+      return { success: false, error: "Lease lost" };
+    }
+    if (err instanceof StaleReviewError) {
+      // A newer push is queued (or will be polled); this review would describe old code
+      log('info', `Job ${jobId} superseded: ${err.message}`);
+      await completeCheck(owner, repo, checkRunId, "skipped", { title: "Review superseded", summary: `${err.message}; the new head will be reviewed instead.` });
+      await supabase.from("review_jobs")
+        .update({ status: "superseded", error: err.message, worker_id: null, lease_expires_at: null })
+        .eq("id", job.id)
+        .eq("worker_id", workerId);
+      return { success: true };
+    }
     const error = err instanceof Error ? err.message : "Unknown error";
     const isRetryable = (err as any)?.retryable || false;
     const isRateLimit = error.includes("rate") || error.includes("429") || (err as any)?.status === 429;
@@ -193,13 +252,18 @@ async function processJob(supabase: ReturnType<typeof createClient>, job: Record
 
     const shouldRetry = attempts < MAX_RETRIES && (isRetryable || isRateLimit);
     const nextRetryDelay = calculateBackoff(attempts, isRateLimit);
+    if (!shouldRetry) {
+      await completeCheck(owner, repo, checkRunId, "neutral", { title: "Review failed", summary: error.slice(0, 1000) });
+    }
     
     await supabase.from("review_jobs").update({
       status: shouldRetry ? "pending" : "failed",
       error,
       attempts,
       next_retry_at: shouldRetry ? new Date(Date.now() + nextRetryDelay).toISOString() : null,
-    }).eq("id", job.id);
+      worker_id: null,
+      lease_expires_at: null,
+    }).eq("id", job.id).eq("worker_id", workerId);
 
     return { success: false, error, retryable: shouldRetry };
   }
@@ -223,55 +287,38 @@ serve(async (req) => {
     const supabase = createClient(SUPABASE_URL, SUPABASE_SERVICE_KEY);
     const results: { pr: string; success: boolean; error?: string; retryable?: boolean }[] = [];
 
-    // Reset stuck jobs
-    const { error: resetError } = await supabase
-      .from("review_jobs")
-      .update({ status: "pending" })
-      .eq("status", "processing")
-      .lt("started_at", new Date(Date.now() - 600000).toISOString());
-
-    if (resetError) {
-      log('warn', 'Failed to reset stuck jobs', { error: resetError.message });
-    }
+    // Claims are leased to this invocation; expired leases are re-queued by the claim itself
+    const workerId = `edge-${crypto.randomUUID().slice(0, 8)}`;
 
     // Process jobs
     for (let i = 0; i < 3 && Date.now() - startTime < 50000; i++) {
       try {
-        // Find next pending job
-        const { data: pendingJob, error: findError } = await supabase
-          .from("review_jobs")
-          .select("id")
-          .eq("status", "pending")
-          .or(`next_retry_at.is.null,next_retry_at.lte.${new Date().toISOString()}`)
-          .order("created_at", { ascending: true })
-          .limit(1)
-          .single();
-
-        if (findError || !pendingJob) {
-          if (findError && findError.code !== 'PGRST116') {
-            log('warn', 'Error finding pending jobs', { error: findError.message });
-          }
-          break;
-        }
+        const { data: claimed, error: claimError } = await supabase.rpc("claim_review_job", {
+          p_worker_id: workerId,
+          p_lease_seconds: LEASE_SECONDS,
+          p_repo_limit: REPO_CONCURRENCY,
+          p_aging_minutes: AGING_MINUTES,
+        });
 
-        // Claim the job
-        const { data: job, error: claimError } = await supabase
-          .from("review_jobs")
-          .update({ status: "processing", started_at: new Date().toISOString() })
-          .eq("id", pendingJob.id)
-          .eq("status", "pending")
-          .select()
-          .single();
-
-        if (claimError || !job) {
-          if (claimError) {
-            log('warn', 'Failed to claim job', { jobId: pendingJob.id, error: claimError.message });
-          }
-          continue;
+        if (claimError) {
+          log('warn', 'Failed to claim job', { error: claimError.message });
+          break;
         }
-
-        // Process the job
-        const result = await processJob(supabase, job);
+        const job = claimed?.[0];
+        if (!job) break;
+
+        // Keep the lease alive while the job runs, and stop it if the lease is taken back
+        const lease = new AbortController();
+        const heartbeat = setInterval(() => {
+          supabase.rpc("heartbeat_review_job", { p_job_id: job.id, p_worker_id: workerId, p_lease_seconds: LEASE_SECONDS })
+            .then(({ data }) => {
+              if (data !== false || lease.signal.aborted) return;
+              log('warn', `Lease lost for job ${job.id}`);
+              clearInterval(heartbeat);
+              lease.abort();
+            });
+        }, (LEASE_SECONDS * 1000) / 3);
+        const result = await processJob(supabase, job, workerId, lease.signal).finally(() => clearInterval(heartbeat));
         results.push({ pr: `${job.repo_full_name}#${job.pr_number}`, ...result });
 
         // Break on rate limit
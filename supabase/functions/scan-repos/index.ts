@@ -1,11 +1,68 @@
 import { serve } from "https://deno.land/std@0.168.0/http/server.ts";
 import { createClient } from "https://esm.sh/@supabase/supabase-js@2";
-import { chat } from "../_shared/llm.ts";
+import { z } from "https://esm.sh/zod@4.3.5";
+import { chat, StructuredOutputError } from "../_shared/llm.ts";
+import { addUsage, checkRepoBudget, emptyUsage, type UsageTotals } from "../_shared/usage.ts";
+import { githubAuthHeaders } from "../_shared/github.ts";
+import { SECRET_COMMENT, scanFileSecrets, type SecretFinding } from "../_shared/review/secrets.ts";
+import {
+  assignFindings, chunkFile, coverageSummary, formatScanBatch, packScanBatches, planScanFiles, scanCoverage, scoreFindings,
+  type ScanChunk, type TreeEntry,
+} from "../_shared/review/scans.ts";
+import { checksEnabled, completeCheck, conclusionFor, createCheck, SCAN_CHECK_NAME, toAnnotations } from "../_shared/checks.ts";
 
-const GITHUB_TOKEN = Deno.env.get("GITHUB_TOKEN") || "";
 const SUPABASE_URL = Deno.env.get("SUPABASE_URL")!;
 const SUPABASE_SERVICE_KEY = Deno.env.get("SUPABASE_SERVICE_ROLE_KEY")!;
 
+const envNumber = (name: string, fallback: number) => Number(Deno.env.get(name)) || fallback;
+
+// A scan run covers the whole tree in batches of about this much prompt text,
+// one LLM call each, and picks up where it left off on the next invocation
+const BATCH_CHARS = envNumber("SCAN_BATCH_CHARS", 24000);
+const CONCURRENCY = envNumber("SCAN_CONCURRENCY", 2);
+const MAX_FILE_BYTES = envNumber("SCAN_MAX_FILE_BYTES", 100000);
+const MAX_FILES = envNumber("SCAN_MAX_FILES", 5000);
+// Time between the end of one full scan of a repo and the start of the next
+const INTERVAL_HOURS = envNumber("SCAN_INTERVAL_HOURS", 2);
+const RUN_MAX_HOURS = 24;
+const MAX_ATTEMPTS = 3;
+const LLM_TIMEOUT_MS = 90000;
+const INVOCATION_MS = 280000; // 4.5 min safety margin
+const MAX_ANNOTATIONS = 500;
+const SEVERITY_ORDER = ["critical", "high", "medium", "low"];
+
+type Supabase = ReturnType<typeof createClient>;
+
+interface ScanRun {
+  id: string;
+  repo_full_name: string;
+  branch: string | null;
+  commit_sha: string | null;
+  total_files: number;
+  scanned_files: number;
+  skipped_files: number;
+  failed_files: number;
+  tree_truncated: boolean;
+  prompt_tokens: number;
+  completion_tokens: number;
+  cost_usd: number;
+  check_run_id: number | null;
+  created_at: string;
+}
+
+interface PendingFile {
+  path: string;
+  blob_sha: string | null;
+  size: number;
+  attempts: number;
+}
+
+interface FileOutcome {
+  file: PendingFile;
+  findings?: Finding[];
+  error?: string;
+}
+
 const SCAN_PROMPT = `You are a senior security engineer performing a THOROUGH code audit. Analyze this code deeply for:
 
 ## SECURITY (Critical Priority)
@@ -45,103 +102,404 @@ const SCAN_PROMPT = `You are a senior security engineer performing a THOROUGH co
 
 Be AGGRESSIVE - report ALL issues found. Better to over-report than miss vulnerabilities.
 
+Code is shown with line numbers ("12| code") under "=== <path> (lines a-b) ===" headers. Report the path from the header and the line number shown.
+
 Return JSON array (empty [] ONLY if code is perfect):
 [{"severity":"critical|high|medium|low","type":"security|bug|quality","title":"<concise issue>","file":"<path>","line":<number>,"problem":"<detailed explanation>","fix":"<specific code fix or recommendation>","cwe":"<CWE-ID if applicable>"}]`;
 
 const ghFetch = async (endpoint: string) => {
-  const headers: Record<string, string> = { Accept: "application/vnd.github+json", "User-Agent": "FoodShare-Scan" };
-  if (GITHUB_TOKEN) headers.Authorization = `Bearer ${GITHUB_TOKEN}`;
+  const headers: Record<string, string> = {
+    Accept: "application/vnd.github+json",
+    "User-Agent": "FoodShare-Scan",
+    ...(await githubAuthHeaders(endpoint)),
+  };
   const res = await fetch(`https://api.github.com${endpoint}`, { headers });
   if (!res.ok) throw new Error(`GitHub ${res.status}`);
   return res.json();
 };
 
-async function getRepoFiles(owner: string, repo: string): Promise<string[]> {
-  for (const branch of ["main", "master", "develop"]) {
-    try {
-      const tree = await ghFetch(`/repos/${owner}/${repo}/git/trees/${branch}?recursive=1`) as { tree?: Array<{ type: string; size: number; path: string }> };
-      return (tree.tree || [])
-        .filter((f) => f.type === "blob" && f.size > 50 && f.size < 100000)
-        .filter((f) => /\.(ts|tsx|js|jsx|py|go|rs|java|rb|php|swift|kt|cs|c|cpp|h)$/i.test(f.path))
-        .filter((f) => !/(node_modules|vendor|dist|build|\.min\.|__tests__|__mocks__|\.test\.|\.spec\.|__pycache__|\.next|\.git|coverage)/i.test(f.path))
-        .sort((a, b) => {
-          // Prioritize security-sensitive files
-          const priority = (p: string) => {
-            if (/auth|login|password|secret|token|api|admin|payment|crypto|session/i.test(p)) return 0;
-            if (/route|controller|handler|middleware|service/i.test(p)) return 1;
-            if (/database|model|schema|migration/i.test(p)) return 2;
-            if (/config|env|setting/i.test(p)) return 3;
-            return 10;
-          };
-          return priority(a.path) - priority(b.path);
-        })
-        .map((f) => f.path)
-        .slice(0, 50); // Increased from 25 to 50 files
-    } catch { continue; }
-  }
-  return [];
+async function getTree(owner: string, repo: string): Promise<{ branch: string; commitSha: string; entries: TreeEntry[]; truncated: boolean }> {
+  const { default_branch: branch } = await ghFetch(`/repos/${owner}/${repo}`) as { default_branch: string };
+  const { sha: commitSha } = await ghFetch(`/repos/${owner}/${repo}/commits/${encodeURIComponent(branch)}`) as { sha: string };
+  // Pinned to the commit so files can't change under a run that spans invocations
+  const tree = await ghFetch(`/repos/${owner}/${repo}/git/trees/${commitSha}?recursive=1`) as { tree?: TreeEntry[]; truncated?: boolean };
+  return { branch, commitSha, entries: tree.tree || [], truncated: !!tree.truncated };
+}
+
+async function getBlob(owner: string, repo: string, sha: string): Promise<string> {
+  const blob = await ghFetch(`/repos/${owner}/${repo}/git/blobs/${sha}`) as { encoding?: string; content?: string };
+  if (blob.encoding !== "base64") return blob.content || "";
+  const bytes = Uint8Array.from(atob((blob.content || "").replace(/\n/g, "")), (c) => c.charCodeAt(0));
+  return new TextDecoder().decode(bytes);
 }
 
-async function getFileContent(owner: string, repo: string, path: string): Promise<string> {
+// Scan results show up as a check run on the scanned commit
+async function startScanCheck(owner: string, repo: string, sha: string): Promise<number | null> {
+  if (!checksEnabled()) return null;
   try {
-    const file = await ghFetch(`/repos/${owner}/${repo}/contents/${path}`) as { encoding?: string; content?: string };
-    if (file.encoding === "base64" && file.content) return atob(file.content.replace(/\n/g, ""));
-  } catch {}
-  return "";
-}
-
-async function scanRepo(owner: string, repo: string): Promise<Record<string, unknown>> {
-  const files = await getRepoFiles(owner, repo);
-  if (!files.length) return { repo: `${owner}/${repo}`, skipped: true, reason: "no files" };
-
-  const codeBlocks: string[] = [];
-  let charCount = 0;
-
-  for (const path of files) {
-    if (charCount >= 15000) break; // Increased from 8000 to 15000
-    const content = await getFileContent(owner, repo, path);
-    if (content.length > 30) {
-      const snippet = content.slice(0, 2000); // Increased from 1200 to 2000
-      charCount += snippet.length;
-      codeBlocks.push(`=== ${path} ===\n${snippet}`);
+    return await createCheck(owner, repo, sha, SCAN_CHECK_NAME, "in_progress");
+  } catch {
+    return null;
+  }
+}
+
+const FindingsSchema = z.array(z.object({
+  severity: z.string().transform((v) => v.toLowerCase()),
+  type: z.string().nullish(),
+  title: z.string(),
+  file: z.string().nullish(),
+  line: z.coerce.number().int().nullish(),
+  problem: z.string().nullish(),
+  fix: z.string().nullish(),
+  cwe: z.string().nullish(),
+}));
+
+type Finding = z.infer<typeof FindingsSchema>[number];
+
+function secretFinding(s: SecretFinding): Finding {
+  return {
+    severity: "critical", type: "security", title: `Secret detected: ${s.name}`,
+    file: s.path, line: s.line, problem: SECRET_COMMENT, fix: "Rotate the credential and load it from the environment or a secret manager.", cwe: "CWE-798",
+  };
+}
+
+
+/** Plan a run over the default branch's tree. Null if another invocation started one first. */
+async function startRun(supabase: Supabase, fullName: string, lockedUntil: string): Promise<ScanRun | null> {
+  const [owner, repo] = fullName.split("/");
+  const { branch, commitSha, entries, truncated } = await getTree(owner, repo);
+  const files = planScanFiles(entries, { maxFileBytes: MAX_FILE_BYTES, maxFiles: MAX_FILES });
+  const coverage = scanCoverage(files);
+
+  // Created locked so no one else works on it before its files are in
+  const { data: run, error } = await supabase.from("scan_runs").insert({
+    repo_full_name: fullName,
+    branch,
+    commit_sha: commitSha,
+    total_files: coverage.total_files,
+    skipped_files: coverage.skipped_files,
+    tree_truncated: truncated,
+    locked_until: lockedUntil,
+  }).select().single();
+  if (error?.code === "23505") return null;
+  if (error) throw new Error(error.message);
+
+  for (let i = 0; i < files.length; i += 500) {
+    const { error } = await supabase.from("scan_files").insert(files.slice(i, i + 500).map((f) => ({ run_id: run.id, ...f })));
+    if (error) {
+      await supabase.from("scan_runs").update({ status: "failed", error: error.message, locked_until: null }).eq("id", run.id);
+      throw new Error(error.message);
     }
   }
 
-  if (!codeBlocks.length) return { repo: `${owner}/${repo}`, skipped: true, reason: "no content" };
+  const checkRunId = await startScanCheck(owner, repo, commitSha);
+  if (checkRunId) await supabase.from("scan_runs").update({ check_run_id: checkRunId }).eq("id", run.id);
+  return { ...run, check_run_id: checkRunId } as ScanRun;
+}
 
-  const code = codeBlocks.join("\n\n").slice(0, 15000); // Increased limit
-  
-  let findings: Array<Record<string, unknown>> = [];
-  try {
-    const response = await chat(`${SCAN_PROMPT}\n\n${code}`, { temperature: 0.2, maxTokens: 2000, timeout: 180000 });
-    let json = response.replace(/```json?\n?/g, "").replace(/```/g, "").trim();
-    const start = json.indexOf("["), end = json.lastIndexOf("]") + 1;
-    if (start >= 0 && end > start) json = json.slice(start, end);
-    findings = JSON.parse(json);
-  } catch (e) {
-    console.error(`Scan parse error: ${e}`);
+/** Take the lock on a run in progress. Null if another invocation holds it. */
+async function claimRun(supabase: Supabase, runId: string, lockedUntil: string): Promise<ScanRun | null> {
+  const now = new Date().toISOString();
+  const { data } = await supabase.from("scan_runs")
+    .update({ locked_until: lockedUntil, updated_at: now })
+    .eq("id", runId)
+    .eq("status", "running")
+    .or(`locked_until.is.null,locked_until.lt.${now}`)
+    .select()
+    .maybeSingle();
+  return data as ScanRun | null;
+}
+
+// Files in order, grouped up to a batch's worth of code; a large file gets a group of its own
+function groupFiles(files: PendingFile[]): PendingFile[][] {
+  const groups: PendingFile[][] = [];
+  let current: PendingFile[] = [];
+  let size = 0;
+  for (const file of files) {
+    if (current.length && size + file.size > BATCH_CHARS) {
+      groups.push(current);
+      current = [];
+      size = 0;
+    }
+    current.push(file);
+    size += file.size;
+  }
+  if (current.length) groups.push(current);
+  return groups;
+}
+
+/**
+ * Scan a group of files: secrets by rule, then the redacted code in
+ * line-numbered batches. Files whose batches didn't all finish before the
+ * deadline get no outcome and stay pending.
+ */
+async function scanGroup(owner: string, repo: string, files: PendingFile[], deadline: number, usage: UsageTotals): Promise<FileOutcome[]> {
+  const outcomes: FileOutcome[] = [];
+  const chunks: ScanChunk[] = [];
+  const findings = new Map<string, Finding[]>();
+  const errors = new Map<string, string>();
+
+  for (const file of files) {
+    if (!file.blob_sha) {
+      outcomes.push({ file, error: "No blob to fetch" });
+      continue;
+    }
+    try {
+      // Secrets are reported by rule and redacted before the code goes to the LLM
+      const scanned = scanFileSecrets(file.path, await getBlob(owner, repo, file.blob_sha));
+      findings.set(file.path, scanned.findings.map(secretFinding));
+      chunks.push(...chunkFile(file.path, scanned.content, BATCH_CHARS));
+    } catch (e) {
+      outcomes.push({ file, error: `Fetch failed: ${e}` });
+    }
+  }
+
+  const unfinished = new Set<string>();
+  for (const batch of packScanBatches(chunks, BATCH_CHARS)) {
+    const paths = [...new Set(batch.map((c) => c.path))];
+    if (Date.now() + LLM_TIMEOUT_MS > deadline) {
+      paths.forEach((p) => unfinished.add(p));
+      continue;
+    }
+    try {
+      const result = await chat(`${SCAN_PROMPT}\n\n${formatScanBatch(batch)}`, {
+        temperature: 0.2,
+        maxTokens: 2000,
+        timeout: LLM_TIMEOUT_MS,
+        schema: FindingsSchema,
+        onUsage: (model, u) => addUsage(usage, model, u),
+      });
+      for (const [path, list] of assignFindings(result, paths)) findings.get(path)!.push(...list);
+    } catch (e) {
+      const error = e instanceof StructuredOutputError ? `Invalid model output: ${e.validationErrors}` : String(e);
+      console.error(`Scan batch failed (${paths.join(", ")}): ${error}`);
+      paths.forEach((p) => errors.set(p, error));
+    }
+  }
+
+  for (const file of files) {
+    if (!findings.has(file.path)) continue;
+    const error = errors.get(file.path);
+    if (error) outcomes.push({ file, error });
+    else if (!unfinished.has(file.path)) outcomes.push({ file, findings: findings.get(file.path) });
+  }
+  return outcomes;
+}
+
+async function recordOutcomes(supabase: Supabase, run: ScanRun, outcomes: FileOutcome[], usage: UsageTotals): Promise<void> {
+  const now = new Date().toISOString();
+  const rows = outcomes.map(({ file, findings, error }) => {
+    const attempts = file.attempts + 1;
+    return {
+      run_id: run.id,
+      path: file.path,
+      attempts,
+      // Failed files are retried on later passes until they run out of attempts
+      status: error ? (attempts >= MAX_ATTEMPTS ? "failed" : "pending") : "scanned",
+      findings: findings || [],
+      error: error ? error.slice(0, 500) : null,
+      scanned_at: error ? null : now,
+    };
+  });
+  if (rows.length) {
+    const { error } = await supabase.from("scan_files").upsert(rows, { onConflict: "run_id,path" });
+    if (error) throw new Error(error.message);
+  }
+
+  run.scanned_files += rows.filter((r) => r.status === "scanned").length;
+  run.failed_files += rows.filter((r) => r.status === "failed").length;
+  run.prompt_tokens += usage.prompt_tokens;
+  run.completion_tokens += usage.completion_tokens;
+  run.cost_usd = Math.round((Number(run.cost_usd) + usage.cost_usd) * 1e6) / 1e6;
+  await supabase.from("scan_runs").update({
+    scanned_files: run.scanned_files,
+    failed_files: run.failed_files,
+    prompt_tokens: run.prompt_tokens,
+    completion_tokens: run.completion_tokens,
+    cost_usd: run.cost_usd,
+    updated_at: now,
+  }).eq("id", run.id);
+}
+
+const rank = (severity: string) => {
+  const i = SEVERITY_ORDER.indexOf(severity);
+  return i < 0 ? SEVERITY_ORDER.length : i;
+};
+
+/** Record the finished run as a security scan over every file it covered. */
+async function finalizeRun(supabase: Supabase, run: ScanRun): Promise<Record<string, unknown>> {
+  const [owner, repo] = run.repo_full_name.split("/");
+  const files: Array<{ status: string; skip_reason: string | null; findings: Finding[] | null }> = [];
+  for (let from = 0; ; from += 1000) {
+    const { data, error } = await supabase.from("scan_files")
+      .select("status, skip_reason, findings")
+      .eq("run_id", run.id)
+      .order("path")
+      .range(from, from + 999);
+    if (error) throw new Error(error.message);
+    files.push(...(data || []));
+    if (!data || data.length < 1000) break;
   }
 
-  const c = findings.filter((f) => f.severity === "critical").length;
-  const h = findings.filter((f) => f.severity === "high").length;
-  const m = findings.filter((f) => f.severity === "medium").length;
-  const l = findings.filter((f) => f.severity === "low").length;
+  const coverage = scanCoverage(files);
+  const findings = files.flatMap((f) => f.findings || []);
+  const { score, grade, threat_level: threat, by_severity } = scoreFindings(findings);
+  const { critical: c, high: h, medium: m, low: l } = by_severity;
+  const summary = [
+    coverageSummary(coverage),
+    `Found ${findings.length} issues: ${c} critical, ${h} high, ${m} medium, ${l} low.`,
+    run.tree_truncated ? "GitHub truncated the file tree, so some files were never listed." : "",
+  ].filter(Boolean).join(" ");
 
-  const score = Math.max(0, Math.min(100, 100 - c * 25 - h * 15 - m * 5 - l * 2));
-  const grade = score >= 90 ? "A" : score >= 80 ? "B" : score >= 70 ? "C" : score >= 60 ? "D" : "F";
-  const threat = c > 0 ? "CRITICAL" : h > 0 ? "HIGH" : m > 0 ? "MEDIUM" : l > 0 ? "LOW" : "SAFE";
+  let scanId: string | null = null;
+  if (coverage.total_files) {
+    const { data: scan, error } = await supabase.from("security_scans").insert({
+      repo_full_name: run.repo_full_name,
+      security_score: score,
+      issues: findings,
+      summary,
+      files_scanned: coverage.scanned_files,
+      scan_metadata: {
+        grade,
+        threat_level: threat,
+        by_severity,
+        branch: run.branch,
+        commit_sha: run.commit_sha,
+        coverage,
+        run_id: run.id,
+      },
+      prompt_tokens: run.prompt_tokens,
+      completion_tokens: run.completion_tokens,
+      cost_usd: run.cost_usd,
+      check_run_id: run.check_run_id,
+    }).select("id").single();
+    if (error) throw new Error(error.message);
+    scanId = scan.id;
+  }
 
+  const now = new Date().toISOString();
+  await supabase.from("scan_runs").update({
+    status: "completed",
+    scanned_files: coverage.scanned_files,
+    skipped_files: coverage.skipped_files,
+    failed_files: coverage.failed_files,
+    scan_id: scanId,
+    locked_until: null,
+    updated_at: now,
+    completed_at: now,
+  }).eq("id", run.id);
+
+  if (!coverage.total_files) {
+    await completeCheck(owner, repo, run.check_run_id, "skipped", { title: "Scan skipped", summary: "No source files." });
+    return { repo: run.repo_full_name, run_id: run.id, skipped: true, reason: "no files" };
+  }
+
+  // Most severe first, so they're the ones that make the annotation cap
+  const checkFindings = [...findings]
+    .sort((a, b) => rank(a.severity) - rank(b.severity))
+    .map((f) => ({ path: f.file, line: f.line, severity: f.severity, title: f.title, body: f.problem || f.title, suggestion: f.fix }));
+  await completeCheck(owner, repo, run.check_run_id, conclusionFor(checkFindings), {
+    title: `Grade ${grade} (${score}/100)`,
+    summary,
+  }, toAnnotations(checkFindings).slice(0, MAX_ANNOTATIONS));
+
+  return { repo: run.repo_full_name, run_id: run.id, status: "completed", scan_id: scanId, score, grade, threat_level: threat, coverage, summary };
+}
+
+/** Work through a run's pending files until it completes or time runs out. */
+async function processRun(supabase: Supabase, run: ScanRun, deadline: number): Promise<Record<string, unknown>> {
+  const [owner, repo] = run.repo_full_name.split("/");
+  // Files that failed this invocation wait for the next one
+  const attempted = new Set<string>();
+
+  while (Date.now() + LLM_TIMEOUT_MS < deadline) {
+    const { data: pending, error } = await supabase.from("scan_files")
+      .select("path, blob_sha, size, attempts")
+      .eq("run_id", run.id)
+      .eq("status", "pending")
+      .order("priority")
+      .order("path")
+      .limit(200);
+    if (error) throw new Error(error.message);
+    if (!pending?.length) return finalizeRun(supabase, run);
+
+    const todo = (pending as PendingFile[]).filter((f) => !attempted.has(f.path));
+    if (!todo.length) break;
+
+    const groups = groupFiles(todo).slice(0, CONCURRENCY);
+    groups.flat().forEach((f) => attempted.add(f.path));
+    const usage = emptyUsage();
+    const outcomes = await Promise.all(groups.map((group) => scanGroup(owner, repo, group, deadline, usage)));
+    await recordOutcomes(supabase, run, outcomes.flat(), usage);
+  }
+
+  await supabase.from("scan_runs").update({ locked_until: null }).eq("id", run.id);
   return {
-    repo: `${owner}/${repo}`, score, grade, threat_level: threat,
-    summary: `Scanned ${codeBlocks.length} files. Found ${findings.length} issues: ${c} critical, ${h} high, ${m} medium, ${l} low.`,
-    by_severity: { critical: c, high: h, medium: m, low: l },
-    files_analyzed: codeBlocks.length,
-    findings,
+    repo: run.repo_full_name,
+    run_id: run.id,
+    status: "running",
+    progress: `${run.scanned_files + run.failed_files + run.skipped_files} of ${run.total_files} files`,
   };
 }
 
+async function scannedRecently(supabase: Supabase, fullName: string): Promise<boolean> {
+  const since = new Date(Date.now() - INTERVAL_HOURS * 3600000).toISOString();
+  const { data } = await supabase.from("scan_runs")
+    .select("id")
+    .eq("repo_full_name", fullName)
+    .eq("status", "completed")
+    .gte("completed_at", since)
+    .limit(1);
+  return !!data?.length;
+}
+
+/** Give up on runs that have been going too long, so their repo can start over. */
+async function abandonStaleRuns(supabase: Supabase, runs: ScanRun[]): Promise<ScanRun[]> {
+  const cutoff = Date.now() - RUN_MAX_HOURS * 3600000;
+  const live: ScanRun[] = [];
+  for (const run of runs) {
+    if (new Date(run.created_at).getTime() >= cutoff) {
+      live.push(run);
+      continue;
+    }
+    await supabase.from("scan_runs")
+      .update({ status: "abandoned", error: `Not finished within ${RUN_MAX_HOURS}h`, locked_until: null, updated_at: new Date().toISOString() })
+      .eq("id", run.id);
+    const [owner, repo] = run.repo_full_name.split("/");
+    await completeCheck(owner, repo, run.check_run_id, "neutral", {
+      title: "Scan abandoned",
+      summary: `Scanned ${run.scanned_files} of ${run.total_files} source files before giving up after ${RUN_MAX_HOURS}h.`,
+    });
+  }
+  return live;
+}
+
+async function scanRepo(supabase: Supabase, fullName: string, running: ScanRun | undefined, deadline: number, force: boolean): Promise<Record<string, unknown>> {
+  const budget = await checkRepoBudget(supabase, fullName);
+  if (budget.action === "skip") return { repo: fullName, skipped: true, reason: `over monthly budget: ${budget.reason}` };
+
+  const lockedUntil = new Date(deadline).toISOString();
+  let run: ScanRun | null;
+  if (running) {
+    run = await claimRun(supabase, running.id, lockedUntil);
+  } else {
+    if (!force && await scannedRecently(supabase, fullName)) return { repo: fullName, skipped: true, reason: "scanned recently" };
+    console.log(`Starting scan of ${fullName}...`);
+    run = await startRun(supabase, fullName, lockedUntil);
+  }
+  if (!run) return { repo: fullName, skipped: true, reason: "scan in progress elsewhere" };
+
+  try {
+    return await processRun(supabase, run, deadline);
+  } catch (e) {
+    await supabase.from("scan_runs").update({ locked_until: null, error: String(e).slice(0, 500) }).eq("id", run.id);
+    throw e;
+  }
+}
+
 serve(async (req) => {
   const start = Date.now();
+  const deadline = start + INVOCATION_MS;
   const url = new URL(req.url);
   const testRepo = url.searchParams.get("repo");
 
@@ -153,25 +511,16 @@ serve(async (req) => {
 
   if (!repos?.length) return Response.json({ error: "No repos configured" }, { status: 400 });
 
+  const { data: runs } = await supabase.from("scan_runs").select("*").eq("status", "running");
+  const running = new Map((await abandonStaleRuns(supabase, (runs || []) as ScanRun[])).map((r) => [r.repo_full_name, r]));
+  // Runs in progress go first so they finish before new ones start
+  const ordered = [...repos].sort((a, b) => Number(running.has(b.full_name)) - Number(running.has(a.full_name)));
+
   const results = [];
-  for (const repo of repos) {
-    if (Date.now() - start > 280000) break; // 4.5 min safety margin
-    const [owner, name] = repo.full_name.split("/");
+  for (const repo of ordered) {
+    if (Date.now() + LLM_TIMEOUT_MS > deadline) break;
     try {
-      console.log(`Scanning ${repo.full_name}...`);
-      const result = await scanRepo(owner, name);
-      results.push(result);
-      
-      if (!result.skipped) {
-        await supabase.from("security_scans").insert({
-          repo_full_name: repo.full_name,
-          security_score: result.score,
-          issues: result.findings,
-          summary: result.summary,
-          files_scanned: result.files_analyzed,
-          scan_metadata: { grade: result.grade, threat_level: result.threat_level, by_severity: result.by_severity },
-        });
-      }
+      results.push(await scanRepo(supabase, repo.full_name, running.get(repo.full_name), deadline, !!testRepo));
     } catch (e) {
       results.push({ repo: repo.full_name, error: String(e).slice(0, 100) });
     }
@@ -0,0 +1,45 @@
+-- Comments posted by the bot, so developer reactions can be traced back to a category
+CREATE TABLE IF NOT EXISTS review_comments (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  repo_full_name TEXT NOT NULL,
+  pr_number INTEGER NOT NULL,
+  github_review_id BIGINT,
+  github_comment_id BIGINT NOT NULL UNIQUE,
+  path TEXT NOT NULL,
+  line INTEGER,
+  category TEXT,
+  severity TEXT,
+  body TEXT,
+  created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_review_comments_repo ON review_comments(repo_full_name);
+CREATE INDEX IF NOT EXISTS idx_review_comments_review ON review_comments(github_review_id);
+
+-- Feedback signals on bot comments (reactions, replies, dismissals)
+CREATE TABLE IF NOT EXISTS review_feedback (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  repo_full_name TEXT NOT NULL,
+  github_comment_id BIGINT NOT NULL REFERENCES review_comments(github_comment_id) ON DELETE CASCADE,
+  signal TEXT NOT NULL, -- thumbs_up, thumbs_down, resolved, wont_fix, dismissed
+  count INTEGER NOT NULL DEFAULT 1,
+  note TEXT,
+  created_at TIMESTAMPTZ DEFAULT NOW(),
+  updated_at TIMESTAMPTZ DEFAULT NOW(),
+  UNIQUE(github_comment_id, signal)
+);
+
+CREATE INDEX IF NOT EXISTS idx_review_feedback_repo ON review_feedback(repo_full_name);
+
+-- Learnings proposed from feedback wait for approval before they reach prompts
+ALTER TABLE review_learnings ADD COLUMN IF NOT EXISTS source TEXT DEFAULT 'manual'; -- manual, feedback
+ALTER TABLE review_learnings ADD COLUMN IF NOT EXISTS status TEXT DEFAULT 'active'; -- active, proposed, rejected
+
+-- Per-category weights learned from feedback (1 = trusted, lower = noisy)
+ALTER TABLE repo_configs ADD COLUMN IF NOT EXISTS category_weights JSONB DEFAULT '{}';
+
+ALTER TABLE review_comments ENABLE ROW LEVEL SECURITY;
+ALTER TABLE review_feedback ENABLE ROW LEVEL SECURITY;
+
+CREATE POLICY "review_comments_all" ON review_comments FOR ALL USING (true);
+CREATE POLICY "review_feedback_all" ON review_feedback FOR ALL USING (true);
@@ -0,0 +1,5 @@
+-- Group chat tool calls by conversation so multi-step tool runs can be traced
+ALTER TABLE tool_audit_logs ADD COLUMN IF NOT EXISTS conversation_id UUID;
+ALTER TABLE tool_audit_logs ADD COLUMN IF NOT EXISTS step INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_tool_audit_conversation ON tool_audit_logs(conversation_id, created_at) WHERE conversation_id IS NOT NULL;
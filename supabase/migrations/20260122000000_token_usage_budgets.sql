@@ -0,0 +1,36 @@
+-- Token usage per review and scan
+ALTER TABLE review_history ADD COLUMN IF NOT EXISTS prompt_tokens INTEGER DEFAULT 0;
+ALTER TABLE review_history ADD COLUMN IF NOT EXISTS completion_tokens INTEGER DEFAULT 0;
+ALTER TABLE review_history ADD COLUMN IF NOT EXISTS cost_usd NUMERIC(12, 6) DEFAULT 0;
+
+ALTER TABLE security_scans ADD COLUMN IF NOT EXISTS prompt_tokens INTEGER DEFAULT 0;
+ALTER TABLE security_scans ADD COLUMN IF NOT EXISTS completion_tokens INTEGER DEFAULT 0;
+ALTER TABLE security_scans ADD COLUMN IF NOT EXISTS cost_usd NUMERIC(12, 6) DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_review_history_repo_created ON review_history(repo_full_name, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_security_scans_repo_created ON security_scans(repo_full_name, created_at DESC);
+
+-- Monthly budgets; NULL means unlimited
+ALTER TABLE repo_configs ADD COLUMN IF NOT EXISTS monthly_token_budget BIGINT;
+ALTER TABLE repo_configs ADD COLUMN IF NOT EXISTS monthly_cost_budget NUMERIC(10, 2);
+ALTER TABLE repo_configs ADD COLUMN IF NOT EXISTS over_budget_action TEXT DEFAULT 'downgrade'
+  CHECK (over_budget_action IN ('downgrade', 'skip'));
+ALTER TABLE repo_configs ADD COLUMN IF NOT EXISTS budget_notified_at TIMESTAMPTZ;
+
+-- Usage for a repo since a point in time, across reviews and scans
+CREATE OR REPLACE FUNCTION repo_usage_since(p_repo TEXT, p_since TIMESTAMPTZ)
+RETURNS TABLE (prompt_tokens BIGINT, completion_tokens BIGINT, cost_usd NUMERIC) AS $$
+  SELECT
+    COALESCE(SUM(u.prompt_tokens), 0)::BIGINT,
+    COALESCE(SUM(u.completion_tokens), 0)::BIGINT,
+    COALESCE(SUM(u.cost_usd), 0)
+  FROM (
+    SELECT rh.prompt_tokens, rh.completion_tokens, rh.cost_usd
+    FROM review_history rh
+    WHERE rh.repo_full_name = p_repo AND rh.created_at >= p_since
+    UNION ALL
+    SELECT ss.prompt_tokens, ss.completion_tokens, ss.cost_usd
+    FROM security_scans ss
+    WHERE ss.repo_full_name = p_repo AND ss.created_at >= p_since
+  ) u;
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,4 @@
+-- GitHub App installation covering each repo, seeded from webhook payloads
+ALTER TABLE repo_configs ADD COLUMN IF NOT EXISTS installation_id BIGINT;
+
+CREATE INDEX IF NOT EXISTS idx_repo_configs_installation ON repo_configs(installation_id);
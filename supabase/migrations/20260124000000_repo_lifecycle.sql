@@ -0,0 +1,18 @@
+-- Move a repo's history to its new name after a rename or transfer on GitHub
+CREATE OR REPLACE FUNCTION rename_repo(p_old TEXT, p_new TEXT)
+RETURNS VOID AS $$
+BEGIN
+  UPDATE repo_configs SET full_name = p_new, updated_at = NOW()
+  WHERE full_name = p_old AND NOT EXISTS (SELECT 1 FROM repo_configs WHERE full_name = p_new);
+
+  UPDATE review_history SET repo_full_name = p_new WHERE repo_full_name = p_old;
+  UPDATE pull_requests SET repo_full_name = p_new WHERE repo_full_name = p_old;
+  UPDATE security_scans SET repo_full_name = p_new WHERE repo_full_name = p_old;
+  UPDATE review_jobs SET repo_full_name = p_new WHERE repo_full_name = p_old;
+  UPDATE review_comments SET repo_full_name = p_new WHERE repo_full_name = p_old;
+  UPDATE review_feedback SET repo_full_name = p_new WHERE repo_full_name = p_old;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Why a repo was switched off automatically (archived, deleted, uninstalled)
+ALTER TABLE repo_configs ADD COLUMN IF NOT EXISTS disabled_reason TEXT;
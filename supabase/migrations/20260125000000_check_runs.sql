@@ -0,0 +1,3 @@
+-- GitHub check run mirroring each review job, and each repo's latest scan
+ALTER TABLE review_jobs ADD COLUMN IF NOT EXISTS check_run_id BIGINT;
+ALTER TABLE security_scans ADD COLUMN IF NOT EXISTS check_run_id BIGINT;
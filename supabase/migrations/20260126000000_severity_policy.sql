@@ -0,0 +1,2 @@
+-- Deterministic merge policy over review line_comments; NULL means block on any critical
+ALTER TABLE repo_configs ADD COLUMN IF NOT EXISTS severity_policy JSONB;
@@ -0,0 +1,65 @@
+-- Workers lease the jobs they claim; a lease that isn't renewed by heartbeat
+-- expires and the job goes back to the queue
+ALTER TABLE review_jobs ADD COLUMN IF NOT EXISTS worker_id TEXT;
+ALTER TABLE review_jobs ADD COLUMN IF NOT EXISTS lease_expires_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_review_jobs_lease ON review_jobs(lease_expires_at) WHERE status = 'processing';
+
+-- Return jobs whose worker stopped heartbeating to the queue. The lost run
+-- counts as an attempt, so a job that keeps crashing its worker ends up failed.
+-- Jobs claimed before leases existed fall back to started_at.
+CREATE OR REPLACE FUNCTION requeue_expired_jobs()
+RETURNS INTEGER AS $$
+DECLARE
+  requeued INTEGER;
+BEGIN
+  UPDATE review_jobs SET
+    status = CASE WHEN attempts + 1 >= max_attempts THEN 'failed' ELSE 'pending' END,
+    attempts = attempts + 1,
+    error = 'Lease expired on worker ' || COALESCE(worker_id, 'unknown'),
+    worker_id = NULL,
+    lease_expires_at = NULL,
+    updated_at = NOW()
+  WHERE status = 'processing'
+    AND (lease_expires_at < NOW() OR (lease_expires_at IS NULL AND started_at < NOW() - INTERVAL '10 minutes'));
+  GET DIAGNOSTICS requeued = ROW_COUNT;
+  RETURN requeued;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Claim the oldest ready job for a worker. SKIP LOCKED lets concurrent
+-- workers each take a different row instead of racing for the same one.
+CREATE OR REPLACE FUNCTION claim_review_job(p_worker_id TEXT, p_lease_seconds INTEGER DEFAULT 300)
+RETURNS SETOF review_jobs AS $$
+BEGIN
+  PERFORM requeue_expired_jobs();
+
+  RETURN QUERY
+  UPDATE review_jobs SET
+    status = 'processing',
+    worker_id = p_worker_id,
+    lease_expires_at = NOW() + make_interval(secs => p_lease_seconds),
+    started_at = NOW(),
+    updated_at = NOW()
+  WHERE id = (
+    SELECT id FROM review_jobs
+    WHERE status = 'pending' AND (next_retry_at IS NULL OR next_retry_at <= NOW())
+    ORDER BY created_at
+    LIMIT 1
+    FOR UPDATE SKIP LOCKED
+  )
+  RETURNING *;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Extend a lease; false means the worker no longer owns the job
+CREATE OR REPLACE FUNCTION heartbeat_review_job(p_job_id UUID, p_worker_id TEXT, p_lease_seconds INTEGER DEFAULT 300)
+RETURNS BOOLEAN AS $$
+BEGIN
+  UPDATE review_jobs SET
+    lease_expires_at = NOW() + make_interval(secs => p_lease_seconds),
+    updated_at = NOW()
+  WHERE id = p_job_id AND worker_id = p_worker_id AND status = 'processing';
+  RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql;
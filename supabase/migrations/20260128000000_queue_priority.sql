@@ -0,0 +1,56 @@
+-- Jobs carry the priority and score from PR analysis (0 low .. 3 critical)
+ALTER TABLE review_jobs ADD COLUMN IF NOT EXISTS priority SMALLINT NOT NULL DEFAULT 1;
+ALTER TABLE review_jobs ADD COLUMN IF NOT EXISTS score INTEGER NOT NULL DEFAULT 0;
+
+UPDATE review_jobs SET
+  priority = CASE analysis->>'priority' WHEN 'critical' THEN 3 WHEN 'high' THEN 2 WHEN 'low' THEN 0 ELSE 1 END,
+  score = COALESCE((analysis->>'score')::INTEGER, 0)
+WHERE status = 'pending' AND analysis IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_review_jobs_processing_repo ON review_jobs(repo_full_name) WHERE status = 'processing';
+
+-- Reviews one repo may have running at once; NULL uses the worker's default
+ALTER TABLE repo_configs ADD COLUMN IF NOT EXISTS max_concurrent_reviews INTEGER CHECK (max_concurrent_reviews > 0);
+
+-- Claim the most urgent ready job. A waiting job gains one priority level per
+-- p_aging_minutes so low-priority work still progresses, and repos already at
+-- their concurrency cap are passed over. The cap is checked without locking
+-- the repo, so simultaneous claims can overshoot it by a job.
+DROP FUNCTION IF EXISTS claim_review_job(TEXT, INTEGER);
+
+CREATE OR REPLACE FUNCTION claim_review_job(
+  p_worker_id TEXT,
+  p_lease_seconds INTEGER DEFAULT 300,
+  p_repo_limit INTEGER DEFAULT 2,
+  p_aging_minutes INTEGER DEFAULT 15
+)
+RETURNS SETOF review_jobs AS $$
+BEGIN
+  PERFORM requeue_expired_jobs();
+
+  RETURN QUERY
+  UPDATE review_jobs SET
+    status = 'processing',
+    worker_id = p_worker_id,
+    lease_expires_at = NOW() + make_interval(secs => p_lease_seconds),
+    started_at = NOW(),
+    updated_at = NOW()
+  WHERE id = (
+    SELECT j.id FROM review_jobs j
+    LEFT JOIN repo_configs rc ON rc.full_name = j.repo_full_name
+    WHERE j.status = 'pending'
+      AND (j.next_retry_at IS NULL OR j.next_retry_at <= NOW())
+      AND (
+        SELECT COUNT(*) FROM review_jobs r
+        WHERE r.repo_full_name = j.repo_full_name AND r.status = 'processing'
+      ) < COALESCE(rc.max_concurrent_reviews, p_repo_limit)
+    ORDER BY
+      j.priority + EXTRACT(EPOCH FROM NOW() - j.created_at) / (60 * GREATEST(p_aging_minutes, 1)) DESC,
+      j.score DESC,
+      j.created_at
+    LIMIT 1
+    FOR UPDATE OF j SKIP LOCKED
+  )
+  RETURNING *;
+END;
+$$ LANGUAGE plpgsql;
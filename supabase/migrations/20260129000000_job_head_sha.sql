@@ -0,0 +1,7 @@
+-- The commit a review job was queued for. A newer push supersedes the job,
+-- and a job whose commit is no longer the PR head is dropped before posting.
+ALTER TABLE review_jobs ADD COLUMN IF NOT EXISTS head_sha TEXT;
+ALTER TABLE review_jobs ADD COLUMN IF NOT EXISTS superseded_by UUID REFERENCES review_jobs(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_review_jobs_active_pr ON review_jobs(repo_full_name, pr_number)
+  WHERE status IN ('pending', 'processing');
@@ -0,0 +1,76 @@
+-- Every error a job hit, not just the last one, so DLQ entries can be diagnosed
+ALTER TABLE review_jobs ADD COLUMN IF NOT EXISTS error_history JSONB NOT NULL DEFAULT '[]';
+
+CREATE OR REPLACE FUNCTION record_job_error()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF NEW.error IS NOT NULL AND NEW.error IS DISTINCT FROM OLD.error THEN
+    NEW.error_history := COALESCE(OLD.error_history, '[]'::jsonb) || jsonb_build_object(
+      'at', NOW(),
+      'attempt', NEW.attempts,
+      'error', NEW.error,
+      'worker_id', OLD.worker_id
+    );
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS review_jobs_error_history ON review_jobs;
+CREATE TRIGGER review_jobs_error_history
+  BEFORE UPDATE OF error ON review_jobs
+  FOR EACH ROW EXECUTE FUNCTION record_job_error();
+
+-- DLQ entries keep the history and the commit, and remember when they were replayed
+ALTER TABLE review_jobs_dlq ADD COLUMN IF NOT EXISTS head_sha TEXT;
+ALTER TABLE review_jobs_dlq ADD COLUMN IF NOT EXISTS error_history JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE review_jobs_dlq ADD COLUMN IF NOT EXISTS replay_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE review_jobs_dlq ADD COLUMN IF NOT EXISTS replayed_at TIMESTAMPTZ;
+ALTER TABLE review_jobs_dlq ADD COLUMN IF NOT EXISTS replayed_job_id UUID;
+
+CREATE OR REPLACE FUNCTION move_to_dlq()
+RETURNS void AS $$
+BEGIN
+  INSERT INTO review_jobs_dlq (
+    original_job_id,
+    repo_full_name,
+    pr_number,
+    owner,
+    repo,
+    attempts,
+    error,
+    analysis,
+    head_sha,
+    error_history,
+    original_created_at,
+    metadata
+  )
+  SELECT
+    id,
+    repo_full_name,
+    pr_number,
+    owner,
+    repo,
+    attempts,
+    error,
+    analysis,
+    head_sha,
+    error_history,
+    created_at,
+    jsonb_build_object(
+      'status', status,
+      'max_attempts', max_attempts,
+      'started_at', started_at,
+      'completed_at', completed_at
+    )
+  FROM review_jobs
+  WHERE status = 'failed'
+    AND attempts >= max_attempts
+    AND updated_at < NOW() - INTERVAL '7 days';
+
+  DELETE FROM review_jobs
+  WHERE status = 'failed'
+    AND attempts >= max_attempts
+    AND updated_at < NOW() - INTERVAL '7 days';
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,3 @@
+-- Which analyzer posted a review comment (eslint, semgrep, ...). NULL for the
+-- model's own comments, so feedback on tool findings doesn't tune the model.
+ALTER TABLE review_comments ADD COLUMN IF NOT EXISTS source TEXT;
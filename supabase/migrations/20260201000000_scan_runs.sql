@@ -0,0 +1,77 @@
+-- Full-repository scans: a run walks the tree at one commit in batches across
+-- as many scan-repos invocations as it needs, tracking every file it covers.
+CREATE TABLE IF NOT EXISTS scan_runs (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  repo_full_name TEXT NOT NULL,
+  branch TEXT,
+  commit_sha TEXT,
+  status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'failed', 'abandoned')),
+  total_files INTEGER NOT NULL DEFAULT 0,
+  scanned_files INTEGER NOT NULL DEFAULT 0,
+  skipped_files INTEGER NOT NULL DEFAULT 0,
+  failed_files INTEGER NOT NULL DEFAULT 0,
+  tree_truncated BOOLEAN NOT NULL DEFAULT false,
+  prompt_tokens INTEGER DEFAULT 0,
+  completion_tokens INTEGER DEFAULT 0,
+  cost_usd NUMERIC(12, 6) DEFAULT 0,
+  check_run_id BIGINT,
+  scan_id UUID REFERENCES security_scans(id) ON DELETE SET NULL,
+  -- Held by the invocation working on the run
+  locked_until TIMESTAMPTZ,
+  error TEXT,
+  created_at TIMESTAMPTZ DEFAULT NOW(),
+  updated_at TIMESTAMPTZ DEFAULT NOW(),
+  completed_at TIMESTAMPTZ
+);
+
+-- One run in progress per repo
+CREATE UNIQUE INDEX IF NOT EXISTS idx_scan_runs_running ON scan_runs(repo_full_name) WHERE status = 'running';
+CREATE INDEX IF NOT EXISTS idx_scan_runs_repo_created ON scan_runs(repo_full_name, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS scan_files (
+  run_id UUID NOT NULL REFERENCES scan_runs(id) ON DELETE CASCADE,
+  path TEXT NOT NULL,
+  blob_sha TEXT,
+  size INTEGER NOT NULL DEFAULT 0,
+  priority INTEGER NOT NULL DEFAULT 10,
+  status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'scanned', 'skipped', 'failed')),
+  skip_reason TEXT,
+  attempts INTEGER NOT NULL DEFAULT 0,
+  findings JSONB NOT NULL DEFAULT '[]',
+  error TEXT,
+  scanned_at TIMESTAMPTZ,
+  PRIMARY KEY (run_id, path)
+);
+
+CREATE INDEX IF NOT EXISTS idx_scan_files_pending ON scan_files(run_id, priority, path) WHERE status = 'pending';
+
+ALTER TABLE scan_runs ENABLE ROW LEVEL SECURITY;
+ALTER TABLE scan_files ENABLE ROW LEVEL SECURITY;
+
+CREATE POLICY "scan_runs_select" ON scan_runs FOR SELECT USING (true);
+CREATE POLICY "scan_files_select" ON scan_files FOR SELECT USING (true);
+
+-- Runs spend tokens before their scan is recorded; count them until it is
+CREATE OR REPLACE FUNCTION repo_usage_since(p_repo TEXT, p_since TIMESTAMPTZ)
+RETURNS TABLE (prompt_tokens BIGINT, completion_tokens BIGINT, cost_usd NUMERIC) AS $$
+  SELECT
+    COALESCE(SUM(u.prompt_tokens), 0)::BIGINT,
+    COALESCE(SUM(u.completion_tokens), 0)::BIGINT,
+    COALESCE(SUM(u.cost_usd), 0)
+  FROM (
+    SELECT rh.prompt_tokens, rh.completion_tokens, rh.cost_usd
+    FROM review_history rh
+    WHERE rh.repo_full_name = p_repo AND rh.created_at >= p_since
+    UNION ALL
+    SELECT ss.prompt_tokens, ss.completion_tokens, ss.cost_usd
+    FROM security_scans ss
+    WHERE ss.repo_full_name = p_repo AND ss.created_at >= p_since
+    UNION ALL
+    SELECT sr.prompt_tokens, sr.completion_tokens, sr.cost_usd
+    FROM scan_runs sr
+    WHERE sr.repo_full_name = p_repo AND sr.created_at >= p_since AND sr.scan_id IS NULL
+  ) u;
+$$ LANGUAGE sql STABLE;
+
+-- Runs continue on every invocation; SCAN_INTERVAL_HOURS still spaces out new ones
+SELECT cron.alter_job(jobid, schedule := '*/10 * * * *') FROM cron.job WHERE jobname = 'scan-repos';
@@ -0,0 +1,36 @@
+-- Renames also move learnings, DLQ entries and scan runs, and the owner/repo
+-- queued jobs call GitHub with. A config already registered under the new name
+-- is folded into the old one, which holds the team's settings.
+CREATE OR REPLACE FUNCTION rename_repo(p_old TEXT, p_new TEXT)
+RETURNS VOID AS $$
+DECLARE
+  v_owner TEXT := split_part(p_new, '/', 1);
+  v_repo TEXT := split_part(p_new, '/', 2);
+BEGIN
+  IF EXISTS (SELECT 1 FROM repo_configs WHERE full_name = p_old) THEN
+    UPDATE repo_configs o SET installation_id = COALESCE(o.installation_id, n.installation_id)
+    FROM repo_configs n
+    WHERE o.full_name = p_old AND n.full_name = p_new;
+
+    DELETE FROM repo_configs WHERE full_name = p_new;
+    UPDATE repo_configs SET full_name = p_new, repo_full_name = p_new, updated_at = NOW() WHERE full_name = p_old;
+  END IF;
+
+  UPDATE review_history SET repo_full_name = p_new WHERE repo_full_name = p_old;
+  UPDATE pull_requests SET repo_full_name = p_new WHERE repo_full_name = p_old;
+  UPDATE security_scans SET repo_full_name = p_new WHERE repo_full_name = p_old;
+  UPDATE review_comments SET repo_full_name = p_new WHERE repo_full_name = p_old;
+  UPDATE review_feedback SET repo_full_name = p_new WHERE repo_full_name = p_old;
+  UPDATE review_learnings SET repo_full_name = p_new WHERE repo_full_name = p_old;
+
+  -- Workers call GitHub with owner/repo, not repo_full_name
+  UPDATE review_jobs SET repo_full_name = p_new, owner = v_owner, repo = v_repo WHERE repo_full_name = p_old;
+  UPDATE review_jobs_dlq SET repo_full_name = p_new, owner = v_owner, repo = v_repo WHERE repo_full_name = p_old;
+
+  -- Only one run per repo may be running
+  UPDATE scan_runs SET status = 'abandoned', error = 'Repository renamed', updated_at = NOW()
+  WHERE repo_full_name = p_old AND status = 'running'
+    AND EXISTS (SELECT 1 FROM scan_runs WHERE repo_full_name = p_new AND status = 'running');
+  UPDATE scan_runs SET repo_full_name = p_new WHERE repo_full_name = p_old;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,14 @@
+-- One active job per commit. Two webhook deliveries for the same push can both
+-- pass the check in enqueueReview; the second insert now fails instead.
+-- Jobs queued without a commit (head_sha NULL) never conflict.
+UPDATE review_jobs j SET status = 'superseded', error = 'Duplicate job for the same commit', updated_at = NOW()
+WHERE j.status = 'pending' AND j.head_sha IS NOT NULL
+  AND EXISTS (
+    SELECT 1 FROM review_jobs o
+    WHERE o.repo_full_name = j.repo_full_name AND o.pr_number = j.pr_number AND o.head_sha = j.head_sha
+      AND o.status IN ('pending', 'processing') AND o.id <> j.id
+      AND (o.status = 'processing' OR o.created_at < j.created_at OR (o.created_at = j.created_at AND o.id < j.id))
+  );
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_review_jobs_active_head ON review_jobs(repo_full_name, pr_number, head_sha)
+  WHERE status IN ('pending', 'processing');
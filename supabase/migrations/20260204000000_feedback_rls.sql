@@ -0,0 +1,14 @@
+-- Feedback trains category weights and learnings, so only the service role
+-- (the worker and signed GitHub webhooks) may write it; anyone may read it
+DROP POLICY IF EXISTS "review_comments_all" ON review_comments;
+DROP POLICY IF EXISTS "review_feedback_all" ON review_feedback;
+
+CREATE POLICY "review_comments_select" ON review_comments FOR SELECT USING (true);
+CREATE POLICY "review_comments_insert" ON review_comments FOR INSERT WITH CHECK (is_service_role());
+CREATE POLICY "review_comments_update" ON review_comments FOR UPDATE USING (is_service_role());
+CREATE POLICY "review_comments_delete" ON review_comments FOR DELETE USING (is_service_role());
+
+CREATE POLICY "review_feedback_select" ON review_feedback FOR SELECT USING (true);
+CREATE POLICY "review_feedback_insert" ON review_feedback FOR INSERT WITH CHECK (is_service_role());
+CREATE POLICY "review_feedback_update" ON review_feedback FOR UPDATE USING (is_service_role());
+CREATE POLICY "review_feedback_delete" ON review_feedback FOR DELETE USING (is_service_role());
@@ -0,0 +1,64 @@
+-- A job whose lease expires with no attempts left goes straight to the DLQ,
+-- where it can be inspected and replayed, instead of sitting in 'failed'
+CREATE OR REPLACE FUNCTION requeue_expired_jobs()
+RETURNS INTEGER AS $$
+DECLARE
+  requeued INTEGER;
+  exhausted UUID[];
+BEGIN
+  WITH expired AS (
+    UPDATE review_jobs SET
+      status = CASE WHEN attempts + 1 >= max_attempts THEN 'failed' ELSE 'pending' END,
+      attempts = attempts + 1,
+      error = 'Lease expired on worker ' || COALESCE(worker_id, 'unknown'),
+      worker_id = NULL,
+      lease_expires_at = NULL,
+      updated_at = NOW()
+    WHERE status = 'processing'
+      AND (lease_expires_at < NOW() OR (lease_expires_at IS NULL AND started_at < NOW() - INTERVAL '10 minutes'))
+    RETURNING id, status
+  )
+  SELECT COUNT(*), COALESCE(array_agg(id) FILTER (WHERE status = 'failed'), '{}')
+  INTO requeued, exhausted
+  FROM expired;
+
+  INSERT INTO review_jobs_dlq (
+    original_job_id,
+    repo_full_name,
+    pr_number,
+    owner,
+    repo,
+    attempts,
+    error,
+    analysis,
+    head_sha,
+    error_history,
+    original_created_at,
+    metadata
+  )
+  SELECT
+    id,
+    repo_full_name,
+    pr_number,
+    owner,
+    repo,
+    attempts,
+    error,
+    analysis,
+    head_sha,
+    error_history,
+    created_at,
+    jsonb_build_object(
+      'status', status,
+      'max_attempts', max_attempts,
+      'started_at', started_at,
+      'completed_at', completed_at
+    )
+  FROM review_jobs
+  WHERE id = ANY(exhausted);
+
+  DELETE FROM review_jobs WHERE id = ANY(exhausted);
+
+  RETURN requeued;
+END;
+$$ LANGUAGE plpgsql;